@@ -0,0 +1,123 @@
+//! Compares the ways plain `cat` can move a file to standard output.
+//!
+//! Run with `cargo bench -p mimic_commands --bench cat`. The input size in MiB can be
+//! changed with `CAT_BENCH_MIB` (default 256).
+use mimic_commands::zerocopy::{copy_with, Method};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::os::fd::OwnedFd;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use std::{env, thread};
+
+const RUNS: usize = 3;
+
+/// The old `write_to_stdout`: a 4 KiB stack buffer.
+fn copy_4k(mut input: &File, mut output: &File) -> io::Result<u64> {
+    let mut buffer = [0; 1024 * 4];
+    let mut copied = 0;
+    loop {
+        let size = input.read(&mut buffer[..])?;
+        if size == 0 {
+            return Ok(copied);
+        }
+        output.write_all(&buffer[..size])?;
+        copied += size as u64;
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Strategy {
+    Baseline,
+    Method(Method),
+}
+
+fn run(strategy: Strategy, input: &Path, output: &File) -> io::Result<u64> {
+    let input = File::open(input)?;
+    match strategy {
+        Strategy::Baseline => copy_4k(&input, output),
+        Strategy::Method(method) => copy_with(method, &input, output),
+    }
+}
+
+/// Reads the other end of the pipe as cheaply as possible, so the writer is what gets measured.
+fn drain<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut buffer = vec![0; 1024 * 1024];
+    let mut drained = 0;
+    loop {
+        let size = reader.read(&mut buffer)?;
+        if size == 0 {
+            return Ok(drained);
+        }
+        drained += size as u64;
+    }
+}
+
+/// The best of a few runs, to keep other activity on the machine out of the numbers.
+fn best_of<F: FnMut() -> io::Result<u64>>(mut f: F) -> io::Result<Duration> {
+    let mut best = Duration::MAX;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        f()?;
+        best = best.min(start.elapsed());
+    }
+    Ok(best)
+}
+
+fn report(target: &str, name: &str, size: u64, elapsed: Duration) {
+    let gib_per_sec = size as f64 / elapsed.as_secs_f64() / (1024.0 * 1024.0 * 1024.0);
+    println!(
+        "{:<6} {:<15} {:>9.1} ms {:>7.2} GiB/s",
+        target,
+        name,
+        elapsed.as_secs_f64() * 1000.0,
+        gib_per_sec
+    );
+}
+
+fn main() -> io::Result<()> {
+    let mib: u64 = env::var("CAT_BENCH_MIB")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(256);
+    let size = mib * 1024 * 1024;
+
+    let dir = env::temp_dir();
+    let input = dir.join(format!("cat_bench_input_{}", std::process::id()));
+    let output = dir.join(format!("cat_bench_output_{}", std::process::id()));
+    let chunk: Vec<u8> = (0..1024 * 1024).map(|i| (i % 251) as u8).collect();
+    let mut file = File::create(&input)?;
+    for _ in 0..mib {
+        file.write_all(&chunk)?;
+    }
+    drop(file);
+
+    let strategies = [
+        ("4 KiB buffer", Strategy::Baseline),
+        ("128 KiB buffer", Strategy::Method(Method::Buffered)),
+        ("copy_file_range", Strategy::Method(Method::CopyFileRange)),
+        ("sendfile", Strategy::Method(Method::Sendfile)),
+        ("splice", Strategy::Method(Method::Splice)),
+    ];
+
+    println!("copying {} MiB, best of {} runs", mib, RUNS);
+    for (name, strategy) in strategies {
+        let elapsed = best_of(|| run(strategy, &input, &File::create(&output)?))?;
+        report("file", name, size, elapsed);
+    }
+    for (name, strategy) in strategies {
+        let elapsed = best_of(|| {
+            let (mut reader, writer) = io::pipe()?;
+            let drain = thread::spawn(move || drain(&mut reader));
+            let writer = File::from(OwnedFd::from(writer));
+            run(strategy, &input, &writer)?;
+            drop(writer);
+            drain.join().expect("drain thread panicked")
+        })?;
+        report("pipe", name, size, elapsed);
+    }
+
+    fs::remove_file(&input)?;
+    fs::remove_file(&output)?;
+    Ok(())
+}
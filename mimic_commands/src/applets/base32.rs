@@ -0,0 +1,6 @@
+use crate::applets::basenc;
+use crate::radix::Alphabet;
+
+pub fn main(args: Vec<String>) {
+    basenc::run("base32", Some(Alphabet::Base32), args);
+}
@@ -0,0 +1,202 @@
+//! What `base64`, `base32` and `basenc` have in common: encoding or decoding one input to
+//! standard output, a fixed size buffer at a time.
+use crate::args::Command;
+use crate::dump;
+use crate::error::{self, Error, Output, Reporter, Style};
+use crate::radix::{self, Alphabet, Decoder};
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::process::exit;
+
+/// How much input is encoded at a time: a whole number of groups in every alphabet.
+const ENCODE_BUFFER_SIZE: usize = 3 * 5 * 2048;
+
+/// How much input is decoded at a time.
+const DECODE_BUFFER_SIZE: usize = 32 * 1024;
+
+/// The column encoded lines are wrapped at unless told otherwise.
+const DEFAULT_WRAP: usize = 76;
+
+/// The `basenc` options that choose the encoding, last one winning.
+const ENCODINGS: [(&str, Alphabet); 5] = [
+    ("base64", Alphabet::Base64),
+    ("base64url", Alphabet::Base64Url),
+    ("base32", Alphabet::Base32),
+    ("base32hex", Alphabet::Base32Hex),
+    ("base16", Alphabet::Base16),
+];
+
+pub fn main(args: Vec<String>) {
+    run("basenc", None, args);
+}
+
+/// Runs the applet `name`, which encodes with `alphabet`, or with the one its options
+/// choose if there is none.
+pub fn run(name: &'static str, alphabet: Option<Alphabet>, args: Vec<String>) {
+    let mut command = Command::new(name, "[OPTION]... [FILE]").about(match alphabet {
+        Some(Alphabet::Base32) => {
+            "Base32 encode or decode FILE, or standard input, to standard output."
+        }
+        Some(_) => "Base64 encode or decode FILE, or standard input, to standard output.",
+        None => "basenc encode or decode FILE, or standard input, to standard output.",
+    });
+    if alphabet.is_none() {
+        command = command
+            .opt("--base64", "same as 'base64' program (RFC4648 section 4)")
+            .opt(
+                "--base64url",
+                "file- and url-safe base64 (RFC4648 section 5)",
+            )
+            .opt("--base32", "same as 'base32' program (RFC4648 section 6)")
+            .opt(
+                "--base32hex",
+                "extended hex alphabet base32 (RFC4648 section 7)",
+            )
+            .opt("--base16", "hex encoding (RFC4648 section 8)");
+    }
+    let command = command
+        .opt("-d, --decode", "decode data")
+        .opt(
+            "-i, --ignore-garbage",
+            "when decoding, ignore non-alphabet characters",
+        )
+        .opt(
+            "-w, --wrap=COLS",
+            "wrap encoded lines after COLS character (default 76); use 0 to disable line wrapping",
+        )
+        .usage_status(1);
+    let matches = command.parse_or_exit(args);
+
+    let alphabet = alphabet.unwrap_or_else(|| {
+        let keys: Vec<&str> = ENCODINGS.iter().map(|(key, _)| *key).collect();
+        match matches.last_of(&keys) {
+            Some(key) => ENCODINGS.iter().find(|(k, _)| *k == key).unwrap().1,
+            None => command.exit_with_usage_error(&"missing encoding type"),
+        }
+    });
+    let path = match matches.operands() {
+        [] => "-",
+        [path] => path,
+        [_, extra, ..] => command.exit_with_usage_error(&format!("extra operand '{}'", extra)),
+    };
+    let wrap = match matches.value("wrap") {
+        Some(value) => value.parse::<usize>().unwrap_or_else(|_| {
+            eprintln!("{}: invalid wrap size: '{}'", name, value);
+            exit(1);
+        }),
+        None => DEFAULT_WRAP,
+    };
+
+    let mut reporter = Reporter::new(name, Style::Bare);
+    let mut reader: Box<dyn Read> = if path == "-" {
+        Box::new(io::stdin().lock())
+    } else {
+        match File::open(path) {
+            Ok(file) => Box::new(file),
+            Err(e) => {
+                reporter.report(&Error::Open(path.to_string(), e));
+                exit(1);
+            }
+        }
+    };
+    let mut writer = BufWriter::new(Output(io::stdout()));
+    let result = if matches.flag("decode") {
+        decode(
+            alphabet,
+            matches.flag("ignore-garbage"),
+            &mut reader,
+            &mut writer,
+        )
+    } else {
+        encode(alphabet, wrap, &mut reader, &mut writer).map_err(Failure::Io)
+    };
+    let result = result.and_then(|_| writer.flush().map_err(Failure::Io));
+    match result {
+        Ok(()) => {}
+        Err(Failure::Io(e)) => match Error::copying(path, e) {
+            Error::Read(_, e) => reporter.fail(&format!("read error: {}", error::describe(&e))),
+            e => reporter.report(&e),
+        },
+        Err(Failure::Invalid(e)) => {
+            // What was decoded before the data went wrong is kept.
+            if let Err(e) = writer.flush() {
+                reporter.report(&Error::writing(e));
+            }
+            reporter.fail(&e);
+        }
+    }
+    reporter.finish();
+}
+
+enum Failure {
+    Io(io::Error),
+    Invalid(radix::InvalidInput),
+}
+
+/// Encodes whole buffers of input, wrapping the lines at column `wrap` if it is not 0.
+fn encode<R: Read, W: Write>(
+    alphabet: Alphabet,
+    wrap: usize,
+    reader: &mut R,
+    writer: &mut W,
+) -> io::Result<()> {
+    let mut buffer = vec![0; ENCODE_BUFFER_SIZE];
+    let mut encoded = Vec::new();
+    let mut column = 0;
+    loop {
+        let size = dump::read_full(reader, &mut buffer)?;
+        if size == 0 {
+            break;
+        }
+        encoded.clear();
+        radix::encode(alphabet, &buffer[..size], &mut encoded);
+        if wrap == 0 {
+            writer.write_all(&encoded)?;
+            continue;
+        }
+        let mut rest = &encoded[..];
+        while !rest.is_empty() {
+            let (line, after) = rest.split_at(rest.len().min(wrap - column));
+            writer.write_all(line)?;
+            column += line.len();
+            if column == wrap {
+                writer.write_all(b"\n")?;
+                column = 0;
+            }
+            rest = after;
+        }
+    }
+    if column > 0 {
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+fn decode<R: Read, W: Write>(
+    alphabet: Alphabet,
+    ignore_garbage: bool,
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<(), Failure> {
+    let mut decoder = Decoder::new(alphabet, ignore_garbage);
+    let mut buffer = vec![0; DECODE_BUFFER_SIZE];
+    let mut decoded = Vec::new();
+    loop {
+        let size = match reader.read(&mut buffer) {
+            Ok(size) => size,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(Failure::Io(e)),
+        };
+        decoded.clear();
+        let result = if size == 0 {
+            decoder.finish(&mut decoded)
+        } else {
+            decoder.update(&buffer[..size], &mut decoded)
+        };
+        writer.write_all(&decoded).map_err(Failure::Io)?;
+        result.map_err(Failure::Invalid)?;
+        if size == 0 {
+            return Ok(());
+        }
+    }
+}
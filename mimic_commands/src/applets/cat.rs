@@ -0,0 +1,271 @@
+use crate::args::Command;
+use crate::error::{Error, Output, Reporter, Style};
+use crate::stdio;
+use crate::{inflate, zerocopy};
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter, Read, Write};
+
+pub fn main(args: Vec<String>) {
+    let matches = Command::new("cat", "[OPTION]... [FILE]...")
+        .about("Concatenate FILE(s) to standard output.")
+        .opt("-A, --show-all", "equivalent to -vET")
+        .opt(
+            "-b, --number-nonblank",
+            "number nonempty output lines, overrides -n",
+        )
+        .opt("-e", "equivalent to -vE")
+        .opt("-E, --show-ends", "display $ at end of each line")
+        .opt("-n, --number", "number all output lines")
+        .opt(
+            "-s, --squeeze-blank",
+            "suppress repeated empty output lines",
+        )
+        .opt("-t", "equivalent to -vT")
+        .opt("-T, --show-tabs", "display TAB characters as ^I")
+        .opt("-u", "(ignored)")
+        .opt(
+            "-v, --show-nonprinting",
+            "use ^ and M- notation, except for LFD and TAB",
+        )
+        .opt(
+            "--decompress",
+            "decompress gzip input; other input is copied as it is",
+        )
+        .parse_or_exit(args);
+
+    let options = Options {
+        number: matches.flag("number"),
+        number_nonblank: matches.flag("number-nonblank"),
+        squeeze_blank: matches.flag("squeeze-blank"),
+        show_ends: matches.flag("show-ends") || matches.flag("show-all") || matches.flag("e"),
+        show_tabs: matches.flag("show-tabs") || matches.flag("show-all") || matches.flag("t"),
+        show_nonprinting: matches.flag("show-nonprinting")
+            || matches.flag("show-all")
+            || matches.flag("e")
+            || matches.flag("t"),
+    };
+    // The formatter carries line numbers and blank-line state from one file to the next.
+    let mut formatter = if options.is_plain() {
+        None
+    } else {
+        Some(Formatter::new(options))
+    };
+
+    // Borrow fds 0 and 1 as Files so plain copies can be handed to the kernel.
+    let stdin = stdio::stdin_file();
+    let stdout = stdio::stdout_file();
+    let decompress = matches.flag("decompress");
+    let mut reporter = Reporter::new("cat", Style::Bare);
+    for path in matches.files() {
+        let result = if path == "-" {
+            write(&stdin, &path, &stdout, &mut formatter, decompress)
+        } else {
+            File::open(&path)
+                .map_err(|e| Error::Open(path.clone(), e))
+                .and_then(|f| write(&f, &path, &stdout, &mut formatter, decompress))
+        };
+        reporter.check(result);
+    }
+    reporter.finish();
+}
+
+fn write(
+    file: &File,
+    path: &str,
+    stdout: &File,
+    formatter: &mut Option<Formatter>,
+    decompress: bool,
+) -> Result<(), Error> {
+    let result = match (formatter, decompress) {
+        (None, false) => zerocopy::copy(file, stdout).map(|_| ()),
+        (formatter, _) => {
+            let mut writer = BufWriter::new(Output(io::stdout()));
+            let mut reader: Box<dyn Read> = if decompress {
+                inflate::decompressed(BufReader::new(file)).map_err(|e| Error::copying(path, e))?
+            } else {
+                Box::new(file)
+            };
+            match formatter {
+                Some(formatter) => formatter.format(reader, &mut writer),
+                None => io::copy(&mut reader, &mut writer).map(|_| ()),
+            }
+            .and_then(|_| writer.flush())
+        }
+    };
+    result.map_err(|e| Error::copying(path, e))
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Options {
+    number: bool,
+    number_nonblank: bool,
+    squeeze_blank: bool,
+    show_ends: bool,
+    show_tabs: bool,
+    show_nonprinting: bool,
+}
+
+impl Options {
+    /// Whether the input can be copied through untouched.
+    fn is_plain(&self) -> bool {
+        !(self.number
+            || self.number_nonblank
+            || self.squeeze_blank
+            || self.show_ends
+            || self.show_tabs
+            || self.show_nonprinting)
+    }
+}
+
+/// Applies the formatting options line by line, across as many inputs as it is given.
+struct Formatter {
+    options: Options,
+    line_number: usize,
+    at_line_start: bool,
+    blank_lines: usize,
+}
+
+impl Formatter {
+    fn new(options: Options) -> Self {
+        Self {
+            options,
+            line_number: 0,
+            at_line_start: true,
+            blank_lines: 0,
+        }
+    }
+
+    fn format<R: Read, W: Write>(&mut self, mut reader: R, writer: &mut W) -> io::Result<()> {
+        let mut buffer = [0; 1024 * 4];
+        loop {
+            let size = reader.read(&mut buffer[..])?;
+            if size == 0 {
+                return Ok(());
+            }
+            for b in &buffer[..size] {
+                self.format_byte(*b, writer)?;
+            }
+        }
+    }
+
+    fn format_byte<W: Write>(&mut self, b: u8, writer: &mut W) -> io::Result<()> {
+        let options = self.options;
+        if self.at_line_start {
+            if b == b'\n' {
+                self.blank_lines += 1;
+                if options.squeeze_blank && self.blank_lines > 1 {
+                    return Ok(());
+                }
+                if options.number && !options.number_nonblank {
+                    self.write_line_number(writer)?;
+                }
+                return self.write_line_end(writer);
+            }
+
+            self.blank_lines = 0;
+            if options.number || options.number_nonblank {
+                self.write_line_number(writer)?;
+            }
+            self.at_line_start = false;
+        }
+
+        match b {
+            b'\n' => {
+                self.at_line_start = true;
+                self.write_line_end(writer)
+            }
+            b'\t' if options.show_tabs => writer.write_all(b"^I"),
+            b'\t' => writer.write_all(b"\t"),
+            _ if options.show_nonprinting => write_visible(b, writer),
+            _ => writer.write_all(&[b]),
+        }
+    }
+
+    fn write_line_number<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.line_number += 1;
+        write!(writer, "{:>6}\t", self.line_number)
+    }
+
+    fn write_line_end<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        if self.options.show_ends {
+            writer.write_all(b"$\n")
+        } else {
+            writer.write_all(b"\n")
+        }
+    }
+}
+
+/// Writes a byte in `^X` / `M-X` notation if it is not printable ASCII.
+fn write_visible<W: Write>(b: u8, writer: &mut W) -> io::Result<()> {
+    let (meta, b) = if b >= 0x80 {
+        (true, b - 0x80)
+    } else {
+        (false, b)
+    };
+    if meta {
+        writer.write_all(b"M-")?;
+    }
+    match b {
+        0..=31 => writer.write_all(&[b'^', b + 64]),
+        127 => writer.write_all(b"^?"),
+        _ => writer.write_all(&[b]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(options: Options, inputs: &[&[u8]]) -> String {
+        let mut formatter = Formatter::new(options);
+        let mut output = Vec::new();
+        for input in inputs {
+            formatter.format(*input, &mut output).unwrap();
+        }
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_format() {
+        let number = Options {
+            number: true,
+            ..Options::default()
+        };
+        assert_eq!(
+            format(number, &[b"a\n\nb", b"c\nd\n"]),
+            "     1\ta\n     2\t\n     3\tbc\n     4\td\n"
+        );
+
+        let number_nonblank = Options {
+            number: true,
+            number_nonblank: true,
+            ..Options::default()
+        };
+        assert_eq!(
+            format(number_nonblank, &[b"a\n\n", b"b\n"]),
+            "     1\ta\n\n     2\tb\n"
+        );
+
+        let squeeze = Options {
+            squeeze_blank: true,
+            show_ends: true,
+            ..Options::default()
+        };
+        assert_eq!(
+            format(squeeze, &[b"\n\na\n\n", b"\n\nb\n"]),
+            "$\na$\n$\nb$\n"
+        );
+
+        let show_all = Options {
+            show_ends: true,
+            show_tabs: true,
+            show_nonprinting: true,
+            ..Options::default()
+        };
+        assert_eq!(
+            format(show_all, &[b"\t\x01\x7f\x80\xff\xe3 \r\n"]),
+            "^I^A^?M-^@M-^?M-c ^M$\n"
+        );
+    }
+}
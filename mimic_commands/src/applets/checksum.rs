@@ -0,0 +1,605 @@
+//! What `md5sum`, `sha1sum`, `sha256sum` and `b2sum` have in common: printing digests of
+//! files, and checking files against lists of digests.
+use crate::args::Command;
+use crate::digest::{self, Blake2b, Digest, Md5, Sha1, Sha256};
+use crate::error::{Error, Output, Reporter, Style};
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::process::exit;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Algorithm {
+    Md5,
+    Sha1,
+    Sha256,
+    Blake2b,
+}
+
+impl Algorithm {
+    fn command(self) -> &'static str {
+        match self {
+            Algorithm::Md5 => "md5sum",
+            Algorithm::Sha1 => "sha1sum",
+            Algorithm::Sha256 => "sha256sum",
+            Algorithm::Blake2b => "b2sum",
+        }
+    }
+
+    /// The name in `--tag` lines.
+    fn tag(self) -> &'static str {
+        match self {
+            Algorithm::Md5 => "MD5",
+            Algorithm::Sha1 => "SHA1",
+            Algorithm::Sha256 => "SHA256",
+            Algorithm::Blake2b => "BLAKE2b",
+        }
+    }
+
+    /// The digest size in bytes, which only BLAKE2b lets be chosen.
+    fn default_size(self) -> usize {
+        match self {
+            Algorithm::Md5 => 16,
+            Algorithm::Sha1 => 20,
+            Algorithm::Sha256 => 32,
+            Algorithm::Blake2b => Blake2b::MAX_SIZE,
+        }
+    }
+
+    fn digest(self, size: usize) -> Box<dyn Digest> {
+        match self {
+            Algorithm::Md5 => Box::new(Md5::new()),
+            Algorithm::Sha1 => Box::new(Sha1::new()),
+            Algorithm::Sha256 => Box::new(Sha256::new()),
+            Algorithm::Blake2b => Box::new(Blake2b::new(size)),
+        }
+    }
+}
+
+/// How much is printed while checking.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CheckOptions {
+    /// Leave out the lines for files that are OK (`--quiet`).
+    quiet: bool,
+    /// Print nothing but errors; only the exit status tells (`--status`).
+    status: bool,
+    /// Fail if any line is improperly formatted (`--strict`).
+    strict: bool,
+    /// Warn about each improperly formatted line (`-w`).
+    warn: bool,
+}
+
+/// Runs the applet for `algorithm`.
+pub fn run(algorithm: Algorithm, args: Vec<String>) {
+    let name = algorithm.command();
+    let about = match algorithm {
+        Algorithm::Md5 => "Print or check MD5 (128-bit) checksums.",
+        Algorithm::Sha1 => "Print or check SHA1 (160-bit) checksums.",
+        Algorithm::Sha256 => "Print or check SHA256 (256-bit) checksums.",
+        Algorithm::Blake2b => "Print or check BLAKE2b 512-bit checksums.",
+    };
+    let mut command = Command::new(name, "[OPTION]... [FILE]...")
+        .about(about)
+        .opt("-b, --binary", "read in binary mode")
+        .opt(
+            "-c, --check",
+            "read checksums from the FILEs and check them",
+        );
+    if algorithm == Algorithm::Blake2b {
+        command = command.opt(
+            "-l, --length=BITS",
+            "digest length in bits; must not exceed the maximum for the blake2 algorithm and must be a multiple of 8",
+        );
+    }
+    let command = command
+        .opt("--tag", "create a BSD-style checksum")
+        .opt("-t, --text", "read in text mode (default)")
+        .opt(
+            "--quiet",
+            "don't print OK for each successfully verified file",
+        )
+        .opt(
+            "--status",
+            "don't output anything, status code shows success",
+        )
+        .opt(
+            "--strict",
+            "exit non-zero for improperly formatted checksum lines",
+        )
+        .opt(
+            "-w, --warn",
+            "warn about improperly formatted checksum lines",
+        );
+    let matches = command.parse_or_exit(args);
+
+    let size = matches.value("length").map(|bits| {
+        let fail = |reason: &str| -> ! {
+            eprintln!("{}: invalid length: '{}'", name, bits);
+            if !reason.is_empty() {
+                eprintln!("{}: {}", name, reason);
+            }
+            exit(1);
+        };
+        match bits.parse::<usize>() {
+            Ok(0) => algorithm.default_size(),
+            Ok(bits) if bits > Blake2b::MAX_SIZE * 8 => {
+                fail("maximum digest length for 'BLAKE2b' is 512 bits")
+            }
+            Ok(bits) if !bits.is_multiple_of(8) => fail("length is not a multiple of 8"),
+            Ok(bits) => bits / 8,
+            Err(_) => fail(""),
+        }
+    });
+    let check = matches.flag("check");
+    let options = CheckOptions {
+        quiet: matches.flag("quiet"),
+        status: matches.flag("status"),
+        strict: matches.flag("strict"),
+        warn: matches.flag("warn"),
+    };
+    let tag = matches.flag("tag");
+    for (option, given) in [
+        ("--quiet", options.quiet),
+        ("--status", options.status),
+        ("--strict", options.strict),
+        ("--warn", options.warn),
+    ] {
+        if given && !check {
+            command.exit_with_usage_error(&format!(
+                "the {} option is meaningful only when verifying checksums",
+                option
+            ));
+        }
+    }
+    if tag && check {
+        command.exit_with_usage_error(&"the --tag option is meaningless when verifying checksums");
+    }
+    if tag && matches.last_of(&["binary", "text"]) == Some("text") {
+        command.exit_with_usage_error(&"--tag does not support --text mode");
+    }
+
+    let mut reporter = Reporter::new(name, Style::Bare);
+    let mut writer = Output(io::stdout());
+    let mut ok = true;
+    for path in matches.files() {
+        let result = if check {
+            // Like GNU b2sum, checking takes BLAKE2b's digest size from each line, not `-l`.
+            let size = Some(algorithm.default_size()).filter(|_| algorithm != Algorithm::Blake2b);
+            check_list(&path, algorithm, size, options, &mut writer, &mut reporter)
+                .map(|passed| ok &= passed)
+        } else {
+            let size = size.unwrap_or_else(|| algorithm.default_size());
+            match digest_file(&path, algorithm.digest(size)) {
+                Ok(digest) => {
+                    let binary = matches.last_of(&["binary", "text"]) == Some("binary");
+                    let line = format_line(algorithm, size, &digest, path.as_bytes(), tag, binary);
+                    writer.write_all(&line).map_err(Error::writing)
+                }
+                Err(e) => Err(e),
+            }
+        };
+        reporter.check(result);
+    }
+    reporter.finish();
+    if !ok {
+        exit(1);
+    }
+}
+
+/// Feeds the file at `path`, or standard input for `-`, to `digest`.
+fn digest_file(path: &str, digest: Box<dyn Digest>) -> Result<Vec<u8>, Error> {
+    let open = |path: &str| -> Result<Box<dyn Read>, Error> {
+        if path == "-" {
+            return Ok(Box::new(io::stdin()));
+        }
+        match File::open(path) {
+            Ok(file) => Ok(Box::new(file)),
+            Err(e) => Err(Error::Open(path.to_string(), e)),
+        }
+    };
+    digest_reader(open(path)?, digest).map_err(|e| Error::Read(path.to_string(), e))
+}
+
+fn digest_reader<R: Read>(mut reader: R, mut digest: Box<dyn Digest>) -> io::Result<Vec<u8>> {
+    let mut buffer = [0; 1024 * 32];
+    loop {
+        let size = match reader.read(&mut buffer) {
+            Ok(size) => size,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if size == 0 {
+            return Ok(digest.finish());
+        }
+        digest.update(&buffer[..size]);
+    }
+}
+
+/// A line of output for one file: `DIGEST  NAME`, `DIGEST *NAME` in binary mode, or
+/// `ALGORITHM (NAME) = DIGEST` with `--tag`. Names with a backslash or newline in them are
+/// escaped, and the line starts with a backslash to say so.
+fn format_line(
+    algorithm: Algorithm,
+    size: usize,
+    digest: &[u8],
+    name: &[u8],
+    tag: bool,
+    binary: bool,
+) -> Vec<u8> {
+    let mut line = Vec::new();
+    let escaped = name.iter().any(|b| *b == b'\\' || *b == b'\n');
+    if escaped {
+        line.push(b'\\');
+    }
+    let mut name_bytes = Vec::new();
+    for b in name {
+        match b {
+            b'\\' if escaped => name_bytes.extend_from_slice(b"\\\\"),
+            b'\n' => name_bytes.extend_from_slice(b"\\n"),
+            b => name_bytes.push(*b),
+        }
+    }
+    if tag {
+        line.extend_from_slice(algorithm.tag().as_bytes());
+        if size != algorithm.default_size() {
+            line.extend_from_slice(format!("-{}", size * 8).as_bytes());
+        }
+        line.extend_from_slice(b" (");
+        line.extend_from_slice(&name_bytes);
+        line.extend_from_slice(b") = ");
+        line.extend_from_slice(digest::hex(digest).as_bytes());
+    } else {
+        line.extend_from_slice(digest::hex(digest).as_bytes());
+        line.extend_from_slice(if binary { b" *" } else { b"  " });
+        line.extend_from_slice(&name_bytes);
+    }
+    line.push(b'\n');
+    line
+}
+
+/// One line of a checksum list.
+#[derive(Debug, PartialEq)]
+struct Entry {
+    digest: String,
+    name: Vec<u8>,
+}
+
+/// Parses a line in either of the formats `format_line` writes. `size` is the digest
+/// size if it is fixed; otherwise BLAKE2b's is taken from the line. `reversed` keeps
+/// whether the list uses the BSD `DIGEST NAME` format with a single space, which GNU
+/// does not allow to be mixed with the usual one, since the names could then be read
+/// two ways.
+fn parse_entry(
+    line: &[u8],
+    algorithm: Algorithm,
+    size: Option<usize>,
+    reversed: &mut Option<bool>,
+) -> Option<Entry> {
+    let is_blank = |b: &u8| *b == b' ' || *b == b'\t';
+    let mut s = &line[line.iter().take_while(|b| is_blank(b)).count()..];
+    let escaped = s.first() == Some(&b'\\');
+    if escaped {
+        s = &s[1..];
+    }
+    let is_hex = |digits: &[u8]| digits.iter().all(u8::is_ascii_hexdigit);
+
+    let (digest, name) = if let Some(rest) = s.strip_prefix(algorithm.tag().as_bytes()) {
+        // The BSD format, `ALGORITHM (NAME) = DIGEST`.
+        let digits = rest
+            .iter()
+            .skip(1)
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+        let (tag_size, rest) = match rest.first() {
+            Some(b'-') if algorithm == Algorithm::Blake2b && digits > 0 => {
+                let bits: usize = std::str::from_utf8(&rest[1..=digits]).ok()?.parse().ok()?;
+                if !bits.is_multiple_of(8) || bits == 0 || bits > Blake2b::MAX_SIZE * 8 {
+                    return None;
+                }
+                (bits / 8, &rest[1 + digits..])
+            }
+            _ => (algorithm.default_size(), rest),
+        };
+        let rest = rest.strip_prefix(b" ").unwrap_or(rest);
+        let rest = rest.strip_prefix(b"(")?;
+        let close = rest.iter().rposition(|b| *b == b')')?;
+        let after = &rest[close + 1..];
+        let after = &after[after.iter().take_while(|b| is_blank(b)).count()..];
+        let after = after.strip_prefix(b"=")?;
+        let digest = &after[after.iter().take_while(|b| is_blank(b)).count()..];
+        if digest.len() != size.unwrap_or(tag_size) * 2 || !is_hex(digest) {
+            return None;
+        }
+        (digest, &rest[..close])
+    } else {
+        // `DIGEST  NAME`, `DIGEST *NAME`, or `DIGEST NAME`.
+        let len = match size {
+            Some(size) => size * 2,
+            None => s.iter().take_while(|b| b.is_ascii_hexdigit()).count(),
+        };
+        if len == 0 || !len.is_multiple_of(2) || len > algorithm.default_size() * 2 {
+            return None;
+        }
+        if s.len() <= len || !is_hex(&s[..len]) || !is_blank(&s[len]) {
+            return None;
+        }
+        let rest = &s[len + 1..];
+        let name =
+            if rest.len() == 1 || (rest.first() != Some(&b' ') && rest.first() != Some(&b'*')) {
+                if *reversed == Some(false) {
+                    return None;
+                }
+                *reversed = Some(true);
+                rest
+            } else if *reversed != Some(true) {
+                *reversed = Some(false);
+                &rest[1..]
+            } else {
+                rest
+            };
+        (&s[..len], name)
+    };
+
+    let name = if escaped {
+        unescape(name)?
+    } else {
+        name.to_vec()
+    };
+    if name.is_empty() {
+        return None;
+    }
+    Some(Entry {
+        digest: String::from_utf8(digest.to_ascii_lowercase()).ok()?,
+        name,
+    })
+}
+
+/// Undoes the escaping of a name: `\\` and `\n`. Any other escape is an error.
+fn unescape(name: &[u8]) -> Option<Vec<u8>> {
+    let mut unescaped = Vec::new();
+    let mut bytes = name.iter();
+    while let Some(b) = bytes.next() {
+        unescaped.push(match b {
+            b'\\' => match bytes.next()? {
+                b'\\' => b'\\',
+                b'n' => b'\n',
+                _ => return None,
+            },
+            b => *b,
+        });
+    }
+    Some(unescaped)
+}
+
+/// Checks the files listed in the checksum list at `path`. Returns whether all of them
+/// matched, with the exit status of GNU in mind: a list with nothing to check fails,
+/// and so does one with bad lines under `--strict`.
+fn check_list<W: Write>(
+    path: &str,
+    algorithm: Algorithm,
+    size: Option<usize>,
+    options: CheckOptions,
+    writer: &mut W,
+    reporter: &mut Reporter,
+) -> Result<bool, Error> {
+    let reader: Box<dyn Read> = if path == "-" {
+        Box::new(io::stdin())
+    } else {
+        Box::new(File::open(path).map_err(|e| Error::Open(path.to_string(), e))?)
+    };
+    let list_name = if path == "-" { "standard input" } else { path };
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    let mut reversed = None;
+    let (mut improper, mut unreadable, mut mismatched, mut found) = (0, 0, 0, false);
+    for number in 1.. {
+        line.clear();
+        let size_read = reader
+            .read_until(b'\n', &mut line)
+            .map_err(|e| Error::Read(path.to_string(), e))?;
+        if size_read == 0 {
+            break;
+        }
+        if line.last() == Some(&b'\n') {
+            line.pop();
+        }
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        // Blank lines and comments are passed over.
+        if line.is_empty() || line[0] == b'#' {
+            continue;
+        }
+        let entry = match parse_entry(&line, algorithm, size, &mut reversed) {
+            Some(entry) => entry,
+            None => {
+                improper += 1;
+                if options.warn {
+                    eprintln!(
+                        "{}: {}: {}: improperly formatted {} checksum line",
+                        algorithm.command(),
+                        list_name,
+                        number,
+                        algorithm.tag()
+                    );
+                }
+                continue;
+            }
+        };
+        found = true;
+
+        let name = OsStr::from_bytes(&entry.name)
+            .to_string_lossy()
+            .into_owned();
+        let result = digest_file(&name, algorithm.digest(entry.digest.len() / 2));
+        let verdict: &[u8] = match result {
+            Ok(digest) if digest::hex(&digest) == entry.digest => {
+                if options.quiet {
+                    continue;
+                }
+                b": OK\n"
+            }
+            Ok(_) => {
+                mismatched += 1;
+                b": FAILED\n"
+            }
+            Err(e) => {
+                reporter.report(&e);
+                unreadable += 1;
+                b": FAILED open or read\n"
+            }
+        };
+        if options.status {
+            continue;
+        }
+        // Names are escaped here only if they need it to stay on one line.
+        let mut out = Vec::new();
+        if entry.name.contains(&b'\n') {
+            out.push(b'\\');
+            for b in &entry.name {
+                match b {
+                    b'\n' => out.extend_from_slice(b"\\n"),
+                    b'\\' => out.extend_from_slice(b"\\\\"),
+                    b => out.push(*b),
+                }
+            }
+        } else {
+            out.extend_from_slice(&entry.name);
+        }
+        out.extend_from_slice(verdict);
+        writer.write_all(&out).map_err(Error::writing)?;
+    }
+
+    if !found {
+        reporter.fail(&format!(
+            "{}: no properly formatted checksum lines found",
+            list_name
+        ));
+        return Ok(false);
+    }
+    if !options.status {
+        let warn = |count: usize, one: &str, many: &str| {
+            if count > 0 {
+                let what = if count == 1 { one } else { many };
+                eprintln!("{}: WARNING: {} {}", algorithm.command(), count, what);
+            }
+        };
+        warn(
+            improper,
+            "line is improperly formatted",
+            "lines are improperly formatted",
+        );
+        warn(
+            unreadable,
+            "listed file could not be read",
+            "listed files could not be read",
+        );
+        warn(
+            mismatched,
+            "computed checksum did NOT match",
+            "computed checksums did NOT match",
+        );
+    }
+    Ok(mismatched == 0 && unreadable == 0 && !(options.strict && improper > 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABC_MD5: &str = "900150983cd24fb0d6963f7d28e17f72";
+
+    fn parse(line: &str, reversed: &mut Option<bool>) -> Option<(String, String)> {
+        parse_entry(line.as_bytes(), Algorithm::Md5, Some(16), reversed)
+            .map(|entry| (entry.digest, String::from_utf8(entry.name).unwrap()))
+    }
+
+    #[test]
+    fn test_format_line() {
+        let mut md5 = Md5::new();
+        md5.update(b"abc");
+        let digest = md5.finish();
+        let line = |name: &str, tag, binary| {
+            String::from_utf8(format_line(
+                Algorithm::Md5,
+                16,
+                &digest,
+                name.as_bytes(),
+                tag,
+                binary,
+            ))
+            .unwrap()
+        };
+        assert_eq!(line("a", false, false), format!("{}  a\n", ABC_MD5));
+        assert_eq!(line("a", false, true), format!("{} *a\n", ABC_MD5));
+        assert_eq!(line("a", true, false), format!("MD5 (a) = {}\n", ABC_MD5));
+        assert_eq!(
+            line("a\\b", false, false),
+            format!("\\{}  a\\\\b\n", ABC_MD5)
+        );
+        assert_eq!(
+            line("n\nl", false, false),
+            format!("\\{}  n\\nl\n", ABC_MD5)
+        );
+        let b2 = format_line(Algorithm::Blake2b, 32, &[0; 32], b"a", true, false);
+        assert!(b2.starts_with(b"BLAKE2b-256 (a) = 0000"));
+    }
+
+    #[test]
+    fn test_parse_entry() {
+        let expected = Some((ABC_MD5.to_string(), "a".to_string()));
+        for line in [
+            format!("{}  a", ABC_MD5),
+            format!("{} *a", ABC_MD5),
+            format!("  {}  a", ABC_MD5),
+            format!("{}  a", ABC_MD5.to_uppercase()),
+            format!("MD5 (a) = {}", ABC_MD5),
+            format!("MD5(a)= {}", ABC_MD5),
+            format!("\\{}  a", ABC_MD5),
+        ] {
+            assert_eq!(parse(&line, &mut None), expected, "{}", line);
+        }
+        assert_eq!(
+            parse(&format!("\\{}  a\\\\b\\nc", ABC_MD5), &mut None)
+                .unwrap()
+                .1,
+            "a\\b\nc"
+        );
+        assert_eq!(parse(&format!("\\{}  a\\q", ABC_MD5), &mut None), None);
+        assert_eq!(parse(&format!("{}a", ABC_MD5), &mut None), None);
+        assert_eq!(parse(&format!("{}  a", &ABC_MD5[1..]), &mut None), None);
+        assert_eq!(parse(&format!("SHA1 (a) = {}", ABC_MD5), &mut None), None);
+        assert_eq!(
+            parse(&format!("{} * a", ABC_MD5), &mut None).unwrap().1,
+            " a"
+        );
+
+        // The single-space format cannot follow the usual one, nor the other way round.
+        let mut reversed = None;
+        assert_eq!(parse(&format!("{}  a", ABC_MD5), &mut reversed), expected);
+        assert_eq!(parse(&format!("{} a", ABC_MD5), &mut reversed), None);
+        let mut reversed = None;
+        assert_eq!(parse(&format!("{} a", ABC_MD5), &mut reversed), expected);
+        assert_eq!(
+            parse(&format!("{}  a", ABC_MD5), &mut reversed).unwrap().1,
+            " a"
+        );
+
+        // Without a fixed size, BLAKE2b takes it from the line.
+        let entry = parse_entry(
+            b"BLAKE2b-16 (a) = abcd",
+            Algorithm::Blake2b,
+            None,
+            &mut None,
+        );
+        assert_eq!(entry.unwrap().digest, "abcd");
+        let entry = parse_entry(b"abcdef  a", Algorithm::Blake2b, None, &mut None);
+        assert_eq!(entry.unwrap().digest, "abcdef");
+        assert_eq!(
+            parse_entry(b"abcdef  a", Algorithm::Blake2b, Some(64), &mut None),
+            None
+        );
+    }
+}
@@ -0,0 +1,248 @@
+use crate::args::Command;
+use crate::error::{Error, Output, Reporter, Style};
+use crate::stdio;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::os::unix::fs::MetadataExt;
+use std::process::exit;
+
+/// Exit status for trouble, as opposed to a difference.
+const EXIT_TROUBLE: i32 = 2;
+
+const BUFFER_SIZE: usize = 1024 * 64;
+
+/// What to report of the differences.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Report {
+    /// The first differing byte and its line.
+    First,
+    /// Every differing byte with its values (`-l`).
+    All,
+    /// Nothing; only the exit status tells (`-s`).
+    Status,
+}
+
+pub fn main(args: Vec<String>) {
+    let command = Command::new("cmp", "[OPTION]... FILE1 [FILE2]")
+        .about("Compare two files byte by byte; a FILE of '-' or a missing FILE2 means standard input.")
+        .opt("-l, --verbose", "output byte numbers and differing byte values")
+        .opt("-s, --quiet, --silent", "suppress all normal output");
+    let matches = command.parse_or_exit(args);
+
+    let report = match (matches.flag("verbose"), matches.flag("quiet")) {
+        (true, true) => command.exit_with_usage_error(&"options -l and -s are incompatible"),
+        (true, false) => Report::All,
+        (false, true) => Report::Status,
+        (false, false) => Report::First,
+    };
+    let names = match matches.operands() {
+        [] => command.exit_with_usage_error(&"missing operand after 'cmp'"),
+        [a] => [a.as_str(), "-"],
+        [a, b] => [a.as_str(), b.as_str()],
+        [_, _, extra, ..] => command.exit_with_usage_error(&format!("extra operand '{}'", extra)),
+    };
+
+    let stdin = stdio::stdin_file();
+    let [a, b] = names.map(|name| {
+        let file = if name == "-" {
+            stdin.try_clone()
+        } else {
+            File::open(name)
+        };
+        file.map_err(|e| Error::Open(name.to_string(), e))
+    });
+    let (a, b) = match (a, b) {
+        (Ok(a), Ok(b)) => (a, b),
+        (Err(e), _) | (_, Err(e)) => trouble(report, &e),
+    };
+    let (meta_a, meta_b) = (a.metadata().ok(), b.metadata().ok());
+    if let (Some(meta_a), Some(meta_b)) = (&meta_a, &meta_b) {
+        if (meta_a.dev(), meta_a.ino()) == (meta_b.dev(), meta_b.ino()) {
+            exit(0);
+        }
+    }
+    // Byte numbers are padded to the width of the largest one possible.
+    let largest = [meta_a, meta_b]
+        .iter()
+        .flatten()
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .fold(i64::MAX as u64, u64::min);
+    let width = largest.to_string().len();
+
+    let mut writer = BufWriter::new(Output(io::stdout()));
+    let outcome = compare([(names[0], a), (names[1], b)], report, width, &mut writer)
+        .unwrap_or_else(|e| trouble(report, &e));
+    // Like GNU cmp, which buffers standard output, the message about the end of an input
+    // comes before the differences listed.
+    if let Outcome::Eof(message) = &outcome {
+        if report != Report::Status {
+            eprintln!("cmp: {}", message);
+        }
+    }
+    if let Err(e) = writer.flush() {
+        trouble(report, &Error::writing(e));
+    }
+    if outcome != Outcome::Same {
+        exit(1);
+    }
+}
+
+/// Reports `e` and exits. Being told to be silent silences only failures to open, as
+/// with GNU cmp.
+fn trouble(report: Report, e: &Error) -> ! {
+    if report != Report::Status || !matches!(e, Error::Open(..)) {
+        Reporter::new("cmp", Style::Bare).report(e);
+    }
+    exit(EXIT_TROUBLE);
+}
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Same,
+    Differ,
+    /// One input ended before the other, with the message saying where.
+    Eof(String),
+}
+
+fn compare<R: Read, W: Write>(
+    inputs: [(&str, R); 2],
+    report: Report,
+    width: usize,
+    writer: &mut W,
+) -> Result<Outcome, Error> {
+    let [(name_a, a), (name_b, b)] = inputs;
+    let mut a = BufReader::with_capacity(BUFFER_SIZE, a);
+    let mut b = BufReader::with_capacity(BUFFER_SIZE, b);
+    let mut offset = 0u64;
+    let mut lines = 0u64;
+    let mut at_line_start = true;
+    let mut differ = false;
+    loop {
+        let chunk_a = a
+            .fill_buf()
+            .map_err(|e| Error::Read(name_a.to_string(), e))?;
+        let chunk_b = b
+            .fill_buf()
+            .map_err(|e| Error::Read(name_b.to_string(), e))?;
+        let size = chunk_a.len().min(chunk_b.len());
+        if size == 0 {
+            let ended = match (chunk_a.is_empty(), chunk_b.is_empty()) {
+                (true, true) if differ => return Ok(Outcome::Differ),
+                (true, true) => return Ok(Outcome::Same),
+                (true, false) => name_a,
+                (false, _) => name_b,
+            };
+            let position = if offset == 0 {
+                "which is empty".to_string()
+            } else if report == Report::All {
+                format!("after byte {}", offset)
+            } else if at_line_start {
+                format!("after byte {}, line {}", offset, lines)
+            } else {
+                format!("after byte {}, in line {}", offset, lines + 1)
+            };
+            return Ok(Outcome::Eof(format!("EOF on {} {}", ended, position)));
+        }
+
+        let (chunk_a, chunk_b) = (&chunk_a[..size], &chunk_b[..size]);
+        for (i, (x, y)) in chunk_a.iter().zip(chunk_b).enumerate() {
+            if x == y {
+                continue;
+            }
+            match report {
+                Report::Status => return Ok(Outcome::Differ),
+                Report::First => {
+                    let line = lines + count_lines(&chunk_a[..i]) + 1;
+                    writeln!(
+                        writer,
+                        "{} {} differ: char {}, line {}",
+                        name_a,
+                        name_b,
+                        offset + i as u64 + 1,
+                        line
+                    )
+                    .map_err(Error::writing)?;
+                    return Ok(Outcome::Differ);
+                }
+                Report::All => {
+                    differ = true;
+                    let number = offset + i as u64 + 1;
+                    writeln!(
+                        writer,
+                        "{:>width$} {:3o} {:3o}",
+                        number,
+                        x,
+                        y,
+                        width = width
+                    )
+                    .map_err(Error::writing)?;
+                }
+            }
+        }
+        lines += count_lines(chunk_a);
+        at_line_start = chunk_a[size - 1] == b'\n';
+        offset += size as u64;
+        a.consume(size);
+        b.consume(size);
+    }
+}
+
+fn count_lines(bytes: &[u8]) -> u64 {
+    bytes.iter().filter(|&&b| b == b'\n').count() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cmp(a: &str, b: &str, report: Report) -> (Outcome, String) {
+        let mut output = Vec::new();
+        let outcome = compare(
+            [("a", a.as_bytes()), ("b", b.as_bytes())],
+            report,
+            2,
+            &mut output,
+        )
+        .unwrap();
+        (outcome, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(cmp("ab\ncd", "ab\ncd", Report::First).0, Outcome::Same);
+        assert_eq!(
+            cmp("ab\ncd", "ab\nxd", Report::First),
+            (Outcome::Differ, "a b differ: char 4, line 2\n".to_string())
+        );
+        assert_eq!(
+            cmp("ab\ncd", "xb\nxe", Report::All),
+            (
+                Outcome::Differ,
+                " 1 141 170\n 4 143 170\n 5 144 145\n".to_string()
+            )
+        );
+        assert_eq!(
+            cmp("ab", "xb", Report::Status),
+            (Outcome::Differ, String::new())
+        );
+    }
+
+    #[test]
+    fn test_eof() {
+        let eof = |a, b, report| match cmp(a, b, report).0 {
+            Outcome::Eof(message) => message,
+            outcome => panic!("{:?}", outcome),
+        };
+        assert_eq!(eof("", "a", Report::First), "EOF on a which is empty");
+        assert_eq!(
+            eof("a\n", "a\nb", Report::First),
+            "EOF on a after byte 2, line 1"
+        );
+        assert_eq!(
+            eof("a\nbc", "a", Report::First),
+            "EOF on b after byte 1, in line 1"
+        );
+        assert_eq!(eof("a\nb", "a\nbc", Report::All), "EOF on a after byte 3");
+    }
+}
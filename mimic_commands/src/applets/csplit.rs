@@ -0,0 +1,832 @@
+use crate::args::Command;
+use crate::error::{self, Error, Output};
+use crate::regex::{Regex, RegexBuilder, Syntax};
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::process::exit;
+
+/// One pattern operand, with any repeat count that followed it.
+#[derive(Debug)]
+struct Step {
+    pattern: Pattern,
+    /// The operand as given, for messages.
+    text: String,
+    repeat: Repeat,
+}
+
+#[derive(Debug)]
+enum Pattern {
+    /// `N`: the piece ends before line N.
+    Line(u64),
+    /// `/REGEX/OFFSET`: the piece ends before the next matching line, moved by the
+    /// offset. With `%REGEX%OFFSET` the lines are skipped instead of written.
+    Regex {
+        regex: Box<Regex>,
+        offset: i64,
+        skip: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Repeat {
+    /// `{N}`: the pattern is used N more times.
+    Times(u64),
+    /// `{*}`: the pattern is used for as long as it matches.
+    Forever,
+}
+
+pub fn main(args: Vec<String>) {
+    let command = Command::new("csplit", "[OPTION]... FILE PATTERN...")
+        .about("Output pieces of FILE separated by PATTERN(s) to files 'xx00', 'xx01', ..., and output byte counts of each piece to standard output.")
+        .opt(
+            "-b, --suffix-format=FORMAT",
+            "use sprintf FORMAT instead of %02d",
+        )
+        .opt("-f, --prefix=PREFIX", "use PREFIX instead of 'xx'")
+        .opt("-k, --keep-files", "do not remove output files on errors")
+        .opt(
+            "-n, --digits=DIGITS",
+            "use specified number of digits instead of 2",
+        )
+        .opt(
+            "-s, --quiet, --silent",
+            "do not print counts of output file sizes",
+        )
+        .opt("-z, --elide-empty-files", "remove empty output files");
+    let matches = command.parse_or_exit(args);
+
+    let (input, patterns) = match matches.operands() {
+        [] => command.exit_with_usage_error(&"missing operand"),
+        [input] => command.exit_with_usage_error(&format!("missing operand after '{}'", input)),
+        [input, patterns @ ..] => (input, patterns),
+    };
+    let digits = match matches.value("digits") {
+        Some(value) => parse_digits(value).unwrap_or_else(|message| die(&message)),
+        None => 2,
+    };
+    let suffix = match matches.value("suffix-format") {
+        Some(format) => SuffixFormat::parse(format).unwrap_or_else(|message| die(&message)),
+        None => SuffixFormat::digits(digits),
+    };
+    let steps = parse_steps(patterns).unwrap_or_else(|message| die(&message));
+
+    let reader: Box<dyn Read> = if input == "-" {
+        Box::new(io::stdin())
+    } else {
+        match File::open(input) {
+            Ok(file) => Box::new(file),
+            Err(e) => die(&Error::Open(input.to_string(), e).message(error::Style::Verbose)),
+        }
+    };
+    let mut csplit = Csplit {
+        input: Lines::new(reader),
+        current: 0,
+        pieces: Pieces {
+            prefix: matches.value("prefix").unwrap_or("xx").to_string(),
+            suffix,
+            elide_empty: matches.flag("elide-empty-files"),
+            quiet: matches.flag("quiet"),
+            created: Vec::new(),
+            open: None,
+            sizes: BufWriter::new(Output(io::stdout())),
+        },
+    };
+    let result = csplit.run(&steps);
+    if let Err(message) = &result {
+        eprintln!("csplit: {}", message);
+        // What was written so far is counted, then thrown away unless it is to be kept.
+        csplit.pieces.close().ok();
+        if !matches.flag("keep-files") {
+            for name in &csplit.pieces.created {
+                fs::remove_file(name).ok();
+            }
+        }
+    }
+    // The sizes come out together at the end, as they do from GNU csplit when its
+    // output is not a terminal.
+    if let Err(e) = csplit.pieces.sizes.flush() {
+        die(&Error::writing(e).message(error::Style::Bare));
+    }
+    if result.is_err() {
+        exit(1);
+    }
+}
+
+fn die(message: &dyn std::fmt::Display) -> ! {
+    eprintln!("csplit: {}", message);
+    exit(1);
+}
+
+/// Parses the pattern operands, each of which may be followed by a repeat count.
+fn parse_steps(patterns: &[String]) -> Result<Vec<Step>, String> {
+    let mut steps: Vec<Step> = Vec::new();
+    let mut last_line = 0;
+    for text in patterns {
+        if let Some(count) = text.strip_prefix('{') {
+            let repeat = match count.strip_suffix('}') {
+                Some("*") => Some(Repeat::Forever),
+                Some(count) if count.bytes().all(|b| b.is_ascii_digit()) => {
+                    count.parse().ok().map(Repeat::Times)
+                }
+                _ => None,
+            };
+            let repeat = repeat.ok_or_else(|| {
+                // GNU csplit words this one oddly, and it is kept for scripts that match it.
+                format!(
+                    "'{}'}}: integer required between '{{' and '}}'",
+                    text.strip_suffix('}').unwrap_or(text)
+                )
+            })?;
+            match steps.last_mut() {
+                Some(step) if step.repeat == Repeat::Times(0) => step.repeat = repeat,
+                _ => return Err(format!("'{}': invalid pattern", text)),
+            }
+            continue;
+        }
+        let pattern = if let Some(delimiter) = text.chars().next().filter(|c| "/%".contains(*c)) {
+            // The last delimiter ends the regex, so the regex itself may contain one.
+            let end = match text[1..].rfind(delimiter) {
+                Some(end) => end + 1,
+                None => {
+                    return Err(format!(
+                        "{}: closing delimiter '{}' missing",
+                        text, delimiter
+                    ))
+                }
+            };
+            let offset = match &text[end + 1..] {
+                "" => 0,
+                offset => parse_offset(offset)
+                    .ok_or_else(|| format!("'{}': integer expected after delimiter", text))?,
+            };
+            let regex = RegexBuilder::new(Syntax::Basic)
+                .build(&[&text[1..end]])
+                .map_err(|e| format!("'{}': invalid regular expression: {}", text, e))?;
+            Pattern::Regex {
+                regex: Box::new(regex),
+                offset,
+                skip: delimiter == '%',
+            }
+        } else {
+            let digits = text.strip_prefix('+').unwrap_or(text);
+            let line: u64 = Some(digits)
+                .filter(|digits| digits.bytes().all(|b| b.is_ascii_digit()))
+                .and_then(|digits| digits.parse().ok())
+                .ok_or_else(|| format!("'{}': invalid pattern", text))?;
+            if line == 0 {
+                return Err(format!("{}: line number must be greater than zero", text));
+            }
+            if line < last_line {
+                return Err(format!(
+                    "line number '{}' is smaller than preceding line number, {}",
+                    text, last_line
+                ));
+            }
+            if line == last_line {
+                eprintln!(
+                    "csplit: warning: line number '{}' is the same as preceding line number",
+                    text
+                );
+            }
+            last_line = line;
+            Pattern::Line(line)
+        };
+        steps.push(Step {
+            pattern,
+            text: text.clone(),
+            repeat: Repeat::Times(0),
+        });
+    }
+    Ok(steps)
+}
+
+/// Parses the number of digits in a suffix, which GNU csplit holds to an `int`.
+fn parse_digits(value: &str) -> Result<usize, String> {
+    let digits = value.strip_prefix(['+', '-']).unwrap_or(value);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("invalid number: '{}'", value));
+    }
+    match value.parse::<i32>() {
+        Ok(n) if n >= 0 => Ok(n as usize),
+        _ if value.starts_with('-') => Err(format!(
+            "invalid number: '{}': Numerical result out of range",
+            value
+        )),
+        _ => Err(format!(
+            "invalid number: '{}': Value too large for defined data type",
+            value
+        )),
+    }
+}
+
+/// Parses an offset such as `+2` or `-1`.
+fn parse_offset(text: &str) -> Option<i64> {
+    let digits = text.strip_prefix(['+', '-']).unwrap_or(text);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+/// The file name suffix: a printf format with a single integer conversion, such as the
+/// default `%02d`.
+#[derive(Debug, PartialEq)]
+struct SuffixFormat {
+    before: String,
+    after: String,
+    /// Pad with spaces on the right rather than the left (`-`).
+    left: bool,
+    /// Pad with zeros rather than spaces (`0`).
+    zeros: bool,
+    /// Mark octal and hex numbers with `0` or `0x` (`#`).
+    alternate: bool,
+    width: usize,
+    precision: Option<usize>,
+    conversion: char,
+}
+
+impl SuffixFormat {
+    /// `%0Nd`.
+    fn digits(digits: usize) -> Self {
+        SuffixFormat {
+            before: String::new(),
+            after: String::new(),
+            left: false,
+            zeros: true,
+            alternate: false,
+            width: digits,
+            precision: None,
+            conversion: 'd',
+        }
+    }
+
+    fn parse(format: &str) -> Result<Self, String> {
+        let mut parsed = None;
+        let mut literal = String::new();
+        let mut chars = format.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                literal.push(c);
+                continue;
+            }
+            if chars.peek() == Some(&'%') {
+                literal.push(chars.next().unwrap_or('%'));
+                continue;
+            }
+            if parsed.is_some() {
+                return Err("too many % conversion specifications in suffix".to_string());
+            }
+            let mut spec = SuffixFormat::digits(0);
+            spec.zeros = false;
+            spec.before = std::mem::take(&mut literal);
+            while let Some(&flag) = chars.peek() {
+                match flag {
+                    '-' => spec.left = true,
+                    '0' => spec.zeros = true,
+                    '#' => spec.alternate = true,
+                    // Digit grouping does nothing to a file number.
+                    '\'' => {}
+                    _ => break,
+                }
+                chars.next();
+            }
+            let number = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+                let mut number = 0usize;
+                while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                    number = number.saturating_mul(10).saturating_add(digit as usize);
+                    chars.next();
+                }
+                number
+            };
+            spec.width = number(&mut chars);
+            if chars.peek() == Some(&'.') {
+                chars.next();
+                spec.precision = Some(number(&mut chars));
+            }
+            match chars.next() {
+                Some(conversion) if "diuoxX".contains(conversion) => spec.conversion = conversion,
+                Some(conversion) => {
+                    return Err(format!(
+                        "invalid conversion specifier in suffix: {}",
+                        conversion
+                    ))
+                }
+                None => return Err("too many % conversion specifications in suffix".to_string()),
+            }
+            // GNU csplit cannot make room for a name as long as this.
+            if spec.width > i32::MAX as usize || spec.precision > Some(i32::MAX as usize) {
+                return Err("memory exhausted".to_string());
+            }
+            parsed = Some(spec);
+        }
+        let mut spec =
+            parsed.ok_or_else(|| "missing % conversion specification in suffix".to_string())?;
+        spec.after = literal;
+        Ok(spec)
+    }
+
+    fn format(&self, n: usize) -> String {
+        let mut digits = match self.conversion {
+            'o' => format!("{:o}", n),
+            'x' => format!("{:x}", n),
+            'X' => format!("{:X}", n),
+            _ => n.to_string(),
+        };
+        match self.precision {
+            // As in C, no digits at all are shown for zero with a precision of zero.
+            Some(0) if n == 0 => digits.clear(),
+            Some(precision) if precision > digits.len() => {
+                digits.insert_str(0, &"0".repeat(precision - digits.len()))
+            }
+            _ => {}
+        }
+        if self.alternate {
+            match self.conversion {
+                'o' if !digits.starts_with('0') => digits.insert(0, '0'),
+                'x' if n != 0 => digits.insert_str(0, "0x"),
+                'X' if n != 0 => digits.insert_str(0, "0X"),
+                _ => {}
+            }
+        }
+        let pad = self.width.saturating_sub(digits.len());
+        let number = if self.left {
+            digits + &" ".repeat(pad)
+        } else if self.zeros && self.precision.is_none() {
+            "0".repeat(pad) + &digits
+        } else {
+            " ".repeat(pad) + &digits
+        };
+        format!("{}{}{}", self.before, number, self.after)
+    }
+}
+
+/// Why splitting stopped early.
+#[derive(Debug, PartialEq)]
+enum Failure {
+    /// A message for standard error; the pieces are removed.
+    Message(String),
+    /// `{*}` ran out of input: not a failure, but nothing more is to be done.
+    Done,
+}
+
+impl From<io::Error> for Failure {
+    fn from(e: io::Error) -> Self {
+        Failure::Message(match Error::writing(e) {
+            Error::Write(e) => format!("write error: {}", error::describe(&e)),
+            e => e.message(error::Style::Bare),
+        })
+    }
+}
+
+/// The input lines, read on demand and held until they are taken.
+struct Lines<R> {
+    reader: BufReader<R>,
+    held: VecDeque<Vec<u8>>,
+    /// The number of the first line held, or of the next to be read if none are.
+    first: u64,
+}
+
+impl<R: Read> Lines<R> {
+    fn new(reader: R) -> Self {
+        Lines {
+            reader: BufReader::new(reader),
+            held: VecDeque::new(),
+            first: 1,
+        }
+    }
+
+    /// Line `number`, which must not have been taken yet, or `None` past the end.
+    fn get(&mut self, number: u64) -> Result<Option<&[u8]>, Failure> {
+        let index = (number - self.first) as usize;
+        while self.held.len() <= index {
+            let mut line = Vec::new();
+            let read = self.reader.read_until(b'\n', &mut line);
+            if read.map_err(|e| Failure::Message(format!("read error: {}", error::describe(&e))))?
+                == 0
+            {
+                return Ok(None);
+            }
+            self.held.push_back(line);
+        }
+        Ok(Some(&self.held[index]))
+    }
+
+    /// Takes the first line that is left.
+    fn take(&mut self) -> Result<Option<Vec<u8>>, Failure> {
+        if self.get(self.first)?.is_none() {
+            return Ok(None);
+        }
+        self.first += 1;
+        Ok(self.held.pop_front())
+    }
+
+    fn at_end(&mut self) -> Result<bool, Failure> {
+        Ok(self.get(self.first)?.is_none())
+    }
+}
+
+/// The output files, created one at a time.
+struct Pieces<W: Write> {
+    prefix: String,
+    suffix: SuffixFormat,
+    elide_empty: bool,
+    quiet: bool,
+    /// Every file created so far, to be removed if splitting fails.
+    created: Vec<String>,
+    /// The file being written, with its size so far.
+    open: Option<(BufWriter<File>, u64)>,
+    /// Where the size of each piece is printed.
+    sizes: W,
+}
+
+impl<W: Write> Pieces<W> {
+    fn create(&mut self) -> Result<(), Failure> {
+        let name = format!("{}{}", self.prefix, self.suffix.format(self.created.len()));
+        let file = File::create(&name).map_err(|e| {
+            Failure::Message(Error::Open(name.clone(), e).message(error::Style::Bare))
+        })?;
+        self.created.push(name);
+        self.open = Some((BufWriter::new(file), 0));
+        Ok(())
+    }
+
+    fn write(&mut self, line: &[u8]) -> Result<(), Failure> {
+        if let Some((writer, size)) = &mut self.open {
+            if let Err(e) = writer.write_all(line) {
+                return Err(self.write_failure(e));
+            }
+            *size += line.len() as u64;
+        }
+        Ok(())
+    }
+
+    /// Finishes the file being written, printing its size or, if it is empty and empty
+    /// files are elided, removing it so that the next file takes its name.
+    fn close(&mut self) -> Result<(), Failure> {
+        let (mut writer, size) = match self.open.take() {
+            Some(open) => open,
+            None => return Ok(()),
+        };
+        if let Err(e) = writer.flush() {
+            return Err(self.write_failure(e));
+        }
+        if self.elide_empty && size == 0 {
+            if let Some(name) = self.created.pop() {
+                fs::remove_file(name).ok();
+            }
+        } else if !self.quiet {
+            writeln!(self.sizes, "{}", size)?;
+        }
+        Ok(())
+    }
+
+    fn write_failure(&self, e: io::Error) -> Failure {
+        let name = self.created.last().map_or("", String::as_str);
+        Failure::Message(format!("{}: {}", name, error::describe(&e)))
+    }
+}
+
+/// The state of a split.
+struct Csplit<R, W: Write> {
+    input: Lines<R>,
+    /// The number of the last line a regex was matched against or taken, the next search
+    /// starting after it. It can be ahead of the lines taken when an offset is negative.
+    current: u64,
+    pieces: Pieces<W>,
+}
+
+impl<R: Read, W: Write> Csplit<R, W> {
+    /// Splits the input at each step in turn, and the rest goes in a last piece.
+    fn run(&mut self, steps: &[Step]) -> Result<(), String> {
+        match self.split(steps) {
+            Ok(()) | Err(Failure::Done) => Ok(()),
+            Err(Failure::Message(message)) => Err(message),
+        }
+    }
+
+    fn split(&mut self, steps: &[Step]) -> Result<(), Failure> {
+        for step in steps {
+            let mut repetition = 0;
+            while match step.repeat {
+                Repeat::Times(times) => repetition <= times,
+                Repeat::Forever => true,
+            } {
+                match &step.pattern {
+                    Pattern::Line(line) => {
+                        self.split_at_line(step, line * (repetition + 1), repetition)?
+                    }
+                    Pattern::Regex {
+                        regex,
+                        offset,
+                        skip,
+                    } => self.split_at_match(step, regex, *offset, *skip, repetition)?,
+                }
+                repetition += 1;
+            }
+        }
+        self.pieces.create()?;
+        self.write_rest()?;
+        self.pieces.close()
+    }
+
+    /// Writes a piece ending before line `line`. The input must not end before that
+    /// line, so that every pattern leaves something for the last piece.
+    fn split_at_line(&mut self, step: &Step, line: u64, repetition: u64) -> Result<(), Failure> {
+        self.pieces.create()?;
+        if self.input.at_end()? {
+            return Err(out_of_range(step, repetition));
+        }
+        while self.input.first < line {
+            match self.take()? {
+                Some(taken) => self.pieces.write(&taken)?,
+                None => return Err(out_of_range(step, repetition)),
+            }
+        }
+        self.pieces.close()?;
+        if self.input.at_end()? {
+            return Err(out_of_range(step, repetition));
+        }
+        Ok(())
+    }
+
+    /// Writes, or skips, a piece ending before the next line to match `regex`, moved by
+    /// `offset`.
+    fn split_at_match(
+        &mut self,
+        step: &Step,
+        regex: &Regex,
+        offset: i64,
+        skip: bool,
+        repetition: u64,
+    ) -> Result<(), Failure> {
+        if !skip {
+            self.pieces.create()?;
+        }
+        // Lines further back than a negative offset can reach are done with as the search
+        // passes them.
+        let held = offset.min(0).unsigned_abs();
+        loop {
+            self.current += 1;
+            while self.input.first + held < self.current {
+                let taken = self.take()?.unwrap_or_default();
+                if !skip {
+                    self.pieces.write(&taken)?;
+                }
+            }
+            let line = match self.input.get(self.current)? {
+                Some(line) => line,
+                None if step.repeat == Repeat::Forever => {
+                    if !skip {
+                        self.write_rest()?;
+                        self.pieces.close()?;
+                    }
+                    return Err(Failure::Done);
+                }
+                None => {
+                    let message = format!("'{}': match not found{}", step.text, on(repetition));
+                    if !skip {
+                        self.write_rest()?;
+                    }
+                    return Err(Failure::Message(message));
+                }
+            };
+            if regex.is_match(line.strip_suffix(b"\n").unwrap_or(line)) {
+                break;
+            }
+        }
+        let end = self.current as i64 + offset;
+        if end < self.input.first as i64 {
+            return Err(out_of_range(step, 0));
+        }
+        while (self.input.first as i64) < end {
+            match self.take()? {
+                Some(taken) if !skip => self.pieces.write(&taken)?,
+                Some(_) => {}
+                None => return Err(out_of_range(step, 0)),
+            }
+        }
+        if !skip {
+            self.pieces.close()?;
+        }
+        if offset > 0 {
+            self.current = end as u64;
+        }
+        Ok(())
+    }
+
+    /// Takes the next line; a regex search never goes back before it.
+    fn take(&mut self) -> Result<Option<Vec<u8>>, Failure> {
+        let line = self.input.take()?;
+        if line.is_some() {
+            self.current = self.current.max(self.input.first - 1);
+        }
+        Ok(line)
+    }
+
+    fn write_rest(&mut self) -> Result<(), Failure> {
+        while let Some(line) = self.take()? {
+            self.pieces.write(&line)?;
+        }
+        Ok(())
+    }
+}
+
+fn out_of_range(step: &Step, repetition: u64) -> Failure {
+    Failure::Message(format!(
+        "'{}': line number out of range{}",
+        step.text,
+        on(repetition)
+    ))
+}
+
+fn on(repetition: u64) -> String {
+    match repetition {
+        0 => String::new(),
+        repetition => format!(" on repetition {}", repetition),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Splits `input` into files in a scratch directory, returning the size lines and
+    /// the pieces, or the failure.
+    fn run(
+        input: &str,
+        patterns: &[&str],
+        elide_empty: bool,
+    ) -> Result<(String, Vec<String>), String> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let dir = env::temp_dir().join(format!(
+            "mimic_csplit_test_{}_{}",
+            process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+        let steps = parse_steps(&patterns)?;
+        let mut csplit = Csplit {
+            input: Lines::new(input.as_bytes()),
+            current: 0,
+            pieces: Pieces {
+                prefix: dir.join("xx").display().to_string(),
+                suffix: SuffixFormat::digits(2),
+                elide_empty,
+                quiet: false,
+                created: Vec::new(),
+                open: None,
+                sizes: Vec::new(),
+            },
+        };
+        let result = csplit.run(&steps);
+        let pieces = csplit
+            .pieces
+            .created
+            .iter()
+            .map(|name| fs::read_to_string(name).unwrap().replace('\n', " "))
+            .collect();
+        fs::remove_dir_all(&dir).unwrap();
+        result.map(|_| (String::from_utf8(csplit.pieces.sizes).unwrap(), pieces))
+    }
+
+    fn pieces(input: &str, patterns: &[&str]) -> Vec<String> {
+        run(input, patterns, false).unwrap().1
+    }
+
+    const TEN: &str = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+
+    #[test]
+    fn test_lines() {
+        assert_eq!(
+            run(TEN, &["3", "7"], false).unwrap(),
+            (
+                "4\n8\n9\n".to_string(),
+                vec![
+                    "1 2 ".to_string(),
+                    "3 4 5 6 ".to_string(),
+                    "7 8 9 10 ".to_string()
+                ]
+            )
+        );
+        assert_eq!(
+            pieces(TEN, &["3", "{2}"]),
+            ["1 2 ", "3 4 5 ", "6 7 8 ", "9 10 "]
+        );
+        assert_eq!(pieces(TEN, &["1"]), ["", "1 2 3 4 5 6 7 8 9 10 "]);
+        assert_eq!(pieces(TEN, &["1"]).len(), 2);
+        assert_eq!(
+            run(TEN, &["1", "2"], true).unwrap().1,
+            ["1 ", "2 3 4 5 6 7 8 9 10 "]
+        );
+        assert_eq!(
+            run(TEN, &["11"], false),
+            Err("'11': line number out of range".to_string())
+        );
+        assert_eq!(
+            run(TEN, &["3", "{*}"], false),
+            Err("'3': line number out of range on repetition 3".to_string())
+        );
+    }
+
+    #[test]
+    fn test_regex() {
+        assert_eq!(
+            pieces(TEN, &["/[2468]/", "{*}"]),
+            ["1 ", "2 3 ", "4 5 ", "6 7 ", "8 9 10 "]
+        );
+        assert_eq!(pieces(TEN, &["/5/+2"]), ["1 2 3 4 5 6 ", "7 8 9 10 "]);
+        assert_eq!(pieces(TEN, &["/5/-2", "%8%"]), ["1 2 ", "8 9 10 "]);
+        assert_eq!(
+            pieces(TEN, &["/5/-1", "/6/"]),
+            ["1 2 3 ", "4 5 ", "6 7 8 9 10 "]
+        );
+        assert_eq!(pieces(TEN, &["/8/+3"]), ["1 2 3 4 5 6 7 8 9 10 ", ""]);
+        assert_eq!(
+            pieces(TEN, &["4", "/4/"]),
+            ["1 2 3 ", "", "4 5 6 7 8 9 10 "]
+        );
+        assert_eq!(pieces(TEN, &["/2/", "%[0-9]%", "{*}"]), ["1 "]);
+        assert_eq!(
+            run(TEN, &["/5/", "/5/"], false),
+            Err("'/5/': match not found".to_string())
+        );
+        assert_eq!(
+            run(TEN, &["/3/-5"], false),
+            Err("'/3/-5': line number out of range".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_steps() {
+        let parse = |patterns: &[&str]| {
+            let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+            parse_steps(&patterns).map(|steps| steps.len())
+        };
+        assert_eq!(parse(&["/a/b/+3", "{*}", "%c%-1", "+4"]), Ok(3));
+        assert_eq!(
+            parse(&["0"]),
+            Err("0: line number must be greater than zero".to_string())
+        );
+        assert_eq!(
+            parse(&["/x"]),
+            Err("/x: closing delimiter '/' missing".to_string())
+        );
+        assert_eq!(parse(&["{3}"]), Err("'{3}': invalid pattern".to_string()));
+        assert_eq!(
+            parse(&["3", "{2}", "{3}"]),
+            Err("'{3}': invalid pattern".to_string())
+        );
+        assert_eq!(
+            parse(&["/x/z"]),
+            Err("'/x/z': integer expected after delimiter".to_string())
+        );
+        assert_eq!(
+            parse(&["3", "{x}"]),
+            Err("'{x'}: integer required between '{' and '}'".to_string())
+        );
+        assert!(parse(&["5", "3"]).is_err());
+    }
+
+    #[test]
+    fn test_suffix_format() {
+        let format = |spec: &str, n| SuffixFormat::parse(spec).map(|f| f.format(n));
+        assert_eq!(SuffixFormat::digits(3).format(7), "007");
+        assert_eq!(format("%03x.txt", 26), Ok("01a.txt".to_string()));
+        assert_eq!(format("%5.3d", 1), Ok("  001".to_string()));
+        assert_eq!(format("%-4x|", 1), Ok("1   |".to_string()));
+        assert_eq!(format("%#o", 1), Ok("01".to_string()));
+        assert_eq!(format("%%%d", 1), Ok("%1".to_string()));
+        assert_eq!(format("%.0u", 0), Ok("".to_string()));
+        assert!(format("x", 0).is_err());
+        assert!(format("%d%d", 0).is_err());
+        assert!(format("%d%", 0).is_err());
+        assert!(format("%s", 0).is_err());
+        assert_eq!(
+            format("%99999999999999999999d", 0),
+            Err("memory exhausted".to_string())
+        );
+        assert_eq!(
+            format("%.2147483648d", 0),
+            Err("memory exhausted".to_string())
+        );
+        assert_eq!(parse_digits("+3"), Ok(3));
+        assert_eq!(parse_digits("x"), Err("invalid number: 'x'".to_string()));
+        assert_eq!(
+            parse_digits("-1"),
+            Err("invalid number: '-1': Numerical result out of range".to_string())
+        );
+        assert_eq!(
+            parse_digits("18446744073709551615"),
+            Err(
+                "invalid number: '18446744073709551615': Value too large for defined data type"
+                    .to_string()
+            )
+        );
+    }
+}
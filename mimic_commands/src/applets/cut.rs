@@ -0,0 +1,491 @@
+use crate::args::Command;
+use crate::error::{Error, Output, Reporter, Style};
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Read, Write};
+
+/// What the numbers in the list count.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Unit {
+    Bytes,
+    Chars,
+    Fields,
+}
+
+pub fn main(args: Vec<String>) {
+    let command = Command::new("cut", "OPTION... [FILE]...")
+        .about("Print selected parts of lines from each FILE to standard output.")
+        .opt("-b, --bytes=LIST", "select only these bytes")
+        .opt("-c, --characters=LIST", "select only these characters")
+        .opt(
+            "-d, --delimiter=DELIM",
+            "use DELIM instead of TAB for field delimiter",
+        )
+        .opt(
+            "-f, --fields=LIST",
+            "select only these fields; also print any line that contains no delimiter character, unless the -s option is specified",
+        )
+        .opt("-n", "(ignored)")
+        .opt(
+            "--complement",
+            "complement the set of selected bytes, characters or fields",
+        )
+        .opt(
+            "-s, --only-delimited",
+            "do not print lines not containing delimiters",
+        )
+        .opt(
+            "--output-delimiter=STRING",
+            "use STRING as the output delimiter; the default is to use the input delimiter",
+        )
+        .usage_status(1);
+    let matches = command.parse_or_exit(args);
+
+    let units = [
+        ("bytes", Unit::Bytes),
+        ("characters", Unit::Chars),
+        ("fields", Unit::Fields),
+    ];
+    let mut given = units.iter().filter(|(key, _)| matches.value(key).is_some());
+    let (key, unit) = match (given.next(), given.next()) {
+        (Some((key, unit)), None) => (*key, *unit),
+        (Some(_), Some(_)) => command.exit_with_usage_error(&"only one list may be specified"),
+        (None, _) => command
+            .exit_with_usage_error(&"you must specify a list of bytes, characters, or fields"),
+    };
+    let list = matches.value(key).unwrap_or_default();
+    let selection = Selection::parse(list, unit == Unit::Fields, matches.flag("complement"))
+        .unwrap_or_else(|message| command.exit_with_usage_error(&message));
+
+    if unit != Unit::Fields && matches.value("delimiter").is_some() {
+        command.exit_with_usage_error(
+            &"an input delimiter may be specified only when operating on fields",
+        );
+    }
+    let delimiter = match matches.value("delimiter") {
+        // An empty delimiter is the NUL byte, as in GNU cut.
+        Some(delimiter) => match delimiter.as_bytes() {
+            [] => b'\0',
+            [delimiter] => *delimiter,
+            _ => command.exit_with_usage_error(&"the delimiter must be a single character"),
+        },
+        None => b'\t',
+    };
+    let only_delimited = matches.flag("only-delimited");
+    if only_delimited && unit != Unit::Fields {
+        command.exit_with_usage_error(
+            &"suppressing non-delimited lines makes sense\n\tonly when operating on fields",
+        );
+    }
+    let options = Options {
+        unit,
+        selection,
+        delimiter,
+        output_delimiter: matches.value("output-delimiter").map_or_else(
+            || match unit {
+                Unit::Fields => vec![delimiter],
+                _ => Vec::new(),
+            },
+            |s| s.as_bytes().to_vec(),
+        ),
+        only_delimited,
+    };
+
+    let mut reporter = Reporter::new("cut", Style::Bare);
+    let mut writer = BufWriter::new(Output(io::stdout()));
+    for path in matches.files() {
+        let reader: Box<dyn Read> = if path == "-" {
+            Box::new(io::stdin())
+        } else {
+            match File::open(&path) {
+                Ok(file) => Box::new(file),
+                Err(e) => {
+                    reporter.report(&Error::Open(path, e));
+                    continue;
+                }
+            }
+        };
+        // Flushed per file so the output stays in order with the error messages.
+        let result = cut(reader, &mut writer, &options).and_then(|_| writer.flush());
+        reporter.check(result.map_err(|e| Error::copying(&path, e)));
+    }
+    reporter.finish();
+}
+
+/// The numbers selected by a list such as `1,3-5,7-`, counted from 1.
+#[derive(Debug, Clone, PartialEq)]
+struct Selection {
+    /// The inclusive ranges selected, sorted and without overlaps. A range open at the
+    /// end goes up to `usize::MAX`. Where each starts is where `--output-delimiter` goes.
+    ranges: Vec<(usize, usize)>,
+}
+
+impl Selection {
+    /// Parses a list the way GNU cut does, with its error messages.
+    fn parse(list: &str, fields: bool, complement: bool) -> Result<Self, String> {
+        let numbered_from_1 = if fields {
+            "fields are numbered from 1"
+        } else {
+            "byte/character positions are numbered from 1"
+        };
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        let (mut value, mut initial) = (0usize, 0usize);
+        let (mut lhs, mut rhs, mut dash) = (false, false, false);
+        let bytes = list.as_bytes();
+        for (i, c) in bytes.iter().copied().chain(Some(b'\0')).enumerate() {
+            match c {
+                b'-' => {
+                    if dash {
+                        return Err(if fields {
+                            "invalid field range"
+                        } else {
+                            "invalid byte or character range"
+                        }
+                        .to_string());
+                    }
+                    dash = true;
+                    if lhs && value == 0 {
+                        return Err(numbered_from_1.to_string());
+                    }
+                    initial = if lhs { value } else { 1 };
+                    value = 0;
+                }
+                b',' | b' ' | b'\t' | b'\0' => {
+                    if dash {
+                        dash = false;
+                        if !lhs && !rhs {
+                            return Err("invalid range with no endpoint: -".to_string());
+                        }
+                        if !rhs {
+                            ranges.push((initial, usize::MAX));
+                        } else if value < initial {
+                            return Err("invalid decreasing range".to_string());
+                        } else {
+                            ranges.push((initial, value));
+                        }
+                    } else if value == 0 {
+                        return Err(numbered_from_1.to_string());
+                    } else {
+                        ranges.push((value, value));
+                    }
+                    value = 0;
+                    lhs = false;
+                    rhs = false;
+                }
+                b'0'..=b'9' => {
+                    if dash {
+                        rhs = true;
+                    } else {
+                        lhs = true;
+                    }
+                    // The largest number stands for the open end of a range.
+                    value = value
+                        .checked_mul(10)
+                        .and_then(|v| v.checked_add(usize::from(c - b'0')))
+                        .filter(|v| *v != usize::MAX)
+                        .ok_or_else(|| {
+                            let what = if fields {
+                                "field number"
+                            } else {
+                                "byte/character offset"
+                            };
+                            let start = bytes[..i]
+                                .iter()
+                                .rposition(|b| !b.is_ascii_digit())
+                                .map_or(0, |p| p + 1);
+                            let end = bytes[i..]
+                                .iter()
+                                .position(|b| !b.is_ascii_digit())
+                                .map_or(bytes.len(), |p| i + p);
+                            format!("{} '{}' is too large", what, &list[start..end])
+                        })?;
+                }
+                _ => {
+                    let what = if fields {
+                        "invalid field value"
+                    } else {
+                        "invalid byte/character position"
+                    };
+                    return Err(format!("{} '{}'", what, &list[i..]));
+                }
+            }
+        }
+
+        ranges.sort_unstable();
+        let mut merged: Vec<(usize, usize)> = Vec::new();
+        for (lo, hi) in ranges {
+            match merged.last_mut() {
+                Some(last) if last.1 >= lo => last.1 = last.1.max(hi),
+                _ => merged.push((lo, hi)),
+            }
+        }
+        if complement {
+            let mut next = 1;
+            let mut inverted = Vec::new();
+            for (lo, hi) in merged {
+                if lo > next {
+                    inverted.push((next, lo - 1));
+                }
+                next = hi.saturating_add(1);
+            }
+            if next != usize::MAX {
+                inverted.push((next, usize::MAX));
+            }
+            merged = inverted;
+        }
+
+        Ok(Selection { ranges: merged })
+    }
+
+    fn contains(&self, n: usize) -> bool {
+        let i = self.ranges.partition_point(|(_, hi)| *hi < n);
+        self.ranges.get(i).is_some_and(|(lo, _)| *lo <= n)
+    }
+
+    fn starts_range(&self, n: usize) -> bool {
+        self.ranges.binary_search_by_key(&n, |(lo, _)| *lo).is_ok()
+    }
+}
+
+#[derive(Debug)]
+struct Options {
+    unit: Unit,
+    selection: Selection,
+    delimiter: u8,
+    output_delimiter: Vec<u8>,
+    only_delimited: bool,
+}
+
+fn cut<R: Read, W: Write>(mut reader: R, writer: &mut W, options: &Options) -> io::Result<()> {
+    let mut buffer = [0; 1024 * 4];
+    let mut cutter = Cutter::new(options);
+    loop {
+        let size = reader.read(&mut buffer[..])?;
+        if size == 0 {
+            break;
+        }
+        cutter.update(&buffer[..size], writer)?;
+    }
+    cutter.finish(writer)
+}
+
+/// Where the line being cut is, carried across chunks.
+struct Cutter<'a> {
+    options: &'a Options,
+    /// The current byte, character or field number in the line, counted from 1.
+    position: usize,
+    /// Whether anything was printed from the line, so the next range needs a delimiter.
+    printed: bool,
+    /// Whether the line has a field delimiter.
+    delimited: bool,
+    /// The first field, held back until it is known whether the line has delimiters.
+    first_field: Vec<u8>,
+    /// Whether the current line has any bytes yet.
+    in_line: bool,
+}
+
+impl<'a> Cutter<'a> {
+    fn new(options: &'a Options) -> Self {
+        Cutter {
+            options,
+            position: 0,
+            printed: false,
+            delimited: false,
+            first_field: Vec::new(),
+            in_line: false,
+        }
+    }
+
+    fn update<W: Write>(&mut self, chunk: &[u8], writer: &mut W) -> io::Result<()> {
+        match self.options.unit {
+            Unit::Fields => self.update_fields(chunk, writer),
+            _ => self.update_positions(chunk, writer),
+        }
+    }
+
+    /// Ends a last line that has no newline as if it had one.
+    fn finish<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        if self.in_line {
+            self.update(b"\n", writer)?;
+        }
+        Ok(())
+    }
+
+    fn update_positions<W: Write>(&mut self, chunk: &[u8], writer: &mut W) -> io::Result<()> {
+        let selection = &self.options.selection;
+        // The start of the selected bytes not yet written.
+        let mut run: Option<usize> = None;
+        for (i, b) in chunk.iter().enumerate() {
+            if *b == b'\n' {
+                if let Some(start) = run.take() {
+                    writer.write_all(&chunk[start..i])?;
+                }
+                writer.write_all(b"\n")?;
+                self.position = 0;
+                self.printed = false;
+                self.in_line = false;
+                continue;
+            }
+            self.in_line = true;
+            // A UTF-8 continuation byte goes with the character it continues.
+            let continues =
+                self.options.unit == Unit::Chars && b & 0xc0 == 0x80 && self.position > 0;
+            if continues {
+                continue;
+            }
+            self.position += 1;
+            if selection.contains(self.position) {
+                if selection.starts_range(self.position) {
+                    if let Some(start) = run.take() {
+                        writer.write_all(&chunk[start..i])?;
+                    }
+                    if self.printed {
+                        writer.write_all(&self.options.output_delimiter)?;
+                    }
+                }
+                run.get_or_insert(i);
+                self.printed = true;
+            } else if let Some(start) = run.take() {
+                writer.write_all(&chunk[start..i])?;
+            }
+        }
+        if let Some(start) = run {
+            writer.write_all(&chunk[start..])?;
+        }
+        Ok(())
+    }
+
+    fn update_fields<W: Write>(&mut self, mut chunk: &[u8], writer: &mut W) -> io::Result<()> {
+        let options = self.options;
+        while !chunk.is_empty() {
+            if self.position == 0 {
+                self.position = 1;
+            }
+            self.in_line = true;
+            let end = chunk
+                .iter()
+                .position(|b| *b == b'\n' || *b == options.delimiter)
+                .unwrap_or(chunk.len());
+            let text = &chunk[..end];
+            if !self.delimited {
+                self.first_field.extend_from_slice(text);
+            } else if options.selection.contains(self.position) {
+                writer.write_all(text)?;
+            }
+            let separator = chunk.get(end).copied();
+            chunk = &chunk[(end + 1).min(chunk.len())..];
+
+            match separator {
+                None => {}
+                Some(b'\n') => {
+                    if self.delimited {
+                        writer.write_all(b"\n")?;
+                    } else if !options.only_delimited {
+                        writer.write_all(&self.first_field)?;
+                        writer.write_all(b"\n")?;
+                    }
+                    self.first_field.clear();
+                    self.position = 0;
+                    self.printed = false;
+                    self.delimited = false;
+                    self.in_line = false;
+                }
+                Some(_) => {
+                    if !self.delimited {
+                        self.delimited = true;
+                        if options.selection.contains(1) {
+                            writer.write_all(&self.first_field)?;
+                            self.printed = true;
+                        }
+                        self.first_field.clear();
+                    }
+                    self.position += 1;
+                    if options.selection.contains(self.position) {
+                        if self.printed {
+                            writer.write_all(&options.output_delimiter)?;
+                        }
+                        self.printed = true;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(input: &str, unit: Unit, list: &str, complement: bool) -> String {
+        let options = Options {
+            unit,
+            selection: Selection::parse(list, unit == Unit::Fields, complement).unwrap(),
+            delimiter: b':',
+            output_delimiter: match unit {
+                Unit::Fields => b":".to_vec(),
+                _ => Vec::new(),
+            },
+            only_delimited: false,
+        };
+        let mut output = Vec::new();
+        cut(input.as_bytes(), &mut output, &options).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let parse = |list: &str| Selection::parse(list, true, false);
+        let selection = parse("2,4-5 7-").unwrap();
+        let selected: Vec<usize> = (1..10).filter(|n| selection.contains(*n)).collect();
+        assert_eq!(selected, vec![2, 4, 5, 7, 8, 9]);
+        assert!(selection.contains(1000));
+        let selection = Selection::parse("-2,5", false, true).unwrap();
+        let selected: Vec<usize> = (1..8).filter(|n| selection.contains(*n)).collect();
+        assert_eq!(selected, vec![3, 4, 6, 7]);
+        assert_eq!(parse("0").unwrap_err(), "fields are numbered from 1");
+        assert_eq!(parse("1,,2").unwrap_err(), "fields are numbered from 1");
+        assert_eq!(parse("3-1").unwrap_err(), "invalid decreasing range");
+        assert_eq!(parse("1--2").unwrap_err(), "invalid field range");
+        assert_eq!(parse("-").unwrap_err(), "invalid range with no endpoint: -");
+        assert_eq!(parse("1x,2").unwrap_err(), "invalid field value 'x,2'");
+        assert_eq!(
+            parse("99999999999999999999").unwrap_err(),
+            "field number '99999999999999999999' is too large"
+        );
+        assert_eq!(
+            parse("1-18446744073709551615").unwrap_err(),
+            "field number '18446744073709551615' is too large"
+        );
+        let selection = parse("3,18446744073709551614").unwrap();
+        assert!(selection.contains(3) && selection.contains(usize::MAX - 1));
+        assert!(!selection.contains(4) && !selection.contains(usize::MAX));
+        assert!(selection.starts_range(usize::MAX - 1));
+    }
+
+    #[test]
+    fn test_cut() {
+        assert_eq!(run("abcdef\nxy", Unit::Bytes, "2-3,5-", false), "bcef\ny\n");
+        assert_eq!(run("abcdef\n", Unit::Bytes, "3", true), "abdef\n");
+        assert_eq!(run("añbc\n", Unit::Chars, "2-3", false), "ñb\n");
+        assert_eq!(
+            run("a:b:c\nnone\n", Unit::Fields, "1,3", false),
+            "a:c\nnone\n"
+        );
+        assert_eq!(run("a::b\n", Unit::Fields, "2-", false), ":b\n");
+        assert_eq!(run("a:b:c", Unit::Fields, "2", true), "a:c\n");
+        assert_eq!(run("a:b\n", Unit::Fields, "5", false), "\n");
+    }
+
+    #[test]
+    fn test_chunk_boundaries() {
+        // Fields and lines that span several reads.
+        let long = "x".repeat(5000);
+        let input = format!("{0}:{0}y:z\n{0}\n", long);
+        assert_eq!(
+            run(&input, Unit::Fields, "2", false),
+            format!("{0}y\n{0}\n", long)
+        );
+        assert_eq!(run(&input, Unit::Bytes, "5001-5002", false), ":x\n\n");
+    }
+}
@@ -0,0 +1,794 @@
+use crate::args::Command;
+use crate::error::{shell_quote, Error, Output, Reporter, Style};
+use crate::myers::{self, Change};
+use crate::stdio;
+use crate::time::{self, LocalTime};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::{self, Metadata};
+use std::io::{self, BufWriter, Read, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+/// Exit status for trouble, as opposed to a difference.
+const EXIT_TROUBLE: i32 = 2;
+
+/// How much of each file is looked at for NUL bytes, to tell whether it is binary.
+const BINARY_CHECK_SIZE: usize = 1024 * 64;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Normal,
+    /// `-c`: both versions of each changed stretch, with `!` marking the changed lines.
+    Context,
+    /// `-u`: the deleted lines followed by the inserted ones, within shared context.
+    Unified,
+}
+
+/// Differences in lines that do not count.
+#[derive(Debug, Clone, Copy, Default)]
+struct Ignore {
+    case: bool,
+    /// `-b`: the amount of white space, and white space at the end of a line.
+    space_change: bool,
+    /// `-w`: all white space.
+    all_space: bool,
+}
+
+impl Ignore {
+    fn any(self) -> bool {
+        self.case || self.space_change || self.all_space
+    }
+
+    /// The part of `line` that counts when comparing it.
+    fn normalize(self, line: &[u8]) -> Cow<'_, [u8]> {
+        if !self.any() {
+            return Cow::Borrowed(line);
+        }
+        let mut result = Vec::with_capacity(line.len());
+        let mut bytes = line.iter().copied().peekable();
+        while let Some(b) = bytes.next() {
+            if is_space(b) && (self.all_space || self.space_change) {
+                while bytes.next_if(|&b| is_space(b)).is_some() {}
+                if !self.all_space && bytes.peek().is_some() {
+                    result.push(b' ');
+                }
+            } else if self.case {
+                result.push(b.to_ascii_lowercase());
+            } else {
+                result.push(b);
+            }
+        }
+        Cow::Owned(result)
+    }
+}
+
+fn is_space(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\r' | b'\x0b' | b'\x0c')
+}
+
+#[derive(Debug)]
+struct Options {
+    format: Format,
+    /// Lines of context around each change, for the context and unified formats.
+    context: usize,
+    brief: bool,
+    recursive: bool,
+    /// Compare files as text even if they look binary.
+    text: bool,
+    ignore: Ignore,
+    /// The options as given, for the line naming each pair of files compared in directories.
+    switches: String,
+}
+
+pub fn main(args: Vec<String>) {
+    let command = Command::new("diff", "[OPTION]... FILE1 FILE2")
+        .about("Compare FILES line by line; either may be a directory, to compare the file of the same name in it.")
+        .opt("--normal", "output a normal diff (the default)")
+        .opt("-q, --brief", "report only when files differ")
+        .opt(
+            "-c, --context[=NUM]",
+            "output NUM (default 3) lines of copied context",
+        )
+        .opt("-C NUM", "same as --context=NUM")
+        .opt(
+            "-u, --unified[=NUM]",
+            "output NUM (default 3) lines of unified context",
+        )
+        .opt("-U NUM", "same as --unified=NUM")
+        .opt(
+            "-r, --recursive",
+            "recursively compare any subdirectories found",
+        )
+        .opt("-i, --ignore-case", "ignore case differences in file contents")
+        .opt(
+            "-b, --ignore-space-change",
+            "ignore changes in the amount of white space",
+        )
+        .opt("-w, --ignore-all-space", "ignore all white space")
+        .opt("-a, --text", "treat all files as text");
+    let matches = command.parse_or_exit(args.clone());
+
+    let styles = [
+        (Format::Normal, matches.flag("normal")),
+        (
+            Format::Context,
+            matches.flag("context") || matches.flag("C"),
+        ),
+        (
+            Format::Unified,
+            matches.flag("unified") || matches.flag("U"),
+        ),
+    ];
+    let mut chosen = styles.iter().filter(|(_, given)| *given);
+    let format = match (chosen.next(), chosen.next()) {
+        (_, Some(_)) => command.exit_with_usage_error(&"conflicting output style options"),
+        (Some((format, _)), None) => *format,
+        (None, None) => Format::Normal,
+    };
+    let context = match matches
+        .last_of(&["context", "C", "unified", "U"])
+        .and_then(|key| matches.value(key))
+    {
+        Some(value) => value.parse().unwrap_or_else(|_| {
+            command.exit_with_usage_error(&format!("invalid context length '{}'", value))
+        }),
+        None => 3,
+    };
+    let (a, b) = match matches.operands() {
+        [a, b] => (a, b),
+        [_, _, extra, ..] => command.exit_with_usage_error(&format!("extra operand '{}'", extra)),
+        _ => {
+            let last = args.last().map_or("diff", String::as_str);
+            command.exit_with_usage_error(&format!("missing operand after '{}'", last))
+        }
+    };
+    let options = Options {
+        format,
+        context,
+        brief: matches.flag("brief"),
+        recursive: matches.flag("recursive"),
+        text: matches.flag("text"),
+        ignore: Ignore {
+            case: matches.flag("ignore-case"),
+            space_change: matches.flag("ignore-space-change"),
+            all_space: matches.flag("ignore-all-space"),
+        },
+        switches: switches(&args, matches.operands()),
+    };
+
+    let mut diff = Diff {
+        options,
+        writer: BufWriter::new(Output(io::stdout())),
+        reporter: Reporter::new("diff", Style::Bare),
+        differ: false,
+    };
+    let result = diff
+        .compare_operands(Path::new(a), Path::new(b))
+        .and_then(|()| diff.writer.flush());
+    if let Err(e) = result {
+        diff.reporter.report(&Error::writing(e));
+        exit(EXIT_TROUBLE);
+    }
+    if diff.reporter.failed() {
+        exit(EXIT_TROUBLE);
+    }
+    exit(if diff.differ { 1 } else { 0 });
+}
+
+/// The options among `args`, which is everything but the `operands`, each quoted for
+/// the shell as GNU diff shows them: ` -r -U 1`.
+fn switches(args: &[String], operands: &[String]) -> String {
+    let mut switches = args.to_vec();
+    for operand in operands.iter().rev() {
+        if let Some(i) = switches.iter().rposition(|arg| arg == operand) {
+            switches.remove(i);
+        }
+    }
+    switches
+        .iter()
+        .map(|arg| format!(" {}", shell_quote(arg)))
+        .collect()
+}
+
+struct Diff<W> {
+    options: Options,
+    writer: W,
+    reporter: Reporter,
+    /// Whether any difference was found.
+    differ: bool,
+}
+
+impl<W: Write> Diff<W> {
+    /// Reports trouble with one of the files, after what was printed about the others.
+    fn trouble(&mut self, e: Error) -> io::Result<()> {
+        self.writer.flush()?;
+        self.reporter.report(&e);
+        Ok(())
+    }
+
+    fn compare_operands(&mut self, a: &Path, b: &Path) -> io::Result<()> {
+        let metadata_a = match metadata(a) {
+            Ok(metadata) => metadata,
+            Err(e) => return self.trouble(Error::Open(a.display().to_string(), e)),
+        };
+        let metadata_b = match metadata(b) {
+            Ok(metadata) => metadata,
+            Err(e) => return self.trouble(Error::Open(b.display().to_string(), e)),
+        };
+        let stdin = Path::new("-");
+        match (metadata_a.is_dir(), metadata_b.is_dir()) {
+            (true, true) => self.compare_directories(a, b),
+            (false, false) => self.compare_files(a, b, &metadata_a, &metadata_b, false),
+            _ if a == stdin || b == stdin => {
+                self.writer.flush()?;
+                self.reporter.fail(&"cannot compare '-' to a directory");
+                Ok(())
+            }
+            // A directory stands for the file of the same name in it.
+            (true, false) => self.compare_operands(&a.join(file_name(b)), b),
+            (false, true) => self.compare_operands(a, &b.join(file_name(a))),
+        }
+    }
+
+    fn compare_directories(&mut self, a: &Path, b: &Path) -> io::Result<()> {
+        let names_a = match list(a) {
+            Ok(names) => names,
+            Err(e) => return self.trouble(Error::Open(a.display().to_string(), e)),
+        };
+        let names_b = match list(b) {
+            Ok(names) => names,
+            Err(e) => return self.trouble(Error::Open(b.display().to_string(), e)),
+        };
+        let (mut i, mut j) = (0, 0);
+        while i < names_a.len() || j < names_b.len() {
+            let (name, in_a, in_b) = match (names_a.get(i), names_b.get(j)) {
+                (Some(x), Some(y)) if x == y => (x, true, true),
+                (Some(x), Some(y)) if x.as_os_str().as_bytes() < y.as_os_str().as_bytes() => {
+                    (x, true, false)
+                }
+                (Some(x), None) => (x, true, false),
+                (_, Some(y)) => (y, false, true),
+                (None, None) => unreachable!(),
+            };
+            i += in_a as usize;
+            j += in_b as usize;
+            if !in_b {
+                self.differ = true;
+                writeln!(self.writer, "Only in {}: {}", a.display(), name.display())?;
+                continue;
+            }
+            if !in_a {
+                self.differ = true;
+                writeln!(self.writer, "Only in {}: {}", b.display(), name.display())?;
+                continue;
+            }
+
+            let (path_a, path_b) = (a.join(name), b.join(name));
+            let metadata_a = match fs::metadata(&path_a) {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    self.trouble(Error::Open(path_a.display().to_string(), e))?;
+                    continue;
+                }
+            };
+            let metadata_b = match fs::metadata(&path_b) {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    self.trouble(Error::Open(path_b.display().to_string(), e))?;
+                    continue;
+                }
+            };
+            match (metadata_a.is_dir(), metadata_b.is_dir()) {
+                (true, true) if self.options.recursive => {
+                    self.compare_directories(&path_a, &path_b)?
+                }
+                (true, true) => writeln!(
+                    self.writer,
+                    "Common subdirectories: {} and {}",
+                    path_a.display(),
+                    path_b.display()
+                )?,
+                (false, false) => {
+                    self.compare_files(&path_a, &path_b, &metadata_a, &metadata_b, true)?
+                }
+                _ => {
+                    self.differ = true;
+                    writeln!(
+                        self.writer,
+                        "File {} is a {} while file {} is a {}",
+                        path_a.display(),
+                        kind(&metadata_a),
+                        path_b.display(),
+                        kind(&metadata_b)
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Compares two files, naming them first with the options if they were found by
+    /// comparing directories.
+    fn compare_files(
+        &mut self,
+        a: &Path,
+        b: &Path,
+        metadata_a: &Metadata,
+        metadata_b: &Metadata,
+        in_directory: bool,
+    ) -> io::Result<()> {
+        if (metadata_a.dev(), metadata_a.ino()) == (metadata_b.dev(), metadata_b.ino()) {
+            return Ok(());
+        }
+        let data_a = match read(a) {
+            Ok(data) => data,
+            Err(e) => return self.trouble(Error::Read(a.display().to_string(), e)),
+        };
+        let data_b = match read(b) {
+            Ok(data) => data,
+            Err(e) => return self.trouble(Error::Read(b.display().to_string(), e)),
+        };
+        if data_a == data_b {
+            return Ok(());
+        }
+
+        let binary = |data: &[u8]| data[..data.len().min(BINARY_CHECK_SIZE)].contains(&0);
+        let (a_name, b_name) = (a.display(), b.display());
+        if self.options.brief && !self.options.ignore.any() {
+            self.differ = true;
+            return writeln!(self.writer, "Files {} and {} differ", a_name, b_name);
+        }
+        if !self.options.text && (binary(&data_a) || binary(&data_b)) {
+            self.differ = true;
+            let what = if self.options.brief {
+                "Files"
+            } else {
+                "Binary files"
+            };
+            return writeln!(self.writer, "{} {} and {} differ", what, a_name, b_name);
+        }
+
+        let (text_a, text_b) = (Text::new(&data_a), Text::new(&data_b));
+        let horizon = match self.options.format {
+            Format::Normal => 0,
+            Format::Context | Format::Unified => self.options.context,
+        };
+        let changes = changes(&text_a, &text_b, self.options.ignore, horizon);
+        if changes.is_empty() {
+            return Ok(());
+        }
+        self.differ = true;
+        if self.options.brief {
+            return writeln!(self.writer, "Files {} and {} differ", a_name, b_name);
+        }
+        if in_directory {
+            writeln!(
+                self.writer,
+                "diff{} {} {}",
+                self.options.switches,
+                shell_quote(&a_name.to_string()),
+                shell_quote(&b_name.to_string())
+            )?;
+        }
+
+        let printer = Printer {
+            a: &text_a,
+            b: &text_b,
+            changes: &changes,
+        };
+        let writer = &mut self.writer;
+        match self.options.format {
+            Format::Normal => printer.normal(writer),
+            Format::Context => {
+                writeln!(
+                    writer,
+                    "*** {}\t{}",
+                    a_name,
+                    modified(a, metadata_a).ctime()
+                )?;
+                writeln!(
+                    writer,
+                    "--- {}\t{}",
+                    b_name,
+                    modified(b, metadata_b).ctime()
+                )?;
+                printer.context(self.options.context, writer)
+            }
+            Format::Unified => {
+                writeln!(writer, "--- {}\t{}", a_name, modified(a, metadata_a).iso())?;
+                writeln!(writer, "+++ {}\t{}", b_name, modified(b, metadata_b).iso())?;
+                printer.unified(self.options.context, writer)
+            }
+        }
+    }
+}
+
+/// The metadata of `path`, or of standard input for `-`.
+fn metadata(path: &Path) -> io::Result<Metadata> {
+    if path == Path::new("-") {
+        stdio::stdin_file().metadata()
+    } else {
+        fs::metadata(path)
+    }
+}
+
+/// All of `path`, or of standard input for `-`.
+fn read(path: &Path) -> io::Result<Vec<u8>> {
+    if path == Path::new("-") {
+        let mut data = Vec::new();
+        io::stdin().read_to_end(&mut data)?;
+        Ok(data)
+    } else {
+        fs::read(path)
+    }
+}
+
+fn file_name(path: &Path) -> &Path {
+    path.file_name().map_or(path, Path::new)
+}
+
+/// The names in the directory, in byte order.
+fn list(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(path)? {
+        names.push(PathBuf::from(entry?.file_name()));
+    }
+    names.sort_by(|x, y| x.as_os_str().as_bytes().cmp(y.as_os_str().as_bytes()));
+    Ok(names)
+}
+
+/// What sort of file this is, in the words of GNU diff.
+fn kind(metadata: &Metadata) -> &'static str {
+    let file_type = metadata.file_type();
+    if file_type.is_dir() {
+        "directory"
+    } else if file_type.is_file() && metadata.len() == 0 {
+        "regular empty file"
+    } else if file_type.is_file() {
+        "regular file"
+    } else if file_type.is_fifo() {
+        "fifo"
+    } else if file_type.is_char_device() {
+        "character special file"
+    } else if file_type.is_block_device() {
+        "block special file"
+    } else if file_type.is_socket() {
+        "socket"
+    } else {
+        "weird file"
+    }
+}
+
+/// A file split into lines.
+#[derive(Debug)]
+struct Text<'a> {
+    /// The lines, without their newlines.
+    lines: Vec<&'a [u8]>,
+    /// Whether the last line has no newline.
+    incomplete: bool,
+}
+
+impl<'a> Text<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        let incomplete = !data.is_empty() && !data.ends_with(b"\n");
+        let data = data.strip_suffix(b"\n").unwrap_or(data);
+        let lines = if data.is_empty() && !incomplete {
+            Vec::new()
+        } else {
+            data.split(|&b| b == b'\n').collect()
+        };
+        Text { lines, incomplete }
+    }
+
+    /// Whether line `i` is the last and has no newline.
+    fn is_incomplete(&self, i: usize) -> bool {
+        self.incomplete && i + 1 == self.lines.len()
+    }
+}
+
+/// The changes between `a` and `b`. As in GNU diff, identical lines at either end are
+/// set aside first, all but a `horizon` of them next to the rest, which limits how far
+/// the changes can slide; so where several sets of changes would do, this one matches
+/// GNU's.
+fn changes<'a>(a: &Text<'a>, b: &Text<'a>, ignore: Ignore, horizon: usize) -> Vec<Change> {
+    let identical =
+        |i: usize, j: usize| a.lines[i] == b.lines[j] && a.is_incomplete(i) == b.is_incomplete(j);
+    let shorter = a.lines.len().min(b.lines.len());
+    let prefix = (0..shorter).take_while(|&i| identical(i, i)).count();
+    let suffix = (0..shorter - prefix)
+        .take_while(|&k| identical(a.lines.len() - 1 - k, b.lines.len() - 1 - k))
+        .count();
+    let start = prefix.saturating_sub(horizon);
+    let (end_a, end_b) = (
+        a.lines.len() - suffix.saturating_sub(horizon),
+        b.lines.len() - suffix.saturating_sub(horizon),
+    );
+
+    // Numbers standing for the lines, the same for lines that compare equal.
+    let mut seen = HashMap::new();
+    let mut key = |text: &Text<'a>, i: usize| {
+        // A last line without its newline differs from the same line with it, unless
+        // white space is being ignored.
+        let incomplete = text.is_incomplete(i) && !(ignore.space_change || ignore.all_space);
+        let next = seen.len();
+        *seen
+            .entry((ignore.normalize(text.lines[i]), incomplete))
+            .or_insert(next)
+    };
+    let keys_a: Vec<_> = (start..end_a).map(|i| key(a, i)).collect();
+    let keys_b: Vec<_> = (start..end_b).map(|j| key(b, j)).collect();
+    let mut changes = myers::diff(&keys_a, &keys_b);
+    for change in &mut changes {
+        change.a = change.a.start + start..change.a.end + start;
+        change.b = change.b.start + start..change.b.end + start;
+    }
+    changes
+}
+
+/// Writes the changes between two texts in one of the formats.
+struct Printer<'a> {
+    a: &'a Text<'a>,
+    b: &'a Text<'a>,
+    changes: &'a [Change],
+}
+
+impl Printer<'_> {
+    fn normal<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for change in self.changes {
+            let letter = match (change.a.is_empty(), change.b.is_empty()) {
+                (true, _) => 'a',
+                (_, true) => 'd',
+                _ => 'c',
+            };
+            writeln!(
+                writer,
+                "{}{}{}",
+                range(change.a.start, change.a.end),
+                letter,
+                range(change.b.start, change.b.end)
+            )?;
+            for i in change.a.clone() {
+                line(writer, "< ", self.a, i)?;
+            }
+            if !change.a.is_empty() && !change.b.is_empty() {
+                writeln!(writer, "---")?;
+            }
+            for i in change.b.clone() {
+                line(writer, "> ", self.b, i)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn context<W: Write>(&self, context: usize, writer: &mut W) -> io::Result<()> {
+        for hunk in self.hunks(context) {
+            let (a, b) = self.extent(hunk, context);
+            writeln!(writer, "***************")?;
+            writeln!(writer, "*** {} ****", range(a.0, a.1))?;
+            if hunk.iter().any(|change| !change.a.is_empty()) {
+                let mut i = a.0;
+                for change in hunk {
+                    for i in i..change.a.start {
+                        line(writer, "  ", self.a, i)?;
+                    }
+                    let mark = if change.b.is_empty() { "- " } else { "! " };
+                    for i in change.a.clone() {
+                        line(writer, mark, self.a, i)?;
+                    }
+                    i = change.a.end;
+                }
+                for i in i..a.1 {
+                    line(writer, "  ", self.a, i)?;
+                }
+            }
+            writeln!(writer, "--- {} ----", range(b.0, b.1))?;
+            if hunk.iter().any(|change| !change.b.is_empty()) {
+                let mut j = b.0;
+                for change in hunk {
+                    for j in j..change.b.start {
+                        line(writer, "  ", self.b, j)?;
+                    }
+                    let mark = if change.a.is_empty() { "+ " } else { "! " };
+                    for j in change.b.clone() {
+                        line(writer, mark, self.b, j)?;
+                    }
+                    j = change.b.end;
+                }
+                for j in j..b.1 {
+                    line(writer, "  ", self.b, j)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn unified<W: Write>(&self, context: usize, writer: &mut W) -> io::Result<()> {
+        for hunk in self.hunks(context) {
+            let (a, b) = self.extent(hunk, context);
+            writeln!(
+                writer,
+                "@@ -{} +{} @@",
+                unified_range(a.0, a.1),
+                unified_range(b.0, b.1)
+            )?;
+            let mut i = a.0;
+            for change in hunk {
+                for i in i..change.a.start {
+                    line(writer, " ", self.a, i)?;
+                }
+                for i in change.a.clone() {
+                    line(writer, "-", self.a, i)?;
+                }
+                for j in change.b.clone() {
+                    line(writer, "+", self.b, j)?;
+                }
+                i = change.a.end;
+            }
+            for i in i..a.1 {
+                line(writer, " ", self.a, i)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The changes grouped into hunks: changes with no more than twice the context
+    /// between them share their context lines.
+    fn hunks(&self, context: usize) -> impl Iterator<Item = &[Change]> {
+        let mut rest = self.changes;
+        std::iter::from_fn(move || {
+            if rest.is_empty() {
+                return None;
+            }
+            let mut end = 1;
+            while end < rest.len() && rest[end].a.start - rest[end - 1].a.end <= 2 * context {
+                end += 1;
+            }
+            let (hunk, remaining) = rest.split_at(end);
+            rest = remaining;
+            Some(hunk)
+        })
+    }
+
+    /// The lines of each text a hunk covers, context and all.
+    fn extent(&self, hunk: &[Change], context: usize) -> ((usize, usize), (usize, usize)) {
+        let (first, last) = (&hunk[0], &hunk[hunk.len() - 1]);
+        let before = context.min(first.a.start);
+        let after = context.min(self.a.lines.len() - last.a.end);
+        (
+            (first.a.start - before, last.a.end + after),
+            (first.b.start - before, last.b.end + after),
+        )
+    }
+}
+
+/// Writes line `i` of `text` after `prefix`, noting if it has no newline.
+fn line<W: Write>(writer: &mut W, prefix: &str, text: &Text, i: usize) -> io::Result<()> {
+    writer.write_all(prefix.as_bytes())?;
+    writer.write_all(text.lines[i])?;
+    writer.write_all(b"\n")?;
+    if text.is_incomplete(i) {
+        writer.write_all(b"\\ No newline at end of file\n")?;
+    }
+    Ok(())
+}
+
+/// Lines `start..end` numbered from 1 as `first,last`, or just one number for a single
+/// line, or for no lines the line before them.
+fn range(start: usize, end: usize) -> String {
+    if end > start + 1 {
+        format!("{},{}", start + 1, end)
+    } else {
+        end.to_string()
+    }
+}
+
+/// Lines `start..end` as the first line numbered from 1 and the count, which is left
+/// out when it is 1; no lines are shown as the line before them and a count of 0.
+fn unified_range(start: usize, end: usize) -> String {
+    match end - start {
+        0 => format!("{},0", start),
+        1 => end.to_string(),
+        count => format!("{},{}", start + 1, count),
+    }
+}
+
+/// When `path` was last modified, for the file headers; standard input is shown as
+/// modified now, as GNU diff shows it.
+fn modified(path: &Path, metadata: &Metadata) -> LocalTime {
+    let (seconds, nanoseconds) = if path == Path::new("-") {
+        time::now()
+    } else {
+        (metadata.mtime(), metadata.mtime_nsec())
+    };
+    LocalTime::new(seconds, nanoseconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn print(a: &str, b: &str, format: Format, context: usize) -> String {
+        let (a, b) = (Text::new(a.as_bytes()), Text::new(b.as_bytes()));
+        let changes = changes(&a, &b, Ignore::default(), context);
+        let printer = Printer {
+            a: &a,
+            b: &b,
+            changes: &changes,
+        };
+        let mut output = Vec::new();
+        match format {
+            Format::Normal => printer.normal(&mut output),
+            Format::Context => printer.context(context, &mut output),
+            Format::Unified => printer.unified(context, &mut output),
+        }
+        .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_normal() {
+        assert_eq!(
+            print("a\nb\nc\n", "a\nx\ny\nc\nd\n", Format::Normal, 0),
+            "2c2,3\n< b\n---\n> x\n> y\n3a5\n> d\n"
+        );
+        assert_eq!(print("a\nb\n", "b\n", Format::Normal, 0), "1d0\n< a\n");
+        assert_eq!(
+            print("a", "a\n", Format::Normal, 0),
+            "1c1\n< a\n\\ No newline at end of file\n---\n> a\n"
+        );
+    }
+
+    #[test]
+    fn test_unified() {
+        let a = "1\n2\n3\n4\n5\n6\n7\n8\n9\n";
+        let b = "1\n2\nx\n4\n5\n6\n7\n8\n";
+        assert_eq!(
+            print(a, b, Format::Unified, 1),
+            "@@ -2,3 +2,3 @@\n 2\n-3\n+x\n 4\n@@ -8,2 +8 @@\n 8\n-9\n"
+        );
+        // Changes close enough share a hunk.
+        assert_eq!(
+            print(a, b, Format::Unified, 3),
+            "@@ -1,9 +1,8 @@\n 1\n 2\n-3\n+x\n 4\n 5\n 6\n 7\n 8\n-9\n"
+        );
+        assert_eq!(print("", "a\n", Format::Unified, 3), "@@ -0,0 +1 @@\n+a\n");
+    }
+
+    #[test]
+    fn test_context() {
+        assert_eq!(
+            print("a\nb\nc\n", "a\nx\nc\nd\n", Format::Context, 1),
+            "***************\n*** 1,3 ****\n  a\n! b\n  c\n--- 1,4 ----\n  a\n! x\n  c\n+ d\n"
+        );
+        assert_eq!(
+            print("a\n", "", Format::Context, 3),
+            "***************\n*** 1 ****\n- a\n--- 0 ----\n"
+        );
+    }
+
+    #[test]
+    fn test_ignore() {
+        let ignore = |case, space_change, all_space, line: &str| {
+            let ignore = Ignore {
+                case,
+                space_change,
+                all_space,
+            };
+            String::from_utf8(ignore.normalize(line.as_bytes()).into_owned()).unwrap()
+        };
+        assert_eq!(ignore(true, false, false, "A b"), "a b");
+        assert_eq!(ignore(false, true, false, " a \t b  "), " a b");
+        assert_eq!(ignore(false, false, true, " a \t b  "), "ab");
+        assert_eq!(shell_quote("--unified=1"), "'--unified=1'");
+        assert_eq!(
+            switches(
+                &["-r".to_string(), "a".to_string(), "b".to_string()],
+                &["a".to_string(), "b".to_string()]
+            ),
+            " -r"
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use crate::args::Command;
+use crate::error::{self, Error, Output, Reporter, Style};
+use crate::human;
+use crate::walk::{self, Follow, Walker};
+use std::collections::HashSet;
+use std::fs::Metadata;
+use std::io::{self, BufWriter, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+pub fn main(args: Vec<String>) {
+    let command = Command::new("du", "[OPTION]... [FILE]...")
+        .about("Summarize the disk usage of each FILE (the current directory by default), recursively for directories.")
+        .opt(
+            "--apparent-size",
+            "print apparent sizes rather than disk usage",
+        )
+        .opt(
+            "-d, --max-depth=N",
+            "print the total for a directory only if it is N or fewer levels below the FILE",
+        )
+        .opt(
+            "-h, --human-readable",
+            "print sizes in human readable format (e.g., 1K 234M 2G)",
+        )
+        .opt("-s, --summarize", "display only a total for each FILE")
+        .usage_status(1);
+    let matches = command.parse_or_exit(args);
+    let max_depth = matches.value("max-depth").map(|value| {
+        value.parse::<usize>().unwrap_or_else(|_| {
+            command.exit_with_usage_error(&format!("invalid maximum depth '{}'", value))
+        })
+    });
+    let max_depth = match (matches.flag("summarize"), max_depth) {
+        (true, Some(0)) => {
+            eprintln!("du: warning: summarizing is the same as using --max-depth=0");
+            Some(0)
+        }
+        (true, Some(depth)) => {
+            eprintln!(
+                "du: warning: summarizing conflicts with --max-depth={}\nTry 'du --help' for more information.",
+                depth
+            );
+            exit(1);
+        }
+        (true, None) => Some(0),
+        (false, depth) => depth,
+    };
+
+    let mut du = Du {
+        apparent: matches.flag("apparent-size"),
+        human: matches.flag("human-readable"),
+        max_depth,
+        seen: HashSet::new(),
+        writer: BufWriter::new(Output(io::stdout())),
+        reporter: Reporter::new("du", Style::Bare),
+    };
+    let mut operands = matches.operands().to_vec();
+    if operands.is_empty() {
+        operands.push(".".to_string());
+    }
+    for operand in operands {
+        du.walk(&operand);
+    }
+    du.flush();
+    du.reporter.finish();
+}
+
+struct Du {
+    apparent: bool,
+    human: bool,
+    /// How far below an operand directories still get a line of their own.
+    max_depth: Option<usize>,
+    /// The device and inode numbers of the directories and hard linked files counted so
+    /// far, so that each is counted once.
+    seen: HashSet<(u64, u64)>,
+    writer: BufWriter<Output<io::Stdout>>,
+    reporter: Reporter,
+}
+
+impl Du {
+    /// Adds up the tree below `root`, printing the total of each directory once all of
+    /// its contents are counted.
+    fn walk(&mut self, root: &str) {
+        // The directories being counted, each with its path and bytes so far.
+        let mut open: Vec<(PathBuf, u64)> = Vec::new();
+        let mut walker = Walker::new(root, Follow::Never);
+        while let Some(entry) = walker.next() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(walk::Error::Io(path, e)) => {
+                    // A directory that was found but could not be listed still counts
+                    // for itself.
+                    let action = match open.last() {
+                        Some((open_path, _)) if *open_path == path => "cannot read directory",
+                        _ => "cannot access",
+                    };
+                    self.fail(&format!(
+                        "{} '{}': {}",
+                        action,
+                        path.display(),
+                        error::describe(&e)
+                    ));
+                    continue;
+                }
+                Err(walk::Error::Loop(..)) => unreachable!("links are not followed"),
+            };
+            while open.len() > entry.depth() {
+                self.close(&mut open);
+            }
+            let metadata = entry.metadata();
+            let id = (metadata.dev(), metadata.ino());
+            if (metadata.is_dir() || metadata.nlink() > 1) && !self.seen.insert(id) {
+                walker.skip_current_dir();
+                continue;
+            }
+            let size = self.size(metadata);
+            if metadata.is_dir() {
+                open.push((entry.path().to_path_buf(), size));
+            } else if let Some((_, total)) = open.last_mut() {
+                *total += size;
+            } else {
+                self.print(size, entry.path());
+            }
+        }
+        while !open.is_empty() {
+            self.close(&mut open);
+        }
+    }
+
+    /// Prints the total of the innermost directory being counted, and adds it to the
+    /// one containing it.
+    fn close(&mut self, open: &mut Vec<(PathBuf, u64)>) {
+        let (path, size) = open.pop().expect("a directory is open");
+        if self.max_depth.is_none_or(|max| open.len() <= max) {
+            self.print(size, &path);
+        }
+        if let Some((_, total)) = open.last_mut() {
+            *total += size;
+        }
+    }
+
+    fn size(&self, metadata: &Metadata) -> u64 {
+        if self.apparent {
+            metadata.len()
+        } else {
+            metadata.blocks() * 512
+        }
+    }
+
+    /// Prints `SIZE<tab>PATH`, the size in kibibytes rounded up unless made human readable.
+    fn print(&mut self, bytes: u64, path: &Path) {
+        let size = if self.human {
+            human::size(bytes)
+        } else {
+            bytes.div_ceil(1024).to_string()
+        };
+        let result = self
+            .writer
+            .write_all(format!("{}\t", size).as_bytes())
+            .and_then(|_| self.writer.write_all(path.as_os_str().as_bytes()))
+            .and_then(|_| self.writer.write_all(b"\n"));
+        if let Err(e) = result {
+            self.reporter.report(&Error::writing(e));
+            exit(1);
+        }
+    }
+
+    /// Reports a failure that does not stop the count, after what was printed so far.
+    fn fail(&mut self, message: &str) {
+        self.flush();
+        self.reporter.fail(&message);
+    }
+
+    fn flush(&mut self) {
+        if let Err(e) = self.writer.flush() {
+            self.reporter.report(&Error::writing(e));
+            exit(1);
+        }
+    }
+}
@@ -0,0 +1,662 @@
+use crate::args::Command;
+use crate::batch::Batch;
+use crate::error::{self, Error, Output, Reporter, Style};
+use crate::glob::Pattern;
+use crate::walk::{self, Entry, Follow, Walker};
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::process::ExitStatusExt;
+use std::process::{self, exit};
+use std::time::SystemTime;
+
+const ABOUT: &str = "\
+Search the directory tree below each PATH (default '.') for files for which EXPRESSION is
+true, printing them unless EXPRESSION has an action of its own.
+
+Tests:
+  -name PATTERN    the last component of the path matches the wildcard PATTERN
+  -iname PATTERN   like -name, ignoring case
+  -type TYPES      the file is of one of TYPES, separated by ',': b c d p f l s
+  -size [+-]N[U]   the size rounded up to units U, c w b k M G (default b, 512 bytes),
+                   is more than, less than or exactly N
+  -mtime [+-]N     the file was last modified more than, less than or exactly N days ago
+  -newer FILE      the file was modified more recently than FILE
+Actions:
+  -print, -print0  print the path, ended by a newline or a null byte
+  -prune           do not descend into the directory
+  -exec COMMAND ;  run COMMAND, with {} replaced by the path; true if it succeeds
+  -exec COMMAND {} +
+                   run COMMAND with as many paths as fit at once
+Operators, from tightest to loosest binding:
+  ( EXPR )   ! EXPR, -not EXPR   EXPR EXPR, EXPR -a EXPR, EXPR -and EXPR
+  EXPR -o EXPR, EXPR -or EXPR
+Global options:
+  -maxdepth N      descend at most N levels below the PATHs
+  -mindepth N      apply EXPRESSION only N or more levels below the PATHs";
+
+pub fn main(args: Vec<String>) {
+    let command = Command::new("find", "[-H] [-L] [-P] [PATH]... [EXPRESSION]")
+        .about(ABOUT)
+        .opt("-H", "follow symbolic links given as a PATH")
+        .opt("-L", "follow all symbolic links")
+        .opt("-P", "never follow symbolic links (the default)")
+        .usage_status(1);
+    // The options come first, then the paths, up to what starts the expression.
+    let is_option = |arg: &String| {
+        arg == "--help"
+            || arg.len() > 1 && arg.starts_with('-') && arg[1..].chars().all(|c| "HLP".contains(c))
+    };
+    let options = args.iter().take_while(|arg| is_option(arg)).count();
+    let matches = command.parse_or_exit(args[..options].to_vec());
+    let follow = match matches.last_of(&["H", "L", "P"]) {
+        Some("H") => Follow::Root,
+        Some("L") => Follow::Always,
+        _ => Follow::Never,
+    };
+    let rest = &args[options..];
+    let starts_expression =
+        |arg: &String| arg.len() > 1 && arg.starts_with('-') || arg == "(" || arg == "!";
+    let paths = rest
+        .iter()
+        .take_while(|arg| !starts_expression(arg))
+        .count();
+    let mut roots = rest[..paths].to_vec();
+    if roots.is_empty() {
+        roots.push(".".to_string());
+    }
+
+    let mut parser = Parser {
+        args: &rest[paths..],
+        position: 0,
+        execs: Vec::new(),
+        has_action: false,
+        min_depth: 0,
+        max_depth: None,
+    };
+    let expression = parser.parse().unwrap_or_else(|message| {
+        eprintln!("find: {}", message);
+        exit(1);
+    });
+    let (min_depth, max_depth) = (parser.min_depth, parser.max_depth);
+    let mut find = Find {
+        execs: parser.execs,
+        now: SystemTime::now(),
+        writer: BufWriter::new(Output(io::stdout())),
+        reporter: Reporter::new("find", Style::Bare),
+        exec_failed: false,
+        pruned: false,
+    };
+
+    for root in roots {
+        let mut walker = Walker::new(&root, follow);
+        while let Some(entry) = walker.next() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(walk::Error::Io(path, e)) => {
+                    find.fail(&format!("'{}': {}", path.display(), error::describe(&e)));
+                    continue;
+                }
+                Err(walk::Error::Loop(path, ancestor)) => {
+                    find.fail(&format!(
+                        "File system loop detected; '{}' is part of the same file system loop as '{}'.",
+                        path.display(),
+                        ancestor.display()
+                    ));
+                    continue;
+                }
+            };
+            if entry.depth() >= min_depth {
+                find.pruned = false;
+                expression.eval(&entry, &mut find);
+                if find.pruned {
+                    walker.skip_current_dir();
+                }
+            }
+            if max_depth == Some(entry.depth()) {
+                walker.skip_current_dir();
+            }
+        }
+    }
+    for i in 0..find.execs.len() {
+        find.run_gathered(i);
+    }
+    find.flush();
+    if find.exec_failed {
+        exit(1);
+    }
+    find.reporter.finish();
+}
+
+/// Compares a number with N, for `-size` and `-mtime`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Compare {
+    /// `-N`
+    Less(i64),
+    /// `N`
+    Exactly(i64),
+    /// `+N`
+    More(i64),
+}
+
+impl Compare {
+    fn parse(arg: &str) -> Option<Self> {
+        let (compare, digits): (fn(i64) -> Self, _) = match arg.as_bytes().first()? {
+            b'+' => (Compare::More, &arg[1..]),
+            b'-' => (Compare::Less, &arg[1..]),
+            _ => (Compare::Exactly, arg),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        Some(compare(digits.parse().ok()?))
+    }
+
+    fn matches(self, n: i64) -> bool {
+        match self {
+            Compare::Less(limit) => n < limit,
+            Compare::Exactly(limit) => n == limit,
+            Compare::More(limit) => n > limit,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    /// A global option, which is true wherever it appears.
+    True,
+    Name(Pattern),
+    /// The file type letters, as for `-type`.
+    Type(Vec<char>),
+    /// The comparison, and the unit that sizes are rounded up to.
+    Size(Compare, u64),
+    Mtime(Compare),
+    Newer(SystemTime),
+    Prune,
+    /// Prints the path, ended by the given byte.
+    Print(u8),
+    /// Runs the command of `-exec` at the index.
+    Exec(usize),
+}
+
+impl Expr {
+    fn eval(&self, entry: &Entry, find: &mut Find) -> bool {
+        let metadata = entry.metadata();
+        match self {
+            Expr::And(a, b) => a.eval(entry, find) && b.eval(entry, find),
+            Expr::Or(a, b) => a.eval(entry, find) || b.eval(entry, find),
+            Expr::Not(a) => !a.eval(entry, find),
+            Expr::True => true,
+            Expr::Name(pattern) => pattern.matches(&entry.file_name().to_string_lossy()),
+            Expr::Type(types) => types.contains(&type_letter(metadata.file_type())),
+            Expr::Size(compare, unit) => {
+                let units = metadata.len().div_ceil(*unit);
+                compare.matches(units.min(i64::MAX as u64) as i64)
+            }
+            Expr::Mtime(compare) => {
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                let seconds = match find.now.duration_since(modified) {
+                    Ok(age) => age.as_secs_f64(),
+                    Err(e) => -e.duration().as_secs_f64(),
+                };
+                match compare {
+                    // GNU find still counts a file N days old to the second as less.
+                    Compare::Less(days) => {
+                        seconds < days.saturating_mul(86400).saturating_add(1) as f64
+                    }
+                    _ => compare.matches((seconds / 86400.0).floor() as i64),
+                }
+            }
+            Expr::Newer(time) => metadata.modified().is_ok_and(|modified| modified > *time),
+            Expr::Prune => {
+                find.pruned = true;
+                true
+            }
+            Expr::Print(end) => {
+                find.print(entry, *end);
+                true
+            }
+            Expr::Exec(i) => find.exec(*i, entry),
+        }
+    }
+}
+
+/// The letter `-type` uses for a file type.
+fn type_letter(file_type: fs::FileType) -> char {
+    if file_type.is_dir() {
+        'd'
+    } else if file_type.is_symlink() {
+        'l'
+    } else if file_type.is_fifo() {
+        'p'
+    } else if file_type.is_socket() {
+        's'
+    } else if file_type.is_block_device() {
+        'b'
+    } else if file_type.is_char_device() {
+        'c'
+    } else {
+        'f'
+    }
+}
+
+/// How `-exec` runs its command.
+#[derive(Debug)]
+enum Exec {
+    /// `-exec COMMAND ;`, once for each file, with `{}` in the arguments replaced by the path.
+    Each(Vec<String>),
+    /// `-exec COMMAND {} +`, with the paths gathered so far.
+    Gathered(Batch),
+}
+
+/// Reads the expression, with the lowest precedence operators parsed first.
+struct Parser<'a> {
+    args: &'a [String],
+    position: usize,
+    execs: Vec<Exec>,
+    /// Whether the expression prints or runs anything itself.
+    has_action: bool,
+    min_depth: usize,
+    max_depth: Option<usize>,
+}
+
+impl Parser<'_> {
+    fn parse(&mut self) -> Result<Expr, String> {
+        if self.args.is_empty() {
+            return Ok(Expr::Print(b'\n'));
+        }
+        let expression = self.or()?;
+        if self.position < self.args.len() {
+            return Err("you have too many ')'".to_string());
+        }
+        Ok(if self.has_action {
+            expression
+        } else {
+            Expr::And(Box::new(expression), Box::new(Expr::Print(b'\n')))
+        })
+    }
+
+    fn peek(&self) -> Option<&str> {
+        self.args.get(self.position).map(String::as_str)
+    }
+
+    /// Moves past the operator just peeked at, requiring an expression after it.
+    fn operator(&mut self) -> Result<(), String> {
+        let operator = &self.args[self.position];
+        self.position += 1;
+        match self.peek() {
+            None | Some(")") => Err(format!("expected an expression after '{}'", operator)),
+            Some(_) => Ok(()),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expression = self.and()?;
+        while let Some("-o" | "-or") = self.peek() {
+            self.operator()?;
+            expression = Expr::Or(Box::new(expression), Box::new(self.and()?));
+        }
+        Ok(expression)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expression = self.not()?;
+        loop {
+            match self.peek() {
+                None | Some(")" | "-o" | "-or") => return Ok(expression),
+                Some("-a" | "-and") => self.operator()?,
+                Some(_) => {}
+            }
+            expression = Expr::And(Box::new(expression), Box::new(self.not()?));
+        }
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        match self.peek() {
+            Some("!" | "-not") => {
+                self.operator()?;
+                Ok(Expr::Not(Box::new(self.not()?)))
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let arg = self.args[self.position].as_str();
+        self.position += 1;
+        match arg {
+            "(" => {
+                if self.peek() == Some(")") {
+                    return Err(
+                        "invalid expression; empty parentheses are not allowed.".to_string()
+                    );
+                }
+                let expression = self.or()?;
+                if self.peek() != Some(")") {
+                    return Err("invalid expression; I was expecting to find a ')' somewhere but did not see one.".to_string());
+                }
+                self.position += 1;
+                return Ok(expression);
+            }
+            ")" => return Err("you have too many ')'".to_string()),
+            "-a" | "-and" | "-o" | "-or" => {
+                return Err(format!(
+                "invalid expression; you have used a binary operator '{}' with nothing before it.",
+                arg
+            ))
+            }
+            "-prune" => return Ok(Expr::Prune),
+            "-print" | "-print0" => {
+                self.has_action = true;
+                return Ok(Expr::Print(if arg == "-print" { b'\n' } else { b'\0' }));
+            }
+            "-exec" => return self.exec(),
+            _ => {}
+        }
+
+        let value = match self.args.get(self.position) {
+            Some(value) => value.as_str(),
+            None if is_predicate(arg) => return Err(format!("missing argument to `{}'", arg)),
+            None => return Err(format!("unknown predicate `{}'", arg)),
+        };
+        let expression = match arg {
+            "-name" => Expr::Name(Pattern::new(value)),
+            "-iname" => Expr::Name(Pattern::new_caseless(value)),
+            "-type" => Expr::Type(parse_types(value)?),
+            "-size" => {
+                let (compare, unit) = parse_size(value)?;
+                Expr::Size(compare, unit)
+            }
+            "-mtime" => Expr::Mtime(
+                Compare::parse(value)
+                    .ok_or_else(|| format!("invalid argument `{}' to `-mtime'", value))?,
+            ),
+            "-newer" => match fs::metadata(value).and_then(|metadata| metadata.modified()) {
+                Ok(time) => Expr::Newer(time),
+                Err(e) => return Err(format!("'{}': {}", value, error::describe(&e))),
+            },
+            "-maxdepth" | "-mindepth" => {
+                let depth = value.parse().map_err(|_| {
+                    format!(
+                        "Expected a positive decimal integer argument to {}, but got '{}'",
+                        arg, value
+                    )
+                })?;
+                if arg == "-maxdepth" {
+                    self.max_depth = Some(depth);
+                } else {
+                    self.min_depth = depth;
+                }
+                Expr::True
+            }
+            _ => return Err(format!("unknown predicate `{}'", arg)),
+        };
+        self.position += 1;
+        Ok(expression)
+    }
+
+    /// Reads the command of `-exec`, up to a `;`, or a `+` right after `{}`.
+    fn exec(&mut self) -> Result<Expr, String> {
+        let rest = &self.args[self.position..];
+        let end = rest
+            .iter()
+            .enumerate()
+            .position(|(i, arg)| arg == ";" || arg == "+" && i > 0 && rest[i - 1] == "{}")
+            .filter(|&end| end > 0)
+            .ok_or_else(|| "missing argument to `-exec'".to_string())?;
+        self.position += end + 1;
+        let exec = if rest[end] == ";" {
+            Exec::Each(rest[..end].to_vec())
+        } else {
+            let fixed = &rest[..end - 1];
+            if fixed.iter().any(|arg| arg.contains("{}")) {
+                return Err("Only one instance of {} is supported with -exec ... +".to_string());
+            }
+            Exec::Gathered(Batch::new(fixed.iter().map(OsString::from).collect(), None))
+        };
+        self.has_action = true;
+        self.execs.push(exec);
+        Ok(Expr::Exec(self.execs.len() - 1))
+    }
+}
+
+fn is_predicate(arg: &str) -> bool {
+    [
+        "-name",
+        "-iname",
+        "-type",
+        "-size",
+        "-mtime",
+        "-newer",
+        "-maxdepth",
+        "-mindepth",
+    ]
+    .contains(&arg)
+}
+
+/// Parses the argument of `-type`: letters separated by commas.
+fn parse_types(value: &str) -> Result<Vec<char>, String> {
+    if value.is_empty() {
+        return Err("Arguments to -type should contain at least one letter".to_string());
+    }
+    let mut types = Vec::new();
+    for (i, part) in value.split(',').enumerate() {
+        let mut chars = part.chars();
+        let letter = match (chars.next(), chars.next()) {
+            (Some(letter), None) => letter,
+            (None, _) if i > 0 => return Err(
+                "Last file type in list argument to -type is missing, i.e., list is ending on: ','"
+                    .to_string(),
+            ),
+            _ => return Err("Must separate multiple arguments to -type using: ','".to_string()),
+        };
+        if !"bcdpfls".contains(letter) {
+            return Err(format!("Unknown argument to -type: {}", letter));
+        }
+        types.push(letter);
+    }
+    Ok(types)
+}
+
+/// Parses the argument of `-size` into the comparison and the unit.
+fn parse_size(value: &str) -> Result<(Compare, u64), String> {
+    let (number, unit) = match value.chars().last() {
+        Some(last) if !last.is_ascii_digit() => {
+            let unit = match last {
+                'c' => 1,
+                'w' => 2,
+                'b' => 512,
+                'k' => 1024,
+                'M' => 1024 * 1024,
+                'G' => 1024 * 1024 * 1024,
+                _ => return Err(format!("invalid -size type `{}'", last)),
+            };
+            (&value[..value.len() - 1], unit)
+        }
+        _ => (value, 512),
+    };
+    let compare =
+        Compare::parse(number).ok_or_else(|| format!("Invalid argument `{}' to -size", value))?;
+    Ok((compare, unit))
+}
+
+/// What the walk needs while the expression is evaluated.
+struct Find {
+    execs: Vec<Exec>,
+    /// When the search started, which `-mtime` measures from.
+    now: SystemTime,
+    writer: BufWriter<Output<io::Stdout>>,
+    reporter: Reporter,
+    /// Whether a command run by `-exec ... +` failed.
+    exec_failed: bool,
+    /// Whether `-prune` was reached for the file just evaluated.
+    pruned: bool,
+}
+
+impl Find {
+    fn print(&mut self, entry: &Entry, end: u8) {
+        let path = entry.path().as_os_str().as_bytes();
+        let result = self
+            .writer
+            .write_all(path)
+            .and_then(|_| self.writer.write_all(&[end]));
+        if let Err(e) = result {
+            self.reporter.report(&Error::writing(e));
+            exit(1);
+        }
+    }
+
+    /// Reports a failure that does not stop the search. As with GNU find, what was
+    /// printed so far comes first.
+    fn fail(&mut self, message: &str) {
+        self.flush();
+        self.reporter.fail(&message);
+    }
+
+    fn flush(&mut self) {
+        if let Err(e) = self.writer.flush() {
+            self.reporter.report(&Error::writing(e));
+            exit(1);
+        }
+    }
+
+    fn exec(&mut self, i: usize, entry: &Entry) -> bool {
+        let path = entry.path().as_os_str();
+        match &mut self.execs[i] {
+            Exec::Each(line) => {
+                let line = line
+                    .iter()
+                    .map(|arg| {
+                        let parts: Vec<&[u8]> = arg.split("{}").map(str::as_bytes).collect();
+                        OsString::from_vec(parts.join(path.as_bytes()))
+                    })
+                    .collect();
+                self.run(line)
+            }
+            Exec::Gathered(batch) => {
+                if !batch.fits(path) {
+                    self.run_gathered(i);
+                }
+                if let Exec::Gathered(batch) = &mut self.execs[i] {
+                    batch.push(path.to_os_string());
+                }
+                true
+            }
+        }
+    }
+
+    /// Runs the command of the `-exec ... +` at `i` on the paths gathered, if any.
+    fn run_gathered(&mut self, i: usize) {
+        let line = match &mut self.execs[i] {
+            Exec::Gathered(batch) if !batch.is_empty() => batch.take(),
+            _ => return,
+        };
+        if !self.run(line) {
+            self.exec_failed = true;
+        }
+    }
+
+    /// Runs a command line, telling whether it succeeded.
+    fn run(&mut self, line: Vec<OsString>) -> bool {
+        // What was printed so far comes before what the command prints.
+        self.flush();
+        let name = line[0].to_string_lossy();
+        match process::Command::new(&line[0]).args(&line[1..]).status() {
+            Ok(status) => {
+                if let Some(signal) = status.signal() {
+                    eprintln!("find: '{}' terminated by signal {}", name, signal);
+                }
+                status.success()
+            }
+            Err(e) => {
+                eprintln!("find: '{}': {}", name, error::describe(&e));
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Expr, String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        Parser {
+            args: &args,
+            position: 0,
+            execs: Vec::new(),
+            has_action: false,
+            min_depth: 0,
+            max_depth: None,
+        }
+        .parse()
+    }
+
+    #[test]
+    fn test_parse() {
+        // `-a` binds tighter than `-o`, and a default `-print` applies to the whole.
+        assert!(matches!(
+            parse(&["-name", "a", "-o", "-name", "b", "-type", "f"]).unwrap(),
+            Expr::And(ref a, ref b)
+                if matches!(**a, Expr::Or(_, ref right) if matches!(**right, Expr::And(..)))
+                    && matches!(**b, Expr::Print(b'\n'))
+        ));
+        assert!(matches!(
+            parse(&["-name", "a", "-prune", "-o", "-print"]).unwrap(),
+            Expr::Or(..)
+        ));
+        assert!(matches!(
+            parse(&["!", "(", "-type", "d", ")", "-print0"]).unwrap(),
+            Expr::And(ref a, ref b) if matches!(**a, Expr::Not(_)) && matches!(**b, Expr::Print(0))
+        ));
+
+        let error = |args: &[&str]| parse(args).unwrap_err();
+        assert_eq!(error(&["-name"]), "missing argument to `-name'");
+        assert_eq!(error(&["-bogus"]), "unknown predicate `-bogus'");
+        assert_eq!(
+            error(&["(", ")"]),
+            "invalid expression; empty parentheses are not allowed."
+        );
+        assert_eq!(error(&["-type", "f", ")"]), "you have too many ')'");
+        assert_eq!(
+            error(&["-type", "f", "-o"]),
+            "expected an expression after '-o'"
+        );
+        assert_eq!(
+            error(&["-exec", "echo", "{}"]),
+            "missing argument to `-exec'"
+        );
+        assert_eq!(
+            error(&["-exec", "echo", "x{}", "{}", "+"]),
+            "Only one instance of {} is supported with -exec ... +"
+        );
+    }
+
+    #[test]
+    fn test_values() {
+        assert_eq!(parse_types("f,d"), Ok(vec!['f', 'd']));
+        assert!(parse_types("fd").unwrap_err().starts_with("Must separate"));
+        assert!(parse_types("f,").unwrap_err().starts_with("Last file type"));
+        assert_eq!(
+            parse_types("q").unwrap_err(),
+            "Unknown argument to -type: q"
+        );
+        assert_eq!(parse_size("+2k"), Ok((Compare::More(2), 1024)));
+        assert_eq!(parse_size("3"), Ok((Compare::Exactly(3), 512)));
+        assert_eq!(parse_size("-1c"), Ok((Compare::Less(1), 1)));
+        assert_eq!(parse_size("3q").unwrap_err(), "invalid -size type `q'");
+        assert_eq!(
+            parse_size("1.5k").unwrap_err(),
+            "Invalid argument `1.5k' to -size"
+        );
+        assert_eq!(Compare::parse("+x"), None);
+        assert!(Compare::Less(1).matches(0));
+        assert!(!Compare::More(1).matches(1));
+    }
+}
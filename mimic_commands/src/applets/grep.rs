@@ -0,0 +1,912 @@
+use crate::args::Command;
+use crate::error::{Error, Output, Reporter, Style};
+use crate::glob::Pattern;
+use crate::pool;
+use crate::regex::{Regex, RegexBuilder, Syntax};
+use crate::walk::{self, Follow, Walker};
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+
+/// Exit status when no line was selected.
+const EXIT_NO_MATCH: i32 = 1;
+/// Exit status for any error, matches or not.
+const EXIT_TROUBLE: i32 = 2;
+
+/// How much of an input is looked at up front to tell whether it is binary.
+const BINARY_CHECK_SIZE: usize = 32 * 1024;
+
+/// What to print for the selected lines.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    /// The lines themselves, with their context.
+    Lines,
+    /// Only the matching parts of the lines (`-o`).
+    OnlyMatching,
+    /// The number of selected lines per file (`-c`).
+    Count,
+    /// The names of files with a selected line (`-l`).
+    FilesWithMatches,
+    /// Nothing, the exit status tells (`-q`).
+    Quiet,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Options {
+    mode: Mode,
+    invert: bool,
+    /// `-H` or `-h`; by default names are shown when there are several inputs.
+    with_filename: Option<bool>,
+    line_number: bool,
+    before: usize,
+    after: usize,
+    /// Treat binary inputs as text (`-a`).
+    text: bool,
+}
+
+pub fn main(args: Vec<String>) {
+    let command = Command::new("grep", "[OPTION]... PATTERNS [FILE]...")
+        .about("Search for PATTERNS in each FILE.")
+        .opt(
+            "-E, --extended-regexp",
+            "PATTERNS are extended regular expressions",
+        )
+        .opt("-F, --fixed-strings", "PATTERNS are strings")
+        .opt(
+            "-G, --basic-regexp",
+            "PATTERNS are basic regular expressions",
+        )
+        .opt("-e, --regexp=PATTERNS", "use PATTERNS for matching")
+        .opt("-f, --file=FILE", "take PATTERNS from FILE")
+        .opt(
+            "-i, --ignore-case",
+            "ignore case distinctions in patterns and data",
+        )
+        .opt(
+            "--no-ignore-case",
+            "do not ignore case distinctions (default)",
+        )
+        .opt("-y", "same as -i (obsolete)")
+        .opt("-w, --word-regexp", "match only whole words")
+        .opt("-x, --line-regexp", "match only whole lines")
+        .opt("-v, --invert-match", "select non-matching lines")
+        .opt(
+            "-c, --count",
+            "print only a count of selected lines per FILE",
+        )
+        .opt(
+            "-l, --files-with-matches",
+            "print only names of FILEs with selected lines",
+        )
+        .opt("-n, --line-number", "print line number with output lines")
+        .opt(
+            "-o, --only-matching",
+            "show only nonempty parts of lines that match",
+        )
+        .opt("-q, --quiet, --silent", "suppress all normal output")
+        .opt("-s, --no-messages", "suppress error messages")
+        .opt("-H, --with-filename", "print file name with output lines")
+        .opt(
+            "-h, --no-filename",
+            "suppress the file name prefix on output",
+        )
+        .opt("-a, --text", "process a binary file as if it were text")
+        .opt("-r, --recursive", "search directories recursively")
+        .opt(
+            "-R, --dereference-recursive",
+            "likewise, but follow all symlinks",
+        )
+        .opt(
+            "--include=GLOB",
+            "search only files that match GLOB (a file pattern)",
+        )
+        .opt("--exclude=GLOB", "skip files that match GLOB")
+        .opt("--exclude-dir=GLOB", "skip directories that match GLOB")
+        .opt(
+            "-A, --after-context=NUM",
+            "print NUM lines of trailing context",
+        )
+        .opt(
+            "-B, --before-context=NUM",
+            "print NUM lines of leading context",
+        )
+        .opt("-C, --context=NUM", "print NUM lines of output context");
+    let matches = command.parse_or_exit(args);
+
+    let mut reporter = Reporter::new("grep", Style::Bare);
+
+    let mut patterns = Vec::new();
+    for value in matches.values("regexp") {
+        patterns.extend(value.split('\n').map(str::to_string));
+    }
+    for path in matches.values("file") {
+        match read_patterns(path) {
+            Ok(read) => patterns.extend(read),
+            Err(e) => {
+                reporter.report(&e);
+                exit(EXIT_TROUBLE);
+            }
+        }
+    }
+    let mut operands = matches.operands().iter();
+    if !matches.flag("regexp") && !matches.flag("file") {
+        match operands.next() {
+            Some(pattern) => patterns.extend(pattern.split('\n').map(str::to_string)),
+            None => {
+                // GNU grep gives just the usage here, with no message.
+                eprintln!("Usage: grep [OPTION]... PATTERNS [FILE]...");
+                eprintln!("Try 'grep --help' for more information.");
+                exit(EXIT_TROUBLE);
+            }
+        }
+    }
+    let operands: Vec<String> = operands.cloned().collect();
+
+    let syntax = match matches.last_of(&["extended-regexp", "fixed-strings", "basic-regexp"]) {
+        Some("extended-regexp") => Syntax::Extended,
+        Some("fixed-strings") => Syntax::Fixed,
+        _ => Syntax::Basic,
+    };
+    let ignore_case = matches.last_of(&["ignore-case", "y", "no-ignore-case"]);
+    let regex = RegexBuilder::new(syntax)
+        .ignore_case(matches!(ignore_case, Some("ignore-case") | Some("y")))
+        .whole_words(matches.flag("word-regexp"))
+        .whole_lines(matches.flag("line-regexp"))
+        .build(&patterns);
+    let regex = match regex {
+        Ok(regex) => regex,
+        Err(e) => {
+            reporter.fail(&e);
+            exit(EXIT_TROUBLE);
+        }
+    };
+    for warning in regex.warnings() {
+        eprintln!("grep: warning: {}", warning);
+    }
+
+    let mut context = |key: &str| {
+        matches.value(key).map(|value| {
+            value.parse::<usize>().unwrap_or_else(|_| {
+                reporter.fail(&format!("{}: invalid context length argument", value));
+                exit(EXIT_TROUBLE);
+            })
+        })
+    };
+    let default_context = context("context").unwrap_or(0);
+    let mode = if matches.flag("quiet") {
+        Mode::Quiet
+    } else if matches.flag("files-with-matches") {
+        Mode::FilesWithMatches
+    } else if matches.flag("count") {
+        Mode::Count
+    } else if matches.flag("only-matching") {
+        Mode::OnlyMatching
+    } else {
+        Mode::Lines
+    };
+    let options = Options {
+        mode,
+        invert: matches.flag("invert-match"),
+        with_filename: matches
+            .last_of(&["with-filename", "no-filename"])
+            .map(|key| key == "with-filename"),
+        line_number: matches.flag("line-number"),
+        before: context("before-context").unwrap_or(default_context),
+        after: context("after-context").unwrap_or(default_context),
+        text: matches.flag("text"),
+    };
+
+    let follow = match matches.last_of(&["recursive", "dereference-recursive"]) {
+        Some("recursive") => Some(Follow::Root),
+        Some(_) => Some(Follow::Always),
+        None => None,
+    };
+    let filters = Filters {
+        include: matches
+            .values("include")
+            .into_iter()
+            .map(Pattern::new)
+            .collect(),
+        exclude: matches
+            .values("exclude")
+            .into_iter()
+            .map(Pattern::new)
+            .collect(),
+        exclude_dir: matches
+            .values("exclude-dir")
+            .into_iter()
+            .map(Pattern::new)
+            .collect(),
+    };
+    let inputs = Inputs::new(operands, follow, &filters, options.with_filename);
+
+    let mut status = Status {
+        reporter,
+        no_messages: matches.flag("no-messages"),
+        failed_silently: false,
+        selected: false,
+        quiet: mode == Mode::Quiet,
+    };
+    let searcher = Searcher::new(regex, options);
+    let mut writer = BufWriter::new(Output(io::stdout()));
+    if follow.is_none() {
+        search_in_turn(inputs, searcher, &mut writer, &mut status);
+    } else {
+        let jobs = thread::available_parallelism().map_or(1, |jobs| jobs.get());
+        search_in_parallel(inputs, jobs, searcher, &mut writer, &mut status);
+    }
+    status.exit();
+}
+
+/// Searches the inputs one by one, streaming the output.
+fn search_in_turn<W: Write>(
+    inputs: Inputs,
+    mut searcher: Searcher,
+    writer: &mut W,
+    status: &mut Status,
+) {
+    for input in inputs {
+        let source = match input {
+            Input::Searchable(source) => source,
+            input => {
+                status.skipped(input);
+                continue;
+            }
+        };
+        let result = searcher.search_source(&source, writer);
+        // Flushing per file keeps the output in order with the error messages.
+        let flushed = writer.flush().map_err(Error::writing);
+        status.searched(&source.name, flushed.and(result));
+    }
+}
+
+/// Searches the inputs on `jobs` threads as the directories are walked. Each file's output
+/// is held until the files before it are done, so it comes out whole and in order.
+fn search_in_parallel<W: Write>(
+    inputs: Inputs,
+    jobs: usize,
+    searcher: Searcher,
+    writer: &mut W,
+    status: &mut Status,
+) {
+    let separates_groups = searcher.separates_groups();
+    let mut printed_group = false;
+    // The regex caches as it goes, so it cannot be shared; each thread gets a copy.
+    let searcher = Mutex::new(searcher);
+    pool::map_ordered_with(
+        inputs,
+        jobs,
+        || {
+            searcher
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone()
+        },
+        |searcher, input| {
+            let mut output = Vec::new();
+            let result = match &input {
+                Input::Searchable(source) => {
+                    searcher.printed_group = false;
+                    Some(searcher.search_source(source, &mut output))
+                }
+                _ => None,
+            };
+            (input, output, result, searcher.printed_group)
+        },
+        |_, (input, output, result, file_printed_group)| {
+            let (source, result) = match (input, result) {
+                (Input::Searchable(source), Some(result)) => (source, result),
+                (input, _) => return status.skipped(input),
+            };
+            // The file's first group was printed as if it were the first of all.
+            let mut written = Ok(());
+            if separates_groups && printed_group && !output.is_empty() {
+                written = writer.write_all(b"--\n");
+            }
+            printed_group |= file_printed_group;
+            let written = written
+                .and_then(|_| writer.write_all(&output))
+                .and_then(|_| writer.flush())
+                .map_err(Error::writing);
+            status.searched(&source.name, written.and(result));
+        },
+    );
+}
+
+/// Keeps track of how the search is going, for messages and the exit status.
+struct Status {
+    reporter: Reporter,
+    no_messages: bool,
+    /// Errors hidden by `-s` still make the exit status 2.
+    failed_silently: bool,
+    selected: bool,
+    quiet: bool,
+}
+
+impl Status {
+    fn searched(&mut self, name: &str, result: Result<Found, Error>) {
+        match result {
+            Ok(found) => {
+                if found.binary {
+                    eprintln!("grep: {}: binary file matches", name);
+                }
+                self.selected |= found.count > 0;
+            }
+            Err(e) => self.error(e),
+        }
+        if self.selected && self.quiet {
+            exit(0);
+        }
+    }
+
+    /// Reports what went wrong with an input that could not be searched at all.
+    fn skipped(&mut self, input: Input) {
+        match input {
+            Input::Searchable(_) => {}
+            Input::Unreadable(name, e) => self.error(Error::Open(name, e)),
+            Input::Loop(name) => {
+                if !self.no_messages {
+                    eprintln!("grep: {}: warning: recursive directory loop", name);
+                }
+            }
+        }
+    }
+
+    fn error(&mut self, e: Error) {
+        match e {
+            Error::Write(_) => self.reporter.report(&e),
+            _ if self.no_messages => self.failed_silently = true,
+            _ => self.reporter.report(&e),
+        }
+    }
+
+    fn exit(&self) -> ! {
+        if self.reporter.failed() || self.failed_silently {
+            exit(EXIT_TROUBLE);
+        }
+        exit(if self.selected { 0 } else { EXIT_NO_MATCH });
+    }
+}
+
+/// Reads the patterns of `-f FILE`, one per line.
+fn read_patterns(path: &str) -> Result<Vec<String>, Error> {
+    let contents = if path == "-" {
+        let mut contents = Vec::new();
+        io::Read::read_to_end(&mut io::stdin(), &mut contents)
+            .map(|_| contents)
+            .map_err(|e| Error::Read(display_name(path).to_string(), e))?
+    } else {
+        fs::read(path).map_err(|e| Error::Open(path.to_string(), e))?
+    };
+    // An empty file has no patterns at all, while an empty line is a pattern matching everything.
+    if contents.is_empty() {
+        return Ok(Vec::new());
+    }
+    let contents = String::from_utf8_lossy(&contents);
+    let contents = contents.strip_suffix('\n').unwrap_or(&contents);
+    Ok(contents.split('\n').map(str::to_string).collect())
+}
+
+fn display_name(path: &str) -> &str {
+    if path == "-" {
+        "(standard input)"
+    } else {
+        path
+    }
+}
+
+/// Which files and directories `--include`, `--exclude` and `--exclude-dir` let through.
+///
+/// Files found in directories are matched by their base name. Like GNU grep, names given
+/// on the command line are matched by every suffix that starts after a `/`, as well as whole.
+#[derive(Debug)]
+struct Filters {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+    exclude_dir: Vec<Pattern>,
+}
+
+impl Filters {
+    fn file(&self, name: &str, on_command_line: bool) -> bool {
+        let matches = |patterns: &[Pattern]| {
+            suffixes(name, on_command_line).any(|name| patterns.iter().any(|p| p.matches(name)))
+        };
+        !matches(&self.exclude) && (self.include.is_empty() || matches(&self.include))
+    }
+
+    fn dir(&self, name: &str, on_command_line: bool) -> bool {
+        !suffixes(name, on_command_line)
+            .any(|name| self.exclude_dir.iter().any(|p| p.matches(name)))
+    }
+}
+
+fn suffixes(name: &str, all: bool) -> impl Iterator<Item = &str> {
+    let starts = name.match_indices('/').map(|(i, _)| i + 1);
+    std::iter::once(0)
+        .chain(starts.filter(move |_| all))
+        .map(move |start| &name[start..])
+        .filter(|suffix| !suffix.is_empty())
+}
+
+/// Something to search, or a reason one could not be.
+#[derive(Debug)]
+enum Input {
+    Searchable(Source),
+    /// A file or directory the walk could not look at.
+    Unreadable(String, io::Error),
+    /// A directory that contains itself through a symbolic link.
+    Loop(String),
+}
+
+/// A file to search, or standard input.
+#[derive(Debug)]
+struct Source {
+    /// `None` for standard input.
+    path: Option<PathBuf>,
+    /// How the input is called in the output and in messages.
+    name: String,
+    show_name: bool,
+}
+
+/// The inputs named by the operands, with those found in directories under `-r`.
+struct Inputs<'a> {
+    operands: std::vec::IntoIter<String>,
+    many: bool,
+    follow: Option<Follow>,
+    filters: &'a Filters,
+    with_filename: Option<bool>,
+    /// The walk in progress, and whether it is of the implicit `.` of a bare `grep -r`.
+    walker: Option<(Walker, bool)>,
+}
+
+impl<'a> Inputs<'a> {
+    fn new(
+        operands: Vec<String>,
+        follow: Option<Follow>,
+        filters: &'a Filters,
+        with_filename: Option<bool>,
+    ) -> Self {
+        let mut inputs = Inputs {
+            many: operands.len() > 1,
+            operands: operands.into_iter(),
+            follow,
+            filters,
+            with_filename,
+            walker: None,
+        };
+        if inputs.operands.len() == 0 {
+            match follow {
+                // Names are then given relative to the working directory, without `./`.
+                Some(follow) => inputs.walker = Some((Walker::new(".", follow), true)),
+                None => inputs.operands = vec!["-".to_string()].into_iter(),
+            }
+        }
+        inputs
+    }
+
+    fn searchable(&self, path: Option<PathBuf>, name: String, below_operand: bool) -> Input {
+        Input::Searchable(Source {
+            path,
+            name,
+            show_name: self.with_filename.unwrap_or(self.many || below_operand),
+        })
+    }
+
+    /// The next input from the walk in progress, if any.
+    fn walk(&mut self) -> Option<Input> {
+        let (walker, implicit) = self.walker.as_mut()?;
+        let implicit = *implicit;
+        let name = |path: &Path| {
+            let name = path.to_string_lossy();
+            match name.strip_prefix("./") {
+                Some(relative) if implicit => relative.to_string(),
+                _ => name.into_owned(),
+            }
+        };
+        loop {
+            let entry = match walker.next() {
+                Some(Ok(entry)) => entry,
+                Some(Err(walk::Error::Io(path, e))) => {
+                    return Some(Input::Unreadable(name(&path), e))
+                }
+                Some(Err(walk::Error::Loop(path, _))) => return Some(Input::Loop(name(&path))),
+                None => {
+                    self.walker = None;
+                    return None;
+                }
+            };
+            let on_command_line = entry.depth() == 0;
+            let checked_name = if on_command_line {
+                entry.path().to_string_lossy()
+            } else {
+                entry.file_name().to_string_lossy()
+            };
+            let file_type = entry.metadata().file_type();
+            if file_type.is_dir() {
+                if !self.filters.dir(&checked_name, on_command_line) {
+                    walker.skip_current_dir();
+                }
+                continue;
+            }
+            // Below the starting point only regular files are read; not devices or
+            // FIFOs, and not symbolic links unless they were followed.
+            if !on_command_line && !file_type.is_file() {
+                continue;
+            }
+            if !self.filters.file(&checked_name, on_command_line) {
+                continue;
+            }
+            let name = name(entry.path());
+            return Some(self.searchable(Some(entry.path().to_path_buf()), name, !on_command_line));
+        }
+    }
+}
+
+impl Iterator for Inputs<'_> {
+    type Item = Input;
+
+    fn next(&mut self) -> Option<Input> {
+        loop {
+            if let Some(input) = self.walk() {
+                return Some(input);
+            }
+            let operand = self.operands.next()?;
+            if operand == "-" {
+                return Some(self.searchable(None, display_name("-").to_string(), false));
+            }
+            match self.follow {
+                Some(follow) => self.walker = Some((Walker::new(&operand, follow), false)),
+                None if self.filters.file(&operand, true) => {
+                    return Some(self.searchable(Some(PathBuf::from(&operand)), operand, false))
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+/// What searching one input found.
+#[derive(Debug, Clone, Copy, Default)]
+struct Found {
+    /// The number of selected lines, or just whether there were any when that is all
+    /// that matters.
+    count: u64,
+    /// Whether selected lines were left out because the input looks binary.
+    binary: bool,
+}
+
+/// Searches inputs one after another, keeping what must carry over between them.
+#[derive(Debug, Clone)]
+struct Searcher {
+    regex: Regex,
+    options: Options,
+    /// Whether a group of lines has been printed yet, so the next one needs a `--` before it.
+    printed_group: bool,
+    /// Whether a line was left out of the current input's output for not being text.
+    hid_binary: bool,
+}
+
+impl Searcher {
+    fn new(regex: Regex, options: Options) -> Self {
+        Searcher {
+            regex,
+            options,
+            printed_group: false,
+            hid_binary: false,
+        }
+    }
+
+    /// Whether groups of lines with their context are told apart by `--` lines.
+    fn separates_groups(&self) -> bool {
+        matches!(self.options.mode, Mode::Lines | Mode::OnlyMatching)
+            && (self.options.before > 0 || self.options.after > 0)
+    }
+
+    fn search_source<W: Write>(&mut self, source: &Source, writer: &mut W) -> Result<Found, Error> {
+        let (name, show_name) = (source.name.as_str(), source.show_name);
+        match &source.path {
+            None => self.search(io::stdin().lock(), name, show_name, writer),
+            Some(path) => {
+                let file = File::open(path).map_err(|e| Error::Open(name.to_string(), e))?;
+                let reader = BufReader::with_capacity(BINARY_CHECK_SIZE, file);
+                self.search(reader, name, show_name, writer)
+            }
+        }
+    }
+
+    /// Searches `reader` and prints what the options ask for, stopping early when the
+    /// rest cannot change the outcome.
+    ///
+    /// Like GNU grep, an input with a NUL byte is binary from there on, and lines that are
+    /// not valid UTF-8 are not printed; either way the caller reports a binary file match.
+    fn search<R: BufRead, W: Write>(
+        &mut self,
+        mut reader: R,
+        name: &str,
+        show_name: bool,
+        writer: &mut W,
+    ) -> Result<Found, Error> {
+        let options = self.options;
+        let read_error = |e| Error::Read(name.to_string(), e);
+        let write_error = Error::writing;
+        let source_name = name;
+        let name = show_name.then_some(name);
+        self.hid_binary = false;
+
+        let mut binary = !options.text && reader.fill_buf().map_err(read_error)?.contains(&0);
+        let mut found = Found::default();
+        let mut line = Vec::new();
+        let mut number = 0;
+        // Lines that may be printed as leading context, with their numbers.
+        let mut before: VecDeque<(u64, Vec<u8>)> = VecDeque::new();
+        let mut after_left = 0;
+        let mut last_printed: Option<u64> = None;
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line).map_err(read_error)? == 0 {
+                break;
+            }
+            number += 1;
+            let text = line.strip_suffix(b"\n").unwrap_or(&line);
+            binary |= !options.text && text.contains(&0);
+            if self.regex.is_match(text) == options.invert {
+                if after_left > 0 && !binary {
+                    after_left -= 1;
+                    last_printed = Some(number);
+                    self.print_context(name, number, text, writer)
+                        .map_err(write_error)?;
+                } else if options.before > 0 {
+                    if before.len() == options.before {
+                        before.pop_front();
+                    }
+                    before.push_back((number, text.to_vec()));
+                }
+                continue;
+            }
+
+            found.count += 1;
+            match options.mode {
+                Mode::Quiet | Mode::FilesWithMatches => break,
+                Mode::Count => continue,
+                Mode::Lines | Mode::OnlyMatching => {}
+            }
+            if binary {
+                // GNU grep counts the message as a group, for the `--` before the next one.
+                found.binary = true;
+                self.printed_group = true;
+                break;
+            }
+            if self.separates_groups() {
+                let first = before.front().map_or(number, |(number, _)| *number);
+                let adjacent = last_printed.is_some_and(|last| last + 1 == first);
+                if self.printed_group && !adjacent {
+                    writer.write_all(b"--\n").map_err(write_error)?;
+                }
+            }
+            for (number, text) in before.drain(..) {
+                self.print_context(name, number, &text, writer)
+                    .map_err(write_error)?;
+            }
+            self.print_selected(name, number, text, writer)
+                .map_err(write_error)?;
+            self.printed_group = true;
+            last_printed = Some(number);
+            after_left = options.after;
+        }
+
+        match options.mode {
+            Mode::Count => {
+                if let Some(name) = name {
+                    write!(writer, "{}:", name).map_err(write_error)?;
+                }
+                writeln!(writer, "{}", found.count).map_err(write_error)?;
+            }
+            // The name is the whole output, so it is printed even for a single input.
+            Mode::FilesWithMatches if found.count > 0 => {
+                writeln!(writer, "{}", source_name).map_err(write_error)?;
+            }
+            _ => {}
+        }
+        found.binary |= self.hid_binary;
+        Ok(found)
+    }
+
+    fn print_selected<W: Write>(
+        &mut self,
+        name: Option<&str>,
+        number: u64,
+        text: &[u8],
+        writer: &mut W,
+    ) -> io::Result<()> {
+        if self.options.mode == Mode::Lines {
+            return self.print_line(name, number, b':', text, writer);
+        }
+        // With -v the selected lines are the ones without matches, so there is nothing to show.
+        if self.options.invert {
+            return Ok(());
+        }
+        let matches: Vec<_> = self.regex.find_iter(text).collect();
+        for (start, end) in matches {
+            if start < end {
+                self.print_line(name, number, b':', &text[start..end], writer)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Prints a line of context. With `-o` it is only counted, for the `--` separators.
+    fn print_context<W: Write>(
+        &mut self,
+        name: Option<&str>,
+        number: u64,
+        text: &[u8],
+        writer: &mut W,
+    ) -> io::Result<()> {
+        if self.options.mode != Mode::Lines {
+            return Ok(());
+        }
+        self.print_line(name, number, b'-', text, writer)
+    }
+
+    /// Prints `text` with the prefixes asked for, `separator` telling selected lines (`:`)
+    /// from context lines (`-`).
+    fn print_line<W: Write>(
+        &mut self,
+        name: Option<&str>,
+        number: u64,
+        separator: u8,
+        text: &[u8],
+        writer: &mut W,
+    ) -> io::Result<()> {
+        if !self.options.text && std::str::from_utf8(text).is_err() {
+            self.hid_binary = true;
+            return Ok(());
+        }
+        if let Some(name) = name {
+            writer.write_all(name.as_bytes())?;
+            writer.write_all(&[separator])?;
+        }
+        if self.options.line_number {
+            write!(writer, "{}", number)?;
+            writer.write_all(&[separator])?;
+        }
+        writer.write_all(text)?;
+        writer.write_all(b"\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(pattern: &str, options: Options, input: &[u8]) -> (String, Found) {
+        let regex = RegexBuilder::new(Syntax::Extended)
+            .build(&[pattern])
+            .unwrap();
+        let mut searcher = Searcher::new(regex, options);
+        let mut output = Vec::new();
+        let show_name = options.with_filename.unwrap_or(false);
+        let found = searcher.search(input, "f", show_name, &mut output).unwrap();
+        (String::from_utf8(output).unwrap(), found)
+    }
+
+    fn grep(pattern: &str, options: Options, input: &str) -> String {
+        search(pattern, options, input.as_bytes()).0
+    }
+
+    fn options() -> Options {
+        Options {
+            mode: Mode::Lines,
+            invert: false,
+            with_filename: None,
+            line_number: false,
+            before: 0,
+            after: 0,
+            text: false,
+        }
+    }
+
+    #[test]
+    fn test_search() {
+        let input = "a\nb\nc\nd\na\nx\ny\nz\na b a";
+        assert_eq!(grep("a", options(), input), "a\na\na b a\n");
+        let numbered = Options {
+            with_filename: Some(true),
+            line_number: true,
+            invert: true,
+            ..options()
+        };
+        assert_eq!(grep("[a-c]", numbered, "a\nd\nc\n"), "f:2:d\n");
+        let count = Options {
+            mode: Mode::Count,
+            ..options()
+        };
+        assert_eq!(grep("a", count, input), "3\n");
+        let only = Options {
+            mode: Mode::OnlyMatching,
+            line_number: true,
+            ..options()
+        };
+        assert_eq!(grep("a|b", only, input), "1:a\n2:b\n5:a\n9:a\n9:b\n9:a\n");
+    }
+
+    #[test]
+    fn test_context() {
+        let input = "a\nb\nc\nd\na\nx\ny\nz\na b a\n";
+        let context = Options {
+            line_number: true,
+            before: 1,
+            after: 1,
+            ..options()
+        };
+        assert_eq!(
+            grep("a", context, input),
+            "1:a\n2-b\n--\n4-d\n5:a\n6-x\n--\n8-z\n9:a b a\n"
+        );
+        let only = Options {
+            mode: Mode::OnlyMatching,
+            ..context
+        };
+        assert_eq!(grep("a", only, input), "1:a\n--\n5:a\n--\n9:a\n9:a\n");
+        let after = Options {
+            after: 2,
+            ..options()
+        };
+        assert_eq!(
+            grep("[ab]", after, input),
+            "a\nb\nc\nd\na\nx\ny\n--\na b a\n"
+        );
+    }
+
+    #[test]
+    fn test_binary() {
+        let (output, found) = search("foo", options(), b"foo\0bar\nfoo\n");
+        assert_eq!((output.as_str(), found.binary), ("", true));
+        let text = Options {
+            text: true,
+            ..options()
+        };
+        let (output, found) = search("foo", text, b"foo\0bar\nfoo\n");
+        assert_eq!((output.as_str(), found.binary), ("foo\0bar\nfoo\n", false));
+        let count = Options {
+            mode: Mode::Count,
+            ..options()
+        };
+        assert_eq!(search("foo", count, b"foo\0\nfoo\n").0, "2\n");
+
+        // Only the lines that are not text are left out.
+        let (output, found) = search("x", options(), b"x\nx\xff\nx\n");
+        assert_eq!((output.as_str(), found.binary), ("x\nx\n", true));
+        let only = Options {
+            mode: Mode::OnlyMatching,
+            ..options()
+        };
+        assert_eq!(search("x", only, b"x\xff\n").0, "x\n");
+    }
+
+    #[test]
+    fn test_filters() {
+        let filters = Filters {
+            include: vec![Pattern::new("*.rs")],
+            exclude: vec![Pattern::new("main*")],
+            exclude_dir: vec![Pattern::new("target")],
+        };
+        assert!(filters.file("lib.rs", false));
+        assert!(!filters.file("main.rs", false));
+        assert!(!filters.file("lib.c", false));
+        assert!(!filters.file("src/main.rs", true));
+        assert!(filters.file("src/lib.rs", true));
+        // `*` matches `/` too.
+        assert!(!filters.file("main/lib.rs", true));
+        assert!(!filters.dir("target", false));
+        assert!(!filters.dir("a/target", true));
+        assert!(filters.dir("target/debug", true));
+        assert!(filters.dir("a/target/", true));
+        assert!(filters.dir("targets", false));
+    }
+}
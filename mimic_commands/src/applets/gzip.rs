@@ -0,0 +1,351 @@
+use crate::args::Command;
+use crate::error::{self, Error, Output, Reporter, Style};
+use crate::inflate::{GzDecoder, GZIP_MAGIC};
+use std::fs::{self, File, FileTimes};
+use std::io::{self, BufRead, BufReader, BufWriter, Cursor, IsTerminal, Read, Write};
+use std::process::exit;
+
+/// Exit status for trouble, and for warnings when there was no trouble.
+const EXIT_TROUBLE: i32 = 1;
+const EXIT_WARNING: i32 = 2;
+
+/// The suffixes of compressed files, and what each is replaced with in the name of the
+/// file decompressed into. They are matched ignoring case.
+const SUFFIXES: [(&str, &str); 6] = [
+    (".gz", ""),
+    ("-gz", ""),
+    (".z", ""),
+    ("-z", ""),
+    ("_z", ""),
+    (".tgz", ".tar"),
+];
+
+pub fn main(args: Vec<String>) {
+    let command = Command::new("gzip", "[OPTION]... [FILE]...")
+        .about("Decompress FILEs in place, or standard input to standard output. Only decompression is supported.")
+        .opt(
+            "-c, --stdout",
+            "write on standard output, keep original files unchanged",
+        )
+        .opt("-d, --decompress", "decompress")
+        .opt(
+            "-f, --force",
+            "overwrite output files and follow links; with -c, copy input that is not compressed as it is",
+        )
+        .opt("-k, --keep", "keep (don't delete) input files")
+        .opt("-t, --test", "test compressed file integrity")
+        .usage_status(EXIT_TROUBLE);
+    let matches = command.parse_or_exit(args);
+    let test = matches.flag("test");
+    if !matches.flag("decompress") && !test {
+        eprintln!("gzip: compression is not supported; use -d to decompress");
+        exit(EXIT_TROUBLE);
+    }
+
+    let mut gzip = Gzip {
+        to_stdout: matches.flag("stdout"),
+        force: matches.flag("force"),
+        keep: matches.flag("keep"),
+        test,
+        writer: BufWriter::new(Output(io::stdout())),
+        reporter: Reporter::new("gzip", Style::Bare),
+        status: 0,
+    };
+    for path in matches.files() {
+        if path == "-" {
+            gzip.stdin();
+        } else {
+            gzip.file(&path);
+        }
+    }
+    gzip.flush();
+    exit(gzip.status);
+}
+
+struct Gzip {
+    to_stdout: bool,
+    force: bool,
+    keep: bool,
+    /// Only check the data, writing nothing.
+    test: bool,
+    writer: BufWriter<Output<io::Stdout>>,
+    /// Reports failures to write to standard output.
+    reporter: Reporter,
+    status: i32,
+}
+
+impl Gzip {
+    fn stdin(&mut self) {
+        let stdin = io::stdin();
+        if stdin.is_terminal() && !self.force {
+            eprintln!("gzip: compressed data not read from a terminal. Use -f to force decompression.\nFor help, type: gzip -h");
+            exit(EXIT_TROUBLE);
+        }
+        self.stream(stdin.lock(), "stdin");
+    }
+
+    fn file(&mut self, path: &str) {
+        let in_place = !self.to_stdout && !self.test;
+        let (path, metadata) = match find(path, in_place && !self.force) {
+            Ok(found) => found,
+            Err((path, e)) => {
+                return self.trouble(&format!("{}: {}", path, error::describe(&e)));
+            }
+        };
+        if metadata.is_dir() {
+            return self.warn(&format!("{} is a directory -- ignored", path));
+        }
+        if in_place && !metadata.is_file() && !self.force {
+            return self.warn(&format!(
+                "{} is not a directory or a regular file - ignored",
+                path
+            ));
+        }
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) => return self.trouble(&format!("{}: {}", path, error::describe(&e))),
+        };
+        if !in_place {
+            return self.stream(BufReader::new(file), &path);
+        }
+
+        let output_path = match output_name(&path) {
+            Some(output_path) => output_path,
+            None => return self.warn(&format!("{}: unknown suffix -- ignored", path)),
+        };
+        if fs::symlink_metadata(&output_path).is_ok() {
+            if !self.force {
+                return self.warn(&format!("{} already exists;\tnot overwritten", output_path));
+            }
+            if let Err(e) = fs::remove_file(&output_path) {
+                return self.trouble(&format!("{}: {}", output_path, error::describe(&e)));
+            }
+        }
+        let output = match File::options()
+            .write(true)
+            .create_new(true)
+            .open(&output_path)
+        {
+            Ok(output) => output,
+            Err(e) => return self.trouble(&format!("{}: {}", output_path, error::describe(&e))),
+        };
+        let mut writer = BufWriter::new(Output(&output));
+        let result =
+            decompress(BufReader::new(file), &path, &mut writer, false).and_then(|garbage| {
+                writer
+                    .flush()
+                    .map_err(|e| Failure::new(Error::writing(e), false, true))?;
+                Ok(garbage)
+            });
+        drop(writer);
+        match result {
+            Ok(garbage) => {
+                // The decompressed file takes after the compressed one, which goes away.
+                if let (Ok(accessed), Ok(modified)) = (metadata.accessed(), metadata.modified()) {
+                    let times = FileTimes::new()
+                        .set_accessed(accessed)
+                        .set_modified(modified);
+                    output.set_times(times).ok();
+                }
+                output.set_permissions(metadata.permissions()).ok();
+                if garbage {
+                    self.trailing_garbage(&path);
+                }
+                if !self.keep {
+                    if let Err(e) = fs::remove_file(&path) {
+                        self.trouble(&format!("{}: {}", path, error::describe(&e)));
+                    }
+                }
+            }
+            Err(failure) => {
+                fs::remove_file(&output_path).ok();
+                self.fail(failure, Some(&output_path));
+            }
+        }
+    }
+
+    /// Decompresses to standard output, or nowhere when testing.
+    fn stream<R: BufRead>(&mut self, reader: R, name: &str) {
+        let result = if self.test {
+            decompress(reader, name, &mut io::sink(), false)
+        } else {
+            decompress(reader, name, &mut self.writer, self.force)
+        };
+        match result {
+            Ok(true) => self.trailing_garbage(name),
+            Ok(false) => {}
+            Err(failure) => self.fail(failure, None),
+        }
+    }
+
+    fn trailing_garbage(&mut self, name: &str) {
+        self.flush();
+        eprintln!();
+        self.warn(&format!(
+            "{}: decompression OK, trailing garbage ignored",
+            name
+        ));
+    }
+
+    /// Reports `failure`, and exits if it is fatal. Errors writing are about `output`, or
+    /// standard output if there is none.
+    fn fail(&mut self, failure: Failure, output: Option<&str>) {
+        match (failure.error, output) {
+            (Error::Write(e), Some(output)) => {
+                self.trouble(&format!("{}: {}", output, error::describe(&e)))
+            }
+            (e @ Error::Write(_), None) => self.reporter.report(&e),
+            (Error::Read(name, e), _) if e.kind() == io::ErrorKind::Unsupported => {
+                // Only the method is worded as something wrong with the file; the flags
+                // are a sentence about it.
+                let message = e.to_string();
+                let separator = if message.starts_with("unknown method") {
+                    ": "
+                } else {
+                    " "
+                };
+                self.trouble(&format!("{}{}{}", name, separator, message));
+            }
+            (e, _) => {
+                self.flush();
+                if failure.blank_line {
+                    eprintln!();
+                }
+                self.trouble(&e.message(Style::Bare));
+            }
+        }
+        if failure.fatal {
+            exit(EXIT_TROUBLE);
+        }
+    }
+
+    fn trouble(&mut self, message: &str) {
+        self.flush();
+        eprintln!("gzip: {}", message);
+        self.status = EXIT_TROUBLE;
+    }
+
+    fn warn(&mut self, message: &str) {
+        eprintln!("gzip: {}", message);
+        if self.status == 0 {
+            self.status = EXIT_WARNING;
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Err(e) = self.writer.flush() {
+            self.reporter.report(&Error::writing(e));
+            exit(EXIT_TROUBLE);
+        }
+    }
+}
+
+/// Finds the file `path` names, trying it with `.gz` added if it does not exist. Links
+/// are not followed if `no_links`; they are an error then.
+fn find(path: &str, no_links: bool) -> Result<(String, fs::Metadata), (String, io::Error)> {
+    let look = |path: &str| {
+        let metadata = fs::symlink_metadata(path)?;
+        if !metadata.file_type().is_symlink() {
+            Ok(metadata)
+        } else if no_links {
+            // ELOOP, as opening with O_NOFOLLOW fails.
+            Err(io::Error::from_raw_os_error(40))
+        } else {
+            fs::metadata(path)
+        }
+    };
+    match look(path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound && output_name(path).is_none() => {
+            let with_suffix = format!("{}.gz", path);
+            look(&with_suffix)
+                .map(|metadata| (with_suffix.clone(), metadata))
+                .map_err(|e| (with_suffix, e))
+        }
+        result => result
+            .map(|metadata| (path.to_string(), metadata))
+            .map_err(|e| (path.to_string(), e)),
+    }
+}
+
+/// A failure to decompress an input, and what GNU gzip makes of it.
+struct Failure {
+    error: Error,
+    /// Whether the message follows a blank line, as it does for data that is broken.
+    blank_line: bool,
+    /// Whether the inputs left are given up on.
+    fatal: bool,
+}
+
+impl Failure {
+    fn new(error: Error, blank_line: bool, fatal: bool) -> Self {
+        Failure {
+            error,
+            blank_line,
+            fatal,
+        }
+    }
+}
+
+/// Decompresses `reader` into `writer`, telling whether garbage followed the data. What
+/// is not compressed is copied as it is if `copy_plain`, as with `-c -f`.
+fn decompress<R: BufRead, W: Write>(
+    mut reader: R,
+    name: &str,
+    writer: &mut W,
+    copy_plain: bool,
+) -> Result<bool, Failure> {
+    let broken = |e| Failure::new(Error::copying(name, e), true, true);
+    let mut magic = Vec::with_capacity(GZIP_MAGIC.len());
+    (&mut reader)
+        .take(GZIP_MAGIC.len() as u64)
+        .read_to_end(&mut magic)
+        .map_err(broken)?;
+    let short = magic.len() < GZIP_MAGIC.len();
+    let plain = magic != GZIP_MAGIC;
+    let mut reader = Cursor::new(magic).chain(reader);
+    if plain && copy_plain {
+        io::copy(&mut reader, writer).map_err(broken)?;
+        return Ok(false);
+    }
+    let mut decoder = GzDecoder::new(reader);
+    io::copy(&mut decoder, writer).map_err(|e| {
+        // Input that is not gzip, or gzip of a kind not understood, is passed over; data
+        // that breaks off, even before there is any telling what it is, ends everything.
+        let (blank_line, fatal) = if plain && !short {
+            (true, false)
+        } else if e.kind() == io::ErrorKind::Unsupported {
+            (false, false)
+        } else {
+            (true, true)
+        };
+        Failure::new(Error::copying(name, e), blank_line, fatal)
+    })?;
+    Ok(decoder.trailing_garbage())
+}
+
+/// The name a compressed file is decompressed into, if it has a known suffix.
+fn output_name(path: &str) -> Option<String> {
+    let name_start = path.rfind('/').map_or(0, |i| i + 1);
+    SUFFIXES.iter().find_map(|(suffix, replacement)| {
+        let start = path.len().checked_sub(suffix.len())?;
+        let matches = start > name_start
+            && path.is_char_boundary(start)
+            && path[start..].eq_ignore_ascii_case(suffix);
+        matches.then(|| format!("{}{}", &path[..start], replacement))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_name() {
+        assert_eq!(output_name("a.gz").as_deref(), Some("a"));
+        assert_eq!(output_name("dir/A.GZ").as_deref(), Some("dir/A"));
+        assert_eq!(output_name("x.tgz").as_deref(), Some("x.tar"));
+        assert_eq!(output_name("plain"), None);
+        assert_eq!(output_name(".gz"), None);
+        assert_eq!(output_name("dir.gz/.gz"), None);
+    }
+}
@@ -0,0 +1,228 @@
+use crate::args::{parse_size, Command};
+use crate::error::{Error, Output, Reporter, Style};
+use crate::inflate;
+use crate::ring::{ByteRing, LineRing};
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter, Read, Write};
+
+/// What part of each input to print.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    /// The first N lines.
+    Lines(u64),
+    /// The first N bytes.
+    Bytes(u64),
+    /// Everything but the last N lines (`-n -N`).
+    AllButLastLines(usize),
+    /// Everything but the last N bytes (`-c -N`).
+    AllButLastBytes(usize),
+}
+
+pub fn main(args: Vec<String>) {
+    let command = Command::new("head", "[OPTION]... [FILE]...")
+        .about("Print the first 10 lines of each FILE to standard output.")
+        .opt(
+            "-c, --bytes=[-]NUM",
+            "print the first NUM bytes of each file; with the leading '-', print all but the last NUM bytes",
+        )
+        .opt(
+            "--decompress",
+            "decompress gzip input; other input is read as it is (-z is not short for this, as GNU head takes it for NUL-terminated lines)",
+        )
+        .opt(
+            "-n, --lines=[-]NUM",
+            "print the first NUM lines instead of the first 10; with the leading '-', print all but the last NUM lines",
+        )
+        .opt("-q, --quiet, --silent", "never print headers giving file names")
+        .opt("-v, --verbose", "always print headers giving file names");
+    let matches = command.parse_or_exit(args);
+
+    let mode = match matches.last_of(&["bytes", "lines"]) {
+        Some(key) => {
+            let value = matches.value(key).unwrap_or_default();
+            parse_mode(key, value).unwrap_or_else(|| {
+                let unit = if key == "bytes" { "bytes" } else { "lines" };
+                command.exit_with_usage_error(&format!("invalid number of {}: '{}'", unit, value))
+            })
+        }
+        None => Mode::Lines(10),
+    };
+
+    let paths = matches.files();
+    let print_headers = match matches.last_of(&["quiet", "verbose"]) {
+        Some("quiet") => false,
+        Some(_) => true,
+        None => paths.len() > 1,
+    };
+
+    let decompress = matches.flag("decompress");
+    let mut reporter = Reporter::new("head", Style::Verbose);
+    let mut writer = BufWriter::new(Output(io::stdout()));
+    // Unopenable files get no header, and no blank line is left for them.
+    let mut header_printed = false;
+    for path in &paths {
+        let header = print_headers.then(|| {
+            let separator = if header_printed { "\n" } else { "" };
+            format!("{}==> {} <==\n", separator, display_name(path))
+        });
+        let result = print_file_head(path, &mut writer, mode, header.as_deref(), decompress);
+        header_printed |= header.is_some() && !matches!(result, Err(Error::Open(..)));
+        reporter.check(result);
+    }
+    reporter.finish();
+}
+
+fn parse_mode(key: &str, value: &str) -> Option<Mode> {
+    let (all_but_last, number) = match value.strip_prefix('-') {
+        Some(number) => (true, number),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let number = parse_size(number)?;
+    let mode = match (key, all_but_last) {
+        ("bytes", false) => Mode::Bytes(number),
+        ("bytes", true) => Mode::AllButLastBytes(usize::try_from(number).ok()?),
+        (_, false) => Mode::Lines(number),
+        (_, true) => Mode::AllButLastLines(usize::try_from(number).ok()?),
+    };
+    Some(mode)
+}
+
+fn display_name(path: &str) -> &str {
+    if path == "-" {
+        "standard input"
+    } else {
+        path
+    }
+}
+
+fn print_file_head<W: Write>(
+    path: &str,
+    writer: &mut W,
+    mode: Mode,
+    header: Option<&str>,
+    decompress: bool,
+) -> Result<(), Error> {
+    let reader: Box<dyn Read> = if path == "-" {
+        Box::new(io::stdin())
+    } else {
+        Box::new(File::open(path).map_err(|e| Error::Open(path.to_string(), e))?)
+    };
+    let result = writer
+        .write_all(header.unwrap_or_default().as_ref())
+        .and_then(|_| {
+            if decompress {
+                head(inflate::decompressed(BufReader::new(reader))?, writer, mode)
+            } else {
+                head(reader, writer, mode)
+            }
+        });
+    // Flushed per file, even after a failure, so the output stays in order with the error messages.
+    let flushed = writer.flush();
+    result
+        .and(flushed)
+        .map_err(|e| Error::copying(display_name(path), e))
+}
+
+fn head<R: Read, W: Write>(reader: R, writer: &mut W, mode: Mode) -> io::Result<()> {
+    match mode {
+        Mode::Lines(max_count) => head_lines(reader, writer, max_count),
+        Mode::Bytes(max_size) => head_bytes(reader, writer, max_size),
+        Mode::AllButLastLines(count) => {
+            let mut ring = LineRing::new(count, b'\n');
+            for_each_chunk(reader, |chunk| {
+                ring.push(chunk, |line| writer.write_all(line))
+            })
+        }
+        Mode::AllButLastBytes(size) => {
+            let mut ring = ByteRing::new(size);
+            for_each_chunk(reader, |chunk| {
+                ring.push(chunk, |bytes| writer.write_all(bytes))
+            })
+        }
+    }
+}
+
+fn head_lines<R: Read, W: Write>(mut reader: R, writer: &mut W, max_count: u64) -> io::Result<()> {
+    let mut buffer = [0; 1024 * 4];
+    let mut line_count = 0;
+    while line_count < max_count {
+        let size = reader.read(&mut buffer[..])?;
+        if size == 0 {
+            break;
+        }
+
+        let mut print_size = 0;
+        for b in &buffer[..size] {
+            print_size += 1;
+
+            if *b == b'\n' {
+                line_count += 1;
+                if line_count == max_count {
+                    break;
+                }
+            }
+        }
+
+        writer.write_all(&buffer[..print_size])?;
+    }
+    Ok(())
+}
+
+fn head_bytes<R: Read, W: Write>(reader: R, writer: &mut W, max_size: u64) -> io::Result<()> {
+    io::copy(&mut reader.take(max_size), writer)?;
+    Ok(())
+}
+
+fn for_each_chunk<R, F>(mut reader: R, mut f: F) -> io::Result<()>
+where
+    R: Read,
+    F: FnMut(&[u8]) -> io::Result<()>,
+{
+    let mut buffer = [0; 1024 * 4];
+    loop {
+        let size = reader.read(&mut buffer[..])?;
+        if size == 0 {
+            return Ok(());
+        }
+        f(&buffer[..size])?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(input: &str, mode: Mode) -> String {
+        let mut output = Vec::new();
+        head(input.as_bytes(), &mut output, mode).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_head() {
+        assert_eq!(run("1\n2\n3\n4", Mode::Lines(2)), "1\n2\n");
+        assert_eq!(run("1\n2\n3\n4", Mode::Lines(10)), "1\n2\n3\n4");
+        assert_eq!(run("1\n2\n3\n4", Mode::Lines(0)), "");
+        assert_eq!(run("1\n2\n3\n4", Mode::Bytes(3)), "1\n2");
+        assert_eq!(run("1\n2\n3\n4", Mode::AllButLastLines(1)), "1\n2\n3\n");
+        assert_eq!(run("1\n2\n3\n4\n", Mode::AllButLastLines(1)), "1\n2\n3\n");
+        assert_eq!(
+            run("1\n2\n3\n4\n", Mode::AllButLastLines(0)),
+            "1\n2\n3\n4\n"
+        );
+        assert_eq!(run("1\n2\n", Mode::AllButLastLines(5)), "");
+        assert_eq!(run("1\n2\n3\n4", Mode::AllButLastBytes(3)), "1\n2\n");
+        assert_eq!(run("1\n2\n", Mode::AllButLastBytes(5)), "");
+    }
+
+    #[test]
+    fn test_parse_mode() {
+        assert_eq!(parse_mode("lines", "5"), Some(Mode::Lines(5)));
+        assert_eq!(parse_mode("lines", "-5"), Some(Mode::AllButLastLines(5)));
+        assert_eq!(parse_mode("bytes", "1K"), Some(Mode::Bytes(1024)));
+        assert_eq!(parse_mode("bytes", "-2"), Some(Mode::AllButLastBytes(2)));
+        assert_eq!(parse_mode("lines", "x"), None);
+    }
+}
@@ -0,0 +1,618 @@
+use crate::args::Command;
+use crate::error::{self, Error, Output, Reporter, Style};
+use crate::human;
+use crate::terminal;
+use crate::time::{self, LocalTime};
+use crate::unicode::str_width;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs::{self, Metadata};
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+/// Exit status for trouble with an operand, as opposed to something found below it.
+const EXIT_SERIOUS: i32 = 2;
+
+/// The narrowest a column gets, a one letter name and the gap after it.
+const MIN_COLUMN_WIDTH: usize = 3;
+
+/// Columns are padded with tabs as far as they go, then spaces, unless the width is
+/// unlimited.
+const TAB_SIZE: usize = 8;
+
+/// How long ago a time may be and still be shown with the hour rather than the year:
+/// half a Gregorian year, as GNU ls has it.
+const SIX_MONTHS: i64 = 31_556_952 / 2;
+
+pub fn main(args: Vec<String>) {
+    let command = Command::new("ls", "[OPTION]... [FILE]...")
+        .about(
+            "List information about the FILEs (the current directory by default), sorted by name.",
+        )
+        .opt("-a, --all", "do not ignore entries starting with .")
+        .opt("-C", "list entries by columns")
+        .opt(
+            "-h, --human-readable",
+            "with -l, print sizes like 1K 234M 2G",
+        )
+        .opt("-l", "use a long listing format")
+        .opt("-r, --reverse", "reverse the order of the sort")
+        .opt("-R, --recursive", "list subdirectories recursively")
+        .opt("-S", "sort by file size, largest first")
+        .opt("-t", "sort by modification time, newest first")
+        .opt(
+            "-w, --width=COLS",
+            "assume the screen is COLS columns wide; 0 means no limit",
+        )
+        .opt("-1", "list one file per line");
+    let matches = command.parse_or_exit(args);
+    let format = match matches.last_of(&["l", "C", "1"]) {
+        Some("l") => Format::Long,
+        Some("C") => Format::Columns,
+        Some(_) => Format::OnePerLine,
+        None if io::stdout().is_terminal() => Format::Columns,
+        None => Format::OnePerLine,
+    };
+    let width = match matches.value("width") {
+        Some(value) => match value.parse() {
+            Ok(0) => usize::MAX,
+            Ok(width) => width,
+            // GNU ls does not suggest --help for this one.
+            Err(_) => {
+                eprintln!("ls: invalid line width: '{}'", value);
+                exit(EXIT_SERIOUS);
+            }
+        },
+        None => terminal::width(1),
+    };
+    let sort = match matches.last_of(&["S", "t"]) {
+        Some("S") => Sort::Size,
+        Some("t") => Sort::Time,
+        _ => Sort::Name,
+    };
+
+    let mut ls = Ls {
+        all: matches.flag("all"),
+        human: matches.flag("human-readable"),
+        recursive: matches.flag("recursive"),
+        reverse: matches.flag("reverse"),
+        format,
+        sort,
+        width,
+        now: time::now(),
+        users: None,
+        groups: None,
+        writer: BufWriter::new(Output(io::stdout())),
+        reporter: Reporter::new("ls", Style::Bare),
+        serious: false,
+        printed: false,
+    };
+
+    let mut operands = matches.operands().to_vec();
+    if operands.is_empty() {
+        operands.push(".".to_string());
+    }
+    let mut files = Vec::new();
+    let mut directories = Vec::new();
+    for operand in &operands {
+        // Links given as operands are followed, unless they are to be shown as links.
+        let metadata = match format {
+            Format::Long => fs::symlink_metadata(operand),
+            _ => fs::metadata(operand).or_else(|e| match fs::symlink_metadata(operand) {
+                Ok(metadata) if metadata.file_type().is_symlink() => Ok(metadata),
+                _ => Err(e),
+            }),
+        };
+        match metadata {
+            Ok(metadata) => {
+                let file = File {
+                    name: operand.into(),
+                    path: operand.into(),
+                    metadata,
+                };
+                if file.metadata.is_dir() {
+                    directories.push(file);
+                } else {
+                    files.push(file);
+                }
+            }
+            Err(e) => {
+                ls.serious = true;
+                ls.reporter.fail(&format!(
+                    "cannot access '{}': {}",
+                    operand,
+                    error::describe(&e)
+                ));
+            }
+        }
+    }
+
+    ls.sort(&mut files);
+    ls.sort(&mut directories);
+    if !files.is_empty() {
+        ls.print(&files);
+        ls.printed = true;
+    }
+    let headers = ls.recursive || operands.len() > 1;
+    for directory in directories {
+        ls.list(&directory.path, headers, true);
+    }
+    ls.flush();
+    if ls.serious {
+        exit(EXIT_SERIOUS);
+    }
+    ls.reporter.finish();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    OnePerLine,
+    /// Names in columns, filled top to bottom, as many as fit across.
+    Columns,
+    /// `-l`: a line of details for each file.
+    Long,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Sort {
+    Name,
+    /// Newest first.
+    Time,
+    /// Largest first.
+    Size,
+}
+
+/// A file to be listed.
+struct File {
+    /// The name shown: the entry name in a directory, or the operand as given.
+    name: OsString,
+    path: PathBuf,
+    metadata: Metadata,
+}
+
+struct Ls {
+    all: bool,
+    human: bool,
+    recursive: bool,
+    reverse: bool,
+    format: Format,
+    sort: Sort,
+    /// The width of the screen in columns, `usize::MAX` if unlimited.
+    width: usize,
+    /// When the listing started, which decides whether times are recent.
+    now: (i64, i64),
+    /// User and group names by ID, read when first needed.
+    users: Option<HashMap<u32, String>>,
+    groups: Option<HashMap<u32, String>>,
+    writer: BufWriter<Output<io::Stdout>>,
+    reporter: Reporter,
+    /// Whether an operand could not be listed, which fails with a status of its own.
+    serious: bool,
+    /// Whether anything was printed yet, so directories after it get a blank line.
+    printed: bool,
+}
+
+impl Ls {
+    fn sort(&self, files: &mut [File]) {
+        files.sort_by(|a, b| {
+            let order = match self.sort {
+                Sort::Name => Ordering::Equal,
+                Sort::Time => modified(&b.metadata).cmp(&modified(&a.metadata)),
+                Sort::Size => b.metadata.len().cmp(&a.metadata.len()),
+            }
+            .then_with(|| a.name.as_bytes().cmp(b.name.as_bytes()));
+            if self.reverse {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+    }
+
+    /// Lists the contents of a directory, then those of its subdirectories if recursive.
+    fn list(&mut self, path: &Path, header: bool, operand: bool) {
+        let mut files = Vec::new();
+        if self.all {
+            for name in [".", ".."] {
+                if let Ok(metadata) = fs::symlink_metadata(path.join(name)) {
+                    files.push(File {
+                        name: name.into(),
+                        path: path.join(name),
+                        metadata,
+                    });
+                }
+            }
+        }
+        let entries = fs::read_dir(path).and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|entry| entry.file_name()))
+                .collect::<io::Result<Vec<_>>>()
+        });
+        let names = match entries {
+            Ok(names) => names,
+            Err(e) => {
+                self.serious |= operand;
+                self.fail(&format!(
+                    "cannot open directory '{}': {}",
+                    path.display(),
+                    error::describe(&e)
+                ));
+                return;
+            }
+        };
+        for name in names {
+            if !self.all && name.as_bytes().starts_with(b".") {
+                continue;
+            }
+            let path = path.join(&name);
+            match fs::symlink_metadata(&path) {
+                Ok(metadata) => files.push(File {
+                    name,
+                    path,
+                    metadata,
+                }),
+                Err(e) => self.fail(&format!(
+                    "cannot access '{}': {}",
+                    path.display(),
+                    error::describe(&e)
+                )),
+            }
+        }
+        self.sort(&mut files);
+
+        let mut out = Vec::new();
+        if header {
+            if self.printed {
+                out.push(b'\n');
+            }
+            out.extend_from_slice(path.as_os_str().as_bytes());
+            out.extend_from_slice(b":\n");
+        }
+        if self.format == Format::Long {
+            let blocks: u64 = files.iter().map(|file| file.metadata.blocks()).sum();
+            let total = if self.human {
+                human::size(blocks * 512)
+            } else {
+                blocks.div_ceil(2).to_string()
+            };
+            out.extend_from_slice(format!("total {}\n", total).as_bytes());
+        }
+        self.write(&out);
+        self.print(&files);
+        self.printed = true;
+
+        if self.recursive {
+            for file in &files {
+                if file.metadata.is_dir() && file.name != "." && file.name != ".." {
+                    self.list(&file.path, true, false);
+                }
+            }
+        }
+    }
+
+    /// Prints the files in the chosen format.
+    fn print(&mut self, files: &[File]) {
+        let out = match self.format {
+            Format::OnePerLine => {
+                let mut out = Vec::new();
+                for file in files {
+                    out.extend_from_slice(file.name.as_bytes());
+                    out.push(b'\n');
+                }
+                out
+            }
+            Format::Columns => columns(files, self.width),
+            Format::Long => self.long(files),
+        };
+        self.write(&out);
+    }
+
+    /// The `-l` lines, with each field as wide as the widest of its kind.
+    fn long(&mut self, files: &[File]) -> Vec<u8> {
+        let rows: Vec<[String; 5]> = files
+            .iter()
+            .map(|file| {
+                let metadata = &file.metadata;
+                [
+                    mode_string(metadata),
+                    metadata.nlink().to_string(),
+                    self.user(metadata.uid()),
+                    self.group(metadata.gid()),
+                    String::new(),
+                ]
+            })
+            .collect();
+        let widest = |field: usize| {
+            rows.iter()
+                .map(|row| str_width(&row[field]))
+                .max()
+                .unwrap_or(0)
+        };
+        let (links, user, group) = (widest(1), widest(2), widest(3));
+
+        // Devices show their major and minor numbers where the size would be, each
+        // column of numbers aligned on its own.
+        let devices: Vec<Option<(u64, u64)>> = files
+            .iter()
+            .map(|file| {
+                let file_type = file.metadata.file_type();
+                (file_type.is_char_device() || file_type.is_block_device())
+                    .then(|| device_numbers(file.metadata.rdev()))
+            })
+            .collect();
+        let numbers_width = |number: fn(&(u64, u64)) -> u64| {
+            devices
+                .iter()
+                .flatten()
+                .map(|device| number(device).to_string().len())
+                .max()
+        };
+        let (major, minor) = (numbers_width(|d| d.0), numbers_width(|d| d.1));
+        let sizes: Vec<String> = files
+            .iter()
+            .zip(&devices)
+            .map(|(file, device)| match device {
+                Some((major_number, minor_number)) => format!(
+                    "{:>major$}, {:>minor$}",
+                    major_number,
+                    minor_number,
+                    major = major.unwrap_or(0),
+                    minor = minor.unwrap_or(0)
+                ),
+                None if self.human => human::size(file.metadata.len()),
+                None => file.metadata.len().to_string(),
+            })
+            .collect();
+        let size = sizes.iter().map(String::len).max().unwrap_or(0);
+
+        let mut out = Vec::new();
+        for ((file, row), file_size) in files.iter().zip(&rows).zip(&sizes) {
+            let [mode, nlink, owner, group_name, _] = row;
+            let line = format!(
+                "{} {:>links$} {:<user$} {:<group$} {:>size$} {} ",
+                mode,
+                nlink,
+                owner,
+                group_name,
+                file_size,
+                self.date(&file.metadata),
+            );
+            out.extend_from_slice(line.as_bytes());
+            out.extend_from_slice(file.name.as_bytes());
+            if file.metadata.file_type().is_symlink() {
+                if let Ok(target) = fs::read_link(&file.path) {
+                    out.extend_from_slice(b" -> ");
+                    out.extend_from_slice(target.as_os_str().as_bytes());
+                }
+            }
+            out.push(b'\n');
+        }
+        out
+    }
+
+    /// The modification time, with the hour if it is within the last six months and the
+    /// year otherwise.
+    fn date(&self, metadata: &Metadata) -> String {
+        let (seconds, nanoseconds) = modified(metadata);
+        let six_months_ago = (self.now.0 - SIX_MONTHS, self.now.1);
+        let recent = six_months_ago < (seconds, nanoseconds) && (seconds, nanoseconds) < self.now;
+        LocalTime::new(seconds, nanoseconds).short(recent)
+    }
+
+    fn user(&mut self, uid: u32) -> String {
+        let users = self.users.get_or_insert_with(|| names("/etc/passwd"));
+        users.get(&uid).cloned().unwrap_or_else(|| uid.to_string())
+    }
+
+    fn group(&mut self, gid: u32) -> String {
+        let groups = self.groups.get_or_insert_with(|| names("/etc/group"));
+        groups.get(&gid).cloned().unwrap_or_else(|| gid.to_string())
+    }
+
+    fn write(&mut self, data: &[u8]) {
+        if let Err(e) = self.writer.write_all(data) {
+            self.reporter.report(&Error::writing(e));
+            exit(EXIT_SERIOUS);
+        }
+    }
+
+    /// Reports a failure that does not stop the listing. As with GNU ls, what was listed
+    /// so far comes first.
+    fn fail(&mut self, message: &str) {
+        self.flush();
+        self.reporter.fail(&message);
+    }
+
+    fn flush(&mut self) {
+        if let Err(e) = self.writer.flush() {
+            self.reporter.report(&Error::writing(e));
+            exit(EXIT_SERIOUS);
+        }
+    }
+}
+
+fn modified(metadata: &Metadata) -> (i64, i64) {
+    (metadata.mtime(), metadata.mtime_nsec())
+}
+
+/// The names by ID in a file laid out like `/etc/passwd`: `name:password:id:...`.
+fn names(path: &str) -> HashMap<u32, String> {
+    let text = fs::read_to_string(path).unwrap_or_default();
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let id = fields.nth(1)?.parse().ok()?;
+            Some((id, name.to_string()))
+        })
+        .collect()
+}
+
+/// The major and minor numbers of a device, split out as glibc's `major` and `minor` do.
+fn device_numbers(rdev: u64) -> (u64, u64) {
+    let major = ((rdev >> 8) & 0xfff) | ((rdev >> 32) & !0xfff);
+    let minor = (rdev & 0xff) | ((rdev >> 12) & !0xff);
+    (major, minor)
+}
+
+/// The type and permissions as `-l` shows them, e.g. `drwxr-xr-x`.
+fn mode_string(metadata: &Metadata) -> String {
+    let file_type = metadata.file_type();
+    let kind = if file_type.is_dir() {
+        'd'
+    } else if file_type.is_symlink() {
+        'l'
+    } else if file_type.is_char_device() {
+        'c'
+    } else if file_type.is_block_device() {
+        'b'
+    } else if file_type.is_fifo() {
+        'p'
+    } else if file_type.is_socket() {
+        's'
+    } else {
+        '-'
+    };
+    let mode = metadata.mode();
+    let bit = |mask: u32, c: char| if mode & mask != 0 { c } else { '-' };
+    // The execute bit shares its place with set-user-ID, set-group-ID and sticky.
+    let execute = |mask: u32, special: u32, set: char| match (mode & mask != 0, mode & special != 0)
+    {
+        (true, true) => set,
+        (false, true) => set.to_ascii_uppercase(),
+        (true, false) => 'x',
+        (false, false) => '-',
+    };
+    [
+        kind,
+        bit(0o400, 'r'),
+        bit(0o200, 'w'),
+        execute(0o100, 0o4000, 's'),
+        bit(0o040, 'r'),
+        bit(0o020, 'w'),
+        execute(0o010, 0o2000, 's'),
+        bit(0o004, 'r'),
+        bit(0o002, 'w'),
+        execute(0o001, 0o1000, 't'),
+    ]
+    .iter()
+    .collect()
+}
+
+/// The names laid out in as many columns as fit in `width`, filled top to bottom.
+fn columns(files: &[File], width: usize) -> Vec<u8> {
+    let widths: Vec<usize> = files
+        .iter()
+        .map(|file| str_width(&file.name.to_string_lossy()))
+        .collect();
+    let column_widths = column_widths(&widths, width);
+    let rows = widths.len().div_ceil(column_widths.len().max(1));
+    let tab_size = if width == usize::MAX { 0 } else { TAB_SIZE };
+
+    let mut out = Vec::new();
+    for row in 0..rows {
+        let mut position = 0;
+        let mut i = row;
+        for column_width in &column_widths {
+            out.extend_from_slice(files[i].name.as_bytes());
+            let end = position + widths[i];
+            i += rows;
+            if i >= files.len() {
+                break;
+            }
+            indent(&mut out, end, position + column_width, tab_size);
+            position += column_width;
+        }
+        out.push(b'\n');
+    }
+    out
+}
+
+/// The widths of the columns, gaps included, for the most columns whose lines stay
+/// narrower than `width`; tried as GNU ls tries them, so the layouts agree.
+fn column_widths(widths: &[usize], width: usize) -> Vec<usize> {
+    if width == usize::MAX {
+        // Everything fits on one line.
+        return widths.iter().map(|w| w + 2).collect();
+    }
+    let most = (width / MIN_COLUMN_WIDTH).clamp(1, widths.len().max(1));
+    // For each number of columns: whether it still fits, the line length and the widths.
+    let mut layouts: Vec<(bool, usize, Vec<usize>)> = (1..=most)
+        .map(|columns| {
+            (
+                true,
+                columns * MIN_COLUMN_WIDTH,
+                vec![MIN_COLUMN_WIDTH; columns],
+            )
+        })
+        .collect();
+    for (i, &name_width) in widths.iter().enumerate() {
+        for (last, (fits, line, column_widths)) in layouts.iter_mut().enumerate() {
+            if !*fits {
+                continue;
+            }
+            let rows = widths.len().div_ceil(last + 1);
+            let column = i / rows;
+            let needed = name_width + if column == last { 0 } else { 2 };
+            if column_widths[column] < needed {
+                *line += needed - column_widths[column];
+                column_widths[column] = needed;
+                *fits = *line < width;
+            }
+        }
+    }
+    let chosen = layouts.iter().rposition(|(fits, _, _)| *fits).unwrap_or(0);
+    layouts.swap_remove(chosen).2
+}
+
+/// Pads from column `from` to column `to` with tabs where a whole tab fits, as GNU ls
+/// does, or only spaces if `tab_size` is 0.
+fn indent(out: &mut Vec<u8>, mut from: usize, to: usize, tab_size: usize) {
+    while from < to {
+        if tab_size != 0 && to / tab_size > (from + 1) / tab_size {
+            out.push(b'\t');
+            from += tab_size - from % tab_size;
+        } else {
+            out.push(b' ');
+            from += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_column_widths() {
+        // Five columns make two rows, of which only three columns get used.
+        let widths = [4, 1, 1, 1, 1, 1];
+        assert_eq!(column_widths(&widths, 20), vec![6, 3, 3, 3, 3]);
+        assert_eq!(column_widths(&widths, 12), vec![6, 3]);
+        assert_eq!(column_widths(&[10, 10], 5), vec![10]);
+        assert_eq!(column_widths(&[], 80), vec![MIN_COLUMN_WIDTH]);
+    }
+
+    #[test]
+    fn test_indent() {
+        let mut out = Vec::new();
+        indent(&mut out, 3, 13, TAB_SIZE);
+        assert_eq!(out, b"\t     ");
+        out.clear();
+        indent(&mut out, 6, 8, TAB_SIZE);
+        assert_eq!(out, b"\t");
+        out.clear();
+        indent(&mut out, 6, 8, 0);
+        assert_eq!(out, b"  ");
+    }
+
+    #[test]
+    fn test_device_numbers() {
+        assert_eq!(device_numbers(0x103), (1, 3));
+        assert_eq!(device_numbers(0x10_082c), (8, 300));
+    }
+}
@@ -0,0 +1,196 @@
+//! The commands themselves, shared by their own binaries and the `mimic` multicall binary.
+pub mod b2sum;
+pub mod base32;
+pub mod base64;
+pub mod basenc;
+pub mod cat;
+pub mod checksum;
+pub mod cmp;
+pub mod csplit;
+pub mod cut;
+pub mod diff;
+pub mod du;
+pub mod find;
+pub mod grep;
+pub mod gzip;
+pub mod head;
+pub mod ls;
+pub mod md5sum;
+pub mod od;
+pub mod paste;
+pub mod pv;
+pub mod rev;
+pub mod sha1sum;
+pub mod sha256sum;
+pub mod sort;
+pub mod split;
+pub mod tac;
+pub mod tail;
+pub mod tee;
+pub mod tr;
+pub mod uniq;
+pub mod wc;
+pub mod xargs;
+pub mod xxd;
+pub mod zcat;
+
+/// A command `mimic` can run.
+#[derive(Debug)]
+pub struct Applet {
+    pub name: &'static str,
+    /// Runs the command with its arguments, excluding the program name. It may exit the process.
+    pub main: fn(Vec<String>),
+}
+
+/// Every applet, in alphabetical order.
+pub const APPLETS: &[Applet] = &[
+    Applet {
+        name: "b2sum",
+        main: b2sum::main,
+    },
+    Applet {
+        name: "base32",
+        main: base32::main,
+    },
+    Applet {
+        name: "base64",
+        main: base64::main,
+    },
+    Applet {
+        name: "basenc",
+        main: basenc::main,
+    },
+    Applet {
+        name: "cat",
+        main: cat::main,
+    },
+    Applet {
+        name: "cmp",
+        main: cmp::main,
+    },
+    Applet {
+        name: "csplit",
+        main: csplit::main,
+    },
+    Applet {
+        name: "cut",
+        main: cut::main,
+    },
+    Applet {
+        name: "diff",
+        main: diff::main,
+    },
+    Applet {
+        name: "du",
+        main: du::main,
+    },
+    Applet {
+        name: "find",
+        main: find::main,
+    },
+    Applet {
+        name: "grep",
+        main: grep::main,
+    },
+    Applet {
+        name: "gzip",
+        main: gzip::main,
+    },
+    Applet {
+        name: "head",
+        main: head::main,
+    },
+    Applet {
+        name: "ls",
+        main: ls::main,
+    },
+    Applet {
+        name: "md5sum",
+        main: md5sum::main,
+    },
+    Applet {
+        name: "od",
+        main: od::main,
+    },
+    Applet {
+        name: "paste",
+        main: paste::main,
+    },
+    Applet {
+        name: "pv",
+        main: pv::main,
+    },
+    Applet {
+        name: "rev",
+        main: rev::main,
+    },
+    Applet {
+        name: "sha1sum",
+        main: sha1sum::main,
+    },
+    Applet {
+        name: "sha256sum",
+        main: sha256sum::main,
+    },
+    Applet {
+        name: "sort",
+        main: sort::main,
+    },
+    Applet {
+        name: "split",
+        main: split::main,
+    },
+    Applet {
+        name: "tac",
+        main: tac::main,
+    },
+    Applet {
+        name: "tail",
+        main: tail::main,
+    },
+    Applet {
+        name: "tee",
+        main: tee::main,
+    },
+    Applet {
+        name: "tr",
+        main: tr::main,
+    },
+    Applet {
+        name: "uniq",
+        main: uniq::main,
+    },
+    Applet {
+        name: "wc",
+        main: wc::main,
+    },
+    Applet {
+        name: "xargs",
+        main: xargs::main,
+    },
+    Applet {
+        name: "xxd",
+        main: xxd::main,
+    },
+    Applet {
+        name: "zcat",
+        main: zcat::main,
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Applet> {
+    APPLETS.iter().find(|applet| applet.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find() {
+        assert!(APPLETS.windows(2).all(|w| w[0].name < w[1].name));
+        assert_eq!(find("wc").map(|applet| applet.name), Some("wc"));
+        assert!(find("mimic").is_none());
+        assert!(find("").is_none());
+    }
+}
@@ -0,0 +1,371 @@
+use crate::args::{self, Command};
+use crate::dump::{self, Kind, Radix, Unit};
+use crate::error::{Error, Output, Reporter, Style};
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Read, Write};
+use std::process::exit;
+use std::vec;
+
+/// How many bytes each line shows.
+const LINE_BYTES: usize = 16;
+
+pub fn main(args: Vec<String>) {
+    let command = Command::new("od", "[OPTION]... [FILE]...")
+        .about("Write an unambiguous representation, octal bytes by default, of FILE to standard output.")
+        .opt(
+            "-A, --address-radix=RADIX",
+            "output format for file offsets; RADIX is one of [doxn], for Decimal, Octal, Hex or None",
+        )
+        .opt("-j, --skip-bytes=BYTES", "skip BYTES input bytes first")
+        .opt(
+            "-N, --read-bytes=BYTES",
+            "limit dump to BYTES input bytes",
+        )
+        .opt("-t, --format=TYPE", "select output format or formats")
+        .opt(
+            "-v, --output-duplicates",
+            "do not use * to mark line suppression",
+        );
+    let matches = command.parse_or_exit(args);
+
+    let radix = match matches.value("address-radix") {
+        None | Some("o") => Radix::Octal,
+        Some("d") => Radix::Decimal,
+        Some("x") => Radix::Hex,
+        Some("n") => Radix::None,
+        Some(radix) => die(&format!(
+            "invalid output address radix '{}'; it must be one character from [doxn]",
+            radix
+        )),
+    };
+    let mut units = Vec::new();
+    for spec in matches.values("format") {
+        units.extend(parse_types(spec).unwrap_or_else(|message| die(&message)));
+    }
+    if units.is_empty() {
+        units.push(Unit {
+            kind: Kind::Octal,
+            size: 2,
+        });
+    }
+    let count = |key: &str, option: &str| {
+        matches.value(key).map(|value| {
+            parse_count(value).unwrap_or_else(|message| {
+                die(&format!("{} -{} argument '{}'", message, option, value))
+            })
+        })
+    };
+    let skip = count("skip-bytes", "j").unwrap_or(0);
+    let limit = count("read-bytes", "N").unwrap_or(u64::MAX);
+    let layout = Layout::new(units, radix);
+
+    let mut reporter = Reporter::new("od", Style::Bare);
+    let result = {
+        let mut inputs = Inputs::new(matches.files(), &mut reporter);
+        if dump::skip(&mut inputs, skip).unwrap_or(0) < skip {
+            die("cannot skip past end of combined input");
+        }
+        let mut writer = BufWriter::new(Output(io::stdout()));
+        let verbose = matches.flag("output-duplicates");
+        od(inputs.take(limit), &mut writer, &layout, skip, verbose).and_then(|_| writer.flush())
+    };
+    if let Err(e) = result {
+        reporter.report(&Error::writing(e));
+    }
+    reporter.finish();
+}
+
+fn die(message: &str) -> ! {
+    eprintln!("od: {}", message);
+    exit(1);
+}
+
+/// Parses `-t` types such as `x1`, `o2`, `d4`, `uL` or `c`, several of which may be run
+/// together. Integers without a size are 4 bytes.
+fn parse_types(spec: &str) -> Result<Vec<Unit>, String> {
+    let mut units = Vec::new();
+    let mut rest = spec;
+    while let Some(letter) = rest.chars().next() {
+        rest = &rest[1..];
+        let kind = match letter {
+            'x' => Kind::Hex,
+            'o' => Kind::Octal,
+            'd' => Kind::Signed,
+            'u' => Kind::Unsigned,
+            'c' => {
+                units.push(Unit {
+                    kind: Kind::Char,
+                    size: 1,
+                });
+                continue;
+            }
+            _ => {
+                return Err(format!(
+                    "invalid character '{}' in type string '{}'",
+                    letter, spec
+                ))
+            }
+        };
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let size = match (&rest[..digits], rest.chars().next()) {
+            ("", Some('C')) => 1,
+            ("", Some('S')) => 2,
+            ("", Some('I')) => 4,
+            ("", Some('L')) => 8,
+            ("", _) => 4,
+            (digits, _) => digits.parse().unwrap_or(0),
+        };
+        rest = match &rest[..digits] {
+            "" if rest.starts_with(['C', 'S', 'I', 'L']) => &rest[1..],
+            _ => &rest[digits..],
+        };
+        if ![1, 2, 4, 8].contains(&size) {
+            return Err(format!(
+                "invalid type string '{}';\nthis system doesn't provide a {}-byte integral type",
+                spec, size
+            ));
+        }
+        units.push(Unit { kind, size });
+    }
+    Ok(units)
+}
+
+/// Parses a byte count for `-j` or `-N`: decimal, octal with a leading `0` or hex with
+/// a leading `0x`, decimal ones taking the usual size suffixes. Errors are the start of
+/// GNU od's message.
+fn parse_count(value: &str) -> Result<u64, &'static str> {
+    if !value.starts_with(|c: char| c.is_ascii_digit()) {
+        return Err("invalid");
+    }
+    let parsed = if let Some(hex) = value.strip_prefix("0x").or(value.strip_prefix("0X")) {
+        u64::from_str_radix(hex, 16).ok()
+    } else if value.len() > 1 && value.starts_with('0') {
+        u64::from_str_radix(&value[1..], 8).ok()
+    } else {
+        args::parse_size(value)
+    };
+    parsed.ok_or("invalid suffix in")
+}
+
+/// Where each column of each kind of unit goes on a line.
+#[derive(Debug)]
+struct Layout {
+    units: Vec<Unit>,
+    radix: Radix,
+    /// The width of the widest kind's units for a whole line, which the others are
+    /// spread out to match.
+    line_width: usize,
+}
+
+impl Layout {
+    fn new(units: Vec<Unit>, radix: Radix) -> Self {
+        let line_width = units
+            .iter()
+            .map(|unit| LINE_BYTES / unit.size * (unit.width() + 1))
+            .max()
+            .unwrap_or(0);
+        Layout {
+            units,
+            radix,
+            line_width,
+        }
+    }
+
+    fn offset_width(&self) -> usize {
+        match self.radix {
+            Radix::Hex => 6,
+            _ => 7,
+        }
+    }
+
+    /// Formats `bytes`, found at `offset`, as one line for each kind of unit.
+    fn format(&self, offset: u64, bytes: &[u8], out: &mut String) {
+        for (i, unit) in self.units.iter().enumerate() {
+            if i == 0 {
+                out.push_str(&self.radix.format(offset, self.offset_width()));
+            } else if self.radix != Radix::None {
+                out.extend(std::iter::repeat_n(' ', self.offset_width()));
+            }
+            // The extra width is shared out among the columns as GNU od does, the earlier
+            // columns taking any remainder.
+            let fields = LINE_BYTES / unit.size;
+            let pad = self.line_width - fields * (unit.width() + 1);
+            let mut pad_remaining = pad;
+            for (field, chunk) in bytes.chunks(unit.size).enumerate() {
+                let next_pad = pad * (fields - field - 1) / fields;
+                out.push(' ');
+                unit.write(chunk, pad_remaining - next_pad + unit.width(), out);
+                pad_remaining = next_pad;
+            }
+            out.push('\n');
+        }
+    }
+}
+
+/// Dumps `reader`, whose first byte is at `offset`, a line at a time. A line the same as
+/// the one before it is shown as `*` unless `verbose`.
+fn od<R: Read, W: Write>(
+    mut reader: R,
+    writer: &mut W,
+    layout: &Layout,
+    mut offset: u64,
+    verbose: bool,
+) -> io::Result<()> {
+    let mut line = [0; LINE_BYTES];
+    let mut previous = None;
+    let mut skipping = false;
+    let mut out = String::new();
+    loop {
+        let size = dump::read_full(&mut reader, &mut line)?;
+        if size == 0 {
+            break;
+        }
+        if !verbose && size == LINE_BYTES && previous == Some(line) {
+            if !skipping {
+                writer.write_all(b"*\n")?;
+                skipping = true;
+            }
+        } else {
+            out.clear();
+            layout.format(offset, &line[..size], &mut out);
+            writer.write_all(out.as_bytes())?;
+            skipping = false;
+        }
+        previous = Some(line);
+        offset += size as u64;
+    }
+    if layout.radix != Radix::None {
+        writeln!(
+            writer,
+            "{}",
+            layout.radix.format(offset, layout.offset_width())
+        )?;
+    }
+    Ok(())
+}
+
+/// The inputs read one after another as one stream. Each is opened only once the ones
+/// before it are used up, and any that fail are reported and passed over.
+struct Inputs<'r> {
+    paths: vec::IntoIter<String>,
+    current: Option<(String, Box<dyn Read>)>,
+    reporter: &'r mut Reporter,
+}
+
+impl<'r> Inputs<'r> {
+    fn new(paths: Vec<String>, reporter: &'r mut Reporter) -> Self {
+        Inputs {
+            paths: paths.into_iter(),
+            current: None,
+            reporter,
+        }
+    }
+}
+
+impl Read for Inputs<'_> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let (path, reader) = match &mut self.current {
+                Some(current) => current,
+                None => {
+                    let path = match self.paths.next() {
+                        Some(path) => path,
+                        None => return Ok(0),
+                    };
+                    let reader: Box<dyn Read> = if path == "-" {
+                        Box::new(io::stdin())
+                    } else {
+                        match File::open(&path) {
+                            Ok(file) => Box::new(file),
+                            Err(e) => {
+                                self.reporter.report(&Error::Open(path, e));
+                                continue;
+                            }
+                        }
+                    };
+                    self.current.insert((path, reader))
+                }
+            };
+            match reader.read(buffer) {
+                Ok(0) => self.current = None,
+                Ok(size) => return Ok(size),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    self.reporter.report(&Error::Read(path.clone(), e));
+                    self.current = None;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(input: &[u8], types: &[&str], radix: Radix, verbose: bool) -> String {
+        let units = types
+            .iter()
+            .flat_map(|spec| parse_types(spec).unwrap())
+            .collect();
+        let mut output = Vec::new();
+        od(input, &mut output, &Layout::new(units, radix), 0, verbose).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let unit = |kind, size| Unit { kind, size };
+        assert_eq!(parse_types("x1").unwrap(), vec![unit(Kind::Hex, 1)]);
+        assert_eq!(
+            parse_types("cdoS").unwrap(),
+            vec![
+                unit(Kind::Char, 1),
+                unit(Kind::Signed, 4),
+                unit(Kind::Octal, 2)
+            ]
+        );
+        assert_eq!(parse_types("uL").unwrap(), vec![unit(Kind::Unsigned, 8)]);
+        assert!(parse_types("x3").is_err());
+        assert!(parse_types("q").is_err());
+        assert_eq!(parse_count("0x10"), Ok(16));
+        assert_eq!(parse_count("010"), Ok(8));
+        assert_eq!(parse_count("1k"), Ok(1024));
+        assert_eq!(parse_count("0"), Ok(0));
+        assert_eq!(parse_count("q"), Err("invalid"));
+        assert_eq!(parse_count("1x"), Err("invalid suffix in"));
+    }
+
+    #[test]
+    fn test_od() {
+        assert_eq!(
+            run(b"hello\n", &["o2"], Radix::Octal, false),
+            "0000000 062550 066154 005157\n0000006\n"
+        );
+        assert_eq!(
+            run(b"ab\n", &["x1", "c"], Radix::Hex, false),
+            "000000  61  62  0a\n         a   b  \\n\n000003\n"
+        );
+        assert_eq!(
+            run(b"\xff\xff\xff\xff", &["d4"], Radix::None, false),
+            format!(" {:>11}\n", -1)
+        );
+        let zeros = [0; 40];
+        assert_eq!(
+            run(&zeros, &["u1"], Radix::Decimal, false),
+            format!(
+                "0000000{0}\n*\n0000032{1}\n0000040\n",
+                "   0".repeat(16),
+                "   0".repeat(8)
+            )
+        );
+        assert_eq!(run(&zeros, &["x8"], Radix::Octal, true).lines().count(), 4);
+        assert_eq!(run(b"", &["c"], Radix::Octal, false), "0000000\n");
+    }
+}
@@ -0,0 +1,286 @@
+use crate::args::Command;
+use crate::error::{Error, Output, Reporter, Style};
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Read, Write};
+use std::process::exit;
+
+pub fn main(args: Vec<String>) {
+    let command = Command::new("paste", "[OPTION]... [FILE]...")
+        .about("Write lines consisting of the sequentially corresponding lines from each FILE, separated by TABs, to standard output.")
+        .opt(
+            "-d, --delimiters=LIST",
+            "reuse characters from LIST instead of TABs",
+        )
+        .opt(
+            "-s, --serial",
+            "paste one file at a time instead of in parallel",
+        )
+        .usage_status(1);
+    let matches = command.parse_or_exit(args);
+
+    let delimiters = match matches.value("delimiters") {
+        Some(list) => parse_delimiters(list).unwrap_or_else(|| {
+            eprintln!(
+                "paste: delimiter list ends with an unescaped backslash: {}",
+                list
+            );
+            exit(1);
+        }),
+        None => vec![b"\t".to_vec()],
+    };
+
+    let mut reporter = Reporter::new("paste", Style::Bare);
+    let paths = matches.files();
+    let mut writer = BufWriter::new(Output(io::stdout()));
+    if matches.flag("serial") {
+        // One file is open at a time, and one that cannot be read is passed over.
+        // Each is flushed so the output stays in order with the error messages.
+        let mut stdin = LineReader::new(io::stdin());
+        for path in &paths {
+            let result = if path == "-" {
+                paste_serial(&mut stdin, &mut writer, &delimiters)
+            } else {
+                match File::open(path) {
+                    Ok(file) => paste_serial(&mut LineReader::new(file), &mut writer, &delimiters),
+                    Err(e) => {
+                        reporter.report(&Error::Open(path.clone(), e));
+                        continue;
+                    }
+                }
+            };
+            let flushed = writer.flush();
+            reporter.check(result.and(flushed).map_err(|e| Error::copying(path, e)));
+        }
+    } else {
+        let result = paste_files(&paths, &mut reporter, &mut writer, &delimiters);
+        reporter.check(result);
+    }
+    reporter.check(writer.flush().map_err(Error::writing));
+    reporter.finish();
+}
+
+/// Pastes the files side by side. All of them are open at once, so the first that
+/// cannot be opened ends the program before anything is written.
+fn paste_files<W: Write>(
+    paths: &[String],
+    reporter: &mut Reporter,
+    writer: &mut W,
+    delimiters: &[Vec<u8>],
+) -> Result<(), Error> {
+    // Every `-` reads from the same standard input, taking turns line by line.
+    let mut sources: Vec<LineReader<Box<dyn Read>>> = Vec::new();
+    let mut stdin_source = None;
+    let mut columns = Vec::new();
+    for path in paths {
+        if path == "-" {
+            let index = *stdin_source.get_or_insert_with(|| {
+                sources.push(LineReader::new(Box::new(io::stdin())));
+                sources.len() - 1
+            });
+            columns.push(index);
+            continue;
+        }
+        match File::open(path) {
+            Ok(file) => {
+                sources.push(LineReader::new(Box::new(file)));
+                columns.push(sources.len() - 1);
+            }
+            Err(e) => {
+                reporter.report(&Error::Open(path.clone(), e));
+                exit(1);
+            }
+        }
+    }
+    paste_parallel(&mut sources, &columns, writer, delimiters).map_err(|e| {
+        // Which input failed is not known, so it is named after the first.
+        Error::copying(&paths[0], e)
+    })
+}
+
+/// Splits `-d`'s list into delimiters, resolving `\n`, `\t`, `\\` and `\0` (no delimiter).
+/// Returns `None` if the list ends with a lone backslash.
+fn parse_delimiters(list: &str) -> Option<Vec<Vec<u8>>> {
+    let mut delimiters = Vec::new();
+    let mut bytes = list.bytes();
+    while let Some(b) = bytes.next() {
+        let delimiter = match b {
+            b'\\' => match bytes.next()? {
+                b'n' => vec![b'\n'],
+                b't' => vec![b'\t'],
+                b'0' => Vec::new(),
+                other => vec![other],
+            },
+            b => vec![b],
+        };
+        delimiters.push(delimiter);
+    }
+    if delimiters.is_empty() {
+        delimiters.push(Vec::new());
+    }
+    Some(delimiters)
+}
+
+/// Copies an input a line at a time through a fixed buffer, so lines of any length
+/// take no more memory than the buffer.
+struct LineReader<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    start: usize,
+    end: usize,
+}
+
+impl<R: Read> LineReader<R> {
+    fn new(reader: R) -> Self {
+        LineReader {
+            reader,
+            buffer: vec![0; 1024 * 4],
+            start: 0,
+            end: 0,
+        }
+    }
+
+    /// Reads more if everything read so far was used; returns false at the end of input.
+    fn fill(&mut self) -> io::Result<bool> {
+        if self.start == self.end {
+            self.start = 0;
+            self.end = self.reader.read(&mut self.buffer)?;
+        }
+        Ok(self.start < self.end)
+    }
+
+    /// Copies the next line without its newline to `writer`, first writing `before` if
+    /// there is a line at all. Returns false if the input has ended.
+    fn copy_line<W: Write>(&mut self, writer: &mut W, before: &[u8]) -> io::Result<bool> {
+        if !self.fill()? {
+            return Ok(false);
+        }
+        writer.write_all(before)?;
+        while self.fill()? {
+            let data = &self.buffer[self.start..self.end];
+            match data.iter().position(|b| *b == b'\n') {
+                Some(at) => {
+                    writer.write_all(&data[..at])?;
+                    self.start += at + 1;
+                    return Ok(true);
+                }
+                None => {
+                    writer.write_all(data)?;
+                    self.start = self.end;
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Joins the lines of each input, `columns` giving the source of each column.
+fn paste_parallel<R: Read, W: Write>(
+    sources: &mut [LineReader<R>],
+    columns: &[usize],
+    writer: &mut W,
+    delimiters: &[Vec<u8>],
+) -> io::Result<()> {
+    // Delimiters after inputs that have ended, written only if the line turns out to
+    // have anything in it.
+    let mut pending = Vec::new();
+    loop {
+        let mut any = false;
+        for (column, index) in columns.iter().enumerate() {
+            if column > 0 {
+                pending.extend_from_slice(&delimiters[(column - 1) % delimiters.len()]);
+            }
+            if sources[*index].copy_line(writer, &pending)? {
+                pending.clear();
+                any = true;
+            }
+        }
+        if !any {
+            return Ok(());
+        }
+        writer.write_all(&pending)?;
+        writer.write_all(b"\n")?;
+        pending.clear();
+    }
+}
+
+/// Joins all the lines of one input into one line, which is ended even if reading fails.
+fn paste_serial<R: Read, W: Write>(
+    source: &mut LineReader<R>,
+    writer: &mut W,
+    delimiters: &[Vec<u8>],
+) -> io::Result<()> {
+    let mut count = 0;
+    loop {
+        let before: &[u8] = match count {
+            0 => &[],
+            n => &delimiters[(n - 1) % delimiters.len()],
+        };
+        match source.copy_line(writer, before) {
+            Ok(true) => count += 1,
+            Ok(false) => break,
+            Err(e) => {
+                writer.write_all(b"\n")?;
+                return Err(e);
+            }
+        }
+    }
+    writer.write_all(b"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parallel(inputs: &[&'static str], columns: &[usize], delimiters: &str) -> String {
+        let mut sources: Vec<LineReader<&[u8]>> = inputs
+            .iter()
+            .map(|input| LineReader::new(input.as_bytes()))
+            .collect();
+        let mut output = Vec::new();
+        let delimiters = parse_delimiters(delimiters).unwrap();
+        paste_parallel(&mut sources, columns, &mut output, &delimiters).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_parse_delimiters() {
+        assert_eq!(
+            parse_delimiters("a\\t\\0\\\\").unwrap(),
+            vec![b"a".to_vec(), b"\t".to_vec(), Vec::new(), b"\\".to_vec()]
+        );
+        assert_eq!(parse_delimiters("").unwrap(), vec![Vec::new()]);
+        assert_eq!(parse_delimiters("a\\"), None);
+    }
+
+    #[test]
+    fn test_parallel() {
+        assert_eq!(
+            parallel(&["1\n2\n3\n", "a\nb", ""], &[0, 1, 2], "\\t"),
+            "1\ta\t\n2\tb\t\n3\t\t\n"
+        );
+        assert_eq!(
+            parallel(&["1\n2\n3\n", "a\nb\n"], &[0, 1, 0, 1], ":,"),
+            "1:a,2:b\n3:,:\n"
+        );
+        assert_eq!(parallel(&["1\n2\n3\n"], &[0, 0], "\\t"), "1\t2\n3\t\n");
+        assert_eq!(parallel(&["", ""], &[0, 1], "\\t"), "");
+    }
+
+    #[test]
+    fn test_serial() {
+        let long = "x".repeat(10000);
+        let input = format!("1\n{}\n3", long);
+        let mut source = LineReader::new(input.as_bytes());
+        let mut output = Vec::new();
+        let delimiters = parse_delimiters(":,").unwrap();
+        paste_serial(&mut source, &mut output, &delimiters).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!("1:{},3\n", long)
+        );
+        let mut output = Vec::new();
+        paste_serial(&mut LineReader::new(&b""[..]), &mut output, &delimiters).unwrap();
+        assert_eq!(output, b"\n");
+    }
+}
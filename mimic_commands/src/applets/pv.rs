@@ -0,0 +1,322 @@
+use crate::args::{parse_size, Command};
+use crate::error::{Error, Output, Reporter, Style};
+use crate::stdio;
+use crate::terminal;
+use std::fs::{self, File};
+use std::io;
+use std::io::{IsTerminal, Read, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How much is copied at once, at most.
+const BUFFER_SIZE: usize = 1024 * 64;
+
+pub fn main(args: Vec<String>) {
+    let command = Command::new("pv", "[OPTION]... [FILE]...")
+        .about("Copy each FILE, or standard input, to standard output, showing the progress on standard error.")
+        .opt(
+            "-f, --force",
+            "show progress even if standard error is not a terminal",
+        )
+        .opt(
+            "-i, --interval=SEC",
+            "update the progress every SEC seconds (default 1)",
+        )
+        .opt(
+            "-L, --rate-limit=RATE",
+            "copy at most RATE bytes a second; RATE may have a suffix such as K or M",
+        )
+        .opt("-q, --quiet", "show no progress")
+        .opt(
+            "-s, --size=SIZE",
+            "assume the total is SIZE bytes, for the bar and ETA",
+        );
+    let matches = command.parse_or_exit(args);
+
+    let interval = match matches.value("interval") {
+        Some(value) => match value.parse::<f64>() {
+            Ok(seconds) if seconds > 0.0 && seconds.is_finite() => Duration::from_secs_f64(seconds),
+            _ => command.exit_with_usage_error(&format!("invalid interval: '{}'", value)),
+        },
+        None => Duration::from_secs(1),
+    };
+    let rate = matches
+        .value("rate-limit")
+        .map(|value| match parse_size(value) {
+            Some(rate) if rate > 0 => rate,
+            _ => command.exit_with_usage_error(&format!("invalid rate limit: '{}'", value)),
+        });
+    let paths = matches.files();
+    let size = match matches.value("size") {
+        Some(value) => Some(parse_size(value).unwrap_or_else(|| {
+            command.exit_with_usage_error(&format!("invalid size: '{}'", value))
+        })),
+        None => total_size(&paths),
+    };
+    let show = !matches.flag("quiet") && (matches.flag("force") || io::stderr().is_terminal());
+    let mut progress = show.then(|| Progress::new(size, interval, terminal::width(2)));
+
+    // Borrow fds 0 and 1 as Files, so that what is read is passed on unbuffered.
+    let stdin = stdio::stdin_file();
+    let stdout = stdio::stdout_file();
+    let mut writer = Output(&*stdout);
+    let mut reporter = Reporter::new("pv", Style::Bare);
+    let mut copier = Copier {
+        start: Instant::now(),
+        copied: 0,
+        rate,
+    };
+    if let Some(progress) = &mut progress {
+        progress.draw(copier.copied, false);
+    }
+    for path in paths {
+        let result = if path == "-" {
+            copier.copy(&*stdin, &mut writer, &mut progress)
+        } else {
+            match File::open(&path) {
+                Ok(file) => copier.copy(file, &mut writer, &mut progress),
+                Err(e) => {
+                    reporter.report(&Error::Open(path, e));
+                    continue;
+                }
+            }
+        };
+        if let Err(e) = result {
+            let e = Error::copying(&path, e);
+            let stop = matches!(e, Error::Write(_));
+            reporter.report(&e);
+            if stop {
+                break;
+            }
+        }
+    }
+    if let Some(progress) = &mut progress {
+        progress.draw(copier.copied, true);
+    }
+    reporter.finish();
+}
+
+/// The sum of the sizes of `paths`, if they are all regular files.
+fn total_size(paths: &[String]) -> Option<u64> {
+    paths
+        .iter()
+        .map(|path| {
+            let metadata = if path == "-" {
+                let stdin = stdio::stdin_file();
+                stdin.metadata()
+            } else {
+                fs::metadata(path)
+            };
+            metadata.ok().filter(|m| m.is_file()).map(|m| m.len())
+        })
+        .sum()
+}
+
+/// Copies the inputs one after another, keeping count and to the rate limit.
+struct Copier {
+    start: Instant,
+    copied: u64,
+    /// The most bytes to copy a second.
+    rate: Option<u64>,
+}
+
+impl Copier {
+    fn copy<R: Read, W: Write>(
+        &mut self,
+        mut reader: R,
+        writer: &mut W,
+        progress: &mut Option<Progress>,
+    ) -> io::Result<()> {
+        // A limited copy goes in small steps, so the output comes steadily rather than
+        // in bursts.
+        let step = match self.rate {
+            Some(rate) => (rate / 10).clamp(1, BUFFER_SIZE as u64) as usize,
+            None => BUFFER_SIZE,
+        };
+        let mut buffer = vec![0; step];
+        loop {
+            let size = match reader.read(&mut buffer) {
+                Ok(0) => return Ok(()),
+                Ok(size) => size,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            writer.write_all(&buffer[..size])?;
+            self.copied += size as u64;
+            if let Some(rate) = self.rate {
+                let due = Duration::from_secs_f64(self.copied as f64 / rate as f64);
+                if let Some(wait) = due.checked_sub(self.start.elapsed()) {
+                    thread::sleep(wait);
+                }
+            }
+            if let Some(progress) = progress {
+                progress.update(self.copied);
+            }
+        }
+    }
+}
+
+/// The progress line on standard error, redrawn in place.
+#[derive(Debug)]
+struct Progress {
+    start: Instant,
+    /// The total expected, if it is known.
+    size: Option<u64>,
+    interval: Duration,
+    width: usize,
+    drawn: Instant,
+}
+
+impl Progress {
+    fn new(size: Option<u64>, interval: Duration, width: usize) -> Self {
+        let now = Instant::now();
+        Progress {
+            start: now,
+            size,
+            interval,
+            width,
+            drawn: now,
+        }
+    }
+
+    /// Redraws the line if it was last drawn an interval ago.
+    fn update(&mut self, copied: u64) {
+        if self.drawn.elapsed() >= self.interval {
+            self.draw(copied, false);
+        }
+    }
+
+    /// Draws the line, and ends it once the copy is `done`.
+    fn draw(&mut self, copied: u64, done: bool) {
+        self.drawn = Instant::now();
+        let line = status_line(copied, self.start.elapsed(), self.size, self.width);
+        let end = if done { "\n" } else { "" };
+        // The progress is only for show, so failing to draw it is no reason to stop.
+        io::stderr()
+            .write_all(format!("\r{}{}", line, end).as_bytes())
+            .ok();
+    }
+}
+
+/// The amount copied, the time taken and the average rate, then with a known `size`,
+/// a bar filling the rest of `width`, the percentage done and the time left.
+fn status_line(copied: u64, elapsed: Duration, size: Option<u64>, width: usize) -> String {
+    let seconds = elapsed.as_secs_f64();
+    let rate = if seconds > 0.0 {
+        copied as f64 / seconds
+    } else {
+        0.0
+    };
+    let mut line = format!(
+        "{:>7} {} [{:>7}/s]",
+        format_size(copied),
+        format_duration(elapsed.as_secs()),
+        format_size(rate as u64)
+    );
+    let size = match size {
+        Some(size) => size,
+        None => return line,
+    };
+    let fraction = if size == 0 {
+        1.0
+    } else {
+        copied as f64 / size as f64
+    };
+    let eta = if copied >= size {
+        format_duration(0)
+    } else if rate > 0.0 {
+        format_duration(((size - copied) as f64 / rate).ceil() as u64)
+    } else {
+        "-:--:--".to_string()
+    };
+    let tail = format!(" {:>3}% ETA {}", (fraction * 100.0) as u64, eta);
+    // The bar takes what is left, brackets and all.
+    let inside = width.saturating_sub(line.len() + tail.len() + 3);
+    if inside > 0 {
+        let filled = ((inside as f64 * fraction.min(1.0)) as usize).min(inside);
+        let bar = match filled {
+            0 => String::new(),
+            filled if filled == inside => "=".repeat(filled),
+            filled => "=".repeat(filled - 1) + ">",
+        };
+        line += &format!(" [{:<inside$}]", bar, inside = inside);
+    }
+    line + &tail
+}
+
+/// A byte count with a binary unit, to three figures: `512B`, `1.50KiB`, `12.3MiB`.
+fn format_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+    if bytes < 1024 {
+        return format!("{}B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    let decimals = match value {
+        value if value < 10.0 => 2,
+        value if value < 100.0 => 1,
+        _ => 0,
+    };
+    format!("{:.*}{}", decimals, value, UNITS[unit])
+}
+
+/// Seconds as `H:MM:SS`.
+fn format_duration(seconds: u64) -> String {
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format_size(0), "0B");
+        assert_eq!(format_size(1023), "1023B");
+        assert_eq!(format_size(1536), "1.50KiB");
+        assert_eq!(format_size(12 * 1024 * 1024 + 300 * 1024), "12.3MiB");
+        assert_eq!(format_size(500 * 1024 * 1024 * 1024), "500GiB");
+        assert_eq!(format_duration(3), "0:00:03");
+        assert_eq!(format_duration(3725), "1:02:05");
+    }
+
+    #[test]
+    fn test_status_line() {
+        let second = Duration::from_secs(1);
+        assert_eq!(
+            status_line(2048, second * 2, None, 80),
+            "2.00KiB 0:00:02 [1.00KiB/s]"
+        );
+        let line = status_line(4096, second * 4, Some(16384), 60);
+        assert_eq!(line.len(), 60);
+        assert!(line.ends_with(" [==>          ]  25% ETA 0:00:12"));
+        let done = status_line(16384, second * 4, Some(16384), 60);
+        assert!(done.ends_with("=] 100% ETA 0:00:00"));
+        // Too narrow for a bar.
+        assert!(status_line(0, second, Some(10), 20).ends_with("]   0% ETA -:--:--"));
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let mut copier = Copier {
+            start: Instant::now(),
+            copied: 0,
+            rate: Some(10_000),
+        };
+        let mut output = Vec::new();
+        copier
+            .copy(&[b'x'; 2_000][..], &mut output, &mut None)
+            .unwrap();
+        assert_eq!(output.len(), 2_000);
+        assert!(copier.start.elapsed() >= Duration::from_millis(200));
+    }
+}
@@ -0,0 +1,93 @@
+use crate::args::Command;
+use crate::error::{self, Error, Output, Reporter, Style};
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::str;
+
+pub fn main(args: Vec<String>) {
+    let command = Command::new("rev", "[OPTION]... [FILE]...")
+        .about("Reverse the characters of each line of each FILE.");
+    let matches = command.parse_or_exit(args);
+
+    let mut reporter = Reporter::new("rev", Style::Bare);
+    let mut writer = BufWriter::new(Output(io::stdout()));
+    for path in matches.files() {
+        let reader: Box<dyn Read> = if path == "-" {
+            Box::new(io::stdin())
+        } else {
+            match File::open(&path) {
+                Ok(file) => Box::new(file),
+                Err(e) => {
+                    reporter.fail(&format!("cannot open {}: {}", path, error::describe(&e)));
+                    continue;
+                }
+            }
+        };
+        let result = rev(reader, &mut writer).and_then(|_| writer.flush());
+        if let Err(e) = result {
+            reporter.report(&Error::copying(&path, e));
+        }
+    }
+    reporter.finish();
+}
+
+/// Writes each line of `reader` reversed, keeping its newline at the end.
+fn rev<R: Read, W: Write>(reader: R, writer: &mut W) -> io::Result<()> {
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    let mut reversed = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            return Ok(());
+        }
+        let newline = line.last() == Some(&b'\n');
+        if newline {
+            line.pop();
+        }
+        reversed.clear();
+        reverse_chars(&line, &mut reversed);
+        if newline {
+            reversed.push(b'\n');
+        }
+        writer.write_all(&reversed)?;
+    }
+}
+
+/// Appends `line` to `out` character by character from the end. Valid UTF-8 sequences
+/// stay whole; any other byte is a character of its own.
+fn reverse_chars(line: &[u8], out: &mut Vec<u8>) {
+    let mut end = line.len();
+    while end > 0 {
+        // The longest valid character ending here, if any.
+        let size = (2..=4.min(end))
+            .rev()
+            .find(|size| {
+                str::from_utf8(&line[end - size..end]).is_ok_and(|s| s.chars().count() == 1)
+            })
+            .unwrap_or(1);
+        out.extend_from_slice(&line[end - size..end]);
+        end -= size;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(input: &[u8]) -> Vec<u8> {
+        let mut output = Vec::new();
+        rev(input, &mut output).unwrap();
+        output
+    }
+
+    #[test]
+    fn test_rev() {
+        assert_eq!(run(b"abc\n\nde"), b"cba\n\ned");
+        assert_eq!(run(b""), b"");
+        assert_eq!(run("añ€😀\n".as_bytes()), "😀€ña\n".as_bytes());
+        // Bytes that are not UTF-8 are reversed one at a time.
+        assert_eq!(run(b"a\xff\xc3b\n"), b"b\xc3\xffa\n");
+    }
+}
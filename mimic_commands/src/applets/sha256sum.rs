@@ -0,0 +1,5 @@
+use crate::applets::checksum::{self, Algorithm};
+
+pub fn main(args: Vec<String>) {
+    checksum::run(Algorithm::Sha256, args);
+}
@@ -0,0 +1,925 @@
+use crate::args::Command;
+use crate::error::{self, Error, Output, Reporter, Style};
+use crate::tempfile;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::mem;
+use std::path::PathBuf;
+use std::process::exit;
+
+/// Exit status for every failure, as in GNU sort.
+const EXIT_TROUBLE: i32 = 2;
+/// How many sorted runs are merged at once, which bounds how many files are open.
+const MERGE_FAN_IN: usize = 16;
+/// Memory counted for each line besides its bytes: its entry in the index.
+const LINE_OVERHEAD: usize = mem::size_of::<(usize, usize)>();
+/// The memory budget when physical memory cannot be found out.
+const DEFAULT_BUFFER_SIZE: usize = 64 * 1024 * 1024;
+
+pub fn main(args: Vec<String>) {
+    let command = Command::new("sort", "[OPTION]... [FILE]...")
+        .about("Write sorted concatenation of all FILE(s) to standard output.")
+        .opt("-b, --ignore-leading-blanks", "ignore leading blanks")
+        .opt(
+            "-d, --dictionary-order",
+            "consider only blanks and alphanumeric characters",
+        )
+        .opt(
+            "-f, --ignore-case",
+            "fold lower case to upper case characters",
+        )
+        .opt(
+            "-h, --human-numeric-sort",
+            "compare human readable numbers (e.g., 2K 1G)",
+        )
+        .opt(
+            "-n, --numeric-sort",
+            "compare according to string numerical value",
+        )
+        .opt("-r, --reverse", "reverse the result of comparisons")
+        .opt(
+            "-k, --key=KEYDEF",
+            "sort via a key; KEYDEF gives location and type",
+        )
+        .opt(
+            "-o, --output=FILE",
+            "write result to FILE instead of standard output",
+        )
+        .opt(
+            "-s, --stable",
+            "stabilize sort by disabling last-resort comparison",
+        )
+        .opt(
+            "-S, --buffer-size=SIZE",
+            "use SIZE for main memory buffer; larger inputs are sorted in temporary files",
+        )
+        .opt(
+            "-t, --field-separator=SEP",
+            "use SEP instead of non-blank to blank transition",
+        )
+        .opt(
+            "-T, --temporary-directory=DIR",
+            "use DIR for temporaries, not $TMPDIR or /tmp",
+        )
+        .opt("-u, --unique", "output only the first of an equal run");
+    let matches = command.parse_or_exit(args);
+
+    let global = Order {
+        blanks_start: matches.flag("ignore-leading-blanks"),
+        blanks_end: matches.flag("ignore-leading-blanks"),
+        dictionary: matches.flag("dictionary-order"),
+        fold: matches.flag("ignore-case"),
+        human: matches.flag("human-numeric-sort"),
+        numeric: matches.flag("numeric-sort"),
+        reverse: matches.flag("reverse"),
+    };
+    let mut keys = Vec::new();
+    for spec in matches.values("key") {
+        let mut key = parse_key(spec).unwrap_or_else(|message| die(&message));
+        // A key with no options of its own takes the global ones.
+        if key.order == Order::default() {
+            key.order = global;
+        }
+        keys.push(key);
+    }
+    if keys.is_empty() {
+        keys.push(Key {
+            order: global,
+            ..Key::default()
+        });
+    }
+    let separator = matches
+        .value("field-separator")
+        .map(|sep| match sep.as_bytes() {
+            [] => die(&"empty tab"),
+            [sep] => *sep,
+            _ => die(&format!("multi-character tab '{}'", sep)),
+        });
+    let unique = matches.flag("unique");
+    let comparator = Comparator {
+        keys,
+        separator,
+        // Equal lines are told apart by their bytes, unless their order is to be kept.
+        last_resort: !matches.flag("stable") && !unique,
+        reverse: global.reverse,
+    };
+
+    let budget = match matches.value("buffer-size") {
+        Some(size) => parse_buffer_size(size)
+            .unwrap_or_else(|| die(&format!("invalid -S argument '{}'", size))),
+        None => physical_memory().map_or(DEFAULT_BUFFER_SIZE, |memory| memory / 8),
+    };
+    let temp_dir = matches
+        .value("temporary-directory")
+        .map_or_else(tempfile::dir, PathBuf::from);
+
+    let mut sorter = Sorter::new(comparator, unique, budget, temp_dir);
+    for path in matches.files() {
+        let result = if path == "-" {
+            sorter.read(io::stdin().lock())
+        } else {
+            match File::open(&path) {
+                Ok(file) => sorter.read(BufReader::new(file)),
+                Err(e) => die(&format!(
+                    "cannot read: {}",
+                    Error::Open(path, e).message(Style::Bare)
+                )),
+            }
+        };
+        if let Err(e) = result {
+            die(&format!(
+                "read failed: {}",
+                Error::Read(path, e).message(Style::Bare)
+            ));
+        }
+    }
+
+    // Only now that every input has been read may the output replace one of them.
+    let result = match matches.value("output") {
+        Some(path) => {
+            let file = File::create(path).unwrap_or_else(|e| {
+                die(&format!(
+                    "open failed: {}",
+                    Error::Open(path.to_string(), e).message(Style::Bare)
+                ))
+            });
+            sorter.finish(BufWriter::new(Output(file)))
+        }
+        None => sorter.finish(BufWriter::new(Output(io::stdout()))),
+    };
+    match result {
+        Ok(()) => {}
+        Err(Error::Read(name, e)) => {
+            die(&format!("read failed: {}: {}", name, error::describe(&e)))
+        }
+        Err(e) => {
+            Reporter::new("sort", Style::Bare).report(&e);
+            exit(EXIT_TROUBLE);
+        }
+    }
+}
+
+/// Reports a failure and exits; GNU sort gives up at the first one.
+fn die(message: &dyn fmt::Display) -> ! {
+    Reporter::new("sort", Style::Bare).fail(message);
+    exit(EXIT_TROUBLE);
+}
+
+/// Parses `-S`: a number of KiB, or of bytes with `b`, or a percentage of memory with `%`.
+fn parse_buffer_size(s: &str) -> Option<usize> {
+    let digits = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let number: usize = s[..digits].parse().ok()?;
+    let size = match &s[digits..] {
+        "" => number.checked_mul(1024)?,
+        "b" => number,
+        "%" if number <= 100 => physical_memory()? / 100 * number,
+        suffix => usize::try_from(crate::args::parse_size(&format!("1{}", suffix))?)
+            .ok()?
+            .checked_mul(number)?,
+    };
+    Some(size)
+}
+
+fn physical_memory() -> Option<usize> {
+    extern "C" {
+        fn sysconf(name: i32) -> i64;
+    }
+    const SC_PAGESIZE: i32 = 30;
+    const SC_PHYS_PAGES: i32 = 85;
+    let (page_size, pages) = unsafe { (sysconf(SC_PAGESIZE), sysconf(SC_PHYS_PAGES)) };
+    let memory = usize::try_from(page_size)
+        .ok()?
+        .checked_mul(usize::try_from(pages).ok()?)?;
+    Some(memory).filter(|memory| *memory > 0)
+}
+
+/// How one key is compared.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Order {
+    /// Ignore blanks at the start of the key (`b` on its start).
+    blanks_start: bool,
+    /// Ignore blanks before the last character of the key (`b` on its end).
+    blanks_end: bool,
+    dictionary: bool,
+    fold: bool,
+    human: bool,
+    numeric: bool,
+    reverse: bool,
+}
+
+/// A `-k` key, with fields and characters counted from 0.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Key {
+    start_field: usize,
+    start_char: usize,
+    /// The last field, or `None` for the end of the line.
+    end_field: Option<usize>,
+    /// The last character in the last field counted from 1, or 0 for all of it.
+    end_char: usize,
+    order: Order,
+}
+
+/// Parses a key definition `F[.C][OPTS][,F[.C][OPTS]]`, with GNU's messages.
+fn parse_key(spec: &str) -> Result<Key, String> {
+    let invalid = |problem: &str| format!("{}: invalid field specification '{}'", problem, spec);
+    let count = |s: &str, after: &str| -> Result<(usize, usize), String> {
+        let digits = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        match s[..digits].parse() {
+            Ok(count) => Ok((count, digits)),
+            Err(_) => Err(format!("{}: invalid count at start of '{}'", after, s)),
+        }
+    };
+
+    let mut key = Key::default();
+    let (field, len) = count(spec, "invalid number at field start")?;
+    let mut rest = &spec[len..];
+    key.start_field = field
+        .checked_sub(1)
+        .ok_or_else(|| invalid("field number is zero"))?;
+    if let Some(after) = rest.strip_prefix('.') {
+        let (offset, len) = count(after, "invalid number after '.'")?;
+        key.start_char = offset
+            .checked_sub(1)
+            .ok_or_else(|| invalid("character offset is zero"))?;
+        rest = &after[len..];
+    }
+    rest = parse_options(rest, &mut key.order, true);
+
+    if let Some(after) = rest.strip_prefix(',') {
+        let (field, len) = count(after, "invalid number after ','")?;
+        rest = &after[len..];
+        key.end_field = Some(
+            field
+                .checked_sub(1)
+                .ok_or_else(|| invalid("field number is zero"))?,
+        );
+        if let Some(after) = rest.strip_prefix('.') {
+            let (offset, len) = count(after, "invalid number after '.'")?;
+            key.end_char = offset;
+            rest = &after[len..];
+        }
+        rest = parse_options(rest, &mut key.order, false);
+    }
+    if !rest.is_empty() {
+        return Err(invalid("stray character in field spec"));
+    }
+    Ok(key)
+}
+
+/// Applies the option letters at the start of `s` and returns the rest.
+fn parse_options<'a>(s: &'a str, order: &mut Order, at_start: bool) -> &'a str {
+    let len = s
+        .find(|c| {
+            match c {
+                'b' if at_start => order.blanks_start = true,
+                'b' => order.blanks_end = true,
+                'd' => order.dictionary = true,
+                'f' => order.fold = true,
+                'h' => order.human = true,
+                'n' => order.numeric = true,
+                'r' => order.reverse = true,
+                _ => return true,
+            }
+            false
+        })
+        .unwrap_or(s.len());
+    &s[len..]
+}
+
+fn is_blank(b: u8) -> bool {
+    b == b' ' || b == b'\t'
+}
+
+fn skip_blanks(line: &[u8], mut i: usize) -> usize {
+    while i < line.len() && is_blank(line[i]) {
+        i += 1;
+    }
+    i
+}
+
+/// Compares lines by their keys, then by their bytes as a last resort.
+#[derive(Debug, Clone)]
+struct Comparator {
+    keys: Vec<Key>,
+    separator: Option<u8>,
+    last_resort: bool,
+    reverse: bool,
+}
+
+impl Comparator {
+    fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        let by_keys = self.compare_keys(a, b);
+        if by_keys != Ordering::Equal || !self.last_resort {
+            return by_keys;
+        }
+        if self.reverse {
+            b.cmp(a)
+        } else {
+            a.cmp(b)
+        }
+    }
+
+    fn compare_keys(&self, a: &[u8], b: &[u8]) -> Ordering {
+        for key in &self.keys {
+            let (a, b) = (self.extract(key, a), self.extract(key, b));
+            let order = key.order;
+            let ordering = if order.human {
+                compare_human(a, b)
+            } else if order.numeric {
+                compare_numeric(a, b)
+            } else if order.dictionary || order.fold {
+                let filter = |key: &[u8]| {
+                    key.iter()
+                        .filter(|b| !order.dictionary || is_blank(**b) || b.is_ascii_alphanumeric())
+                        .map(|b| {
+                            if order.fold {
+                                b.to_ascii_uppercase()
+                            } else {
+                                *b
+                            }
+                        })
+                        .collect::<Vec<u8>>()
+                };
+                filter(a).cmp(&filter(b))
+            } else {
+                a.cmp(b)
+            };
+            if ordering != Ordering::Equal {
+                return if order.reverse {
+                    ordering.reverse()
+                } else {
+                    ordering
+                };
+            }
+        }
+        Ordering::Equal
+    }
+
+    /// The part of `line` that `key` covers, found the way GNU sort finds it.
+    fn extract<'l>(&self, key: &Key, line: &'l [u8]) -> &'l [u8] {
+        let mut start = self.skip_fields(line, 0, key.start_field, true);
+        if key.order.blanks_start {
+            start = skip_blanks(line, start);
+        }
+        start = line.len().min(start + key.start_char);
+
+        let end = match key.end_field {
+            None => line.len(),
+            // The whole end field: up to the start of the next one.
+            Some(field) if key.end_char == 0 => self.skip_fields(line, 0, field + 1, false),
+            Some(field) => {
+                let mut end = self.skip_fields(line, 0, field, true);
+                if key.order.blanks_end {
+                    end = skip_blanks(line, end);
+                }
+                line.len().min(end + key.end_char)
+            }
+        };
+        &line[start..end.max(start)]
+    }
+
+    /// Moves past `count` fields from `i`. With a separator, the one after the last field
+    /// is skipped too when `past_separator` is set, or there is more than one field.
+    fn skip_fields(&self, line: &[u8], mut i: usize, count: usize, past_separator: bool) -> usize {
+        for n in 0..count {
+            if i >= line.len() {
+                break;
+            }
+            match self.separator {
+                Some(separator) => {
+                    while i < line.len() && line[i] != separator {
+                        i += 1;
+                    }
+                    if i < line.len() && (n + 1 < count || past_separator) {
+                        i += 1;
+                    }
+                }
+                None => {
+                    i = skip_blanks(line, i);
+                    while i < line.len() && !is_blank(line[i]) {
+                        i += 1;
+                    }
+                }
+            }
+        }
+        i
+    }
+}
+
+/// A number as `sort -n` reads it: blanks, an optional `-`, digits and a fraction.
+#[derive(Debug)]
+struct Number<'a> {
+    negative: bool,
+    /// The integer digits without leading zeros.
+    integer: &'a [u8],
+    /// The fraction digits without trailing zeros.
+    fraction: &'a [u8],
+    /// Whatever follows the number.
+    rest: &'a [u8],
+}
+
+impl<'a> Number<'a> {
+    fn parse(s: &'a [u8]) -> Self {
+        let mut i = skip_blanks(s, 0);
+        let negative = s.get(i) == Some(&b'-');
+        if negative {
+            i += 1;
+        }
+        let digits =
+            |from: usize| from + s[from..].iter().take_while(|b| b.is_ascii_digit()).count();
+        let end = digits(i);
+        let mut integer = &s[i..end];
+        while let [b'0', rest @ ..] = integer {
+            integer = rest;
+        }
+        let (mut fraction, end) = match s.get(end) {
+            Some(b'.') => {
+                let fraction_end = digits(end + 1);
+                (&s[end + 1..fraction_end], fraction_end)
+            }
+            _ => (&s[end..end], end),
+        };
+        while let [rest @ .., b'0'] = fraction {
+            fraction = rest;
+        }
+        let is_zero = integer.is_empty() && fraction.is_empty();
+        Number {
+            negative: negative && !is_zero,
+            integer,
+            fraction,
+            rest: &s[end..],
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.integer.is_empty() && self.fraction.is_empty()
+    }
+}
+
+fn compare_numeric(a: &[u8], b: &[u8]) -> Ordering {
+    let (a, b) = (Number::parse(a), Number::parse(b));
+    let sign = |n: &Number| match (n.negative, n.is_zero()) {
+        (true, _) => -1,
+        (false, true) => 0,
+        (false, false) => 1,
+    };
+    let by_sign = sign(&a).cmp(&sign(&b));
+    if by_sign != Ordering::Equal {
+        return by_sign;
+    }
+    let magnitude = a
+        .integer
+        .len()
+        .cmp(&b.integer.len())
+        .then_with(|| a.integer.cmp(b.integer))
+        .then_with(|| a.fraction.cmp(b.fraction));
+    if a.negative {
+        magnitude.reverse()
+    } else {
+        magnitude
+    }
+}
+
+/// Compares numbers like `2K` and `1G`: first by the unit, which is negated for negative
+/// numbers, then by value.
+fn compare_human(a: &[u8], b: &[u8]) -> Ordering {
+    let unit = |s: &[u8]| {
+        let number = Number::parse(s);
+        if number.is_zero() {
+            return 0;
+        }
+        let order = match number.rest.first() {
+            Some(unit) => b"KMGTPEZYRQ"
+                .iter()
+                .position(|u| u == unit || (*unit == b'k' && *u == b'K'))
+                .map_or(0, |i| i as i32 + 1),
+            None => 0,
+        };
+        if number.negative {
+            -order
+        } else {
+            order
+        }
+    };
+    unit(a).cmp(&unit(b)).then_with(|| compare_numeric(a, b))
+}
+
+/// Lines held in memory: their bytes, and where each one is.
+#[derive(Debug, Default)]
+struct Chunk {
+    data: Vec<u8>,
+    lines: Vec<(usize, usize)>,
+}
+
+impl Chunk {
+    /// The memory the chunk counts as using.
+    fn size(&self) -> usize {
+        self.data.len() + self.lines.len() * LINE_OVERHEAD
+    }
+
+    fn sort(&mut self, comparator: &Comparator) {
+        let data = &self.data;
+        self.lines
+            .sort_by(|a, b| comparator.compare(&data[a.0..a.1], &data[b.0..b.1]));
+    }
+
+    fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+        comparator: &Comparator,
+        unique: bool,
+    ) -> io::Result<()> {
+        let mut previous: Option<&[u8]> = None;
+        for (start, end) in &self.lines {
+            let line = &self.data[*start..*end];
+            if unique
+                && previous.is_some_and(|p| comparator.compare_keys(p, line) == Ordering::Equal)
+            {
+                continue;
+            }
+            writer.write_all(line)?;
+            writer.write_all(b"\n")?;
+            previous = Some(line);
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self) {
+        self.data.clear();
+        self.lines.clear();
+    }
+}
+
+/// A sorted run in a temporary file, and how many merges went into it.
+#[derive(Debug)]
+struct Run {
+    file: File,
+    level: u32,
+}
+
+/// Sorts in memory until the budget is used up, then spills sorted runs to temporary
+/// files and merges them, `MERGE_FAN_IN` at a time.
+struct Sorter {
+    comparator: Comparator,
+    unique: bool,
+    budget: usize,
+    temp_dir: PathBuf,
+    chunk: Chunk,
+    /// Runs in input order, so merging neighbours keeps equal lines in order.
+    runs: Vec<Run>,
+}
+
+impl Sorter {
+    fn new(comparator: Comparator, unique: bool, budget: usize, temp_dir: PathBuf) -> Self {
+        Sorter {
+            comparator,
+            unique,
+            budget,
+            temp_dir,
+            chunk: Chunk::default(),
+            runs: Vec::new(),
+        }
+    }
+
+    fn read<R: BufRead>(&mut self, mut reader: R) -> io::Result<()> {
+        loop {
+            let start = self.chunk.data.len();
+            if reader.read_until(b'\n', &mut self.chunk.data)? == 0 {
+                return Ok(());
+            }
+            let end = match self.chunk.data.last() {
+                Some(b'\n') => self.chunk.data.len() - 1,
+                _ => self.chunk.data.len(),
+            };
+            self.chunk.lines.push((start, end));
+            if self.chunk.size() >= self.budget {
+                self.spill();
+            }
+        }
+    }
+
+    /// Sorts what is in memory into a new run, then merges runs of the same level
+    /// whenever there are enough of them, so few files are open at once.
+    fn spill(&mut self) {
+        self.chunk.sort(&self.comparator);
+        let (comparator, unique) = (&self.comparator, self.unique);
+        let chunk = &self.chunk;
+        let file = self.write_run(|writer| chunk.write_to(writer, comparator, unique));
+        self.chunk.clear();
+        self.runs.push(Run { file, level: 0 });
+
+        while let Some(level) = self.runs.last().map(|run| run.level) {
+            let same = self
+                .runs
+                .iter()
+                .rev()
+                .take_while(|run| run.level == level)
+                .count();
+            if same < MERGE_FAN_IN {
+                break;
+            }
+            let runs = self.runs.split_off(self.runs.len() - MERGE_FAN_IN);
+            let file = self.merge_to_run(runs);
+            self.runs.push(Run {
+                file,
+                level: level + 1,
+            });
+        }
+    }
+
+    fn merge_to_run(&self, runs: Vec<Run>) -> File {
+        let files = runs.into_iter().map(|run| run.file).collect();
+        self.write_run(|writer| merge(files, writer, &self.comparator, self.unique))
+    }
+
+    /// How runs are named in messages, as they are unlinked as soon as they are made.
+    fn run_name(&self) -> String {
+        format!("temporary file in '{}'", self.temp_dir.display())
+    }
+
+    /// Writes a run with `write` and rewinds it for reading.
+    fn write_run<F>(&self, write: F) -> File
+    where
+        F: FnOnce(&mut BufWriter<Output<&File>>) -> io::Result<()>,
+    {
+        let file = tempfile::create_in(&self.temp_dir).unwrap_or_else(|e| {
+            die(&format!(
+                "cannot create temporary file in '{}': {}",
+                self.temp_dir.display(),
+                error::describe(&e)
+            ))
+        });
+        let mut writer = BufWriter::new(Output(&file));
+        let result = write(&mut writer)
+            .and_then(|_| writer.flush())
+            .and_then(|_| Output(&file).seek(SeekFrom::Start(0)));
+        drop(writer);
+        // When merging, what fails may be reading the runs merged rather than writing.
+        let (action, e) = match result.map_err(|e| Error::copying(&self.run_name(), e)) {
+            Ok(_) => return file,
+            Err(Error::Read(_, e)) => ("read", e),
+            Err(Error::Open(_, e) | Error::Write(e)) => ("write", e),
+        };
+        die(&format!(
+            "{} failed: {}: {}",
+            action,
+            self.run_name(),
+            error::describe(&e)
+        ))
+    }
+
+    /// Writes the sorted lines to `writer`, which is an [`Output`] so that failing to
+    /// read back a run is told apart from failing to write.
+    fn finish<W: Write>(mut self, mut writer: W) -> Result<(), Error> {
+        if self.runs.is_empty() {
+            self.chunk.sort(&self.comparator);
+            return self
+                .chunk
+                .write_to(&mut writer, &self.comparator, self.unique)
+                .and_then(|_| writer.flush())
+                .map_err(Error::writing);
+        }
+
+        if !self.chunk.lines.is_empty() {
+            self.spill();
+        }
+        while self.runs.len() > MERGE_FAN_IN {
+            let rest = self.runs.split_off(MERGE_FAN_IN);
+            let first = mem::replace(&mut self.runs, rest);
+            let file = self.merge_to_run(first);
+            self.runs.insert(0, Run { file, level: 0 });
+        }
+        let files = mem::take(&mut self.runs)
+            .into_iter()
+            .map(|run| run.file)
+            .collect();
+        merge(files, &mut writer, &self.comparator, self.unique)
+            .and_then(|_| writer.flush())
+            .map_err(|e| Error::copying(&self.run_name(), e))
+    }
+}
+
+/// Merges sorted runs into `writer`. Equal lines come from the earliest run first.
+fn merge<W: Write>(
+    files: Vec<File>,
+    writer: &mut W,
+    comparator: &Comparator,
+    unique: bool,
+) -> io::Result<()> {
+    let mut readers: Vec<BufReader<File>> = files.into_iter().map(BufReader::new).collect();
+    // The current line of each run, without its newline; `None` once it is used up.
+    let mut heads: Vec<Option<Vec<u8>>> = Vec::with_capacity(readers.len());
+    for reader in &mut readers {
+        heads.push(read_line(reader, Vec::new())?);
+    }
+
+    let mut previous: Option<Vec<u8>> = None;
+    loop {
+        let mut smallest: Option<usize> = None;
+        for (i, head) in heads.iter().enumerate() {
+            let line = match head {
+                Some(line) => line,
+                None => continue,
+            };
+            let is_smaller = match smallest.and_then(|s| heads[s].as_deref()) {
+                Some(best) => comparator.compare(line, best) == Ordering::Less,
+                None => true,
+            };
+            if is_smaller {
+                smallest = Some(i);
+            }
+        }
+        let i = match smallest {
+            Some(i) => i,
+            None => return Ok(()),
+        };
+
+        let line = heads[i].take().unwrap_or_default();
+        let duplicate = unique
+            && previous
+                .as_deref()
+                .is_some_and(|p| comparator.compare_keys(p, &line) == Ordering::Equal);
+        if !duplicate {
+            writer.write_all(&line)?;
+            writer.write_all(b"\n")?;
+        }
+        // Reuse the old line's buffer for the next one from this run.
+        let buffer = if duplicate {
+            line
+        } else {
+            previous.replace(line).unwrap_or_default()
+        };
+        heads[i] = read_line(&mut readers[i], buffer)?;
+    }
+}
+
+fn read_line<R: BufRead>(reader: &mut R, mut buffer: Vec<u8>) -> io::Result<Option<Vec<u8>>> {
+    buffer.clear();
+    if reader.read_until(b'\n', &mut buffer)? == 0 {
+        return Ok(None);
+    }
+    if buffer.last() == Some(&b'\n') {
+        buffer.pop();
+    }
+    Ok(Some(buffer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comparator(keys: &[&str], separator: Option<u8>) -> Comparator {
+        Comparator {
+            keys: keys.iter().map(|spec| parse_key(spec).unwrap()).collect(),
+            separator,
+            last_resort: true,
+            reverse: false,
+        }
+    }
+
+    fn sort(input: &str, comparator: Comparator, unique: bool, budget: usize) -> String {
+        let mut sorter = Sorter::new(comparator, unique, budget, tempfile::dir());
+        sorter.read(input.as_bytes()).unwrap();
+        let mut output = Vec::new();
+        sorter.finish(&mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_parse_key() {
+        let key = parse_key("2.3b,4.5nr").unwrap();
+        assert_eq!((key.start_field, key.start_char), (1, 2));
+        assert_eq!((key.end_field, key.end_char), (Some(3), 5));
+        assert!(key.order.blanks_start && !key.order.blanks_end);
+        assert!(key.order.numeric && key.order.reverse);
+        assert!(parse_key("1,1b").unwrap().order.blanks_end);
+        assert_eq!(
+            parse_key("0").unwrap_err(),
+            "field number is zero: invalid field specification '0'"
+        );
+        assert_eq!(
+            parse_key("1.0").unwrap_err(),
+            "character offset is zero: invalid field specification '1.0'"
+        );
+        assert_eq!(
+            parse_key("a").unwrap_err(),
+            "invalid number at field start: invalid count at start of 'a'"
+        );
+        assert_eq!(
+            parse_key("1,1x").unwrap_err(),
+            "stray character in field spec: invalid field specification '1,1x'"
+        );
+        assert_eq!(
+            parse_key("1,").unwrap_err(),
+            "invalid number after ',': invalid count at start of ''"
+        );
+    }
+
+    #[test]
+    fn test_extract() {
+        let blanks = comparator(&["2"], None);
+        let extract = |c: &Comparator, line: &'static str| {
+            std::str::from_utf8(c.extract(&c.keys[0], line.as_bytes())).unwrap()
+        };
+        assert_eq!(extract(&blanks, "a  b c"), "  b c");
+        let c = comparator(&["2,2"], None);
+        assert_eq!(extract(&c, "a  b c"), "  b");
+        let c = comparator(&["2b,2"], None);
+        assert_eq!(extract(&c, "a  b c"), "b");
+        let c = comparator(&["1.2,1.3"], None);
+        assert_eq!(extract(&c, "abcd e"), "bc");
+        let c = comparator(&["2,3"], Some(b':'));
+        assert_eq!(extract(&c, "a:b:c:d"), "b:c");
+        let c = comparator(&["2.2,2.2"], Some(b':'));
+        assert_eq!(extract(&c, "a:bcd:e"), "c");
+        let c = comparator(&["5"], Some(b':'));
+        assert_eq!(extract(&c, "a:b"), "");
+    }
+
+    #[test]
+    fn test_numbers() {
+        let numeric = |a: &str, b: &str| compare_numeric(a.as_bytes(), b.as_bytes());
+        assert_eq!(numeric(" 10", "9"), Ordering::Greater);
+        assert_eq!(numeric("-0", "0.000"), Ordering::Equal);
+        assert_eq!(numeric("abc", "0"), Ordering::Equal);
+        assert_eq!(numeric("-1.5", "-1.25"), Ordering::Less);
+        assert_eq!(numeric("007.50", "7.5x"), Ordering::Equal);
+        assert_eq!(numeric("123456789012345678901", "99"), Ordering::Greater);
+        let human = |a: &str, b: &str| compare_human(a.as_bytes(), b.as_bytes());
+        assert_eq!(human("2", "1K"), Ordering::Less);
+        assert_eq!(human("1023K", "1M"), Ordering::Less);
+        assert_eq!(human("-1M", "-5K"), Ordering::Less);
+        assert_eq!(human("3k", "1K"), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_sort() {
+        let input = "b 2\na 10\nc 1\nb 2\n";
+        let by_line = Comparator {
+            keys: vec![Key::default()],
+            ..comparator(&[], None)
+        };
+        assert_eq!(
+            sort(input, by_line.clone(), false, 1 << 20),
+            "a 10\nb 2\nb 2\nc 1\n"
+        );
+        assert_eq!(
+            sort(input, comparator(&["2n"], None), false, 1 << 20),
+            "c 1\nb 2\nb 2\na 10\n"
+        );
+        let unique = Comparator {
+            last_resort: false,
+            ..comparator(&["1,1f"], None)
+        };
+        assert_eq!(sort("B\na\nb\nA\n", unique, true, 1 << 20), "a\nB\n");
+    }
+
+    #[test]
+    fn test_external_sort() {
+        // Tiny budgets spill every few lines and merge over several levels.
+        let lines: Vec<String> = (0..2000)
+            .map(|n| format!("{}", (n * 7919) % 1000))
+            .collect();
+        let input = lines.join("\n") + "\n";
+        let numeric = Comparator {
+            keys: vec![Key {
+                order: Order {
+                    numeric: true,
+                    ..Order::default()
+                },
+                ..Key::default()
+            }],
+            ..comparator(&[], None)
+        };
+        let mut expected = lines.clone();
+        expected.sort_by_key(|line| line.parse::<u32>().unwrap());
+        let expected = expected.join("\n") + "\n";
+        assert_eq!(sort(&input, numeric.clone(), false, 1 << 20), expected);
+        assert_eq!(sort(&input, numeric.clone(), false, 64), expected);
+
+        let unique = Comparator {
+            last_resort: false,
+            ..numeric
+        };
+        let expected: Vec<String> = (0..1000).map(|n| n.to_string()).collect();
+        assert_eq!(sort(&input, unique, true, 64), expected.join("\n") + "\n");
+    }
+
+    #[test]
+    fn test_run_read_failure() {
+        // A run that cannot be read back fails as a read, not as a write.
+        let path = tempfile::dir().join(format!("mimic.sort-test.{}", std::process::id()));
+        let file = File::create(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let mut sorter = Sorter::new(comparator(&[], None), false, 1 << 20, tempfile::dir());
+        sorter.runs.push(Run { file, level: 0 });
+        match sorter.finish(Output(Vec::new())) {
+            Err(Error::Read(name, _)) => assert!(name.starts_with("temporary file in")),
+            result => panic!("{:?}", result),
+        }
+    }
+}
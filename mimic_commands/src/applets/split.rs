@@ -0,0 +1,764 @@
+use crate::args::{parse_size, Command};
+use crate::error::{self, Error, Output, Reporter, Style};
+use crate::stdio;
+use std::env;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::os::unix::process::ExitStatusExt;
+use std::process::{self, Child, ExitStatus, Stdio};
+
+/// How much of the input is read at once.
+const BUFFER_SIZE: usize = 1024 * 64;
+
+/// The longest suffix GNU split takes, as it keeps a `size_t` for each letter.
+const MAX_SUFFIX_LENGTH: usize = usize::MAX / std::mem::size_of::<usize>();
+
+/// How the input is cut into pieces.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    /// A piece every N lines.
+    Lines(u64),
+    /// A piece every N bytes.
+    Bytes(u64),
+    /// `-n`: N pieces of about the same size, or only the Kth of them on standard output.
+    Chunks {
+        how: Chunking,
+        k: Option<u64>,
+        n: u64,
+    },
+}
+
+/// How `-n` shares the input out among its pieces.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Chunking {
+    /// Equal runs of bytes, the last piece taking what is left over.
+    Bytes,
+    /// The same, but lines are not broken up (`l/N`).
+    Lines,
+    /// Lines dealt out in turn (`r/N`).
+    RoundRobin,
+}
+
+pub fn main(args: Vec<String>) {
+    let command = Command::new("split", "[OPTION]... [FILE [PREFIX]]")
+        .about("Output pieces of FILE to PREFIXaa, PREFIXab, ...; default size is 1000 lines, and default PREFIX is 'x'.")
+        .opt(
+            "-a, --suffix-length=N",
+            "generate suffixes of length N (default 2)",
+        )
+        .opt("-b, --bytes=SIZE", "put SIZE bytes per output file")
+        .opt("-d", "use numeric suffixes starting at 0, not alphabetic")
+        .opt(
+            "--filter=COMMAND",
+            "write to shell COMMAND; file name is $FILE",
+        )
+        .opt("-l, --lines=NUMBER", "put NUMBER lines per output file")
+        .opt(
+            "-n, --number=CHUNKS",
+            "generate CHUNKS output files: N, K/N, l/N, l/K/N, r/N or r/K/N",
+        );
+    let matches = command.parse_or_exit(args);
+
+    let ways: Vec<&str> = ["lines", "bytes", "number"]
+        .iter()
+        .copied()
+        .filter(|key| matches.flag(key))
+        .collect();
+    let mode = match ways[..] {
+        [] => Mode::Lines(1000),
+        [key] => {
+            let value = matches.value(key).unwrap_or_default();
+            let mode = match key {
+                "lines" => parse_count(value, "number of lines").map(Mode::Lines),
+                "bytes" => parse_count(value, "number of bytes").map(Mode::Bytes),
+                _ => parse_chunks(value),
+            };
+            mode.unwrap_or_else(|message| die(&message))
+        }
+        _ => command.exit_with_usage_error(&"cannot split in more than one way"),
+    };
+    let filter = matches.value("filter");
+    if filter.is_some() && matches!(mode, Mode::Chunks { k: Some(_), .. }) {
+        command.exit_with_usage_error(&"--filter does not process a chunk extracted to stdout");
+    }
+    let (input, prefix) = match matches.operands() {
+        [] => ("-", "x"),
+        [input] => (input.as_str(), "x"),
+        [input, prefix] => (input.as_str(), prefix.as_str()),
+        [_, _, extra, ..] => command.exit_with_usage_error(&format!("extra operand '{}'", extra)),
+    };
+
+    let alphabet: &[u8] = if matches.flag("d") {
+        b"0123456789"
+    } else {
+        b"abcdefghijklmnopqrstuvwxyz"
+    };
+    let length = match matches.value("suffix-length") {
+        Some(value) => parse_suffix_length(value).unwrap_or_else(|message| die(&message)),
+        None => 0,
+    };
+    let suffixes = match mode {
+        Mode::Chunks { n, .. } => {
+            // Every piece is named up front, so the suffix must be long enough for them all.
+            let needed = Suffixes::length_for(alphabet, n);
+            if length != 0 && length < needed {
+                die(&format!(
+                    "the suffix length needs to be at least {}",
+                    needed
+                ));
+            }
+            Suffixes::new(alphabet, length.max(needed).max(2), false)
+        }
+        // Without a length, the suffixes grow longer rather than run out.
+        _ if length == 0 => Suffixes::new(alphabet, 2, true),
+        _ => Suffixes::new(alphabet, length, false),
+    };
+    let mut outputs = Outputs {
+        prefix: prefix.to_string(),
+        suffixes,
+        filter: filter.map(str::to_string),
+    };
+
+    let mut reporter = Reporter::new("split", Style::Bare);
+    let file = if input == "-" {
+        // Borrow fd 0 as a File, so the size of a redirected regular file can be found.
+        let stdin = stdio::stdin_file();
+        stdin.try_clone()
+    } else {
+        File::open(input)
+    };
+    let file = match file {
+        Ok(file) => file,
+        Err(e) => die(&format!(
+            "cannot open '{}' for reading: {}",
+            input,
+            error::describe(&e)
+        )),
+    };
+    let result = match mode {
+        Mode::Lines(count) => split_every(&file, &mut outputs, count, true),
+        Mode::Bytes(count) => split_every(&file, &mut outputs, count, false),
+        Mode::Chunks { how, k, n } => {
+            let size = match how {
+                Chunking::RoundRobin => 0,
+                _ => match input_size(&file) {
+                    Ok(Some(size)) => size,
+                    Ok(None) => die(&format!("{}: cannot determine file size", input)),
+                    Err(e) => die(&format!(
+                        "{}: cannot determine file size: {}",
+                        input,
+                        error::describe(&e)
+                    )),
+                },
+            };
+            match k {
+                Some(k) => {
+                    let mut writer = BufWriter::new(Output(io::stdout()));
+                    extract_chunk(&file, size, how, k, n, &mut writer).and_then(|_| writer.flush())
+                }
+                None => split_chunks(&file, size, how, n, &mut outputs),
+            }
+        }
+    };
+    if let Err(e) = result {
+        reporter.report(&Error::copying(input, e));
+    }
+    reporter.finish();
+}
+
+fn die(message: &dyn fmt::Display) -> ! {
+    eprintln!("split: {}", message);
+    process::exit(1);
+}
+
+/// Parses a positive count, which may have a size suffix.
+fn parse_count(value: &str, what: &str) -> Result<u64, String> {
+    match parse_size(value) {
+        Some(0) => Err(format!(
+            "invalid {}: '{}': Numerical result out of range",
+            what, value
+        )),
+        Some(count) => Ok(count),
+        None if value.bytes().all(|b| b.is_ascii_digit()) => Err(format!(
+            "invalid {}: '{}': Value too large for defined data type",
+            what, value
+        )),
+        None => Err(format!("invalid {}: '{}'", what, value)),
+    }
+}
+
+/// Parses a suffix length, where 0 stands for the default.
+fn parse_suffix_length(value: &str) -> Result<usize, String> {
+    let digits = value.strip_prefix('+').unwrap_or(value);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("invalid suffix length: '{}'", value));
+    }
+    match digits.parse::<usize>() {
+        Ok(length) if length <= MAX_SUFFIX_LENGTH => Ok(length),
+        _ => Err(format!(
+            "invalid suffix length: '{}': Value too large for defined data type",
+            value
+        )),
+    }
+}
+
+/// Parses the `-n` forms `N`, `K/N`, `l/N`, `l/K/N`, `r/N` and `r/K/N`.
+fn parse_chunks(value: &str) -> Result<Mode, String> {
+    let (how, rest) = match value.split_once('/') {
+        Some(("l", rest)) => (Chunking::Lines, rest),
+        Some(("r", rest)) => (Chunking::RoundRobin, rest),
+        _ => (Chunking::Bytes, value),
+    };
+    let (k, n) = match rest.split_once('/') {
+        Some((k, n)) => (Some(k), n),
+        None => (None, rest),
+    };
+    let n_text = n;
+    let n = parse_count(n, "number of chunks")?;
+    // Byte and line chunks are offsets into the input, so there can be no more than a
+    // file offset goes up to.
+    if how != Chunking::RoundRobin && n > i64::MAX as u64 {
+        return Err(format!(
+            "invalid number of chunks: '{}': Value too large for defined data type",
+            n_text
+        ));
+    }
+    let k = match k {
+        Some(k) => match parse_count(k, "chunk number")? {
+            k if k > n => {
+                return Err(format!(
+                    "invalid chunk number: '{}': Numerical result out of range",
+                    k
+                ))
+            }
+            k => Some(k),
+        },
+        None => None,
+    };
+    Ok(Mode::Chunks { how, k, n })
+}
+
+/// How many bytes of `file` are left to read, if it is a regular file.
+fn input_size(mut file: &File) -> io::Result<Option<u64>> {
+    let metadata = file.metadata()?;
+    if !metadata.is_file() {
+        return Ok(None);
+    }
+    let position = file.stream_position()?;
+    Ok(Some(metadata.len().saturating_sub(position)))
+}
+
+/// The suffixes of the output files: `aa`, `ab`, ... or `00`, `01`, ...
+#[derive(Debug)]
+struct Suffixes {
+    alphabet: &'static [u8],
+    /// The current suffix, as indexes into `alphabet`; `None` before the first.
+    digits: Option<Vec<usize>>,
+    length: usize,
+    /// Whether the suffix widens instead of running out. The first letter stays in front
+    /// and the rest starts over one longer, so `yz` is followed by `zaaa` and `89` by
+    /// `9000`, and the names still sort in order.
+    widen: bool,
+    /// The letters kept in front by widening.
+    widened: String,
+}
+
+impl Suffixes {
+    fn new(alphabet: &'static [u8], length: usize, widen: bool) -> Self {
+        Suffixes {
+            alphabet,
+            digits: None,
+            length,
+            widen,
+            widened: String::new(),
+        }
+    }
+
+    /// The shortest length that gives `count` different suffixes.
+    fn length_for(alphabet: &[u8], count: u64) -> usize {
+        let mut length = 1;
+        let mut names = alphabet.len() as u64;
+        while names < count {
+            names = names.saturating_mul(alphabet.len() as u64);
+            length += 1;
+        }
+        length
+    }
+
+    /// The next suffix, or `None` once they have run out.
+    fn next(&mut self) -> Option<String> {
+        let alphabet = self.alphabet;
+        let last = alphabet.len() - 1;
+        let digits = match &mut self.digits {
+            None => {
+                // The length is only held to what GNU split takes, which can be far more
+                // than there is memory for.
+                let mut digits = Vec::new();
+                if digits.try_reserve_exact(self.length).is_err() {
+                    die(&"memory exhausted");
+                }
+                digits.resize(self.length, 0);
+                self.digits.insert(digits)
+            }
+            Some(digits) => {
+                let i = digits.iter().rposition(|&digit| digit < last)?;
+                digits[i] += 1;
+                digits[i + 1..].iter_mut().for_each(|digit| *digit = 0);
+                if self.widen && digits[0] == last {
+                    self.widened.push(alphabet[last] as char);
+                    *digits = vec![0; digits.len() + 1];
+                }
+                digits
+            }
+        };
+        let mut suffix = self.widened.clone();
+        suffix.extend(digits.iter().map(|&digit| alphabet[digit] as char));
+        Some(suffix)
+    }
+}
+
+/// Where the pieces go: a file for each, or a filter command given the file name.
+#[derive(Debug)]
+struct Outputs {
+    prefix: String,
+    suffixes: Suffixes,
+    filter: Option<String>,
+}
+
+impl Outputs {
+    /// Starts the next piece. Failing to is fatal.
+    fn create(&mut self) -> Piece {
+        let name = match self.suffixes.next() {
+            Some(suffix) => format!("{}{}", self.prefix, suffix),
+            None => die(&"output file suffixes exhausted"),
+        };
+        let (writer, filter): (Box<dyn Write>, _) = match &self.filter {
+            None => match File::create(&name) {
+                Ok(file) => (Box::new(file), None),
+                Err(e) => die(&Error::Open(name, e).message(Style::Bare)),
+            },
+            Some(command) => {
+                let shell = match env::var("SHELL") {
+                    Ok(shell) if !shell.is_empty() => shell,
+                    _ => "/bin/sh".to_string(),
+                };
+                let child = process::Command::new(&shell)
+                    .arg("-c")
+                    .arg(command)
+                    .env("FILE", &name)
+                    .stdin(Stdio::piped())
+                    .spawn();
+                match child {
+                    Ok(mut child) => {
+                        let stdin = child.stdin.take().expect("stdin is piped");
+                        (Box::new(stdin), Some((child, command.clone())))
+                    }
+                    Err(e) => die(&format!(
+                        "failed to run command: \"{} -c {}\": {}",
+                        shell,
+                        command,
+                        error::describe(&e)
+                    )),
+                }
+            }
+        };
+        Piece {
+            name,
+            writer: BufWriter::new(writer),
+            filter,
+        }
+    }
+}
+
+/// One output file, or the input of one filter.
+struct Piece {
+    name: String,
+    writer: BufWriter<Box<dyn Write>>,
+    /// The filter reading the piece, and its command line.
+    filter: Option<(Child, String)>,
+}
+
+impl Piece {
+    /// Writes to the piece. Failing to is fatal, except that a filter may stop reading
+    /// early and drop the rest of its piece.
+    fn write_all(&mut self, data: &[u8]) {
+        let result = self.writer.write_all(data);
+        self.check(result);
+    }
+
+    /// Ends the piece, waiting for its filter, if any, to finish with it.
+    fn finish(mut self) {
+        let result = self.writer.flush();
+        self.check(result);
+        let Piece {
+            name,
+            writer,
+            filter,
+        } = self;
+        drop(writer);
+        if let Some((mut child, command)) = filter {
+            match child.wait() {
+                Ok(status) => check_filter(status, &name, &command),
+                Err(e) => die(&format!(
+                    "waiting for child process: {}",
+                    error::describe(&e)
+                )),
+            }
+        }
+    }
+
+    fn check(&self, result: io::Result<()>) {
+        match result {
+            Ok(()) => {}
+            Err(e) if self.filter.is_some() && e.kind() == io::ErrorKind::BrokenPipe => {}
+            Err(e) => die(&format!("{}: {}", self.name, error::describe(&e))),
+        }
+    }
+}
+
+/// Exits as the filter for `name` did, if it failed. Dying of SIGPIPE counts as success,
+/// as the filter was only given more than it wanted.
+fn check_filter(status: ExitStatus, name: &str, command: &str) {
+    let (what, code) = match (status.code(), status.signal()) {
+        (Some(0), _) | (_, Some(13)) => return,
+        (Some(code), _) => (format!("exit {}", code), code),
+        (None, Some(signal)) => (format!("signal {}", signal_name(signal)), 128 + signal),
+        (None, None) => return,
+    };
+    eprintln!(
+        "split: with FILE={}, {} from command: {}",
+        name, what, command
+    );
+    process::exit(code);
+}
+
+fn signal_name(signal: i32) -> String {
+    const NAMES: &[&str] = &[
+        "HUP", "INT", "QUIT", "ILL", "TRAP", "ABRT", "BUS", "FPE", "KILL", "USR1", "SEGV", "USR2",
+        "PIPE", "ALRM", "TERM",
+    ];
+    match NAMES.get(signal as usize - 1) {
+        Some(name) => name.to_string(),
+        None => signal.to_string(),
+    }
+}
+
+/// Calls `f` with each buffer read from `reader` until it ends or `f` wants no more.
+fn for_each_chunk<R, F>(mut reader: R, mut f: F) -> io::Result<()>
+where
+    R: Read,
+    F: FnMut(&[u8]) -> io::Result<bool>,
+{
+    let mut buffer = vec![0; BUFFER_SIZE];
+    loop {
+        let size = match reader.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(size) => size,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if !f(&buffer[..size])? {
+            return Ok(());
+        }
+    }
+}
+
+/// Starts a new piece after every `count` lines, or bytes if not `by_lines`. No piece is
+/// made for an empty input.
+fn split_every<R: Read>(
+    reader: R,
+    outputs: &mut Outputs,
+    count: u64,
+    by_lines: bool,
+) -> io::Result<()> {
+    let mut piece: Option<Piece> = None;
+    let mut left = count;
+    for_each_chunk(reader, |mut data| {
+        while !data.is_empty() {
+            let end = if by_lines {
+                let mut end = data.len();
+                for (i, &b) in data.iter().enumerate() {
+                    if b == b'\n' {
+                        left -= 1;
+                        if left == 0 {
+                            end = i + 1;
+                            break;
+                        }
+                    }
+                }
+                end
+            } else {
+                let end = left.min(data.len() as u64);
+                left -= end;
+                end as usize
+            };
+            piece
+                .get_or_insert_with(|| outputs.create())
+                .write_all(&data[..end]);
+            if left == 0 {
+                piece.take().expect("just written to").finish();
+                left = count;
+            }
+            data = &data[end..];
+        }
+        Ok(true)
+    })?;
+    if let Some(piece) = piece {
+        piece.finish();
+    }
+    Ok(())
+}
+
+/// Deals out the first `size` bytes of `reader` among `n` chunks, calling `f` with the
+/// index of the chunk each part goes to, in order, until it wants no more.
+///
+/// Each chunk has `size / n` bytes, at least one, and the last takes the rest. With
+/// `Chunking::Lines` a line goes whole to the chunk it starts in, and with
+/// `Chunking::RoundRobin` the lines go to each chunk in turn, regardless of `size`.
+fn chunks<R, F>(reader: R, size: u64, how: Chunking, n: u64, mut f: F) -> io::Result<()>
+where
+    R: Read,
+    F: FnMut(u64, &[u8]) -> io::Result<bool>,
+{
+    let chunk_size = (size / n).max(1);
+    let chunk_of = |position: u64| (position / chunk_size).min(n - 1);
+    let mut position = 0;
+    let mut line = 0;
+    let mut line_chunk = 0;
+    let mut at_line_start = true;
+    let reader: Box<dyn Read> = match how {
+        Chunking::RoundRobin => Box::new(reader),
+        _ => Box::new(reader.take(size)),
+    };
+    for_each_chunk(reader, |mut data| {
+        while !data.is_empty() {
+            let (chunk, end) = match how {
+                Chunking::Bytes => {
+                    let chunk = chunk_of(position);
+                    let end = if chunk + 1 < n {
+                        ((chunk + 1) * chunk_size - position).min(data.len() as u64)
+                    } else {
+                        data.len() as u64
+                    };
+                    (chunk, end as usize)
+                }
+                Chunking::Lines | Chunking::RoundRobin => {
+                    if at_line_start {
+                        line_chunk = match how {
+                            Chunking::Lines => chunk_of(position),
+                            _ => line % n,
+                        };
+                        line += 1;
+                    }
+                    let end = data.iter().position(|&b| b == b'\n');
+                    at_line_start = end.is_some();
+                    (line_chunk, end.map_or(data.len(), |end| end + 1))
+                }
+            };
+            if !f(chunk, &data[..end])? {
+                return Ok(false);
+            }
+            position += end as u64;
+            data = &data[end..];
+        }
+        Ok(true)
+    })
+}
+
+/// Writes `n` pieces, all of them even if some are empty.
+fn split_chunks<R: Read>(
+    reader: R,
+    size: u64,
+    how: Chunking,
+    n: u64,
+    outputs: &mut Outputs,
+) -> io::Result<()> {
+    if how == Chunking::RoundRobin {
+        let mut pieces: Vec<Piece> = (0..n).map(|_| outputs.create()).collect();
+        chunks(reader, size, how, n, |chunk, data| {
+            pieces[chunk as usize].write_all(data);
+            Ok(true)
+        })?;
+        pieces.into_iter().for_each(Piece::finish);
+        return Ok(());
+    }
+    let mut piece = outputs.create();
+    let mut current = 0;
+    chunks(reader, size, how, n, |chunk, data| {
+        while current < chunk {
+            std::mem::replace(&mut piece, outputs.create()).finish();
+            current += 1;
+        }
+        piece.write_all(data);
+        Ok(true)
+    })?;
+    piece.finish();
+    for _ in current + 1..n {
+        outputs.create().finish();
+    }
+    Ok(())
+}
+
+/// Writes only the `k`th of `n` chunks to `writer`.
+fn extract_chunk<R: Read + Seek, W: Write>(
+    mut reader: R,
+    size: u64,
+    how: Chunking,
+    k: u64,
+    n: u64,
+    writer: &mut W,
+) -> io::Result<()> {
+    if how == Chunking::Bytes {
+        // The chunk is found without reading what comes before it.
+        let chunk_size = (size / n).max(1);
+        let start = ((k - 1) * chunk_size).min(size);
+        let end = if k == n {
+            size
+        } else {
+            (k * chunk_size).min(size)
+        };
+        reader.seek(SeekFrom::Current(start as i64))?;
+        io::copy(&mut reader.take(end - start), writer)?;
+        return Ok(());
+    }
+    chunks(reader, size, how, n, |chunk, data| {
+        if chunk == k - 1 {
+            writer.write_all(data)?;
+        }
+        // Round robin goes on to the end, and chunks of lines end once past the one wanted.
+        Ok(how == Chunking::RoundRobin || chunk < k)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Cursor;
+
+    fn suffixes(alphabet: &'static [u8], length: usize, widen: bool, count: usize) -> Vec<String> {
+        let mut suffixes = Suffixes::new(alphabet, length, widen);
+        (0..count).map_while(|_| suffixes.next()).collect()
+    }
+
+    fn chunk_sizes(input: &str, how: Chunking, n: u64) -> Vec<String> {
+        let mut pieces = vec![String::new(); n as usize];
+        chunks(
+            input.as_bytes(),
+            input.len() as u64,
+            how,
+            n,
+            |chunk, data| {
+                pieces[chunk as usize].push_str(std::str::from_utf8(data).unwrap());
+                Ok(true)
+            },
+        )
+        .unwrap();
+        pieces
+    }
+
+    fn extract(input: &str, how: Chunking, k: u64, n: u64) -> String {
+        let mut output = Vec::new();
+        let reader = Cursor::new(input.as_bytes());
+        extract_chunk(reader, input.len() as u64, how, k, n, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse_count("2K", "number of lines"), Ok(2048));
+        assert!(parse_count("0", "number of lines").is_err());
+        assert!(parse_count("x", "number of lines").is_err());
+        let chunks = |how, k, n| Mode::Chunks { how, k, n };
+        assert_eq!(parse_chunks("4"), Ok(chunks(Chunking::Bytes, None, 4)));
+        assert_eq!(parse_chunks("2/4"), Ok(chunks(Chunking::Bytes, Some(2), 4)));
+        assert_eq!(parse_chunks("l/4"), Ok(chunks(Chunking::Lines, None, 4)));
+        assert_eq!(
+            parse_chunks("r/3/4"),
+            Ok(chunks(Chunking::RoundRobin, Some(3), 4))
+        );
+        assert!(parse_chunks("5/4").is_err());
+        assert!(parse_chunks("x/4").is_err());
+        assert_eq!(
+            parse_chunks("18446744073709551615"),
+            Err("invalid number of chunks: '18446744073709551615': Value too large for defined data type".to_string())
+        );
+        assert!(parse_chunks("r/18446744073709551615").is_ok());
+        assert_eq!(parse_suffix_length("+3"), Ok(3));
+        assert!(parse_suffix_length("-0").is_err());
+        assert!(parse_suffix_length("x").is_err());
+        assert_eq!(
+            parse_suffix_length("18446744073709551615"),
+            Err("invalid suffix length: '18446744073709551615': Value too large for defined data type".to_string())
+        );
+    }
+
+    #[test]
+    fn test_suffixes() {
+        assert_eq!(suffixes(b"abc", 2, false, 3), ["aa", "ab", "ac"]);
+        assert_eq!(suffixes(b"01", 2, false, 5).len(), 4);
+        assert_eq!(
+            suffixes(b"abc", 2, true, 9)[5..],
+            ["bc", "caaa", "caab", "caac"]
+        );
+        let numeric = suffixes(b"0123456789", 2, true, 1000);
+        assert_eq!(numeric[89..91], ["89", "9000"]);
+        assert_eq!(numeric[989..991], ["9899", "990000"]);
+        assert_eq!(Suffixes::length_for(b"0123456789", 100), 2);
+        assert_eq!(Suffixes::length_for(b"0123456789", 101), 3);
+    }
+
+    #[test]
+    fn test_chunks() {
+        assert_eq!(
+            chunk_sizes("abcdefg", Chunking::Bytes, 3),
+            ["ab", "cd", "efg"]
+        );
+        assert_eq!(
+            chunk_sizes("abc", Chunking::Bytes, 5),
+            ["a", "b", "c", "", ""]
+        );
+        assert_eq!(
+            chunk_sizes("one\ntwo\nthree\nfour\n", Chunking::Lines, 3),
+            ["one\ntwo\n", "three\n", "four\n"]
+        );
+        assert_eq!(
+            chunk_sizes("a\nb\nc", Chunking::Lines, 5),
+            ["a\n", "", "b\n", "", "c"]
+        );
+        assert_eq!(
+            chunk_sizes("1\n2\n3\n4\n5", Chunking::RoundRobin, 2),
+            ["1\n3\n5", "2\n4\n"]
+        );
+        assert_eq!(extract("abcdefg", Chunking::Bytes, 3, 3), "efg");
+        assert_eq!(extract("abc", Chunking::Bytes, 4, 5), "");
+        assert_eq!(extract("a\nb\nc", Chunking::Lines, 3, 5), "b\n");
+        assert_eq!(
+            extract("1\n2\n3\n4\n5", Chunking::RoundRobin, 1, 2),
+            "1\n3\n5"
+        );
+    }
+
+    #[test]
+    fn test_split_every() {
+        let dir = env::temp_dir().join(format!("mimic_split_test_{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut outputs = Outputs {
+            prefix: dir.join("x").display().to_string(),
+            suffixes: Suffixes::new(b"0123456789", 2, true),
+            filter: None,
+        };
+        split_every("1\n2\n3\n4\n5".as_bytes(), &mut outputs, 2, true).unwrap();
+        split_every("abcde".as_bytes(), &mut outputs, 4, false).unwrap();
+        split_every("".as_bytes(), &mut outputs, 4, false).unwrap();
+        let read = |name: &str| fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(
+            ["x00", "x01", "x02", "x03", "x04"].map(read),
+            ["1\n2\n", "3\n4\n", "5", "abcd", "e"]
+        );
+        assert!(!dir.join("x05").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,166 @@
+use crate::args::Command;
+use crate::backward::{Records, Separator};
+use crate::error::{self, Error, Output, Reporter, Style};
+use crate::regex::{RegexBuilder, Syntax};
+use crate::stdio;
+use crate::tempfile;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Read, Seek, Write};
+use std::process::exit;
+
+/// How much of an input is held at once while looking for separators.
+const BUFFER_SIZE: usize = 1024 * 64;
+
+pub fn main(args: Vec<String>) {
+    let command = Command::new("tac", "[OPTION]... [FILE]...")
+        .about("Write each FILE to standard output, last line first.")
+        .opt(
+            "-b, --before",
+            "attach the separator before instead of after",
+        )
+        .opt(
+            "-r, --regex",
+            "interpret the separator as a regular expression",
+        )
+        .opt(
+            "-s, --separator=STRING",
+            "use STRING as the separator instead of newline",
+        );
+    let matches = command.parse_or_exit(args);
+
+    let mut reporter = Reporter::new("tac", Style::Bare);
+    // An empty separator is taken to mean the NUL byte.
+    let separator = match matches.value("separator") {
+        Some("") => "\0",
+        Some(separator) => separator,
+        None => "\n",
+    };
+    // GNU tac compiles the separator in GNU regex's default syntax, not either POSIX one.
+    let separator = if matches.flag("regex") {
+        match RegexBuilder::new(Syntax::Emacs).build(&[separator]) {
+            Ok(regex) => Separator::Regex(Box::new(regex)),
+            Err(e) => {
+                reporter.fail(&e);
+                exit(1);
+            }
+        }
+    } else {
+        Separator::Bytes(separator.as_bytes().to_vec())
+    };
+    let before = matches.flag("before");
+
+    let mut writer = BufWriter::new(Output(io::stdout()));
+    for path in matches.files() {
+        let file = if path == "-" {
+            // Borrow fd 0 as a File so a redirected regular file can still be seeked.
+            let stdin = stdio::stdin_file();
+            stdin.try_clone()
+        } else {
+            File::open(&path)
+        };
+        let file = match file {
+            Ok(file) => file,
+            Err(e) => {
+                reporter.fail(&format!(
+                    "failed to open '{}' for reading: {}",
+                    path,
+                    error::describe(&e)
+                ));
+                continue;
+            }
+        };
+        let result = tac_file(file, &mut writer, &separator, before).and_then(|_| writer.flush());
+        if let Err(e) = result {
+            match Error::copying(&path, e) {
+                Error::Read(name, e) => {
+                    reporter.fail(&format!("{}: read error: {}", name, error::describe(&e)))
+                }
+                e => reporter.report(&e),
+            }
+        }
+    }
+    reporter.finish();
+}
+
+/// Reverses a regular file in place, and anything else by way of a scratch copy.
+fn tac_file<W: Write>(
+    file: File,
+    writer: &mut W,
+    separator: &Separator,
+    before: bool,
+) -> io::Result<()> {
+    let metadata = file.metadata()?;
+    if metadata.is_file() {
+        return tac(file, metadata.len(), writer, separator, before);
+    }
+    let (copy, size) = spool(file)?;
+    tac(copy, size, writer, separator, before)
+}
+
+/// Copies `reader` to a scratch file, which can then be read from its end.
+fn spool<R: Read>(mut reader: R) -> io::Result<(File, u64)> {
+    let mut copy = tempfile::create_in(&tempfile::dir())?;
+    let size = io::copy(&mut reader, &mut copy)?;
+    Ok((copy, size))
+}
+
+/// Writes the records of the first `size` bytes of `reader`, last first.
+fn tac<R: Read + Seek, W: Write>(
+    reader: R,
+    size: u64,
+    writer: &mut W,
+    separator: &Separator,
+    before: bool,
+) -> io::Result<()> {
+    let mut records = Records::new(reader, size, separator.clone(), before, BUFFER_SIZE);
+    while let Some(range) = records.next_back()? {
+        records.copy(range, writer)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn run(input: &str, separator: Separator, before: bool) -> String {
+        let mut output = Vec::new();
+        let reader = Cursor::new(input.as_bytes());
+        tac(reader, input.len() as u64, &mut output, &separator, before).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_tac() {
+        let newline = || Separator::Bytes(b"\n".to_vec());
+        assert_eq!(run("a\nb\nc\n", newline(), false), "c\nb\na\n");
+        assert_eq!(run("a\nb\nc", newline(), false), "cb\na\n");
+        assert_eq!(run("a\n\n", newline(), true), "\n\na");
+        assert_eq!(run("", newline(), false), "");
+        let regex = RegexBuilder::new(Syntax::Emacs).build(&["[0-9]+"]).unwrap();
+        assert_eq!(
+            run("a1b22c", Separator::Regex(Box::new(regex)), false),
+            "c2b2a1"
+        );
+    }
+
+    #[test]
+    fn test_spool() {
+        // Inputs that cannot be seeked are reversed from a copy.
+        let long = "x".repeat(BUFFER_SIZE * 2);
+        let input = format!("{0}\n{0}y\n", long);
+        let (copy, size) = spool(input.as_bytes()).unwrap();
+        let mut output = Vec::new();
+        tac(
+            copy,
+            size,
+            &mut output,
+            &Separator::Bytes(b"\n".to_vec()),
+            false,
+        )
+        .unwrap();
+        assert_eq!(output, format!("{0}y\n{0}\n", long).into_bytes());
+    }
+}
@@ -0,0 +1,720 @@
+use crate::args::{parse_size, Command};
+use crate::backward::{Records, Separator};
+use crate::error::{self, Error, Output, Reporter, Style};
+use crate::inflate;
+use crate::ring::{ByteRing, LineRing};
+use crate::stdio;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::time::Duration;
+use std::{fs, io, thread};
+
+/// What part of each input to print.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    /// The last N lines.
+    Lines(usize),
+    /// The last N bytes.
+    Bytes(usize),
+    /// Everything from the Nth line on (`-n +N`), counting from 1.
+    FromLine(u64),
+    /// Everything from the Nth byte on (`-c +N`), counting from 1.
+    FromByte(u64),
+}
+
+/// How `-f` keeps track of a followed file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Follow {
+    /// Keep reading the opened descriptor, even if the file is renamed or removed (`-f`).
+    Descriptor,
+    /// Keep reading whatever file the name refers to, reopening it after rotation (`-F`).
+    Name,
+}
+
+pub fn main(args: Vec<String>) {
+    let command = Command::new("tail", "[OPTION]... [FILE]...")
+        .about("Print the last 10 lines of each FILE to standard output.")
+        .opt(
+            "-c, --bytes=[+]NUM",
+            "output the last NUM bytes; or use -c +NUM to output starting with byte NUM of each file",
+        )
+        .opt(
+            "-f, --follow[=HOW]",
+            "output appended data as the file grows; HOW is 'descriptor' (default) or 'name'",
+        )
+        .opt("-F", "same as --follow=name --retry")
+        .opt(
+            "--decompress",
+            "decompress gzip input; other input is read as it is (-z is not short for this, but NUL-terminated lines)",
+        )
+        .opt(
+            "-n, --lines=[+]NUM",
+            "output the last NUM lines, instead of the last 10; or use -n +NUM to skip NUM-1 lines at the start",
+        )
+        .opt("--pid=PID", "with -f, terminate after process ID, PID dies")
+        .opt(
+            "--retry",
+            "keep trying to open a file if it is inaccessible",
+        )
+        .opt(
+            "-s, --sleep-interval=N",
+            "with -f, sleep for approximately N seconds (default 1.0) between iterations",
+        )
+        .opt("-z, --zero-terminated", "line delimiter is NUL, not newline");
+    let matches = command.parse_or_exit(args);
+
+    let mode = match matches.last_of(&["bytes", "lines"]) {
+        Some(key) => {
+            let value = matches.value(key).unwrap_or_default();
+            parse_mode(key, value).unwrap_or_else(|| {
+                command.exit_with_usage_error(&format!("invalid number of {}: '{}'", key, value))
+            })
+        }
+        None => Mode::Lines(10),
+    };
+
+    let follow = match matches.last_of(&["follow", "F"]) {
+        Some("F") => Some(Follow::Name),
+        Some(_) => match matches.value("follow") {
+            None | Some("descriptor") => Some(Follow::Descriptor),
+            Some("name") => Some(Follow::Name),
+            Some(how) => {
+                command.exit_with_usage_error(&format!("invalid argument '{}' for '--follow'", how))
+            }
+        },
+        None => None,
+    };
+    let decompress = matches.flag("decompress");
+    if decompress && follow.is_some() {
+        command.exit_with_usage_error(&"cannot follow decompressed input");
+    }
+    let delimiter = if matches.flag("zero-terminated") {
+        b'\0'
+    } else {
+        b'\n'
+    };
+    let retry = matches.flag("retry") || matches.flag("F");
+    let interval = match matches.value("sleep-interval") {
+        Some(value) => match value.parse::<f64>() {
+            Ok(seconds) if seconds >= 0.0 && seconds.is_finite() => {
+                Duration::from_secs_f64(seconds)
+            }
+            _ => command.exit_with_usage_error(&format!("invalid number of seconds: '{}'", value)),
+        },
+        None => Duration::from_secs(1),
+    };
+    let pid = matches.value("pid").map(|value| {
+        value
+            .parse::<i32>()
+            .unwrap_or_else(|_| command.exit_with_usage_error(&format!("invalid PID: '{}'", value)))
+    });
+
+    let paths = matches.files();
+    let print_headers = paths.len() > 1;
+    let mut reporter = Reporter::new("tail", Style::Verbose);
+    let mut writer = BufWriter::new(Output(io::stdout()));
+    let mut followed = Vec::new();
+    // Unopenable files get no header, and no blank line is left for them.
+    let mut header_printed = false;
+    for (operand, path) in paths.iter().enumerate() {
+        let header = print_headers.then(|| {
+            let separator = if header_printed { "\n" } else { "" };
+            format!("{}==> {} <==\n", separator, display_name(path))
+        });
+        let result = print_tail_of_file(
+            path,
+            &mut writer,
+            mode,
+            delimiter,
+            header.as_deref(),
+            decompress,
+        );
+        header_printed |= header.is_some() && !matches!(result, Err(Error::Open(..)));
+
+        // GNU tail ignores -f when standard input is a pipe.
+        let file = reporter
+            .check(result)
+            .filter(|f| path != "-" || is_regular(f));
+        if path != "-" || file.is_some() {
+            followed.push(Followed::new(operand, path, file));
+        }
+    }
+
+    if let Some(how) = follow {
+        if how == Follow::Descriptor && !retry {
+            followed.retain(|f| f.file.is_some());
+        }
+        let last_printed = paths.len().saturating_sub(1);
+        let follower = Follower {
+            how,
+            interval,
+            pid,
+            print_headers,
+        };
+        let result = follower.run(followed, last_printed);
+        reporter.check(result);
+    }
+    reporter.finish();
+}
+
+fn parse_mode(key: &str, value: &str) -> Option<Mode> {
+    if let Some(number) = value.strip_prefix('+') {
+        let number = parse_size(number)?;
+        return match key {
+            "bytes" => Some(Mode::FromByte(number)),
+            _ => Some(Mode::FromLine(number)),
+        };
+    }
+
+    let number = usize::try_from(parse_size(value.strip_prefix('-').unwrap_or(value))?).ok()?;
+    match key {
+        "bytes" => Some(Mode::Bytes(number)),
+        _ => Some(Mode::Lines(number)),
+    }
+}
+
+fn display_name(path: &str) -> &str {
+    if path == "-" {
+        "standard input"
+    } else {
+        path
+    }
+}
+
+/// Prints the tail of the file and hands it back, positioned at its end, for `-f`.
+fn print_tail_of_file<W: Write>(
+    path: &str,
+    writer: &mut W,
+    mode: Mode,
+    delimiter: u8,
+    header: Option<&str>,
+    decompress: bool,
+) -> Result<File, Error> {
+    let file = if path == "-" {
+        // Borrow fd 0 as a File so a redirected regular file can still be seeked.
+        let stdin = stdio::stdin_file();
+        stdin.try_clone()
+    } else {
+        File::open(path)
+    }
+    .map_err(|e| Error::Open(path.to_string(), e))?;
+
+    let result = writer
+        .write_all(header.unwrap_or_default().as_ref())
+        .and_then(|_| {
+            if decompress {
+                let reader = inflate::decompressed(BufReader::new(&file))?;
+                tail_stream(reader, writer, mode, delimiter)
+            } else {
+                print_tail_of(&file, writer, mode, delimiter)
+            }
+        });
+    // Flushed per file, even after a failure, so the output stays in order with the error messages.
+    let flushed = writer.flush();
+    result
+        .and(flushed)
+        .map_err(|e| Error::copying(display_name(path), e))?;
+    Ok(file)
+}
+
+fn is_regular(file: &File) -> bool {
+    file.metadata().map(|m| m.is_file()).unwrap_or(false)
+}
+
+/// Seeks from the end when possible, and streams pipes, FIFOs and terminals through a ring buffer.
+fn print_tail_of<W: Write>(
+    mut file: &File,
+    writer: &mut W,
+    mode: Mode,
+    delimiter: u8,
+) -> io::Result<()> {
+    let file_type = file.metadata()?.file_type();
+    if file_type.is_fifo() || file_type.is_char_device() || file_type.is_socket() {
+        tail_stream(file, writer, mode, delimiter)
+    } else {
+        let mut buffer = [0; 1024 * 4];
+        let seek_from = calc_seek_from(&mut file, mode, delimiter, &mut buffer)?;
+        file.seek(seek_from)?;
+        io::copy(&mut file, writer)?;
+        Ok(())
+    }
+}
+
+/// A file being watched by `-f`/`-F`.
+struct Followed {
+    /// Where the file is among the operands, which is what a header tells apart.
+    operand: usize,
+    path: String,
+    file: Option<File>,
+    /// Device and inode of `file`, to notice when `-F` should switch to a new file.
+    id: Option<(u64, u64)>,
+    position: u64,
+}
+
+impl Followed {
+    fn new(operand: usize, path: &str, mut file: Option<File>) -> Self {
+        let id = file
+            .as_ref()
+            .and_then(|f| f.metadata().ok())
+            .map(|m| (m.dev(), m.ino()));
+        let position = file
+            .as_mut()
+            .and_then(|f| f.stream_position().ok())
+            .unwrap_or(0);
+        Self {
+            operand,
+            path: path.to_string(),
+            file,
+            id,
+            position,
+        }
+    }
+
+    /// Whether the name now refers to a different file than the one we have open, or to nothing.
+    fn name_changed(&self) -> bool {
+        let id = fs::metadata(&self.path).ok().map(|m| (m.dev(), m.ino()));
+        id != self.id
+    }
+
+    /// Switches to whatever file the name refers to now, after rotation, recreation or removal.
+    fn reopen(&mut self) {
+        let opened = File::open(&self.path).and_then(|f| {
+            let metadata = f.metadata()?;
+            Ok((f, (metadata.dev(), metadata.ino())))
+        });
+        match opened {
+            Ok((file, id)) => {
+                if self.id.is_some() {
+                    eprintln!(
+                        "tail: '{}' has been replaced;  following new file",
+                        self.path
+                    );
+                } else {
+                    eprintln!("tail: '{}' has appeared;  following new file", self.path);
+                }
+                self.file = Some(file);
+                self.id = Some(id);
+                self.position = 0;
+            }
+            Err(e) => {
+                if self.file.take().is_some() {
+                    eprintln!(
+                        "tail: '{}' has become inaccessible: {}",
+                        self.path,
+                        error::describe(&e)
+                    );
+                }
+                self.id = None;
+            }
+        }
+    }
+
+    /// Rewinds when the file shrank below what we have already printed.
+    fn check_truncated(&mut self) -> io::Result<()> {
+        if let Some(file) = &mut self.file {
+            let metadata = file.metadata()?;
+            if metadata.is_file() && metadata.len() < self.position {
+                eprintln!("tail: {}: file truncated", self.path);
+                file.seek(SeekFrom::Start(0))?;
+                self.position = 0;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Polls followed files and copies whatever was appended to them.
+struct Follower {
+    how: Follow,
+    interval: Duration,
+    pid: Option<i32>,
+    print_headers: bool,
+}
+
+impl Follower {
+    fn run(&self, mut files: Vec<Followed>, mut last_printed: usize) -> Result<(), Error> {
+        let stdout = io::stdout();
+        let mut writer = Output(stdout.lock());
+        let mut buffer = [0; 1024 * 4];
+
+        loop {
+            // Checked before reading so the writer's last output is still printed.
+            let writer_alive = self.pid.is_none_or(is_process_alive);
+            let mut read_any = false;
+
+            for followed in files.iter_mut() {
+                let mut copy = |followed: &mut Followed| -> io::Result<bool> {
+                    followed.check_truncated()?;
+                    let mut read_any =
+                        self.copy_appended(followed, &mut last_printed, &mut writer, &mut buffer)?;
+                    if self.how == Follow::Name && followed.name_changed() {
+                        // Drain what was written to the old file right before it was rotated.
+                        self.copy_appended(followed, &mut last_printed, &mut writer, &mut buffer)?;
+                        followed.reopen();
+                        read_any |= self.copy_appended(
+                            followed,
+                            &mut last_printed,
+                            &mut writer,
+                            &mut buffer,
+                        )?;
+                    }
+                    Ok(read_any)
+                };
+                read_any |=
+                    copy(followed).map_err(|e| Error::copying(display_name(&followed.path), e))?;
+            }
+            writer.flush().map_err(Error::writing)?;
+
+            if !writer_alive {
+                return Ok(());
+            }
+            if files.is_empty() {
+                eprintln!("tail: no files remaining");
+                return Ok(());
+            }
+            if !read_any {
+                thread::sleep(self.interval);
+            }
+        }
+    }
+
+    fn copy_appended<W: Write>(
+        &self,
+        followed: &mut Followed,
+        last_printed: &mut usize,
+        writer: &mut W,
+        buffer: &mut [u8],
+    ) -> io::Result<bool> {
+        let file = match &mut followed.file {
+            Some(file) => file,
+            None => return Ok(false),
+        };
+
+        let mut read_any = false;
+        loop {
+            let size = file.read(buffer)?;
+            if size == 0 {
+                return Ok(read_any);
+            }
+            if self.print_headers && *last_printed != followed.operand {
+                writer
+                    .write_all(format!("\n==> {} <==\n", display_name(&followed.path)).as_ref())?;
+                *last_printed = followed.operand;
+            }
+            writer.write_all(&buffer[..size])?;
+            followed.position += size as u64;
+            read_any = true;
+        }
+    }
+}
+
+extern "C" {
+    fn kill(pid: i32, sig: i32) -> i32;
+}
+
+/// Signal 0 checks for existence; EPERM still means the process is there.
+fn is_process_alive(pid: i32) -> bool {
+    const EPERM: i32 = 1;
+    let result = unsafe { kill(pid, 0) };
+    result == 0 || io::Error::last_os_error().raw_os_error() == Some(EPERM)
+}
+
+/// Finds where the output starts, reading backwards from the end in buffer-sized chunks
+/// for the "last N" modes so that only the tail of a big file is ever read.
+fn calc_seek_from<T: Read + Seek>(
+    reader: &mut T,
+    mode: Mode,
+    delimiter: u8,
+    buffer: &mut [u8],
+) -> io::Result<SeekFrom> {
+    let file_size = reader.seek(SeekFrom::End(0))?;
+    let seek_from = match mode {
+        Mode::Lines(max_line) => {
+            let start = find_last_lines(reader, file_size, max_line, delimiter, buffer.len())?;
+            SeekFrom::End(-((file_size - start) as i64))
+        }
+        Mode::Bytes(max_size) => SeekFrom::End(-(file_size.min(max_size as u64) as i64)),
+        Mode::FromLine(line) => SeekFrom::Start(find_line_start(reader, line, delimiter, buffer)?),
+        Mode::FromByte(byte) => SeekFrom::Start(byte.saturating_sub(1).min(file_size)),
+    };
+    Ok(seek_from)
+}
+
+/// The offset at which the last `max_line` lines start. A delimiter at the very end of the
+/// file terminates the last line instead of starting a new, empty one.
+fn find_last_lines<T: Read + Seek>(
+    reader: &mut T,
+    file_size: u64,
+    max_line: usize,
+    delimiter: u8,
+    buffer_size: usize,
+) -> io::Result<u64> {
+    let separator = Separator::Bytes(vec![delimiter]);
+    let mut lines = Records::new(reader, file_size, separator, false, buffer_size);
+    let mut start = file_size;
+    for _ in 0..max_line {
+        match lines.next_back()? {
+            Some(line) => start = line.start,
+            None => break,
+        }
+    }
+    Ok(start)
+}
+
+/// The offset at which line `line` (counting from 1) starts, or the file size if there is none.
+fn find_line_start<T: Read + Seek>(
+    reader: &mut T,
+    line: u64,
+    delimiter: u8,
+    buffer: &mut [u8],
+) -> io::Result<u64> {
+    let mut read_line_count = 1;
+    let mut offset = 0;
+    reader.seek(SeekFrom::Start(0))?;
+    while read_line_count < line {
+        let size = reader.read(buffer)?;
+        if size == 0 {
+            break;
+        }
+        for b in &buffer[..size] {
+            offset += 1;
+            if *b == delimiter {
+                read_line_count += 1;
+                if read_line_count == line {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(offset)
+}
+
+/// Reads the whole input once, keeping only the last lines or bytes in memory
+/// or skipping up to the starting line or byte.
+fn tail_stream<R: Read, W: Write>(
+    mut reader: R,
+    writer: &mut W,
+    mode: Mode,
+    delimiter: u8,
+) -> io::Result<()> {
+    let mut buffer = [0; 1024 * 4];
+    match mode {
+        Mode::Lines(max_line) => {
+            let mut ring = LineRing::new(max_line, delimiter);
+            loop {
+                let size = reader.read(&mut buffer[..])?;
+                if size == 0 {
+                    break;
+                }
+                ring.push(&buffer[..size], |_| Ok(()))?;
+            }
+            for line in ring.lines() {
+                writer.write_all(line)?;
+            }
+        }
+        Mode::Bytes(max_size) => {
+            let mut ring = ByteRing::new(max_size);
+            loop {
+                let size = reader.read(&mut buffer[..])?;
+                if size == 0 {
+                    break;
+                }
+                ring.push(&buffer[..size], |_| Ok(()))?;
+            }
+            let (front, back) = ring.as_slices();
+            writer.write_all(front)?;
+            writer.write_all(back)?;
+        }
+        Mode::FromLine(line) => {
+            let mut read_line_count = 1;
+            loop {
+                let size = reader.read(&mut buffer[..])?;
+                if size == 0 {
+                    break;
+                }
+                let mut skip_size = 0;
+                while read_line_count < line && skip_size < size {
+                    if buffer[skip_size] == delimiter {
+                        read_line_count += 1;
+                    }
+                    skip_size += 1;
+                }
+                writer.write_all(&buffer[skip_size..size])?;
+            }
+        }
+        Mode::FromByte(byte) => {
+            io::copy(
+                &mut (&mut reader).take(byte.saturating_sub(1)),
+                &mut io::sink(),
+            )?;
+            io::copy(&mut reader, writer)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_tail_stream() {
+        let run = |input: &str, mode: Mode| {
+            let mut output = Vec::new();
+            tail_stream(input.as_bytes(), &mut output, mode, b'\n').unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        assert_eq!(run("1\n2\n3\n4", Mode::Lines(1)), "4");
+        assert_eq!(run("1\n2\n3\n4\n", Mode::Lines(2)), "3\n4\n");
+        assert_eq!(run("1\n2\n", Mode::Lines(10)), "1\n2\n");
+        assert_eq!(run("1\n2\n", Mode::Lines(0)), "");
+        assert_eq!(run("1\n2\n3\n4", Mode::Bytes(3)), "3\n4");
+        assert_eq!(run("1\n2", Mode::Bytes(10)), "1\n2");
+        assert_eq!(run("1\n2\n3\n4", Mode::FromLine(3)), "3\n4");
+        assert_eq!(run("1\n2\n3\n4", Mode::FromLine(0)), "1\n2\n3\n4");
+        assert_eq!(run("1\n2\n", Mode::FromLine(5)), "");
+        assert_eq!(run("1\n2\n3\n4", Mode::FromByte(3)), "2\n3\n4");
+        assert_eq!(run("1\n2", Mode::FromByte(10)), "");
+
+        let mut output = Vec::new();
+        tail_stream("a\0b\0c\0".as_bytes(), &mut output, Mode::Lines(2), b'\0').unwrap();
+        assert_eq!(output, b"b\0c\0");
+    }
+
+    #[test]
+    fn test_follow_appended_and_truncated() {
+        let path = env::temp_dir().join(format!("tail_follow_test_{}", std::process::id()));
+        fs::write(&path, "a\n").unwrap();
+        let mut file = File::open(&path).unwrap();
+        file.seek(SeekFrom::End(0)).unwrap();
+        let mut followed = Followed::new(1, path.to_str().unwrap(), Some(file));
+        let follower = Follower {
+            how: Follow::Name,
+            interval: Duration::from_millis(10),
+            pid: None,
+            print_headers: true,
+        };
+        let mut output = Vec::new();
+        let mut last_printed = 0;
+        let mut copy = |followed: &mut Followed, output: &mut Vec<u8>| {
+            follower
+                .copy_appended(followed, &mut last_printed, output, &mut [0; 4])
+                .unwrap()
+        };
+
+        assert!(!copy(&mut followed, &mut output));
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"bcdefg\n")
+            .unwrap();
+        assert!(copy(&mut followed, &mut output));
+        assert_eq!(followed.position, 9);
+        assert!(!followed.name_changed());
+
+        fs::write(&path, "h\n").unwrap();
+        followed.check_truncated().unwrap();
+        assert_eq!(followed.position, 0);
+        assert!(copy(&mut followed, &mut output));
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!("\n==> {} <==\nbcdefg\nh\n", path.display())
+        );
+
+        fs::remove_file(&path).unwrap();
+        assert!(followed.name_changed());
+    }
+
+    #[test]
+    fn test_calc_seek_from() {
+        use Mode::*;
+        let cases: &[(&str, Mode, usize, SeekFrom)] = &[
+            // buffer is smaller than file size
+            ("1\n2\n3\n4", Lines(1), 1, SeekFrom::End(-1)),
+            ("1\n2\n3\n44", Lines(1), 1, SeekFrom::End(-2)),
+            ("1\n2\n3\n4", Lines(2), 1, SeekFrom::End(-3)),
+            ("1\n2\n3\n4", Lines(100), 1, SeekFrom::End(-7)),
+            ("1\n2\n3\n4\n", Lines(1), 1, SeekFrom::End(-2)),
+            ("1\n2\n3\n4\n", Lines(2), 3, SeekFrom::End(-4)),
+            // buffer is bigger than file size
+            ("1\n2\n3\n4", Lines(1), 1024, SeekFrom::End(-1)),
+            ("1\n2\n3\n44", Lines(1), 1024, SeekFrom::End(-2)),
+            ("1\n2\n3\n4", Lines(2), 1024, SeekFrom::End(-3)),
+            ("1\n2\n3\n4", Lines(100), 1024, SeekFrom::End(-7)),
+            ("1\n2\n3\n4\n", Lines(1), 1024, SeekFrom::End(-2)),
+            // file size is a multiple of the buffer size
+            ("1\n2\n3\n4\n", Lines(1), 2, SeekFrom::End(-2)),
+            ("1\n2\n3\n4\n", Lines(3), 4, SeekFrom::End(-6)),
+            ("1\n2\n3\n4\n", Lines(100), 4, SeekFrom::End(-8)),
+            ("1\n2\n3\n4\n", Lines(100), 8, SeekFrom::End(-8)),
+            ("12\n34\n", Lines(1), 3, SeekFrom::End(-3)),
+            // no newline at all, empty lines and empty files
+            ("1234", Lines(1), 2, SeekFrom::End(-4)),
+            ("\n\n\n", Lines(2), 1, SeekFrom::End(-2)),
+            ("", Lines(10), 1, SeekFrom::End(0)),
+            ("1\n2", Lines(0), 1, SeekFrom::End(0)),
+            // last N bytes
+            ("1\n2\n3\n4", Bytes(3), 1, SeekFrom::End(-3)),
+            ("1\n2\n3\n4", Bytes(100), 1, SeekFrom::End(-7)),
+            ("", Bytes(3), 1, SeekFrom::End(0)),
+            // from line N
+            ("1\n2\n3\n4", FromLine(1), 1, SeekFrom::Start(0)),
+            ("1\n2\n3\n4", FromLine(0), 1, SeekFrom::Start(0)),
+            ("1\n2\n3\n4", FromLine(3), 1, SeekFrom::Start(4)),
+            ("1\n2\n3\n4", FromLine(3), 1024, SeekFrom::Start(4)),
+            ("1\n2\n3\n4", FromLine(4), 2, SeekFrom::Start(6)),
+            ("1\n2\n3\n4\n", FromLine(5), 2, SeekFrom::Start(8)),
+            ("1\n2\n3\n4", FromLine(100), 2, SeekFrom::Start(7)),
+            ("", FromLine(2), 1, SeekFrom::Start(0)),
+            // from byte N
+            ("1\n2\n3\n4", FromByte(1), 1, SeekFrom::Start(0)),
+            ("1\n2\n3\n4", FromByte(0), 1, SeekFrom::Start(0)),
+            ("1\n2\n3\n4", FromByte(3), 1, SeekFrom::Start(2)),
+            ("1\n2\n3\n4", FromByte(100), 1, SeekFrom::Start(7)),
+        ];
+
+        for (input, mode, buffer_size, expected) in cases {
+            assert_eq!(
+                calc_seek_from(
+                    &mut Cursor::new(input),
+                    *mode,
+                    b'\n',
+                    &mut vec![0; *buffer_size]
+                )
+                .unwrap(),
+                *expected,
+                "{:?} {:?} buffer={}",
+                input,
+                mode,
+                buffer_size
+            );
+        }
+
+        // NUL-delimited records
+        assert_eq!(
+            calc_seek_from(
+                &mut Cursor::new("a\0b\nc\0d\0"),
+                Lines(2),
+                b'\0',
+                &mut [0; 2]
+            )
+            .unwrap(),
+            SeekFrom::End(-6)
+        );
+        assert_eq!(
+            calc_seek_from(
+                &mut Cursor::new("a\0b\nc\0d\0"),
+                FromLine(2),
+                b'\0',
+                &mut [0; 2]
+            )
+            .unwrap(),
+            SeekFrom::Start(2)
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use crate::args::Command;
+use crate::error::{self, Reporter, Style, EXIT_BROKEN_PIPE};
+use crate::stdio;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::{Read, Write};
+use std::process::exit;
+
+/// How much is read at once; each read is passed on before the next.
+const BUFFER_SIZE: usize = 1024 * 64;
+
+/// What to do when an output cannot be written.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OnError {
+    /// Without `--output-error`: a closed pipe ends the command as SIGPIPE would, and
+    /// other errors are reported.
+    Default,
+    Warn,
+    /// `-p`: as `Warn`, but a closed pipe is dropped without a word.
+    WarnNoPipe,
+    Exit,
+    /// As `Exit`, but a closed pipe is dropped without a word.
+    ExitNoPipe,
+}
+
+const MODES: &[(&str, OnError)] = &[
+    ("warn", OnError::Warn),
+    ("warn-nopipe", OnError::WarnNoPipe),
+    ("exit", OnError::Exit),
+    ("exit-nopipe", OnError::ExitNoPipe),
+];
+
+pub fn main(args: Vec<String>) {
+    let command = Command::new("tee", "[OPTION]... [FILE]...")
+        .about("Copy standard input to each FILE, and also to standard output.")
+        .opt(
+            "-a, --append",
+            "append to the given FILEs, do not overwrite",
+        )
+        .opt("-i, --ignore-interrupts", "ignore interrupt signals")
+        .opt("-p", "diagnose errors writing to non pipes")
+        .opt(
+            "--output-error[=MODE]",
+            "set behavior on write error: warn, warn-nopipe, exit or exit-nopipe",
+        );
+    let matches = command.parse_or_exit(args);
+
+    let on_error = match matches.last_of(&["p", "output-error"]) {
+        None => OnError::Default,
+        Some("p") => OnError::WarnNoPipe,
+        Some(_) => match matches.value("output-error") {
+            None => OnError::WarnNoPipe,
+            Some(mode) => parse_mode(mode).unwrap_or_else(|problem| {
+                let valid: String = MODES
+                    .iter()
+                    .map(|(name, _)| format!("\n  - '{}'", name))
+                    .collect();
+                command.exit_with_usage_error(&format!(
+                    "{} argument '{}' for '--output-error'\nValid arguments are:{}",
+                    problem, mode, valid
+                ))
+            }),
+        },
+    };
+    if matches.flag("ignore-interrupts") {
+        ignore_interrupts();
+    }
+
+    let mut reporter = Reporter::new("tee", Style::Bare);
+    // Standard output is borrowed as a File so that it is written unbuffered, as the
+    // files are.
+    let stdout = stdio::stdout_file();
+    let mut outputs = Vec::new();
+    match stdout.try_clone() {
+        Ok(stdout) => outputs.push(("standard output".to_string(), stdout)),
+        Err(e) => reporter.fail(&format!("standard output: {}", error::describe(&e))),
+    }
+    for path in matches.operands() {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .append(matches.flag("append"))
+            .truncate(!matches.flag("append"))
+            .open(path);
+        match file {
+            Ok(file) => outputs.push((path.clone(), file)),
+            Err(e) => reporter.fail(&format!(
+                "{}: {}",
+                error::quote_if_needed(path),
+                error::describe(&e)
+            )),
+        }
+    }
+
+    if let Err(e) = tee(io::stdin(), outputs, on_error, &mut reporter) {
+        reporter.fail(&format!("read error: {}", error::describe(&e)));
+    }
+    reporter.finish();
+}
+
+/// Finds a mode by its name or an unambiguous start of it, or says what is wrong.
+fn parse_mode(name: &str) -> Result<OnError, &'static str> {
+    if let Some((_, mode)) = MODES.iter().find(|(mode, _)| *mode == name) {
+        return Ok(*mode);
+    }
+    let mut candidates = MODES.iter().filter(|(mode, _)| mode.starts_with(name));
+    match (candidates.next(), candidates.next()) {
+        (Some((_, mode)), None) if !name.is_empty() => Ok(*mode),
+        (Some(_), Some(_)) if !name.is_empty() => Err("ambiguous"),
+        _ => Err("invalid"),
+    }
+}
+
+fn ignore_interrupts() {
+    extern "C" {
+        fn signal(signum: i32, handler: usize) -> usize;
+    }
+    const SIGINT: i32 = 2;
+    const SIG_IGN: usize = 1;
+    unsafe {
+        signal(SIGINT, SIG_IGN);
+    }
+}
+
+/// Copies `reader` to every output until it ends or no output is left. An output that
+/// fails is dropped, after `on_error` has been dealt with. Only read errors are returned.
+fn tee<R: Read, W: Write>(
+    mut reader: R,
+    mut outputs: Vec<(String, W)>,
+    on_error: OnError,
+    reporter: &mut Reporter,
+) -> io::Result<()> {
+    let mut buffer = vec![0; BUFFER_SIZE];
+    while !outputs.is_empty() {
+        let size = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(size) => size,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        outputs.retain_mut(|(name, writer)| match writer.write_all(&buffer[..size]) {
+            Ok(()) => true,
+            Err(e) => {
+                fail_output(name, &e, on_error, reporter);
+                false
+            }
+        });
+    }
+    Ok(())
+}
+
+fn fail_output(name: &str, e: &io::Error, on_error: OnError, reporter: &mut Reporter) {
+    let broken_pipe = e.kind() == io::ErrorKind::BrokenPipe;
+    match on_error {
+        OnError::Default if broken_pipe => exit(EXIT_BROKEN_PIPE),
+        OnError::WarnNoPipe | OnError::ExitNoPipe if broken_pipe => return,
+        _ => {}
+    }
+    reporter.fail(&format!(
+        "{}: {}",
+        error::quote_if_needed(name),
+        error::describe(e)
+    ));
+    if matches!(on_error, OnError::Exit | OnError::ExitNoPipe) {
+        exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Takes `limit` bytes, then fails like a closed pipe.
+    struct Pipe {
+        written: Vec<u8>,
+        limit: usize,
+    }
+
+    impl Write for Pipe {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.written.len() >= self.limit {
+                return Err(io::ErrorKind::BrokenPipe.into());
+            }
+            let size = buf.len().min(self.limit - self.written.len());
+            self.written.extend_from_slice(&buf[..size]);
+            Ok(size)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_parse_mode() {
+        assert_eq!(parse_mode("warn"), Ok(OnError::Warn));
+        assert_eq!(parse_mode("exit-"), Ok(OnError::ExitNoPipe));
+        assert_eq!(parse_mode("e"), Err("ambiguous"));
+        assert_eq!(parse_mode("bogus"), Err("invalid"));
+        assert_eq!(parse_mode(""), Err("invalid"));
+    }
+
+    #[test]
+    fn test_tee() {
+        let mut reporter = Reporter::new("tee", Style::Bare);
+        let mut first = Vec::new();
+        let mut second = Vec::new();
+        let outputs = vec![
+            ("a".to_string(), &mut first),
+            ("b".to_string(), &mut second),
+        ];
+        tee(&b"hello"[..], outputs, OnError::Default, &mut reporter).unwrap();
+        assert_eq!(
+            (first.as_slice(), second.as_slice()),
+            (&b"hello"[..], &b"hello"[..])
+        );
+        assert!(!reporter.failed());
+
+        // A closed pipe is dropped quietly, and copying goes on to the other outputs.
+        let mut pipe = Pipe {
+            written: Vec::new(),
+            limit: 2,
+        };
+        let mut file = Pipe {
+            written: Vec::new(),
+            limit: usize::MAX,
+        };
+        let outputs = vec![
+            ("pipe".to_string(), &mut pipe),
+            ("file".to_string(), &mut file),
+        ];
+        let input = vec![b'x'; BUFFER_SIZE * 3];
+        tee(&input[..], outputs, OnError::WarnNoPipe, &mut reporter).unwrap();
+        assert_eq!((pipe.written.len(), file.written), (2, input));
+        assert!(!reporter.failed());
+    }
+}
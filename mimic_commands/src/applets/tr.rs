@@ -0,0 +1,568 @@
+use crate::args::Command;
+use crate::error::{self, Error, Output, Reporter, Style};
+use std::io;
+use std::io::{BufWriter, Read, Write};
+use std::process::exit;
+
+pub fn main(args: Vec<String>) {
+    let command = Command::new("tr", "[OPTION]... SET1 [SET2]")
+        .about("Translate, squeeze, and/or delete characters from standard input, writing to standard output.")
+        .opt("-c, --complement", "use the complement of SET1")
+        .opt("-C", "same as -c")
+        .opt("-d, --delete", "delete characters in SET1, do not translate")
+        .opt(
+            "-s, --squeeze-repeats",
+            "replace each sequence of a repeated character that is listed in the last specified SET, with a single occurrence of that character",
+        )
+        .usage_status(1);
+    let matches = command.parse_or_exit(args);
+    let complement = matches.flag("complement") || matches.flag("C");
+    let delete = matches.flag("delete");
+    let squeeze = matches.flag("squeeze-repeats");
+
+    let operands = matches.operands();
+    // Translating, or deleting and squeezing, takes two sets; the rest take one.
+    let needed = if delete == squeeze { 2 } else { 1 };
+    // Squeezing alone takes one or two sets.
+    let allowed = if squeeze && !delete { 2 } else { needed };
+    match operands {
+        [] => command.exit_with_usage_error(&"missing operand"),
+        [set] if needed == 2 => {
+            let why = if delete {
+                "Two strings must be given when both deleting and squeezing repeats."
+            } else {
+                "Two strings must be given when translating."
+            };
+            command.exit_with_usage_error(&format!("missing operand after '{}'\n{}", set, why))
+        }
+        _ if operands.len() > allowed => {
+            let extra = &operands[allowed];
+            if delete && !squeeze {
+                command.exit_with_usage_error(&format!(
+                    "extra operand '{}'\nOnly one string may be given when deleting without squeezing repeats.",
+                    extra
+                ))
+            } else {
+                command.exit_with_usage_error(&format!("extra operand '{}'", extra))
+            }
+        }
+        _ => {}
+    }
+
+    let set1 = operands[0].as_str();
+    let set2 = operands.get(1).map(String::as_str);
+    let translator =
+        Translator::new(set1, set2, complement, delete, squeeze).unwrap_or_else(|message| {
+            eprintln!("tr: {}", message);
+            exit(1);
+        });
+
+    let mut reporter = Reporter::new("tr", Style::Bare);
+    let mut writer = BufWriter::new(Output(io::stdout()));
+    let result = tr(io::stdin(), &mut writer, translator).and_then(|_| writer.flush());
+    if let Err(e) = result {
+        match Error::copying("", e) {
+            Error::Read(_, e) => reporter.fail(&format!("read error: {}", error::describe(&e))),
+            e => reporter.report(&e),
+        }
+    }
+    reporter.finish();
+}
+
+/// A character class, as `[:name:]` in a set.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Class {
+    Alnum,
+    Alpha,
+    Blank,
+    Cntrl,
+    Digit,
+    Graph,
+    Lower,
+    Print,
+    Punct,
+    Space,
+    Upper,
+    Xdigit,
+}
+
+impl Class {
+    fn parse(name: &[u8]) -> Option<Self> {
+        let class = match name {
+            b"alnum" => Class::Alnum,
+            b"alpha" => Class::Alpha,
+            b"blank" => Class::Blank,
+            b"cntrl" => Class::Cntrl,
+            b"digit" => Class::Digit,
+            b"graph" => Class::Graph,
+            b"lower" => Class::Lower,
+            b"print" => Class::Print,
+            b"punct" => Class::Punct,
+            b"space" => Class::Space,
+            b"upper" => Class::Upper,
+            b"xdigit" => Class::Xdigit,
+            _ => return None,
+        };
+        Some(class)
+    }
+
+    /// Whether `b` is in the class, in the C locale.
+    fn contains(self, b: u8) -> bool {
+        match self {
+            Class::Alnum => b.is_ascii_alphanumeric(),
+            Class::Alpha => b.is_ascii_alphabetic(),
+            Class::Blank => b == b' ' || b == b'\t',
+            Class::Cntrl => b.is_ascii_control(),
+            Class::Digit => b.is_ascii_digit(),
+            Class::Graph => b.is_ascii_graphic(),
+            Class::Lower => b.is_ascii_lowercase(),
+            Class::Print => b.is_ascii_graphic() || b == b' ',
+            Class::Punct => b.is_ascii_punctuation(),
+            Class::Space => b.is_ascii_whitespace() || b == 0x0b,
+            Class::Upper => b.is_ascii_uppercase(),
+            Class::Xdigit => b.is_ascii_hexdigit(),
+        }
+    }
+
+    /// Its members in ascending order.
+    fn members(self) -> impl Iterator<Item = u8> {
+        (0..=255).filter(move |b| self.contains(*b))
+    }
+}
+
+/// One piece of a set.
+#[derive(Debug, Clone, PartialEq)]
+enum Element {
+    Byte(u8),
+    Range(u8, u8),
+    Class(Class),
+    /// `[c*n]`, or `[c*]` to fill SET2 up to the length of SET1.
+    Repeat(u8, Option<usize>),
+}
+
+/// Parses a set after its backslash escapes are resolved, the way GNU tr does.
+fn parse_set(set: &str) -> Result<Vec<Element>, String> {
+    let units = unescape(set);
+    // Whether the unit at `i` is the unescaped byte `b`.
+    let is = |i: usize, b: u8| units.get(i) == Some(&(b, false));
+    let mut elements = Vec::new();
+    let mut i = 0;
+    while i < units.len() {
+        if is(i, b'[') && (is(i + 1, b':') || is(i + 1, b'=')) {
+            let delimiter = units[i + 1].0;
+            let close = (i + 2..units.len()).find(|j| is(*j, delimiter) && is(j + 1, b']'));
+            if let Some(close) = close {
+                let name: Vec<u8> = units[i + 2..close].iter().map(|(b, _)| *b).collect();
+                let element = if delimiter == b':' {
+                    let class = Class::parse(&name).ok_or_else(|| {
+                        format!(
+                            "invalid character class '{}'",
+                            String::from_utf8_lossy(&name)
+                        )
+                    })?;
+                    Element::Class(class)
+                } else {
+                    match name[..] {
+                        [b] => Element::Byte(b),
+                        _ => {
+                            return Err(format!(
+                                "{}: equivalence class operand must be a single character",
+                                String::from_utf8_lossy(&name)
+                            ))
+                        }
+                    }
+                };
+                elements.push(element);
+                i = close + 2;
+                continue;
+            }
+        }
+        if is(i, b'[') && is(i + 2, b'*') {
+            if let Some(close) = (i + 3..units.len()).find(|j| is(*j, b']')) {
+                let digits: String = units[i + 3..close]
+                    .iter()
+                    .map(|(b, _)| *b as char)
+                    .collect();
+                let count = if digits.is_empty() {
+                    None
+                } else {
+                    let radix = if digits.starts_with('0') { 8 } else { 10 };
+                    match usize::from_str_radix(&digits, radix) {
+                        Ok(0) => None,
+                        Ok(count) => Some(count),
+                        Err(_) => {
+                            return Err(format!(
+                                "invalid repeat count '{}' in [c*n] construct",
+                                digits
+                            ))
+                        }
+                    }
+                };
+                elements.push(Element::Repeat(units[i + 1].0, count));
+                i = close + 1;
+                continue;
+            }
+        }
+        if is(i + 1, b'-') && i + 2 < units.len() {
+            let (lo, hi) = (units[i].0, units[i + 2].0);
+            if hi < lo {
+                return Err(format!(
+                    "range-endpoints of '{}-{}' are in reverse collating sequence order",
+                    lo as char, hi as char
+                ));
+            }
+            elements.push(Element::Range(lo, hi));
+            i += 3;
+            continue;
+        }
+        elements.push(Element::Byte(units[i].0));
+        i += 1;
+    }
+    Ok(elements)
+}
+
+/// Resolves backslash escapes into bytes, marking which ones were escaped.
+fn unescape(set: &str) -> Vec<(u8, bool)> {
+    let bytes = set.as_bytes();
+    let mut units = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'\\' {
+            units.push((bytes[i], false));
+            i += 1;
+            continue;
+        }
+        let next = match bytes.get(i + 1) {
+            Some(next) => *next,
+            None => {
+                eprintln!("tr: warning: an unescaped backslash at end of string is not portable");
+                units.push((b'\\', false));
+                break;
+            }
+        };
+        i += 2;
+        let b = match next {
+            b'a' => 0x07,
+            b'b' => 0x08,
+            b'f' => 0x0c,
+            b'n' => b'\n',
+            b'r' => b'\r',
+            b't' => b'\t',
+            b'v' => 0x0b,
+            b'0'..=b'7' => {
+                // Up to three octal digits, as long as the value fits in a byte.
+                let mut value = u32::from(next - b'0');
+                for _ in 0..2 {
+                    match bytes.get(i) {
+                        Some(d @ b'0'..=b'7') if value * 8 + u32::from(d - b'0') <= 0xff => {
+                            value = value * 8 + u32::from(d - b'0');
+                            i += 1;
+                        }
+                        _ => break,
+                    }
+                }
+                value as u8
+            }
+            other => other,
+        };
+        units.push((b, true));
+    }
+    units
+}
+
+/// The bytes a set stands for, in order, and where its `[:upper:]` and `[:lower:]` are.
+#[derive(Debug, Default)]
+struct Expanded {
+    bytes: Vec<u8>,
+    cases: Vec<(usize, Class)>,
+    /// Whether the set ends with a character class.
+    ends_with_class: bool,
+}
+
+/// Expands `elements`; a `[c*]` takes up whatever `fill_to` leaves over.
+fn expand(elements: &[Element], fill_to: Option<usize>) -> Result<Expanded, String> {
+    let fixed: usize = elements
+        .iter()
+        .map(|element| match element {
+            Element::Byte(_) => 1,
+            Element::Range(lo, hi) => usize::from(hi - lo) + 1,
+            Element::Class(class) => class.members().count(),
+            Element::Repeat(_, count) => count.unwrap_or(0),
+        })
+        .sum();
+    let mut expanded = Expanded::default();
+    let mut filled = false;
+    for element in elements {
+        match element {
+            Element::Byte(b) => expanded.bytes.push(*b),
+            Element::Range(lo, hi) => expanded.bytes.extend(*lo..=*hi),
+            Element::Class(class) => {
+                if matches!(class, Class::Upper | Class::Lower) {
+                    expanded.cases.push((expanded.bytes.len(), *class));
+                }
+                expanded.bytes.extend(class.members());
+            }
+            Element::Repeat(b, Some(count)) => {
+                expanded.bytes.extend(std::iter::repeat_n(*b, *count))
+            }
+            Element::Repeat(b, None) => {
+                let fill_to = match fill_to {
+                    Some(fill_to) => fill_to,
+                    None => {
+                        return Err(
+                            "the [c*] repeat construct may not appear in string1".to_string()
+                        )
+                    }
+                };
+                if filled {
+                    return Err("only one [c*] repeat construct may appear in string2".to_string());
+                }
+                filled = true;
+                let count = fill_to.saturating_sub(fixed);
+                expanded.bytes.extend(std::iter::repeat_n(*b, count));
+            }
+        }
+    }
+    expanded.ends_with_class = matches!(elements.last(), Some(Element::Class(_)));
+    Ok(expanded)
+}
+
+/// Byte tables built from the sets, and what was last written for squeezing.
+#[derive(Debug)]
+struct Translator {
+    map: [u8; 256],
+    delete: [bool; 256],
+    squeeze: [bool; 256],
+    /// The last byte written, if it is one to squeeze.
+    last: Option<u8>,
+    output: Vec<u8>,
+}
+
+impl Translator {
+    fn new(
+        set1: &str,
+        set2: Option<&str>,
+        complement: bool,
+        delete: bool,
+        squeeze: bool,
+    ) -> Result<Self, String> {
+        let elements1 = parse_set(set1)?;
+        let elements2 = set2.map(parse_set).transpose()?;
+        let mut expanded1 = expand(&elements1, None)?;
+        if complement {
+            let mut members = [false; 256];
+            for b in &expanded1.bytes {
+                members[usize::from(*b)] = true;
+            }
+            expanded1.bytes = (0..=255).filter(|b| !members[usize::from(*b)]).collect();
+            expanded1.cases.clear();
+        }
+        let set1 = expanded1.bytes;
+
+        let mut translator = Translator {
+            map: [0; 256],
+            delete: [false; 256],
+            squeeze: [false; 256],
+            last: None,
+            output: Vec::new(),
+        };
+        for (i, b) in translator.map.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        let translating = !delete && elements2.is_some();
+        let set2 = match &elements2 {
+            Some(elements2) => {
+                let expanded2 = expand(elements2, Some(set1.len()))?;
+                if translating {
+                    check_classes(elements2, &expanded1.cases, &expanded2, set1.len())?;
+                }
+                let mut set2 = expanded2.bytes;
+                if translating && set2.len() < set1.len() {
+                    let last = *set2
+                        .last()
+                        .ok_or("when not truncating set1, string2 must be non-empty")?;
+                    set2.resize(set1.len(), last);
+                }
+                set2
+            }
+            None => Vec::new(),
+        };
+
+        if delete {
+            for b in &set1 {
+                translator.delete[usize::from(*b)] = true;
+            }
+        } else if translating {
+            for (from, to) in set1.iter().zip(&set2) {
+                translator.map[usize::from(*from)] = *to;
+            }
+        }
+        if squeeze {
+            // Squeezing applies to the last set given.
+            let squeezed = if elements2.is_some() { &set2 } else { &set1 };
+            for b in squeezed {
+                translator.squeeze[usize::from(*b)] = true;
+            }
+        }
+        Ok(translator)
+    }
+
+    fn update<W: Write>(&mut self, chunk: &[u8], writer: &mut W) -> io::Result<()> {
+        self.output.clear();
+        for b in chunk {
+            if self.delete[usize::from(*b)] {
+                continue;
+            }
+            let b = self.map[usize::from(*b)];
+            if self.squeeze[usize::from(b)] {
+                if self.last == Some(b) {
+                    continue;
+                }
+                self.last = Some(b);
+            } else {
+                self.last = None;
+            }
+            self.output.push(b);
+        }
+        writer.write_all(&self.output)
+    }
+}
+
+/// Checks the classes in SET2 against SET1, since only case conversions line up.
+fn check_classes(
+    elements2: &[Element],
+    cases1: &[(usize, Class)],
+    expanded2: &Expanded,
+    len1: usize,
+) -> Result<(), String> {
+    let only_cases = elements2.iter().all(|element| {
+        !matches!(element, Element::Class(class) if !matches!(class, Class::Upper | Class::Lower))
+    });
+    if !only_cases {
+        return Err("when translating, the only character classes that may appear in\nstring2 are 'upper' and 'lower'".to_string());
+    }
+    if expanded2.ends_with_class && len1 > expanded2.bytes.len() {
+        return Err("when translating with string1 longer than string2,\nthe latter string must not end with a character class".to_string());
+    }
+    let aligned = expanded2
+        .cases
+        .iter()
+        .all(|(at, _)| cases1.iter().any(|(at1, _)| at1 == at));
+    if !aligned {
+        return Err("misaligned [:upper:] and/or [:lower:] construct".to_string());
+    }
+    Ok(())
+}
+
+fn tr<R: Read, W: Write>(
+    mut reader: R,
+    writer: &mut W,
+    mut translator: Translator,
+) -> io::Result<()> {
+    let mut buffer = [0; 1024 * 4];
+    loop {
+        let size = reader.read(&mut buffer[..])?;
+        if size == 0 {
+            return Ok(());
+        }
+        translator.update(&buffer[..size], writer)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(input: &str, set1: &str, set2: Option<&str>, flags: &str) -> Result<String, String> {
+        let translator = Translator::new(
+            set1,
+            set2,
+            flags.contains('c'),
+            flags.contains('d'),
+            flags.contains('s'),
+        )?;
+        let mut output = Vec::new();
+        tr(input.as_bytes(), &mut output, translator).unwrap();
+        Ok(String::from_utf8_lossy(&output).into_owned())
+    }
+
+    #[test]
+    fn test_parse_set() {
+        assert_eq!(
+            parse_set("a-c\\n[:digit:][x*3][y*][=z=]").unwrap(),
+            vec![
+                Element::Range(b'a', b'c'),
+                Element::Byte(b'\n'),
+                Element::Class(Class::Digit),
+                Element::Repeat(b'x', Some(3)),
+                Element::Repeat(b'y', None),
+                Element::Byte(b'z'),
+            ]
+        );
+        assert_eq!(
+            parse_set("\\[a*]\\101\\0123-").unwrap(),
+            vec![
+                Element::Byte(b'['),
+                Element::Byte(b'a'),
+                Element::Byte(b'*'),
+                Element::Byte(b']'),
+                Element::Byte(b'A'),
+                Element::Byte(0o12),
+                Element::Byte(b'3'),
+                Element::Byte(b'-'),
+            ]
+        );
+        assert_eq!(
+            parse_set("[x*010]").unwrap(),
+            vec![Element::Repeat(b'x', Some(8))]
+        );
+        assert!(parse_set("z-a").is_err());
+        assert!(parse_set("[:foo:]").is_err());
+    }
+
+    #[test]
+    fn test_tr() {
+        let ok = |input, set1, set2, flags| run(input, set1, set2, flags).unwrap();
+        assert_eq!(ok("hello", "a-y", Some("b-z"), ""), "ifmmp");
+        assert_eq!(ok("abcdef", "a-f", Some("x[y*]z"), ""), "xyyyyz");
+        assert_eq!(ok("abcdef", "a-f", Some("xy"), ""), "xyyyyy");
+        assert_eq!(ok("Hello", "[:lower:]", Some("[:upper:]"), ""), "HELLO");
+        assert_eq!(
+            ok(
+                "Hello",
+                "[:upper:][:lower:]",
+                Some("[:lower:][:upper:]"),
+                ""
+            ),
+            "hELLO"
+        );
+        assert_eq!(ok("abc\n", "a-c\\n", Some("_"), "c"), "abc\n");
+        assert_eq!(ok("abcdef", "a-c", Some("_"), "c"), "abc___");
+        assert_eq!(ok("hello world", "lo", None, "d"), "he wrd");
+        assert_eq!(ok("aabbccdd  ee", "a-c ", None, "s"), "abcdd ee");
+        assert_eq!(ok("aabbccdd", "a-d", Some("xxyy"), "s"), "xy");
+        assert_eq!(ok("hello  world", "lo", Some(" "), "ds"), "he wrd");
+        assert_eq!(ok("ab1", "[:alpha:]", None, "cd"), "ab");
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = |set1, set2, flags| run("", set1, set2, flags).unwrap_err();
+        assert_eq!(
+            err("a", Some(""), ""),
+            "when not truncating set1, string2 must be non-empty"
+        );
+        assert_eq!(
+            err("[a*]", Some("x"), ""),
+            "the [c*] repeat construct may not appear in string1"
+        );
+        assert_eq!(
+            err("a-z", Some("[:upper:]"), ""),
+            "misaligned [:upper:] and/or [:lower:] construct"
+        );
+        assert!(err("[:lower:]", Some("[:digit:]"), "").starts_with("when translating, the only"));
+        assert!(
+            err("a", Some("[:upper:]"), "c").starts_with("when translating with string1 longer")
+        );
+    }
+}
@@ -0,0 +1,273 @@
+use crate::args::Command;
+use crate::error::{Error, Output, Reporter, Style};
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Read, Write};
+use std::mem;
+use std::process::exit;
+
+/// Which lines of each group of equal lines to print.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Options {
+    count: bool,
+    /// Print only groups of more than one line (`-d`).
+    repeated: bool,
+    /// Print only groups of a single line (`-u`).
+    unique: bool,
+    ignore_case: bool,
+    skip_fields: usize,
+    skip_chars: usize,
+}
+
+pub fn main(args: Vec<String>) {
+    let command = Command::new("uniq", "[OPTION]... [INPUT [OUTPUT]]")
+        .about("Filter adjacent matching lines from INPUT (or standard input), writing to OUTPUT (or standard output).")
+        .opt("-c, --count", "prefix lines by the number of occurrences")
+        .opt("-d, --repeated", "only print duplicate lines, one for each group")
+        .opt(
+            "-f, --skip-fields=N",
+            "avoid comparing the first N fields",
+        )
+        .opt(
+            "-i, --ignore-case",
+            "ignore differences in case when comparing",
+        )
+        .opt(
+            "-s, --skip-chars=N",
+            "avoid comparing the first N characters",
+        )
+        .opt("-u, --unique", "only print unique lines")
+        .usage_status(1);
+    let matches = command.parse_or_exit(args);
+
+    let skip = |key: &str, what: &str| match matches.value(key) {
+        Some(value) => value.parse().unwrap_or_else(|_| {
+            eprintln!("uniq: {}: invalid number of {} to skip", value, what);
+            exit(1);
+        }),
+        None => 0,
+    };
+    let options = Options {
+        count: matches.flag("count"),
+        repeated: matches.flag("repeated"),
+        unique: matches.flag("unique"),
+        ignore_case: matches.flag("ignore-case"),
+        skip_fields: skip("skip-fields", "fields"),
+        skip_chars: skip("skip-chars", "bytes"),
+    };
+    let (input, output) = match matches.operands() {
+        [] => ("-", None),
+        [input] => (input.as_str(), None),
+        [input, output] => (input.as_str(), Some(output.as_str())),
+        [_, _, extra, ..] => command.exit_with_usage_error(&format!("extra operand '{}'", extra)),
+    };
+
+    let mut reporter = Reporter::new("uniq", Style::Bare);
+    let reader: Box<dyn Read> = if input == "-" {
+        Box::new(io::stdin())
+    } else {
+        match File::open(input) {
+            Ok(file) => Box::new(file),
+            Err(e) => {
+                reporter.report(&Error::Open(input.to_string(), e));
+                exit(1);
+            }
+        }
+    };
+    let writer: Box<dyn Write> = match output {
+        Some(path) if path != "-" => match File::create(path) {
+            Ok(file) => Box::new(file),
+            Err(e) => {
+                reporter.report(&Error::Open(path.to_string(), e));
+                exit(1);
+            }
+        },
+        _ => Box::new(io::stdout()),
+    };
+    let mut writer = BufWriter::new(Output(writer));
+    let result = uniq(reader, &mut writer, options).and_then(|_| writer.flush());
+    if let Err(e) = result {
+        match Error::copying(input, e) {
+            // GNU uniq names the input but not the reason.
+            Error::Read(name, _) => reporter.fail(&format!("error reading '{}'", name)),
+            e => reporter.report(&e),
+        }
+    }
+    reporter.finish();
+}
+
+/// Filters `reader` into `writer`, holding no more than two lines at a time.
+fn uniq<R: Read, W: Write>(mut reader: R, writer: &mut W, options: Options) -> io::Result<()> {
+    let mut buffer = [0; 1024 * 4];
+    let mut filter = Filter::new(options);
+    loop {
+        let size = reader.read(&mut buffer[..])?;
+        if size == 0 {
+            break;
+        }
+        filter.update(&buffer[..size], writer)?;
+    }
+    filter.finish(writer)
+}
+
+/// Compares each line with the first of the current group as the chunks come in.
+struct Filter {
+    options: Options,
+    /// The first line of the current group, without its newline.
+    first: Vec<u8>,
+    /// How many lines the current group has; 0 before the first line.
+    count: u64,
+    /// The line being read, continued across chunks.
+    line: Vec<u8>,
+}
+
+impl Filter {
+    fn new(options: Options) -> Self {
+        Filter {
+            options,
+            first: Vec::new(),
+            count: 0,
+            line: Vec::new(),
+        }
+    }
+
+    fn update<W: Write>(&mut self, mut chunk: &[u8], writer: &mut W) -> io::Result<()> {
+        while let Some(end) = chunk.iter().position(|b| *b == b'\n') {
+            self.line.extend_from_slice(&chunk[..end]);
+            self.end_line(writer)?;
+            chunk = &chunk[end + 1..];
+        }
+        self.line.extend_from_slice(chunk);
+        Ok(())
+    }
+
+    /// Ends the input; a last line without a newline still counts as a line.
+    fn finish<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        if !self.line.is_empty() {
+            self.end_line(writer)?;
+        }
+        self.print_group(writer)
+    }
+
+    fn end_line<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        let same = self.count > 0 && {
+            let (a, b) = (self.key(&self.first), self.key(&self.line));
+            if self.options.ignore_case {
+                a.eq_ignore_ascii_case(b)
+            } else {
+                a == b
+            }
+        };
+        if same {
+            self.count += 1;
+        } else {
+            self.print_group(writer)?;
+            mem::swap(&mut self.first, &mut self.line);
+            self.count = 1;
+        }
+        self.line.clear();
+        Ok(())
+    }
+
+    fn print_group<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let wanted = match self.count {
+            0 => false,
+            1 => !self.options.repeated,
+            _ => !self.options.unique,
+        };
+        if !wanted {
+            return Ok(());
+        }
+        if self.options.count {
+            write!(writer, "{:7} ", self.count)?;
+        }
+        writer.write_all(&self.first)?;
+        writer.write_all(b"\n")
+    }
+
+    /// The part of `line` that is compared: what follows the skipped fields and characters.
+    fn key<'l>(&self, line: &'l [u8]) -> &'l [u8] {
+        let is_blank = |b: &u8| *b == b' ' || *b == b'\t';
+        let mut i = 0;
+        for _ in 0..self.options.skip_fields {
+            if i >= line.len() {
+                break;
+            }
+            while i < line.len() && is_blank(&line[i]) {
+                i += 1;
+            }
+            while i < line.len() && !is_blank(&line[i]) {
+                i += 1;
+            }
+        }
+        &line[line.len().min(i.saturating_add(self.options.skip_chars))..]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(input: &str, options: Options) -> String {
+        let mut output = Vec::new();
+        uniq(input.as_bytes(), &mut output, options).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_uniq() {
+        let plain = Options {
+            count: false,
+            repeated: false,
+            unique: false,
+            ignore_case: false,
+            skip_fields: 0,
+            skip_chars: 0,
+        };
+        assert_eq!(run("a\na\nb\na\n", plain), "a\nb\na\n");
+        assert_eq!(run("a\nb\nb", plain), "a\nb\n");
+        assert_eq!(run("", plain), "");
+        assert_eq!(run("\n\n", plain), "\n");
+        let counted = Options {
+            count: true,
+            ignore_case: true,
+            ..plain
+        };
+        assert_eq!(run("a\nA\nb\n", counted), "      2 a\n      1 b\n");
+        let repeated = Options {
+            repeated: true,
+            ..plain
+        };
+        assert_eq!(run("a\na\nb\nc\nc\n", repeated), "a\nc\n");
+        let unique = Options {
+            unique: true,
+            ..plain
+        };
+        assert_eq!(run("a\na\nb\nc\nc\n", unique), "b\n");
+        let skipping = Options {
+            skip_fields: 1,
+            skip_chars: 1,
+            ..plain
+        };
+        assert_eq!(run("x ab\ny\tab\nz  ab\n", skipping), "x ab\nz  ab\n");
+    }
+
+    #[test]
+    fn test_long_lines() {
+        // Lines much longer than the read buffer are compared whole.
+        let long = "x".repeat(10000);
+        let input = format!("{0}\n{0}\n{0}y\n", long);
+        let plain = Options {
+            count: true,
+            repeated: false,
+            unique: false,
+            ignore_case: false,
+            skip_fields: 0,
+            skip_chars: 0,
+        };
+        assert_eq!(
+            run(&input, plain),
+            format!("      2 {0}\n      1 {0}y\n", long)
+        );
+    }
+}
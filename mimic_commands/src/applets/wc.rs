@@ -0,0 +1,298 @@
+use crate::args::Command;
+use crate::count::{count, count_file_parallel, Count};
+use crate::error::{self, Error, Reporter, Style};
+use crate::{inflate, pool};
+use std::fs::{self, File};
+use std::io;
+use std::io::{BufReader, Read, Write};
+use std::process::exit;
+
+pub fn main(args: Vec<String>) {
+    let command = Command::new("wc", "[OPTION]... [FILE]...")
+        .about("Print newline, word, and byte counts for each FILE, and a total line if more than one FILE is specified.")
+        .opt("-c, --bytes", "print the byte counts")
+        .opt("-m, --chars", "print the character counts")
+        .opt("-l, --lines", "print the newline counts")
+        .opt(
+            "--files0-from=F",
+            "read input from the files specified by NUL-terminated names in file F; If F is - then read names from standard input",
+        )
+        .opt(
+            "-j, --jobs=N",
+            "count up to N files at once, or split a single large file into N chunks",
+        )
+        .opt("-L, --max-line-length", "print the maximum display width")
+        .opt("-w, --words", "print the word counts")
+        .opt(
+            "--decompress",
+            "count gzip input decompressed; other input is counted as it is",
+        );
+    let matches = command.parse_or_exit(args);
+
+    let mut selection = Selection {
+        lines: matches.flag("lines"),
+        words: matches.flag("words"),
+        chars: matches.flag("chars"),
+        bytes: matches.flag("bytes"),
+        max_line_length: matches.flag("max-line-length"),
+    };
+    if selection.len() == 0 {
+        selection = Selection {
+            lines: true,
+            words: true,
+            bytes: true,
+            ..selection
+        };
+    }
+    let jobs = match matches.value("jobs") {
+        Some(value) => match value.parse::<usize>() {
+            Ok(jobs) if jobs > 0 => jobs,
+            _ => command.exit_with_usage_error(&format!("invalid number of jobs: '{}'", value)),
+        },
+        None => 1,
+    };
+
+    let decompress = matches.flag("decompress");
+    let mut reporter = Reporter::new("wc", Style::Bare);
+    let (inputs, width) = match matches.value("files0-from") {
+        Some(source) => {
+            if let Some(extra) = matches.operands().first() {
+                command.exit_with_usage_error(&format!(
+                    "extra operand '{}'\nfile operands cannot be combined with --files0-from",
+                    extra
+                ));
+            }
+            let inputs = match read_files0(source) {
+                Ok(inputs) => inputs,
+                Err(e) => {
+                    eprintln!(
+                        "wc: cannot open '{}' for reading: {}",
+                        source,
+                        error::describe(&e)
+                    );
+                    exit(1);
+                }
+            };
+            // Names read from a pipe cannot be sized up front.
+            let width = if source == "-" {
+                1
+            } else {
+                let paths: Vec<String> = inputs.iter().filter_map(|i| i.clone().ok()).collect();
+                number_width(&paths, selection, decompress)
+            };
+            (inputs, width)
+        }
+        None => {
+            let paths = matches.files();
+            let width = number_width(&paths, selection, decompress);
+            (paths.into_iter().map(Ok).collect(), width)
+        }
+    };
+
+    let mut total = Count::new();
+    let decode = selection.needs_chars();
+    let count_stream = |reader: &mut dyn Read| {
+        if decompress {
+            count(inflate::decompressed(BufReader::new(reader))?, decode)
+        } else {
+            count(reader, decode)
+        }
+    };
+    // Standard input is unnamed unless it was given explicitly as `-`.
+    let show_names = !matches.operands().is_empty() || matches.value("files0-from").is_some();
+    let count_input = |input: &Input| -> Option<Result<Count, Error>> {
+        let path = input.as_ref().ok()?;
+        if path == "-" {
+            let result = count_stream(&mut io::stdin());
+            let name = if show_names { "-" } else { "standard input" };
+            return Some(result.map_err(|e| Error::Read(name.to_string(), e)));
+        }
+        let mut f = match File::open(path) {
+            Ok(f) => f,
+            Err(e) => return Some(Err(Error::Open(path.clone(), e))),
+        };
+        let is_large = f
+            .metadata()
+            .map(|m| m.is_file() && m.len() >= PARALLEL_MIN_SIZE);
+        let result = if jobs > 1 && inputs.len() == 1 && is_large.unwrap_or(false) && !decompress {
+            count_file_parallel(&f, jobs, decode)
+        } else {
+            count_stream(&mut f)
+        };
+        Some(result.map_err(|e| Error::Read(path.clone(), e)))
+    };
+    let mut stdout = io::stdout();
+    pool::map_ordered(&inputs, jobs, count_input, |i, result| {
+        let path = match &inputs[i] {
+            Ok(path) => path,
+            Err(message) => return reporter.fail(message),
+        };
+        let count = match result {
+            Some(Ok(count)) => count,
+            // Like GNU wc, an input that fails while being read is still listed.
+            Some(Err(e @ Error::Read(..))) => {
+                reporter.report(&e);
+                Count::new()
+            }
+            Some(Err(e)) => return reporter.report(&e),
+            None => return,
+        };
+        let name = if show_names { path.as_str() } else { "" };
+        let result = print_count(&mut stdout, name, &count, selection, width);
+        reporter.check(result.map_err(Error::writing));
+        total += count;
+    });
+
+    if inputs.len() >= 2 {
+        let result = print_count(&mut stdout, "total", &total, selection, width);
+        reporter.check(result.map_err(Error::writing));
+    }
+    reporter.finish();
+}
+
+/// Files smaller than this are not worth splitting across threads.
+const PARALLEL_MIN_SIZE: u64 = 4 * 1024 * 1024;
+
+/// A file name to count, or the error to report in its place.
+type Input = Result<String, String>;
+
+/// Reads NUL-separated file names from `source`, or standard input for `-`.
+fn read_files0(source: &str) -> io::Result<Vec<Input>> {
+    let mut names = Vec::new();
+    if source == "-" {
+        io::stdin().read_to_end(&mut names)?;
+    } else {
+        File::open(source)?.read_to_end(&mut names)?;
+    }
+    if names.last() == Some(&0) {
+        names.pop();
+    }
+    if names.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let inputs = names
+        .split(|b| *b == 0)
+        .enumerate()
+        .map(|(i, name)| {
+            let name = String::from_utf8_lossy(name).into_owned();
+            if name.is_empty() {
+                Err(format!(
+                    "{}:{}: invalid zero-length file name",
+                    source,
+                    i + 1
+                ))
+            } else if source == "-" && name == "-" {
+                Err("when reading file names from stdin, no file name of '-' allowed".to_string())
+            } else {
+                Ok(name)
+            }
+        })
+        .collect();
+    Ok(inputs)
+}
+
+/// Which counters to print. They are always printed in this order, whatever the option order.
+#[derive(Debug, Clone, Copy)]
+struct Selection {
+    lines: bool,
+    words: bool,
+    chars: bool,
+    bytes: bool,
+    max_line_length: bool,
+}
+
+impl Selection {
+    fn len(&self) -> usize {
+        [
+            self.lines,
+            self.words,
+            self.chars,
+            self.bytes,
+            self.max_line_length,
+        ]
+        .iter()
+        .filter(|selected| **selected)
+        .count()
+    }
+
+    /// Whether the input has to be decoded as UTF-8, which is much slower than counting bytes.
+    fn needs_chars(&self) -> bool {
+        self.words || self.chars || self.max_line_length
+    }
+}
+
+/// Like GNU wc, sizes the columns by the total size of the regular files to be read,
+/// with at least 7 columns when some input's size is unknown (e.g. a pipe, or any input
+/// that is to be decompressed).
+fn number_width(paths: &[String], selection: Selection, decompress: bool) -> usize {
+    if paths.len() == 1 && selection.len() == 1 {
+        return 1;
+    }
+
+    let mut minimum_width = 1;
+    let mut regular_total: u64 = 0;
+    for path in paths {
+        let path = if path == "-" { "/dev/stdin" } else { path };
+        match fs::metadata(path) {
+            Ok(metadata) if metadata.is_file() && !decompress => regular_total += metadata.len(),
+            Ok(_) => minimum_width = 7,
+            Err(_) => {}
+        }
+    }
+    regular_total.to_string().len().max(minimum_width)
+}
+
+fn print_count<W: Write>(
+    writer: &mut W,
+    path: &str,
+    count: &Count,
+    selection: Selection,
+    width: usize,
+) -> io::Result<()> {
+    let columns = [
+        (selection.lines, count.lines),
+        (selection.words, count.words),
+        (selection.chars, count.chars),
+        (selection.bytes, count.bytes),
+        (selection.max_line_length, count.max_line_length),
+    ];
+    let mut output = columns
+        .iter()
+        .filter(|(selected, _)| *selected)
+        .map(|(_, n)| format!("{:>width$}", n, width = width))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if !path.is_empty() {
+        output += &format!(" {}", path);
+    }
+    output += "\n";
+
+    writer.write_all(output.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_read_files0() {
+        let path = env::temp_dir().join(format!("wc_files0_test_{}", std::process::id()));
+        fs::write(&path, "a\0\0b c\0-").unwrap();
+        let source = path.to_str().unwrap();
+        assert_eq!(
+            read_files0(source).unwrap(),
+            vec![
+                Ok("a".to_string()),
+                Err(format!("{}:2: invalid zero-length file name", source)),
+                Ok("b c".to_string()),
+                Ok("-".to_string()),
+            ]
+        );
+
+        fs::write(&path, "").unwrap();
+        assert_eq!(read_files0(source).unwrap(), vec![]);
+        fs::remove_file(&path).unwrap();
+    }
+}
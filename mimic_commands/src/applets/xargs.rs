@@ -0,0 +1,545 @@
+use crate::args::Command;
+use crate::batch::Batch;
+use crate::error::{self, Error, Reporter, Style};
+use std::ffi::{OsStr, OsString};
+use std::io::{self, BufRead, BufReader};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::process::ExitStatusExt;
+use std::process::{self, Child, ExitStatus, Stdio};
+
+/// Exit statuses, as GNU xargs has them.
+const EXIT_FAILED: i32 = 123;
+const EXIT_ABORTED: i32 = 124;
+const EXIT_SIGNALED: i32 = 125;
+const EXIT_CANNOT_RUN: i32 = 126;
+const EXIT_NOT_FOUND: i32 = 127;
+
+pub fn main(args: Vec<String>) {
+    let command = Command::new("xargs", "[OPTION]... [COMMAND [INITIAL-ARGS]...]")
+        .about(
+            "Run COMMAND (default echo) with INITIAL-ARGS and arguments read from standard input.",
+        )
+        .opt(
+            "-0, --null",
+            "arguments are ended by a null byte, and quotes and backslashes are not special",
+        )
+        .opt(
+            "-I R",
+            "run COMMAND for each line of input, with R in INITIAL-ARGS replaced by the line",
+        )
+        .opt(
+            "-L, --max-lines=MAX-LINES",
+            "use at most MAX-LINES non-blank input lines per command line",
+        )
+        .opt(
+            "-n, --max-args=MAX-ARGS",
+            "use at most MAX-ARGS arguments per command line",
+        )
+        .opt(
+            "-P, --max-procs=MAX-PROCS",
+            "run up to MAX-PROCS commands at a time; 0 means as many as possible",
+        )
+        .opt(
+            "-r, --no-run-if-empty",
+            "if there are no arguments, do not run COMMAND at all",
+        )
+        .opt(
+            "-t, --verbose",
+            "print each command line on standard error before running it",
+        )
+        .stop_at_operand()
+        .usage_status(1);
+    let matches = command.parse_or_exit(args);
+
+    let number = |key: &str, letter: char, least: i64| {
+        matches.value(key).map(|value| match value.parse::<i64>() {
+            Ok(n) if n >= least => n as usize,
+            Ok(n) => command.exit_with_usage_error(&format!(
+                "value {} for -{} option should be >= {}",
+                n, letter, least
+            )),
+            Err(_) => command.exit_with_usage_error(&format!(
+                "invalid number \"{}\" for -{} option",
+                value, letter
+            )),
+        })
+    };
+    let procs = number("max-procs", 'P', 0).unwrap_or(1);
+    // Only the last of -I, -L and -n counts, as with GNU xargs, except that -n 1 goes
+    // with -I, which runs one line at a time anyway.
+    let mut last = matches.last_of(&["I", "max-lines", "max-args"]);
+    let compatible = last == Some("max-args")
+        && matches.value("max-args") == Some("1")
+        && matches.last_of(&["I", "max-lines"]) == Some("I");
+    if compatible {
+        last = Some("I");
+    }
+    let (last, described) = match last {
+        Some("I") => ("I", "--replace/-I/-i"),
+        Some("max-lines") => ("max-lines", "-L"),
+        Some(_) => ("max-args", "--max-args/-n"),
+        None => ("", ""),
+    };
+    for (key, previous) in [
+        ("max-args", "--max-args"),
+        ("max-lines", "--max-lines"),
+        ("I", "--replace"),
+    ] {
+        if key != last && matches.flag(key) && !(compatible && key == "max-args") {
+            eprintln!(
+                "xargs: warning: options {} and {} are mutually exclusive, ignoring previous {} value",
+                previous, described, previous
+            );
+        }
+    }
+    let limit = match last {
+        "I" => Limit::Replace(matches.value("I").unwrap_or_default().to_string()),
+        "max-lines" => Limit::Lines(number("max-lines", 'L', 1).unwrap_or(1)),
+        "max-args" => Limit::Args(number("max-args", 'n', 1).unwrap_or(1)),
+        _ => Limit::None,
+    };
+    let split = if matches.flag("null") {
+        Split::Null
+    } else if let Limit::Replace(_) = limit {
+        Split::Lines
+    } else {
+        Split::Blanks
+    };
+    let initial: Vec<OsString> = match matches.operands() {
+        [] => vec![OsString::from("echo")],
+        operands => operands.iter().map(OsString::from).collect(),
+    };
+
+    let mut runner = Runner {
+        procs,
+        verbose: matches.flag("verbose"),
+        running: Vec::new(),
+        status: 0,
+    };
+    let mut items = Items {
+        reader: BufReader::new(io::stdin().lock()),
+        split,
+        found_null: false,
+    };
+    let result = build(
+        &mut items,
+        &initial,
+        &limit,
+        matches.flag("no-run-if-empty"),
+        &mut runner,
+    );
+    runner.wait_all();
+    if result.is_err() {
+        process::exit(1);
+    }
+    process::exit(runner.status);
+}
+
+/// How many arguments go on each command line.
+#[derive(Debug, PartialEq)]
+enum Limit {
+    /// As many as fit.
+    None,
+    Args(usize),
+    Lines(usize),
+    /// One line each, put in place of the string.
+    Replace(String),
+}
+
+/// Reads the arguments from `items` and runs the command lines made with them.
+fn build<R: BufRead>(
+    items: &mut Items<R>,
+    initial: &[OsString],
+    limit: &Limit,
+    run_if_empty: bool,
+    runner: &mut Runner,
+) -> Result<(), Problem> {
+    if let Limit::Replace(replace) = limit {
+        while let Some(item) = items.next().inspect_err(Problem::report)? {
+            let line = initial
+                .iter()
+                .map(|arg| OsString::from_vec(split_around(arg, replace).join(&item.arg[..])))
+                .collect();
+            runner.run(line);
+        }
+        return Ok(());
+    }
+
+    let max_args = match limit {
+        Limit::Args(max) => Some(*max),
+        _ => None,
+    };
+    let mut batch = Batch::new(initial.to_vec(), max_args);
+    let mut lines = 0;
+    let mut ran = false;
+    loop {
+        let item = match items.next() {
+            Ok(Some(item)) => item,
+            Ok(None) => break,
+            Err(problem) => {
+                // What was read before still runs, after the message, as with GNU xargs.
+                problem.report();
+                if !batch.is_empty() {
+                    runner.run(batch.take());
+                }
+                return Err(problem);
+            }
+        };
+        let arg = OsString::from_vec(item.arg);
+        if !batch.fits(&arg) && !batch.is_empty() {
+            runner.run(batch.take());
+            ran = true;
+            lines = 0;
+        }
+        if !batch.fits(&arg) {
+            Problem::TooLong.report();
+            return Err(Problem::TooLong);
+        }
+        batch.push(arg);
+        if item.ends_line {
+            lines += 1;
+        }
+        // A command line runs as soon as it has all its arguments, or all its lines.
+        if batch.is_full() || *limit == Limit::Lines(lines) {
+            runner.run(batch.take());
+            ran = true;
+            lines = 0;
+        }
+    }
+    if !batch.is_empty() || !ran && !run_if_empty {
+        runner.run(batch.take());
+    }
+    Ok(())
+}
+
+/// The parts of `arg` around each `replace` in it.
+fn split_around<'a>(arg: &'a OsStr, replace: &str) -> Vec<&'a [u8]> {
+    let (bytes, replace) = (arg.as_bytes(), replace.as_bytes());
+    if replace.is_empty() {
+        return vec![bytes];
+    }
+    let mut parts = Vec::new();
+    let (mut start, mut i) = (0, 0);
+    while i + replace.len() <= bytes.len() {
+        if bytes[i..].starts_with(replace) {
+            parts.push(&bytes[start..i]);
+            i += replace.len();
+            start = i;
+        } else {
+            i += 1;
+        }
+    }
+    parts.push(&bytes[start..]);
+    parts
+}
+
+/// Why reading the arguments stopped early.
+#[derive(Debug)]
+enum Problem {
+    /// A quote, `single` or `double`, left open at the end of a line.
+    Quote(&'static str),
+    /// An argument too long for any command line.
+    TooLong,
+    Read(Error),
+}
+
+impl Problem {
+    fn report(&self) {
+        match self {
+            Problem::Quote(quote) => eprintln!(
+                "xargs: unmatched {} quote; by default quotes are special to xargs unless you use the -0 option",
+                quote
+            ),
+            Problem::TooLong => eprintln!("xargs: argument line too long"),
+            Problem::Read(e) => Reporter::new("xargs", Style::Bare).report(e),
+        }
+    }
+}
+
+/// How the input is split into arguments.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Split {
+    /// At blanks and newlines, outside quotes.
+    Blanks,
+    /// At newlines only, leading blanks aside (`-I`).
+    Lines,
+    /// At null bytes only (`-0`).
+    Null,
+}
+
+/// An argument read from the input.
+#[derive(Debug, PartialEq)]
+struct Item {
+    arg: Vec<u8>,
+    /// Whether a newline ended it, ending an input line for `-L`. A line that ends in a
+    /// blank goes on to the next.
+    ends_line: bool,
+}
+
+struct Items<R> {
+    reader: R,
+    split: Split,
+    /// Whether a null byte was found, when they are not what splits the input.
+    found_null: bool,
+}
+
+impl<R: BufRead> Items<R> {
+    fn byte(&mut self) -> Result<Option<u8>, Problem> {
+        let byte = match self.reader.fill_buf() {
+            Ok(buffer) => buffer.first().copied(),
+            Err(e) => return Err(Problem::Read(Error::Read("standard input".to_string(), e))),
+        };
+        if byte.is_some() {
+            self.reader.consume(1);
+        }
+        Ok(byte)
+    }
+
+    /// An argument split at blanks or newlines, warning the first time that it holds a
+    /// null byte, which cannot be passed on.
+    fn item(&mut self, arg: Vec<u8>, ends_line: bool) -> Item {
+        if !self.found_null && arg.contains(&0) {
+            eprintln!("xargs: WARNING: a NUL character occurred in the input.  It cannot be passed through in the argument list.  Did you mean to use the --null option?");
+            self.found_null = true;
+        }
+        Item { arg, ends_line }
+    }
+
+    fn next(&mut self) -> Result<Option<Item>, Problem> {
+        if self.split == Split::Null {
+            let mut arg = Vec::new();
+            return match self.reader.read_until(0, &mut arg) {
+                Ok(0) => Ok(None),
+                Ok(_) => {
+                    if arg.last() == Some(&0) {
+                        arg.pop();
+                    }
+                    Ok(Some(Item {
+                        arg,
+                        ends_line: true,
+                    }))
+                }
+                Err(e) => Err(Problem::Read(Error::Read("standard input".to_string(), e))),
+            };
+        }
+
+        let mut arg = Vec::new();
+        // Whether an argument was started, perhaps by an empty pair of quotes.
+        let mut started = false;
+        let mut quote = None;
+        while let Some(byte) = self.byte()? {
+            if let Some(open) = quote {
+                match byte {
+                    b'\n' => {
+                        return Err(Problem::Quote(if open == b'\'' {
+                            "single"
+                        } else {
+                            "double"
+                        }))
+                    }
+                    _ if byte == open => quote = None,
+                    _ => arg.push(byte),
+                }
+                continue;
+            }
+            match byte {
+                b'\n' if started => return Ok(Some(self.item(arg, true))),
+                b'\n' => {}
+                b' ' | b'\t' if !started => {}
+                b' ' | b'\t' if self.split == Split::Blanks => {
+                    return Ok(Some(self.item(arg, false)))
+                }
+                b'\'' | b'"' => {
+                    quote = Some(byte);
+                    started = true;
+                }
+                b'\\' => {
+                    if let Some(escaped) = self.byte()? {
+                        arg.push(escaped);
+                    }
+                    started = true;
+                }
+                _ => {
+                    arg.push(byte);
+                    started = true;
+                }
+            }
+        }
+        match quote {
+            Some(b'\'') => Err(Problem::Quote("single")),
+            Some(_) => Err(Problem::Quote("double")),
+            None if started => Ok(Some(self.item(arg, true))),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Runs the command lines, up to `procs` at a time, keeping track of how they did.
+struct Runner {
+    /// The most commands to run at once, or 0 for no limit.
+    procs: usize,
+    verbose: bool,
+    /// The commands running, with their names.
+    running: Vec<(Child, String)>,
+    /// The exit status so far: 0, or `EXIT_FAILED` once a command failed.
+    status: i32,
+}
+
+impl Runner {
+    fn run(&mut self, mut line: Vec<OsString>) {
+        // As with GNU xargs, an argument ends at a null byte in it.
+        for arg in &mut line {
+            if let Some(null) = arg.as_bytes().iter().position(|&b| b == 0) {
+                *arg = OsString::from_vec(arg.as_bytes()[..null].to_vec());
+            }
+        }
+        let name = line[0].to_string_lossy().into_owned();
+        if self.verbose {
+            let shown: Vec<_> = line
+                .iter()
+                .map(|arg| error::shell_quote(&arg.to_string_lossy()).into_owned())
+                .collect();
+            eprintln!("{}", shown.join(" "));
+        }
+        let child = process::Command::new(&line[0])
+            .args(&line[1..])
+            .stdin(Stdio::null())
+            .spawn();
+        match child {
+            Ok(child) => {
+                self.running.push((child, name));
+                // Input is read no further until another command could start.
+                while self.procs != 0 && self.running.len() >= self.procs {
+                    self.wait_any();
+                }
+            }
+            Err(e) => {
+                self.wait_all();
+                eprintln!("xargs: {}: {}", name, error::describe(&e));
+                process::exit(if e.kind() == io::ErrorKind::NotFound {
+                    EXIT_NOT_FOUND
+                } else {
+                    EXIT_CANNOT_RUN
+                });
+            }
+        }
+    }
+
+    /// Waits for whichever command ends first.
+    fn wait_any(&mut self) {
+        extern "C" {
+            fn waitpid(pid: i32, status: *mut i32, options: i32) -> i32;
+        }
+        let mut status = 0;
+        let pid = loop {
+            let pid = unsafe { waitpid(-1, &mut status, 0) };
+            if pid != -1 || io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+                break pid;
+            }
+        };
+        // The children are only ever reaped here, so any pid is one of them.
+        let i = match self
+            .running
+            .iter()
+            .position(|(child, _)| child.id() as i32 == pid)
+        {
+            Some(i) => i,
+            None => {
+                self.running.clear();
+                return;
+            }
+        };
+        let (_, name) = self.running.swap_remove(i);
+        self.check(ExitStatus::from_raw(status), &name);
+    }
+
+    fn wait_all(&mut self) {
+        while !self.running.is_empty() {
+            self.wait_any();
+        }
+    }
+
+    /// Notes how a command ended, stopping for good if it asked to or was killed.
+    fn check(&mut self, status: ExitStatus, name: &str) {
+        let (message, code) = match (status.code(), status.signal()) {
+            (Some(0), _) => return,
+            (Some(255), _) => (
+                format!("{}: exited with status 255; aborting", name),
+                EXIT_ABORTED,
+            ),
+            (Some(_), _) => {
+                self.status = EXIT_FAILED;
+                return;
+            }
+            (None, Some(signal)) => (
+                format!("{}: terminated by signal {}", name, signal),
+                EXIT_SIGNALED,
+            ),
+            (None, None) => return,
+        };
+        self.wait_all();
+        eprintln!("xargs: {}", message);
+        process::exit(code);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(input: &str, split: Split) -> Result<Vec<(String, bool)>, String> {
+        let mut items = Items {
+            reader: input.as_bytes(),
+            split,
+            found_null: false,
+        };
+        let mut found = Vec::new();
+        loop {
+            match items.next() {
+                Ok(Some(item)) => {
+                    found.push((String::from_utf8(item.arg).unwrap(), item.ends_line))
+                }
+                Ok(None) => return Ok(found),
+                Err(Problem::Quote(quote)) => return Err(quote.to_string()),
+                Err(Problem::TooLong) => unreachable!(),
+                Err(Problem::Read(e)) => return Err(e.message(Style::Bare)),
+            }
+        }
+    }
+
+    fn args(input: &str, split: Split) -> Vec<String> {
+        items(input, split)
+            .unwrap()
+            .into_iter()
+            .map(|(arg, _)| arg)
+            .collect()
+    }
+
+    #[test]
+    fn test_items() {
+        assert_eq!(
+            args("a  b\n\tc \"d e\" f\\ g '' h'i'\n", Split::Blanks),
+            ["a", "b", "c", "d e", "f g", "", "hi"]
+        );
+        assert_eq!(
+            items("a b\nc \nd\n", Split::Blanks).unwrap(),
+            [
+                ("a".to_string(), false),
+                ("b".to_string(), true),
+                ("c".to_string(), false),
+                ("d".to_string(), true)
+            ]
+        );
+        assert_eq!(args("  a  b \n\nc\n", Split::Lines), ["a  b ", "c"]);
+        assert_eq!(args("a b\0\0c\n", Split::Null), ["a b", "", "c\n"]);
+        assert_eq!(items("a 'b\nc'", Split::Blanks), Err("single".to_string()));
+        assert_eq!(items("a \"b", Split::Blanks), Err("double".to_string()));
+    }
+
+    #[test]
+    fn test_replace() {
+        let arg = OsStr::new("x{}y{}");
+        assert_eq!(split_around(arg, "{}"), [&b"x"[..], &b"y"[..], &b""[..]]);
+        assert_eq!(split_around(arg, "z"), [&b"x{}y{}"[..]]);
+    }
+}
@@ -0,0 +1,483 @@
+use crate::args::Command;
+use crate::dump::{self, Unit};
+use crate::error::{Error, Output, Reporter, Style};
+use crate::stdio;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::process::exit;
+
+/// The most bytes a line may show.
+const MAX_COLUMNS: i64 = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    /// Offset, hex and text columns.
+    Normal,
+    /// Hex only (`-p`).
+    Plain,
+    /// A C array (`-i`).
+    Include,
+}
+
+pub fn main(args: Vec<String>) {
+    let command = Command::new("xxd", "[OPTION]... [INFILE [OUTFILE]]")
+        .about(
+            "Make a hex dump of INFILE (or standard input), or turn a hex dump back into binary.",
+        )
+        .opt(
+            "-c cols",
+            "format <cols> octets per line. Default 16 (-i: 12, -p: 30)",
+        )
+        .opt(
+            "-g bytes",
+            "number of octets per group in normal output. Default 2",
+        )
+        .opt("-i", "output in C include file style")
+        .opt("-l len", "stop after <len> octets")
+        .opt("-p", "output in plain hexdump style")
+        .opt(
+            "-r",
+            "reverse operation: convert (or patch) hexdump into binary",
+        )
+        .opt(
+            "-s [+][-]seek",
+            "start at <seek> bytes abs. infile offset; with -r, add <seek> to file positions",
+        );
+    let matches = command.parse_or_exit(args);
+
+    let mode = if matches.flag("p") {
+        Mode::Plain
+    } else if matches.flag("i") {
+        Mode::Include
+    } else {
+        Mode::Normal
+    };
+    let columns = match matches.value("c").map_or(0, parse_number) {
+        0 => match mode {
+            Mode::Normal => 16,
+            Mode::Plain => 30,
+            Mode::Include => 12,
+        },
+        columns if !(1..=MAX_COLUMNS).contains(&columns) => {
+            die(1, "invalid number of columns (max. 256).")
+        }
+        columns => columns as usize,
+    };
+    let group = match matches.value("g").map_or(2, parse_number) {
+        group if group < 1 || group as usize > columns => columns,
+        group => group as usize,
+    };
+    let seek = matches.value("s").map_or(0, parse_number);
+    let length = matches.value("l").map(|l| parse_number(l).max(0) as u64);
+    let (input, output) = match matches.operands() {
+        [] => ("-", None),
+        [input] => (input.as_str(), None),
+        [input, output] => (input.as_str(), Some(output.as_str())),
+        [_, _, extra, ..] => command.exit_with_usage_error(&format!("extra operand '{}'", extra)),
+    };
+
+    let file = if input == "-" {
+        // Borrow fd 0 as a File so a redirected regular file can still be seeked.
+        let stdin = stdio::stdin_file();
+        stdin.try_clone()
+    } else {
+        File::open(input)
+    };
+    let file =
+        file.unwrap_or_else(|e| die(2, &Error::Open(input.to_string(), e).message(Style::Bare)));
+
+    let mut reporter = Reporter::new("xxd", Style::Bare);
+    if matches.flag("r") {
+        // The output is patched in place, so it is not truncated.
+        let target = match output.filter(|path| *path != "-") {
+            Some(path) => OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(path)
+                .unwrap_or_else(|e| die(3, &Error::Open(path.to_string(), e).message(Style::Bare))),
+            // Borrowed like stdin, so that a redirected regular file can be seeked too.
+            None => stdio::stdout_file()
+                .try_clone()
+                .unwrap_or_else(|e| die(3, &Error::writing(e).message(Style::Bare))),
+        };
+        let seekable = target.metadata().is_ok_and(|m| m.is_file());
+        let mut patcher = Patcher::new(BufWriter::new(Output(target)), seekable);
+        let result =
+            revert(file, &mut patcher, mode, columns, seek).and_then(|_| patcher.writer.flush());
+        if let Err(e) = result {
+            reporter.report(&Error::copying(input, e));
+        }
+        reporter.finish();
+        return;
+    }
+
+    let (reader, offset) = seek_input(file, seek);
+    let reader = reader.take(length.unwrap_or(u64::MAX));
+    let writer: Box<dyn Write> = match output.filter(|path| *path != "-") {
+        Some(path) => Box::new(
+            File::create(path)
+                .unwrap_or_else(|e| die(3, &Error::Open(path.to_string(), e).message(Style::Bare))),
+        ),
+        None => Box::new(io::stdout()),
+    };
+    let mut writer = BufWriter::new(Output(writer));
+    let result = match mode {
+        Mode::Normal => dump_normal(reader, &mut writer, offset, columns, group),
+        Mode::Plain => dump_plain(reader, &mut writer, columns),
+        Mode::Include => {
+            let name = Some(input).filter(|input| *input != "-").map(variable_name);
+            dump_include(reader, &mut writer, name.as_deref(), columns)
+        }
+    }
+    .and_then(|_| writer.flush());
+    if let Err(e) = result {
+        reporter.report(&Error::copying(input, e));
+    }
+    reporter.finish();
+}
+
+fn die(code: i32, message: &str) -> ! {
+    eprintln!("xxd: {}", message);
+    exit(code);
+}
+
+/// Parses a number as C's `strtol` with base 0 does: an optional sign, then hex after
+/// `0x`, octal after `0` or decimal, up to the first character that does not fit.
+fn parse_number(s: &str) -> i64 {
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let (radix, digits) = if let Some(hex) = s.strip_prefix("0x").or(s.strip_prefix("0X")) {
+        (16, hex)
+    } else if s.len() > 1 && s.starts_with('0') {
+        (8, &s[1..])
+    } else {
+        (10, s)
+    };
+    let mut number: i64 = 0;
+    for c in digits.chars() {
+        match c.to_digit(radix) {
+            Some(digit) => {
+                number = number
+                    .saturating_mul(radix.into())
+                    .saturating_add(digit.into())
+            }
+            None => break,
+        }
+    }
+    if negative {
+        -number
+    } else {
+        number
+    }
+}
+
+/// Moves to where `-s` says to start, from the end if it is negative, and returns the
+/// input with the offset reached. Inputs that cannot seek are read up to it instead.
+fn seek_input(mut file: File, seek: i64) -> (File, u64) {
+    if seek == 0 {
+        return (file, 0);
+    }
+    let target = if seek < 0 {
+        SeekFrom::End(seek)
+    } else {
+        SeekFrom::Start(seek as u64)
+    };
+    match file.seek(target) {
+        Ok(offset) => (file, offset),
+        Err(_) if seek > 0 => match dump::skip(&mut file, seek as u64) {
+            Ok(skipped) if skipped == seek as u64 => (file, skipped),
+            _ => die(4, "Sorry, cannot seek."),
+        },
+        Err(_) => die(4, "Sorry, cannot seek."),
+    }
+}
+
+/// Writes lines of an offset, the bytes in hex in groups of `group`, and the bytes as text.
+fn dump_normal<R: Read, W: Write>(
+    mut reader: R,
+    writer: &mut W,
+    mut offset: u64,
+    columns: usize,
+    group: usize,
+) -> io::Result<()> {
+    let mut line = vec![0; columns];
+    let hex_width = columns * 2 + (columns - 1) / group;
+    let mut out = String::new();
+    loop {
+        let size = dump::read_full(&mut reader, &mut line)?;
+        if size == 0 {
+            return Ok(());
+        }
+        out.clear();
+        out.push_str(&format!("{:08x}: ", offset));
+        let hex_start = out.len();
+        for (i, b) in line[..size].iter().enumerate() {
+            if i > 0 && i % group == 0 {
+                out.push(' ');
+            }
+            Unit::HEX_BYTE.write(&[*b], 2, &mut out);
+        }
+        let hex_end = hex_start + hex_width;
+        out.extend(std::iter::repeat_n(' ', hex_end - out.len() + 2));
+        out.extend(line[..size].iter().map(|b| dump::printable(*b)));
+        out.push('\n');
+        writer.write_all(out.as_bytes())?;
+        offset += size as u64;
+    }
+}
+
+/// Writes the bytes in hex, `columns` of them to a line.
+fn dump_plain<R: Read, W: Write>(mut reader: R, writer: &mut W, columns: usize) -> io::Result<()> {
+    let mut line = vec![0; columns];
+    let mut out = String::new();
+    loop {
+        let size = dump::read_full(&mut reader, &mut line)?;
+        if size == 0 {
+            return Ok(());
+        }
+        out.clear();
+        for b in &line[..size] {
+            Unit::HEX_BYTE.write(&[*b], 2, &mut out);
+        }
+        out.push('\n');
+        writer.write_all(out.as_bytes())?;
+    }
+}
+
+/// Writes the bytes as the elements of a C array, with the array's declaration and a
+/// length variable around them if the input has a `name`.
+fn dump_include<R: Read, W: Write>(
+    mut reader: R,
+    writer: &mut W,
+    name: Option<&str>,
+    columns: usize,
+) -> io::Result<()> {
+    if let Some(name) = name {
+        writeln!(writer, "unsigned char {}[] = {{", name)?;
+    }
+    let mut buffer = [0; 1024 * 4];
+    let mut total = 0;
+    let mut out = String::new();
+    loop {
+        let size = reader.read(&mut buffer)?;
+        if size == 0 {
+            break;
+        }
+        out.clear();
+        for b in &buffer[..size] {
+            out.push_str(match total {
+                0 => "  0x",
+                n if n % columns as u64 == 0 => ",\n  0x",
+                _ => ", 0x",
+            });
+            Unit::HEX_BYTE.write(&[*b], 2, &mut out);
+            total += 1;
+        }
+        writer.write_all(out.as_bytes())?;
+    }
+    if total > 0 {
+        writer.write_all(b"\n")?;
+    }
+    if let Some(name) = name {
+        writeln!(writer, "}};\nunsigned int {}_len = {};", name, total)?;
+    }
+    Ok(())
+}
+
+/// The C identifier made from a file name: anything but letters and digits becomes `_`,
+/// and a leading digit is preceded by `__`.
+fn variable_name(path: &str) -> String {
+    let name: String = path
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("__{}", name)
+    } else {
+        name
+    }
+}
+
+/// Writes bytes at the offsets a dump gives for them.
+struct Patcher<W> {
+    writer: W,
+    /// Whether the output can seek; if not, gaps are filled with zeros and going back
+    /// is an error.
+    seekable: bool,
+    position: u64,
+}
+
+impl<W: Write + Seek> Patcher<W> {
+    fn new(writer: W, seekable: bool) -> Self {
+        Patcher {
+            writer,
+            seekable,
+            position: 0,
+        }
+    }
+
+    fn write_at(&mut self, offset: u64, bytes: &[u8]) -> io::Result<()> {
+        if offset != self.position {
+            if self.seekable {
+                self.writer.seek(SeekFrom::Start(offset))?;
+            } else if offset > self.position {
+                io::copy(
+                    &mut io::repeat(0).take(offset - self.position),
+                    &mut self.writer,
+                )?;
+            } else {
+                self.writer.flush()?;
+                die(5, "Sorry, cannot seek backwards.");
+            }
+        }
+        self.writer.write_all(bytes)?;
+        self.position = offset + bytes.len() as u64;
+        Ok(())
+    }
+}
+
+/// Turns a dump back into binary. Normal dumps give each line's offset, which `seek`
+/// is added to; plain ones are just hex digits.
+fn revert<R: Read, W: Write + Seek>(
+    reader: R,
+    patcher: &mut Patcher<W>,
+    mode: Mode,
+    columns: usize,
+    seek: i64,
+) -> io::Result<()> {
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    let mut nibble = None;
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            return Ok(());
+        }
+        if mode == Mode::Plain {
+            let mut bytes = Vec::new();
+            for digit in line.iter().filter_map(|c| hex_digit(*c)) {
+                match nibble.take() {
+                    Some(high) => bytes.push(high << 4 | digit),
+                    None => nibble = Some(digit),
+                }
+            }
+            let position = patcher.position;
+            patcher.write_at(position, &bytes)?;
+        } else if let Some((offset, bytes)) = parse_dump_line(&line, columns) {
+            let offset = (offset as i64).saturating_add(seek).max(0) as u64;
+            patcher.write_at(offset, &bytes)?;
+        }
+    }
+}
+
+fn hex_digit(c: u8) -> Option<u8> {
+    (c as char).to_digit(16).map(|digit| digit as u8)
+}
+
+/// Reads a line of a normal dump: a hex offset, an optional colon, then hex bytes up to
+/// `columns` of them. Two spaces in a row, as before the text column, or anything that
+/// is not hex end the bytes; a digit left without a partner is dropped.
+fn parse_dump_line(line: &[u8], columns: usize) -> Option<(u64, Vec<u8>)> {
+    let digits = line.iter().take_while(|c| hex_digit(**c).is_some()).count();
+    if digits == 0 {
+        return None;
+    }
+    let offset = line[..digits].iter().fold(0u64, |offset, c| {
+        offset.wrapping_shl(4) | u64::from(hex_digit(*c).unwrap())
+    });
+    let mut rest = &line[digits..];
+    if rest.first() == Some(&b':') {
+        rest = &rest[1..];
+    }
+    let mut bytes = Vec::new();
+    let mut nibble = None;
+    let mut spaces = 0;
+    for c in rest {
+        if bytes.len() >= columns {
+            break;
+        }
+        if let Some(digit) = hex_digit(*c) {
+            spaces = 0;
+            match nibble.take() {
+                Some(high) => bytes.push(high << 4 | digit),
+                None => nibble = Some(digit),
+            }
+        } else if *c == b' ' || *c == b'\t' {
+            nibble = None;
+            spaces += 1;
+            if spaces == 2 {
+                break;
+            }
+        } else {
+            break;
+        }
+    }
+    Some((offset, bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const INPUT: &[u8] = b"hello world, this is xxd\n\0\x01\xff";
+
+    fn normal(columns: usize, group: usize) -> String {
+        let mut output = Vec::new();
+        dump_normal(INPUT, &mut output, 0, columns, group).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    fn reverted(dump: &str, mode: Mode, columns: usize) -> Vec<u8> {
+        let mut patcher = Patcher::new(Cursor::new(Vec::new()), true);
+        revert(dump.as_bytes(), &mut patcher, mode, columns, 0).unwrap();
+        patcher.writer.into_inner()
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("16"), 16);
+        assert_eq!(parse_number("0x1a"), 26);
+        assert_eq!(parse_number("010"), 8);
+        assert_eq!(parse_number("-4"), -4);
+        assert_eq!(parse_number("+5k"), 5);
+        assert_eq!(parse_number("q"), 0);
+    }
+
+    #[test]
+    fn test_dump() {
+        assert_eq!(
+            normal(16, 2),
+            "00000000: 6865 6c6c 6f20 776f 726c 642c 2074 6869  hello world, thi\n\
+             00000010: 7320 6973 2078 7864 0a00 01ff            s is xxd....\n"
+        );
+        assert_eq!(
+            normal(7, 3).lines().next().unwrap(),
+            "00000000: 68656c 6c6f20 77  hello w"
+        );
+        let mut output = Vec::new();
+        dump_plain(&INPUT[..10], &mut output, 4).unwrap();
+        assert_eq!(output, b"68656c6c\n6f20776f\n726c\n");
+        let mut output = Vec::new();
+        dump_include(&INPUT[..3], &mut output, Some(&variable_name("9x-y.z")), 2).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "unsigned char __9x_y_z[] = {\n  0x68, 0x65,\n  0x6c\n};\nunsigned int __9x_y_z_len = 3;\n"
+        );
+    }
+
+    #[test]
+    fn test_revert() {
+        assert_eq!(reverted(&normal(16, 2), Mode::Normal, 16), INPUT);
+        assert_eq!(reverted(&normal(5, 1), Mode::Normal, 5), INPUT);
+        assert_eq!(reverted("0: 41  4243\n", Mode::Normal, 16), b"A");
+        assert_eq!(reverted("0: 41 4 2 43\n", Mode::Normal, 16), b"AC");
+        assert_eq!(reverted("zz\n2:41\n0:42\n", Mode::Normal, 16), b"B\0A");
+        assert_eq!(reverted("0: 414243\n", Mode::Normal, 2), b"AB");
+        assert_eq!(reverted("41 4\n2\n4", Mode::Plain, 16), b"AB");
+    }
+}
@@ -0,0 +1,8 @@
+use crate::applets::gzip;
+
+/// `zcat` is `gzip -cd`, down to the name in its messages, as the GNU script is.
+pub fn main(args: Vec<String>) {
+    let mut gzip_args = vec!["-c".to_string(), "-d".to_string()];
+    gzip_args.extend(args);
+    gzip::main(gzip_args);
+}
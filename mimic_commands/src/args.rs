@@ -0,0 +1,501 @@
+//! A small POSIX/GNU style command line parser shared by the mimic commands.
+//!
+//! Options are declared with the same text that appears in `--help`, e.g.
+//! `"-n, --lines=NUM"`, so the declaration and the usage output cannot drift apart.
+use std::fmt;
+use std::io::{self, Write};
+use std::process::exit;
+
+/// Exit status used for usage errors, as GNU coreutils does.
+pub const EXIT_USAGE: i32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ValueKind {
+    None,
+    Required,
+    Optional,
+}
+
+#[derive(Debug)]
+struct Opt {
+    short: Option<char>,
+    long: Vec<&'static str>,
+    value: ValueKind,
+    spec: &'static str,
+    help: &'static str,
+}
+
+impl Opt {
+    /// Parses a spec such as `-n, --lines=NUM`, `--pid=PID`, `-s N` or `--color[=WHEN]`.
+    /// Several long names may be given as aliases, e.g. `-q, --quiet, --silent`.
+    fn parse(spec: &'static str, help: &'static str) -> Self {
+        let mut opt = Opt {
+            short: None,
+            long: Vec::new(),
+            value: ValueKind::None,
+            spec,
+            help,
+        };
+        for part in spec.split(", ") {
+            let part = part.trim();
+            if let Some(long) = part.strip_prefix("--") {
+                let end = long.find(['=', '[']).unwrap_or(long.len());
+                opt.long.push(&long[..end]);
+                if long[end..].starts_with("[=") {
+                    opt.value = ValueKind::Optional;
+                } else if long[end..].starts_with('=') {
+                    opt.value = ValueKind::Required;
+                }
+            } else if let Some(short) = part.strip_prefix('-') {
+                let mut chars = short.chars();
+                opt.short = chars.next();
+                if chars.as_str().starts_with(' ') {
+                    opt.value = ValueKind::Required;
+                }
+            }
+        }
+        assert!(
+            opt.short.is_some() || !opt.long.is_empty(),
+            "invalid option spec: {}",
+            spec
+        );
+        opt
+    }
+
+    /// The name used to look the option up in `Matches`: the first long name, or the short letter.
+    fn key(&self) -> String {
+        match (self.long.first(), self.short) {
+            (Some(long), _) => long.to_string(),
+            (None, Some(short)) => short.to_string(),
+            (None, None) => unreachable!(),
+        }
+    }
+}
+
+/// Errors produced while parsing the command line.
+#[derive(Debug, PartialEq)]
+pub enum ArgError {
+    /// `--help` was given; not an error, but parsing stops there.
+    Help,
+    UnknownShort(char),
+    UnknownLong(String),
+    AmbiguousLong(String),
+    MissingValue(String),
+    UnexpectedValue(String),
+    /// A value that the command itself rejected, e.g. `invalid number of lines: 'x'`.
+    Invalid(String),
+}
+
+impl fmt::Display for ArgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgError::Help => write!(f, "help requested"),
+            ArgError::UnknownShort(c) => write!(f, "invalid option -- '{}'", c),
+            ArgError::UnknownLong(name) => write!(f, "unrecognized option '--{}'", name),
+            ArgError::AmbiguousLong(name) => write!(f, "option '--{}' is ambiguous", name),
+            ArgError::MissingValue(name) => {
+                if name.len() == 1 {
+                    write!(f, "option requires an argument -- '{}'", name)
+                } else {
+                    write!(f, "option '--{}' requires an argument", name)
+                }
+            }
+            ArgError::UnexpectedValue(name) => {
+                write!(f, "option '--{}' doesn't allow an argument", name)
+            }
+            ArgError::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// Describes the options and operands a command accepts.
+pub struct Command {
+    name: &'static str,
+    usage: &'static str,
+    about: &'static str,
+    opts: Vec<Opt>,
+    stop_at_operand: bool,
+    usage_status: i32,
+}
+
+impl Command {
+    /// `usage` is the part after the command name, e.g. `[OPTION]... [FILE]...`.
+    pub fn new(name: &'static str, usage: &'static str) -> Self {
+        Self {
+            name,
+            usage,
+            about: "",
+            opts: Vec::new(),
+            stop_at_operand: false,
+            usage_status: EXIT_USAGE,
+        }
+    }
+
+    pub fn about(mut self, about: &'static str) -> Self {
+        self.about = about;
+        self
+    }
+
+    /// Declares an option by its help text, e.g. `opt("-n, --lines=NUM", "print NUM lines")`.
+    pub fn opt(mut self, spec: &'static str, help: &'static str) -> Self {
+        self.opts.push(Opt::parse(spec, help));
+        self
+    }
+
+    /// Treats everything after the first operand as operands too (needed by `xargs`-like commands).
+    pub fn stop_at_operand(mut self) -> Self {
+        self.stop_at_operand = true;
+        self
+    }
+
+    /// Exits with `status` rather than `EXIT_USAGE` on usage errors, as findutils does with 1.
+    pub fn usage_status(mut self, status: i32) -> Self {
+        self.usage_status = status;
+        self
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Parses the arguments, excluding the program name.
+    pub fn parse<I: IntoIterator<Item = String>>(&self, args: I) -> Result<Matches, ArgError> {
+        let mut matches = Matches::default();
+        let mut args = args.into_iter();
+        let mut only_operands = false;
+
+        while let Some(arg) = args.next() {
+            if only_operands {
+                matches.operands.push(arg);
+            } else if arg == "--" {
+                only_operands = true;
+            } else if let Some(long) = arg.strip_prefix("--") {
+                let (name, value) = match long.find('=') {
+                    Some(i) => (&long[..i], Some(long[i + 1..].to_string())),
+                    None => (long, None),
+                };
+                if name == "help" && value.is_none() {
+                    return Err(ArgError::Help);
+                }
+                let opt = self.find_long(name)?;
+                let value = match (opt.value, value) {
+                    (ValueKind::None, Some(_)) => return Err(ArgError::UnexpectedValue(opt.key())),
+                    (ValueKind::Required, None) => match args.next() {
+                        Some(v) => Some(v),
+                        None => return Err(ArgError::MissingValue(opt.key())),
+                    },
+                    (_, value) => value,
+                };
+                matches.opts.push((opt.key(), value));
+            } else if arg.len() > 1 && arg.starts_with('-') {
+                let flags = &arg[1..];
+                for (i, c) in flags.char_indices() {
+                    let opt = self
+                        .opts
+                        .iter()
+                        .find(|o| o.short == Some(c))
+                        .ok_or(ArgError::UnknownShort(c))?;
+                    if opt.value == ValueKind::None {
+                        matches.opts.push((opt.key(), None));
+                        continue;
+                    }
+
+                    let rest = &flags[i + c.len_utf8()..];
+                    let value = if !rest.is_empty() {
+                        Some(rest.to_string())
+                    } else if opt.value == ValueKind::Required {
+                        match args.next() {
+                            Some(v) => Some(v),
+                            None => return Err(ArgError::MissingValue(c.to_string())),
+                        }
+                    } else {
+                        None
+                    };
+                    matches.opts.push((opt.key(), value));
+                    break;
+                }
+            } else {
+                matches.operands.push(arg);
+                if self.stop_at_operand {
+                    only_operands = true;
+                }
+            }
+        }
+
+        Ok(matches)
+    }
+
+    /// Parses the process arguments, printing help or a usage error and exiting when needed.
+    pub fn parse_or_exit<I: IntoIterator<Item = String>>(&self, args: I) -> Matches {
+        match self.parse(args) {
+            Ok(matches) => matches,
+            Err(ArgError::Help) => {
+                io::stdout().write_all(self.help().as_bytes()).ok();
+                exit(0);
+            }
+            Err(e) => self.exit_with_usage_error(&e),
+        }
+    }
+
+    /// Reports an invalid usage the GNU way and exits with the usage status.
+    pub fn exit_with_usage_error(&self, error: &dyn fmt::Display) -> ! {
+        io::stderr()
+            .write_all(
+                format!(
+                    "{0}: {1}\nTry '{0} --help' for more information.\n",
+                    self.name, error
+                )
+                .as_ref(),
+            )
+            .ok();
+        exit(self.usage_status);
+    }
+
+    /// Builds the `--help` text.
+    pub fn help(&self) -> String {
+        let column = |o: &Opt| {
+            if o.short.is_none() {
+                format!("    {}", o.spec.trim())
+            } else {
+                o.spec.to_string()
+            }
+        };
+        let width = self
+            .opts
+            .iter()
+            .map(|o| column(o).len())
+            .chain(std::iter::once("    --help".len()))
+            .max()
+            .unwrap_or(0);
+
+        let mut help = format!("Usage: {} {}\n", self.name, self.usage);
+        if !self.about.is_empty() {
+            help += self.about;
+            help += "\n";
+        }
+        help += "\n";
+        for opt in &self.opts {
+            help += &format!("  {:<width$}  {}\n", column(opt), opt.help, width = width);
+        }
+        help += &format!(
+            "  {:<width$}  display this help and exit\n",
+            "    --help",
+            width = width
+        );
+        help
+    }
+
+    fn find_long(&self, name: &str) -> Result<&Opt, ArgError> {
+        if let Some(opt) = self.opts.iter().find(|o| o.long.contains(&name)) {
+            return Ok(opt);
+        }
+
+        // GNU getopt accepts any unambiguous prefix of a long option.
+        let mut candidates = self
+            .opts
+            .iter()
+            .filter(|o| o.long.iter().any(|l| l.starts_with(name)));
+        match (candidates.next(), candidates.next()) {
+            (Some(opt), None) if !name.is_empty() => Ok(opt),
+            (Some(_), Some(_)) => Err(ArgError::AmbiguousLong(name.to_string())),
+            _ => Err(ArgError::UnknownLong(name.to_string())),
+        }
+    }
+}
+
+/// Parses a size with an optional GNU multiplier suffix: `b` (512), `K`/`KiB` (1024), `KB` (1000),
+/// and likewise `M`, `G`, `T`, `P`, `E`.
+pub fn parse_size(s: &str) -> Option<u64> {
+    let digits = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    if digits == 0 {
+        return None;
+    }
+    let number: u64 = s[..digits].parse().ok()?;
+    let multiplier: u64 = match &s[digits..] {
+        "" => 1,
+        "b" => 512,
+        suffix => {
+            let mut chars = suffix.chars();
+            let exponent = match chars.next()? {
+                'k' | 'K' => 1,
+                'M' => 2,
+                'G' => 3,
+                'T' => 4,
+                'P' => 5,
+                'E' => 6,
+                _ => return None,
+            };
+            let base: u64 = match chars.as_str() {
+                "" | "iB" => 1024,
+                "B" => 1000,
+                _ => return None,
+            };
+            base.checked_pow(exponent)?
+        }
+    };
+    number.checked_mul(multiplier)
+}
+
+/// The result of parsing: options in the order given, and the remaining operands.
+#[derive(Debug, Default)]
+pub struct Matches {
+    opts: Vec<(String, Option<String>)>,
+    operands: Vec<String>,
+}
+
+impl Matches {
+    /// Whether the option was given at least once.
+    pub fn flag(&self, key: &str) -> bool {
+        self.opts.iter().any(|(k, _)| k == key)
+    }
+
+    /// How many times the option was given, e.g. for `-vv`.
+    pub fn count(&self, key: &str) -> usize {
+        self.opts.iter().filter(|(k, _)| k == key).count()
+    }
+
+    /// The value of the last occurrence of the option.
+    pub fn value(&self, key: &str) -> Option<&str> {
+        self.values(key).pop()
+    }
+
+    /// The values of every occurrence of the option, in order.
+    pub fn values(&self, key: &str) -> Vec<&str> {
+        self.opts
+            .iter()
+            .filter(|(k, _)| k == key)
+            .filter_map(|(_, v)| v.as_deref())
+            .collect()
+    }
+
+    /// Which of the given options appeared last, for mutually overriding flags like `-q`/`-v`.
+    pub fn last_of<'a>(&self, keys: &[&'a str]) -> Option<&'a str> {
+        self.opts
+            .iter()
+            .rev()
+            .find_map(|(k, _)| keys.iter().find(|key| *key == k).copied())
+    }
+
+    pub fn operands(&self) -> &[String] {
+        &self.operands
+    }
+
+    /// The file operands, defaulting to `-` (standard input) when none were given.
+    pub fn files(&self) -> Vec<String> {
+        if self.operands.is_empty() {
+            vec!["-".to_string()]
+        } else {
+            self.operands.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command() -> Command {
+        Command::new("test", "[OPTION]... [FILE]...")
+            .opt("-n, --lines=NUM", "lines")
+            .opt("-q, --quiet, --silent", "quiet")
+            .opt("-v, --verbose", "verbose")
+            .opt("--pid=PID", "pid")
+            .opt("--color[=WHEN]", "color")
+            .opt("-s N", "sleep")
+    }
+
+    fn parse(args: &[&str]) -> Result<Matches, ArgError> {
+        command().parse(args.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn test_parse_values() {
+        for args in [
+            &["-n", "5"][..],
+            &["-n5"],
+            &["--lines=5"],
+            &["--lines", "5"],
+            &["--li=5"],
+        ] {
+            assert_eq!(parse(args).unwrap().value("lines"), Some("5"), "{:?}", args);
+        }
+        assert_eq!(parse(&["-n", "-5"]).unwrap().value("lines"), Some("-5"));
+        assert_eq!(parse(&["-qn3"]).unwrap().value("lines"), Some("3"));
+        assert_eq!(parse(&["-s", "2"]).unwrap().value("s"), Some("2"));
+        assert_eq!(parse(&["--pid=1"]).unwrap().value("pid"), Some("1"));
+        assert_eq!(parse(&["--color"]).unwrap().value("color"), None);
+        assert!(parse(&["--color"]).unwrap().flag("color"));
+        assert_eq!(
+            parse(&["--color=never"]).unwrap().value("color"),
+            Some("never")
+        );
+    }
+
+    #[test]
+    fn test_parse_flags_and_operands() {
+        let m = parse(&["-qv", "a", "-", "--", "-n", "b"]).unwrap();
+        assert!(m.flag("quiet"));
+        assert!(m.flag("verbose"));
+        assert!(parse(&["--silent"]).unwrap().flag("quiet"));
+        assert!(!m.flag("lines"));
+        assert_eq!(m.operands(), ["a", "-", "-n", "b"]);
+        assert_eq!(m.last_of(&["quiet", "verbose"]), Some("verbose"));
+        assert_eq!(parse(&[]).unwrap().files(), ["-"]);
+
+        let m = command()
+            .stop_at_operand()
+            .parse(vec!["-q".to_string(), "a".to_string(), "-v".to_string()])
+            .unwrap();
+        assert!(!m.flag("verbose"));
+        assert_eq!(m.operands(), ["a", "-v"]);
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("10"), Some(10));
+        assert_eq!(parse_size("2b"), Some(1024));
+        assert_eq!(parse_size("1K"), Some(1024));
+        assert_eq!(parse_size("1KiB"), Some(1024));
+        assert_eq!(parse_size("1kB"), Some(1000));
+        assert_eq!(parse_size("3M"), Some(3 * 1024 * 1024));
+        assert_eq!(parse_size("1GB"), Some(1_000_000_000));
+        assert_eq!(parse_size(""), None);
+        assert_eq!(parse_size("K"), None);
+        assert_eq!(parse_size("1X"), None);
+        assert_eq!(parse_size("-1"), None);
+        assert_eq!(parse_size("99999999999E"), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse(&["--help"]).unwrap_err(), ArgError::Help);
+        assert_eq!(parse(&["-x"]).unwrap_err(), ArgError::UnknownShort('x'));
+        assert_eq!(
+            parse(&["--nope"]).unwrap_err(),
+            ArgError::UnknownLong("nope".to_string())
+        );
+        assert_eq!(
+            parse(&["--p"]).unwrap_err(),
+            ArgError::MissingValue("pid".to_string())
+        );
+        assert_eq!(
+            command()
+                .opt("--lines-max", "")
+                .parse(vec!["--line".to_string()])
+                .unwrap_err(),
+            ArgError::AmbiguousLong("line".to_string())
+        );
+        assert_eq!(
+            parse(&["-n"]).unwrap_err(),
+            ArgError::MissingValue("n".to_string())
+        );
+        assert_eq!(
+            parse(&["--quiet=1"]).unwrap_err(),
+            ArgError::UnexpectedValue("quiet".to_string())
+        );
+        assert_eq!(
+            parse(&["-n"]).unwrap_err().to_string(),
+            "option requires an argument -- 'n'"
+        );
+    }
+}
@@ -0,0 +1,275 @@
+//! Reading a seekable input from its end backwards, one line (or other record) at a time,
+//! for `tac` and `tail`.
+//!
+//! Only a buffer's worth of the input is held at once: records are located by reading
+//! buffer-sized chunks backwards from the end, and a record longer than the buffer is
+//! copied out by seeking to it and reading it forwards.
+use crate::regex::Regex;
+use std::convert::TryFrom;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+
+/// How far back a regex match may reach into the chunk before the one being searched.
+const REGEX_OVERLAP: usize = 1024;
+
+/// What separates records.
+#[derive(Debug, Clone)]
+pub enum Separator {
+    Bytes(Vec<u8>),
+    /// Matches of a regex. Where matches overlap, the one starting last wins, as in GNU tac.
+    Regex(Box<Regex>),
+}
+
+/// Hands out the records of `reader` from the last to the first.
+#[derive(Debug)]
+pub struct Records<R> {
+    reader: R,
+    separator: Separator,
+    /// Whether the separator starts each record (`tac -b`) rather than ending it.
+    before: bool,
+    buffer: Vec<u8>,
+    /// Where the bytes in `buffer` come from, and how many there are.
+    cached: (u64, usize),
+    /// The non-empty regex matches in `buffer`, in order, relative to its start.
+    matches: Vec<(usize, usize)>,
+    /// The end of the next record.
+    end: u64,
+    /// Separators are looked for before here: where the last one found started.
+    search_end: u64,
+}
+
+impl<R: Read + Seek> Records<R> {
+    /// Reads the records of the first `end` bytes of `reader`, usually its size.
+    pub fn new(
+        reader: R,
+        end: u64,
+        separator: Separator,
+        before: bool,
+        buffer_size: usize,
+    ) -> Self {
+        // A chunk must hold at least a whole separator besides the overlap with the next.
+        let buffer_size = match &separator {
+            Separator::Bytes(bytes) => buffer_size.max(bytes.len() * 2),
+            Separator::Regex(_) => buffer_size.max(2),
+        };
+        Records {
+            reader,
+            separator,
+            before,
+            buffer: vec![0; buffer_size],
+            cached: (0, 0),
+            matches: Vec::new(),
+            end,
+            search_end: end,
+        }
+    }
+
+    /// The byte range of the record before the ones already handed out, separator
+    /// included, or `None` once the start of the input is reached.
+    pub fn next_back(&mut self) -> io::Result<Option<Range<u64>>> {
+        while self.end > 0 {
+            let end = self.end;
+            let start = match self.find_separator()? {
+                Some((start, _)) if self.before => start,
+                Some((_, separator_end)) => separator_end,
+                None => 0,
+            };
+            self.end = start;
+            // The text after a separator at the very end is an empty record, and skipped.
+            if start < end {
+                return Ok(Some(start..end));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Copies the bytes in `range` to `writer`.
+    pub fn copy<W: Write>(&mut self, range: Range<u64>, writer: &mut W) -> io::Result<()> {
+        let (cache_start, cache_len) = self.cached;
+        if range.start >= cache_start && range.end <= cache_start + cache_len as u64 {
+            let from = (range.start - cache_start) as usize;
+            let to = (range.end - cache_start) as usize;
+            return writer.write_all(&self.buffer[from..to]);
+        }
+
+        // Too long for what is buffered: stream it through the buffer, which loses its contents.
+        self.cached = (0, 0);
+        self.matches.clear();
+        self.reader.seek(SeekFrom::Start(range.start))?;
+        let mut remaining = range.end - range.start;
+        while remaining > 0 {
+            let size =
+                usize::try_from(remaining).map_or(self.buffer.len(), |r| r.min(self.buffer.len()));
+            self.reader.read_exact(&mut self.buffer[..size])?;
+            writer.write_all(&self.buffer[..size])?;
+            remaining -= size as u64;
+        }
+        Ok(())
+    }
+
+    /// Finds the separator that starts last before `search_end`, searching chunk by chunk
+    /// towards the start of the input.
+    fn find_separator(&mut self) -> io::Result<Option<(u64, u64)>> {
+        let search_end = self.search_end;
+        let (cache_start, cache_len) = self.cached;
+        let cached = cache_start < search_end && search_end <= cache_start + cache_len as u64;
+        if !cached {
+            self.load(search_end)?;
+        }
+        loop {
+            let chunk_start = self.cached.0;
+            if let Some((start, end)) = self.find_in_chunk(search_end) {
+                self.search_end = chunk_start + start as u64;
+                return Ok(Some((self.search_end, chunk_start + end as u64)));
+            }
+            if chunk_start == 0 {
+                self.search_end = 0;
+                return Ok(None);
+            }
+            // The next chunk overlaps this one, for separators that straddle the two.
+            self.load(chunk_start + self.overlap() as u64)?;
+        }
+    }
+
+    fn overlap(&self) -> usize {
+        match &self.separator {
+            Separator::Bytes(bytes) => bytes.len().saturating_sub(1),
+            Separator::Regex(_) => REGEX_OVERLAP.min(self.buffer.len() / 2),
+        }
+    }
+
+    /// Fills the buffer with the chunk that ends at `end`.
+    fn load(&mut self, end: u64) -> io::Result<()> {
+        let start = end.saturating_sub(self.buffer.len() as u64);
+        let len = (end - start) as usize;
+        self.cached = (0, 0);
+        self.reader.seek(SeekFrom::Start(start))?;
+        self.reader.read_exact(&mut self.buffer[..len])?;
+        self.cached = (start, len);
+        if let Separator::Regex(regex) = &self.separator {
+            self.matches = regex
+                .find_iter(&self.buffer[..len])
+                .filter(|(start, end)| end > start)
+                .collect();
+        }
+        Ok(())
+    }
+
+    /// The separator in the buffered chunk that starts last, ending by `search_end`.
+    fn find_in_chunk(&mut self, search_end: u64) -> Option<(usize, usize)> {
+        let (cache_start, cache_len) = self.cached;
+        let limit = usize::try_from(search_end.saturating_sub(cache_start))
+            .map_or(cache_len, |limit| limit.min(cache_len));
+        let haystack = &self.buffer[..limit];
+        match &self.separator {
+            Separator::Bytes(bytes) if bytes.len() == 1 => haystack
+                .iter()
+                .rposition(|b| *b == bytes[0])
+                .map(|at| (at, at + 1)),
+            Separator::Bytes(bytes) => haystack
+                .windows(bytes.len())
+                .rposition(|window| window == &bytes[..])
+                .map(|at| (at, at + bytes.len())),
+            Separator::Regex(regex) => {
+                while let Some((start, end)) = self.matches.last().copied() {
+                    if start >= limit {
+                        self.matches.pop();
+                        continue;
+                    }
+                    // A match may also start inside this one, or this one may be cut
+                    // short by `limit`; either way the later start is taken.
+                    for at in (start..end.min(limit)).rev() {
+                        if let Some((found_start, found_end)) = regex.find_at(haystack, at) {
+                            if found_start == at && found_end > at {
+                                return Some((at, found_end));
+                            }
+                        }
+                    }
+                    self.matches.pop();
+                }
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regex::{RegexBuilder, Syntax};
+    use std::io::Cursor;
+
+    /// The records of `input` in the order they are found, for several buffer sizes.
+    fn records(input: &str, separator: Separator, before: bool) -> Vec<String> {
+        let mut results = Vec::new();
+        for buffer_size in [1, 2, 3, 7, 64 * 1024] {
+            let mut records = Records::new(
+                Cursor::new(input.as_bytes()),
+                input.len() as u64,
+                separator.clone(),
+                before,
+                buffer_size,
+            );
+            let mut found = Vec::new();
+            while let Some(range) = records.next_back().unwrap() {
+                let mut record = Vec::new();
+                records.copy(range, &mut record).unwrap();
+                found.push(String::from_utf8(record).unwrap());
+            }
+            results.push(found);
+        }
+        results.dedup();
+        assert_eq!(
+            results.len(),
+            1,
+            "buffer size changed the records: {:?}",
+            results
+        );
+        results.pop().unwrap()
+    }
+
+    fn newline() -> Separator {
+        Separator::Bytes(b"\n".to_vec())
+    }
+
+    #[test]
+    fn test_lines() {
+        assert_eq!(records("a\nbc\n", newline(), false), vec!["bc\n", "a\n"]);
+        assert_eq!(records("a\nbc", newline(), false), vec!["bc", "a\n"]);
+        assert_eq!(
+            records("\n\nx\n", newline(), false),
+            vec!["x\n", "\n", "\n"]
+        );
+        assert_eq!(records("", newline(), false), Vec::<String>::new());
+        assert_eq!(records("a\n\n", newline(), true), vec!["\n", "\n", "a"]);
+        let long = "x".repeat(100);
+        assert_eq!(
+            records(&format!("{0}\n{0}y\n", long), newline(), false),
+            vec![format!("{}y\n", long), format!("{}\n", long)]
+        );
+    }
+
+    #[test]
+    fn test_separators() {
+        let xy = Separator::Bytes(b"XY".to_vec());
+        assert_eq!(
+            records("aXYbXYc", xy.clone(), false),
+            vec!["c", "bXY", "aXY"]
+        );
+        assert_eq!(records("aXYbXYc", xy, true), vec!["XYc", "XYb", "a"]);
+        let aa = Separator::Bytes(b"aa".to_vec());
+        assert_eq!(records("xaaay", aa, false), vec!["y", "xaaa"]);
+
+        let regex = RegexBuilder::new(Syntax::Extended)
+            .build(&["[0-9]+"])
+            .unwrap();
+        assert_eq!(
+            records("a1b22c", Separator::Regex(Box::new(regex.clone())), false),
+            vec!["c", "2", "b2", "a1"]
+        );
+        assert_eq!(
+            records("a1b22c", Separator::Regex(Box::new(regex)), true),
+            vec!["2c", "2", "1b", "a"]
+        );
+    }
+}
@@ -0,0 +1,83 @@
+//! Command lines filled with as many arguments as fit, for `find -exec ... +` and `xargs`.
+use std::ffi::{OsStr, OsString};
+
+/// The most bytes a command line may take, counting a terminating NUL for each argument.
+/// This is the size GNU xargs settles on by default.
+pub const ARG_MAX: usize = 128 * 1024;
+
+/// A command line being filled: the fixed arguments it starts with, then those added.
+#[derive(Debug)]
+pub struct Batch {
+    fixed: Vec<OsString>,
+    added: Vec<OsString>,
+    size: usize,
+    /// The most arguments to add, if limited.
+    max_args: Option<usize>,
+}
+
+impl Batch {
+    pub fn new(fixed: Vec<OsString>, max_args: Option<usize>) -> Self {
+        let size = fixed.iter().map(|arg| arg.len() + 1).sum();
+        Batch {
+            fixed,
+            added: Vec::new(),
+            size,
+            max_args,
+        }
+    }
+
+    /// Whether `arg` can be added without going over a limit.
+    pub fn fits(&self, arg: &OsStr) -> bool {
+        self.size + arg.len() < ARG_MAX && self.max_args.is_none_or(|max| self.added.len() < max)
+    }
+
+    pub fn push(&mut self, arg: OsString) {
+        self.size += arg.len() + 1;
+        self.added.push(arg);
+    }
+
+    /// Whether as many arguments were added as are allowed.
+    pub fn is_full(&self) -> bool {
+        self.max_args.is_some_and(|max| self.added.len() >= max)
+    }
+
+    /// Whether no arguments were added since the last [`Batch::take`].
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+    }
+
+    /// The whole command line, leaving the batch empty for the next one.
+    pub fn take(&mut self) -> Vec<OsString> {
+        self.size -= self.added.iter().map(|arg| arg.len() + 1).sum::<usize>();
+        let mut line = self.fixed.clone();
+        line.append(&mut self.added);
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch() {
+        let arg = |s: &str| OsString::from(s);
+        let mut batch = Batch::new(vec![arg("echo")], Some(2));
+        assert!(batch.is_empty());
+        batch.push(arg("a"));
+        assert!(batch.fits(&arg("b")));
+        assert!(!batch.is_full());
+        batch.push(arg("b"));
+        assert!(batch.is_full());
+        assert!(!batch.fits(&arg("c")));
+        assert_eq!(batch.take(), ["echo", "a", "b"]);
+        assert!(batch.is_empty());
+
+        let mut batch = Batch::new(vec![arg("echo")], None);
+        assert!(!batch.is_full());
+        assert!(!batch.fits(&arg(&"x".repeat(ARG_MAX))));
+        batch.push(arg(&"a".repeat(ARG_MAX / 2)));
+        assert!(!batch.fits(&arg(&"b".repeat(ARG_MAX / 2))));
+        assert!(batch.fits(&arg(&"b".repeat(ARG_MAX / 4))));
+    }
+}
@@ -0,0 +1,94 @@
+#!/usr/bin/env bash
+set -e
+
+script_dir=$(cd "$(dirname "${BASH_SOURCE[0]}")" &>/dev/null && pwd -P)
+pj_root_dir="$script_dir/../../../"
+cd "$pj_root_dir"
+
+cargo build --release --bin base64 --bin base32 --bin basenc
+my_bin="$PWD/target/release"
+export LC_ALL=C
+
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+
+# Runs both commands in $tmp with standard input from the file `input` there, and
+# compares what they printed and their exit statuses.
+compare() {
+    local command=$1
+    shift
+    (cd "$tmp" && "$my_bin/$command" "$@" <input >mine.out 2>&1 || echo $? >>mine.out)
+    (cd "$tmp" && command "$command" "$@" <input >theirs.out 2>&1 || echo $? >>theirs.out)
+    cmp "$tmp/mine.out" "$tmp/theirs.out"
+}
+
+# Encodes with both, then decodes what they encoded with both.
+round_trip() {
+    local file=$1
+    shift
+    for command in base64 base32 "basenc --base64url" "basenc --base32hex" "basenc --base16"; do
+        # shellcheck disable=SC2086
+        compare $command "$@" "$file"
+        cp "$tmp/theirs.out" "$tmp/input"
+        # shellcheck disable=SC2086
+        compare $command -d
+        cmp "$tmp/mine.out" "$tmp/$file"
+    done
+}
+
+cd "$tmp"
+: >empty
+for size in 1 2 3 4 5 6 7; do head -c $size "$pj_root_dir/Cargo.lock" >"short$size"; done
+head -c 1000000 /dev/urandom >random
+cp "$pj_root_dir/Cargo.lock" lock
+: >input
+cd "$pj_root_dir"
+
+for file in empty short1 short2 short3 short4 short5 short6 short7 lock random; do
+    round_trip $file
+done
+round_trip lock -w 0
+round_trip lock -w 1
+round_trip random --wrap=100
+
+# Decoding what is not quite right.
+decode() {
+    printf "$1" >"$tmp/input"
+    shift
+    compare "$@" -d
+}
+decode 'YWJj\nZGVm\n' base64
+decode 'YQ==YWJj' base64
+decode 'YR==' base64
+decode 'YQ' base64
+decode 'YQ=' base64
+decode 'YQ=A' base64
+decode 'Y' base64
+decode '=YQ' base64
+decode 'YW Jj' base64
+decode 'YW Jj' base64 -i
+decode 'Y*W\n*J=j' base64 -i
+decode 'YQ=*=' base64 --ignore-garbage
+decode 'YWJj\r\n' base64
+decode 'MFRGG===' base32
+decode 'MFRG' base32
+decode 'MFRG*GZD' base32
+decode 'MFR=====' base32
+decode 'ME=====' base32
+decode 'mfrggzdf' base32
+decode '61626' basenc --base16
+decode '6a6B' basenc --base16
+decode '61 62' basenc --base16 -i
+decode '_-8' basenc --base64url
+decode '/+8=' basenc --base64url
+decode 'ab' basenc --base64 --base16
+
+# Mistakes in the options and the operands.
+: >"$tmp/input"
+compare base64 -w x
+compare base64 -w -1
+compare base64 a b
+compare base64 nope
+compare base64 /
+compare basenc -d
+compare base32 --bogus
@@ -8,5 +8,24 @@ cd "$pj_root_dir"
 cargo build --release --bin cat
 my_cat="./target/release/cat"
 command diff <($my_cat invalid_file 2>&1) <(cat invalid_file 2>&1)
+command diff <($my_cat invalid_file Cargo.toml mimic_commands 2>&1; echo $?) <(cat invalid_file Cargo.toml mimic_commands 2>&1; echo $?)
+command diff <($my_cat Cargo.toml < mimic_commands 2>&1; echo $?) <(cat Cargo.toml < mimic_commands 2>&1; echo $?)
+command diff <($my_cat - < mimic_commands 2>&1; echo $?) <(cat - < mimic_commands 2>&1; echo $?)
 command diff <($my_cat Cargo.toml) <(cat Cargo.toml)
 command diff <($my_cat Cargo.toml Cargo.lock) <(cat Cargo.toml Cargo.lock)
+# regular file, pipe and /proc inputs redirected to a regular file
+output=$(mktemp)
+$my_cat Cargo.lock - /proc/self/limits < Cargo.toml > "$output"
+command diff "$output" <(cat Cargo.lock Cargo.toml /proc/self/limits)
+$my_cat Cargo.lock >> "$output"
+command diff "$output" <(cat Cargo.lock Cargo.toml /proc/self/limits Cargo.lock)
+rm "$output"
+command diff <($my_cat -n Cargo.toml Cargo.lock) <(cat -n Cargo.toml Cargo.lock)
+command diff <($my_cat -bs Cargo.toml Cargo.lock) <(cat -bs Cargo.toml Cargo.lock)
+command diff <(printf 'a\tb\x01\xff\n\n\n' | $my_cat -A) <(printf 'a\tb\x01\xff\n\n\n' | cat -A)
+command diff <(printf 'a\tb\n\n\nc' | $my_cat -nsET) <(printf 'a\tb\n\n\nc' | cat -nsET)
+gz=$(mktemp)
+gzip -c Cargo.lock > "$gz"
+command diff <($my_cat --decompress "$gz" Cargo.toml - < "$gz") <(zcat -f "$gz" Cargo.toml - < "$gz")
+command diff <($my_cat --decompress -n "$gz") <(zcat "$gz" | cat -n)
+rm "$gz"
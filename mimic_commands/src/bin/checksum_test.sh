@@ -0,0 +1,82 @@
+#!/usr/bin/env bash
+set -e
+
+script_dir=$(cd "$(dirname "${BASH_SOURCE[0]}")" &>/dev/null && pwd -P)
+pj_root_dir="$script_dir/../../../"
+cd "$pj_root_dir"
+
+cargo build --release --bin md5sum --bin sha1sum --bin sha256sum --bin b2sum
+release="$PWD/target/release"
+export LC_ALL=C
+
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+
+# Standard output with the exit status, then standard error, as the two are buffered
+# differently.
+compare() {
+    local sum=$1
+    shift
+    command diff <("$release/$sum" "$@" 2>/dev/null || echo $?) <($sum "$@" 2>/dev/null || echo $?)
+    command diff <("$release/$sum" "$@" 2>&1 >/dev/null) <($sum "$@" 2>&1 >/dev/null)
+}
+
+cd "$tmp"
+printf 'abc' >abc
+: >empty
+head -c 1000000 /dev/zero | tr '\0' a >million
+printf 'x' >'back\slash'
+printf 'y' >"$(printf 'new\nline')"
+printf 'z' >'sp ace'
+
+for sum in md5sum sha1sum sha256sum b2sum; do
+    compare $sum abc empty million
+    compare $sum -b abc
+    compare $sum --tag abc million
+    compare $sum 'back\slash' "$(printf 'new\nline')" 'sp ace'
+    command diff <("$release/$sum" - <abc) <($sum - <abc)
+    compare $sum abc nope empty
+    compare $sum .
+
+    $sum abc empty million 'back\slash' "$(printf 'new\nline')" >good
+    compare $sum -c good
+    compare $sum --check --quiet good
+    compare $sum -c --status good
+    $sum --tag abc empty >tagged
+    compare $sum -c tagged
+    command diff <("$release/$sum" -c - <good) <($sum -c - <good)
+
+    # A mismatch, a missing file, and lines that cannot be read.
+    cp good bad
+    printf 'changed' >empty2
+    sed -n 2p good | sed 's/empty/empty2/' >>bad
+    sed -n 1p good | sed 's/abc$/nope/' >>bad
+    printf '# a comment\nnot a checksum\n\n' >>bad
+    sed -n 1p good | sed 's/  / /' >>bad
+    compare $sum -c bad
+    compare $sum -c --quiet bad
+    compare $sum -c --status bad
+    compare $sum -c --strict good
+    compare $sum -c --strict bad
+    compare $sum -c -w bad
+    printf 'junk\n' >junk
+    compare $sum -c junk
+    compare $sum -c --status junk
+    compare $sum -c nope good
+    # The single-space format, which cannot be mixed with the usual one.
+    $sum abc | sed 's/  / /' >single
+    $sum empty >>single
+    compare $sum -c single
+    printf '%s\r\n' "$($sum abc)" >crlf
+    compare $sum -c crlf
+done
+
+compare b2sum -l 256 abc
+compare b2sum -l 256 --tag abc
+b2sum -l 128 abc >short
+compare b2sum -c short
+b2sum -l 128 --tag abc >>short
+compare b2sum -c short
+compare b2sum -l 256 -c short
+compare b2sum -l 7 abc
+compare b2sum -l 1024 abc
@@ -0,0 +1,70 @@
+#!/usr/bin/env bash
+set -e
+
+script_dir=$(cd "$(dirname "${BASH_SOURCE[0]}")" &>/dev/null && pwd -P)
+pj_root_dir="$script_dir/../../../"
+cd "$pj_root_dir"
+
+cargo build --release --bin cmp
+my_cmp="$PWD/target/release/cmp"
+export LC_ALL=C
+
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+
+# Runs both cmps in $tmp and compares what they printed and their exit statuses. With
+# `--stdin FILE`, FILE is the standard input of both.
+compare() {
+    local stdin=/dev/null
+    if [ "$1" = --stdin ]; then
+        stdin=$2
+        shift 2
+    fi
+    (cd "$tmp" && "$my_cmp" "$@" <"$stdin" >mine.out 2>&1 || echo $? >>mine.out)
+    (cd "$tmp" && cmp "$@" <"$stdin" >theirs.out 2>&1 || echo $? >>theirs.out)
+    command diff "$tmp/mine.out" "$tmp/theirs.out"
+}
+
+cd "$tmp"
+head -c 200000 /dev/urandom >random
+cp random random_copy
+cp random changed
+printf 'x' | dd of=changed bs=1 seek=150000 conv=notrunc 2>/dev/null
+printf 'y' | dd of=changed bs=1 seek=7 conv=notrunc 2>/dev/null
+head -c 100000 random >prefix
+seq 1 1000 >lines
+seq 1 1000 | sed '500s/0/x/' >lines_changed
+printf 'abc' >partial
+printf 'abcd\n' >longer
+printf 'abc\n' >line
+: >empty
+cd "$pj_root_dir"
+
+for options in "" -l -s; do
+    compare $options random random_copy
+    compare $options random changed
+    compare $options random prefix
+    compare $options prefix random
+    compare $options lines lines_changed
+    compare $options partial longer
+    compare $options line longer
+    compare $options empty line
+    compare $options line empty
+    compare $options empty empty
+    compare $options lines lines
+    compare $options lines missing
+    compare $options missing lines
+    compare --stdin lines_changed $options lines
+    compare --stdin lines $options - lines_changed
+    compare $options lines .
+done
+compare --verbose lines lines_changed
+compare --quiet lines lines_changed
+# Usage errors differ only in how the hint to try --help is prefixed, so only the
+# messages themselves are compared.
+# Further operands are byte counts to skip in GNU cmp, which this one lacks, so extra
+# operands are left out.
+for usage in "-l -s lines lines" "" "--bogus lines lines"; do
+    # shellcheck disable=SC2086
+    command diff <(cd "$tmp" && "$my_cmp" $usage 2>&1 | head -1) <(cd "$tmp" && cmp $usage 2>&1 | head -1)
+done
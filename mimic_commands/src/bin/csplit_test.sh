@@ -0,0 +1,96 @@
+#!/usr/bin/env bash
+set -e
+
+script_dir=$(cd "$(dirname "${BASH_SOURCE[0]}")" &>/dev/null && pwd -P)
+pj_root_dir="$script_dir/../../../"
+cd "$pj_root_dir"
+
+cargo build --release --bin csplit
+my_csplit="$PWD/target/release/csplit"
+export LC_ALL=C
+
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+
+# Runs each csplit in a directory of its own, then compares what they printed, their exit
+# statuses and the files they made. With `-i FILE`, FILE is the standard input of both.
+compare() {
+    local stdin=/dev/null
+    if [ "$1" = -i ]; then
+        stdin=$2
+        shift 2
+    fi
+    rm -rf "$tmp/mine" "$tmp/theirs"
+    mkdir "$tmp/mine" "$tmp/theirs"
+    (cd "$tmp/mine" && "$my_csplit" "$@" <"$stdin" >../mine.out 2>&1 || echo $? >>../mine.out)
+    (cd "$tmp/theirs" && csplit "$@" <"$stdin" >../theirs.out 2>&1 || echo $? >>../theirs.out)
+    command diff "$tmp/mine.out" "$tmp/theirs.out"
+    command diff -r "$tmp/mine" "$tmp/theirs"
+}
+
+ten="$tmp/ten"
+seq 1 10 >"$ten"
+printf 'a\nb\nc' >"$tmp/unterminated"
+seq 1 100000 >"$tmp/long"
+
+compare "$ten" 3 7
+compare "$ten" 3 '{2}'
+compare "$ten" +3
+compare "$ten" 1
+compare "$ten" 10
+compare "$ten" 4 4
+compare "$ten" 4 /4/
+compare "$ten" 4 /5/
+compare "$ten" '/[2468]/' '{*}'
+compare "$ten" '/[13579]/' '{3}'
+compare "$ten" '/[13579]/' '{4}'
+compare "$ten" /5/ '{*}'
+compare "$ten" /1/
+compare "$ten" /5/+2
+compare "$ten" /5/-2 %8%
+compare "$ten" /5/-1 /6/
+compare "$ten" /5/-1 %6%
+compare "$ten" /8/+3
+compare "$ten" /2/ '%[0-9]%' '{*}'
+compare "$ten" '%^[3-5]$%' /9/
+compare "$tmp/unterminated" /b/ 3
+compare -i "$ten" - 2 /7/
+compare -i "$tmp/unterminated" - 2
+compare "$tmp/long" '/0000$/' '{*}'
+compare "$tmp/long" '/5$/-3' '{20}'
+compare -s "$ten" 5
+compare -z "$ten" 1 2 /3/
+compare -z "$ten" '/[2468]/+1' '{*}'
+compare -f part_ -n 3 "$ten" 2 '{3}'
+compare -b '%03x.txt' "$ten" 5
+compare -b 'no%%%d' "$ten" 5
+compare -b '%#o' "$ten" 5
+compare -b '%-4d' "$ten" 5
+
+# Failures remove the pieces made so far, unless they are kept.
+compare "$ten" 11
+compare "$ten" 3 '{*}'
+compare "$ten" /11/
+compare -k "$ten" /3/ /11/
+compare "$ten" /5/ /5/
+compare "$ten" /3/-5
+compare -k "$ten" /8/+5
+compare "$ten" 0
+compare "$ten" 5 3
+compare "$ten" /x
+compare "$ten" %x
+compare "$ten" /x/z
+compare "$ten" abc
+compare "$ten" '{3}'
+compare "$ten" 3 '{x}'
+compare "$ten" 3 '{2}' '{3}'
+compare -b x "$ten" 5
+compare -b %d%d "$ten" 5
+compare -b %s "$ten" 5
+compare -b %99999999999999999999d "$ten" 5
+compare -n x "$ten" 5
+compare -n -1 "$ten" 5
+compare -n 18446744073709551615 "$ten" 5
+compare -n 2147483648 "$ten" 5
+compare "$tmp/nope" 3
+compare -f no/such/dir "$ten" 3
@@ -0,0 +1,57 @@
+#!/usr/bin/env bash
+set -e
+
+script_dir=$(cd "$(dirname "${BASH_SOURCE[0]}")" &>/dev/null && pwd -P)
+pj_root_dir="$script_dir/../../../"
+cd "$pj_root_dir"
+
+cargo build --release --bin cut
+my_cut="$PWD/target/release/cut"
+# Both sides work on bytes.
+export LC_ALL=C
+
+
+compare() {
+    command diff <($my_cut "$@" 2>&1 || echo $?) <(cut "$@" 2>&1 || echo $?)
+}
+
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+printf 'a:b:c:d\n::x\nno delimiter\n\n1:2\n3:4:5:6:7:8:9:10:11:12' >"$tmp/fields"
+printf 'one\ttwo\tthree\nfour\n' >"$tmp/tabs"
+head -c 10000 /dev/zero | tr '\0' y >"$tmp/long"
+printf ':end\n' >>"$tmp/long"
+
+compare -b 1-3 Cargo.toml
+compare -b 2,4-5,7- Cargo.toml Cargo.lock
+compare -c 1,3 Cargo.toml
+compare -b 1-2,3-4,6 --output-delimiter=: Cargo.toml
+compare -b 1-3,2-4 --output-delimiter=: Cargo.toml
+compare --complement -b 3 --output-delimiter=: Cargo.toml
+compare -b -3 --complement Cargo.toml
+compare -d : -f 2 "$tmp/fields"
+compare -d : -f 1,3- "$tmp/fields"
+compare -d : -f 3,1 --output-delimiter=XY "$tmp/fields"
+compare -d : -f 2- -s "$tmp/fields"
+compare -d : -f 2 --complement "$tmp/fields"
+compare -d : -f 11- "$tmp/fields"
+compare -f 2 "$tmp/tabs"
+compare -f 1,3 -s "$tmp/tabs"
+compare -d : -f 2 "$tmp/long"
+compare -b 9999-10002 "$tmp/long"
+compare -d : -f 9999999999999 "$tmp/fields"
+compare -d : -f 2-18446744073709551614 --complement "$tmp/fields"
+compare -d : -f 18446744073709551615 "$tmp/fields"
+compare -b 1-99999999999999999999 Cargo.toml
+compare -b 0 Cargo.toml
+compare -b 3-1 Cargo.toml
+compare -b x Cargo.toml
+compare -f 1--2 Cargo.toml
+compare -b - Cargo.toml
+compare -f 1 -b 2 Cargo.toml
+compare -d ab -f 1 Cargo.toml
+compare -d : -b 1 Cargo.toml
+compare -s -b 1 Cargo.toml
+compare Cargo.toml
+compare -b 1 invalid_file Cargo.toml
+compare -b 1 mimic_commands Cargo.toml
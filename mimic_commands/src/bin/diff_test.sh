@@ -0,0 +1,105 @@
+#!/usr/bin/env bash
+set -e
+
+script_dir=$(cd "$(dirname "${BASH_SOURCE[0]}")" &>/dev/null && pwd -P)
+pj_root_dir="$script_dir/../../../"
+cd "$pj_root_dir"
+
+cargo build --release --bin diff
+my_diff="$PWD/target/release/diff"
+export LC_ALL=C
+
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+
+# Runs both diffs in $tmp and compares what they printed and their exit statuses. With
+# `--stdin FILE`, FILE is the standard input of both.
+compare() {
+    local stdin=/dev/null
+    if [ "$1" = --stdin ]; then
+        stdin=$2
+        shift 2
+    fi
+    (cd "$tmp" && "$my_diff" "$@" <"$stdin" >mine.out 2>&1 || echo $? >>mine.out)
+    (cd "$tmp" && command diff "$@" <"$stdin" >theirs.out 2>&1 || echo $? >>theirs.out)
+    command diff "$tmp/mine.out" "$tmp/theirs.out"
+}
+
+# Edited copies of real sources, the kind of difference diff is mostly used on.
+cd "$tmp"
+cp "$pj_root_dir/mimic_commands/src/args.rs" args1
+sed -e '20,30d' -e '45s/Opt/Option/' -e '100a\    // added' -e '$d' args1 >args2
+cp "$pj_root_dir/mimic_commands/src/applets/mod.rs" mod1
+grep -v '^    Applet {$' mod1 | sed 's/main: /main:  /' >mod2
+seq 1 1000 >seq1
+seq 1 1000 | sed -e '1d' -e '500,510s/$/x/' -e '700a new' -e '999,$d' >seq2
+seq 1 2000 | sed 's/0/ 0/' >spaced
+seq 1 2000 | sed 's/0/\t\t0 /' >tabbed
+printf 'One\nTWO\nthree\n' >upper
+printf 'one\ntwo\nthree\n' >lower
+printf 'a\nb\nc' >partial1
+printf 'a\nb\nd' >partial2
+printf 'a\nb\nc\n' >complete
+printf 'a\0b\n' >binary1
+printf 'a\0c\n' >binary2
+: >empty
+cd "$pj_root_dir"
+
+for format in "" -u -c -U0 -U1 -C2 --unified=5 -q; do
+    compare $format args1 args2
+    compare $format mod1 mod2
+    compare $format seq1 seq2
+    compare $format seq2 seq1
+    compare $format partial1 partial2
+    compare $format partial1 complete
+    compare $format empty seq1
+    compare $format seq1 empty
+    compare $format seq1 seq1
+done
+compare spaced tabbed
+compare -b spaced tabbed
+compare -w spaced tabbed
+compare -u -w seq1 tabbed
+compare upper lower
+compare -i upper lower
+compare -iq upper lower
+compare -b partial1 complete
+compare binary1 binary2
+compare -q binary1 binary2
+compare -a binary1 binary2
+compare --stdin seq1 - seq2
+# Standard input is shown as modified now, which is not the same now for both.
+compare --stdin seq2 seq1 -
+
+# Directories.
+mkdir -p "$tmp/a/sub/deeper" "$tmp/b/sub/deeper" "$tmp/a/only_a" "$tmp/b/kind"
+cp "$tmp/args1" "$tmp/a/args"
+cp "$tmp/args2" "$tmp/b/args"
+cp "$tmp/seq1" "$tmp/a/sub/deeper/seq"
+cp "$tmp/seq2" "$tmp/b/sub/deeper/seq"
+cp "$tmp/seq1" "$tmp/a/sub/same"
+cp "$tmp/seq1" "$tmp/b/sub/same"
+cp "$tmp/binary1" "$tmp/a/binary"
+cp "$tmp/binary2" "$tmp/b/binary"
+: >"$tmp/a/kind"
+echo b >"$tmp/b/only_b"
+for options in "" -r -ru -rc -rq "-r -U 1" --recursive; do
+    # shellcheck disable=SC2086
+    compare $options a b
+done
+compare a/args b
+compare a b/args
+compare -r a/sub b/sub/
+
+# Trouble.
+compare missing seq1
+compare seq1 missing
+compare -r a missing
+compare --stdin seq1 a -
+compare --stdin seq1 - a
+# Usage errors differ only in how the hint to try --help is prefixed, so only the
+# messages themselves are compared.
+for usage in "-u -c seq1 seq2" "-U x seq1 seq2" "seq1" "seq1 seq2 seq3"; do
+    # shellcheck disable=SC2086
+    command diff <(cd "$tmp" && "$my_diff" $usage 2>&1 | head -1) <(cd "$tmp" && command diff $usage 2>&1 | head -1)
+done
@@ -0,0 +1,58 @@
+#!/usr/bin/env bash
+set -e
+
+script_dir=$(cd "$(dirname "${BASH_SOURCE[0]}")" &>/dev/null && pwd -P)
+pj_root_dir="$script_dir/../../../"
+cd "$pj_root_dir"
+
+cargo build --release --bin du
+my_du="$PWD/target/release/du"
+export LC_ALL=C
+
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+
+# Runs both dus in $tmp/tree and compares what they printed and their exit statuses.
+# Both list directories in the order the file system does, so the output needs no sorting.
+compare() {
+    (cd "$tmp/tree" && "$my_du" "$@" >../mine.out 2>&1 || echo $? >>../mine.out)
+    (cd "$tmp/tree" && command du "$@" >../theirs.out 2>&1 || echo $? >>../theirs.out)
+    diff "$tmp/mine.out" "$tmp/theirs.out"
+}
+
+mkdir -p "$tmp/tree" && cd "$tmp/tree"
+mkdir -p src/applets docs/Guide/deeper .hidden empty_dir
+cp "$pj_root_dir"/mimic_commands/src/*.rs src
+cp "$pj_root_dir"/mimic_commands/src/applets/*.rs src/applets
+printf 'notes\n' >docs/README.TXT
+head -c 5000 /dev/zero >docs/Guide/blank.bin
+head -c 3000000 /dev/urandom >docs/Guide/deeper/big.bin
+truncate -s 50M docs/sparse
+: >.hidden/empty
+ln docs/Guide/deeper/big.bin src/hard
+ln -s docs link
+ln -s nowhere dangling
+cd "$pj_root_dir"
+
+compare
+compare .
+compare -s
+compare -h
+compare -sh docs src
+compare --apparent-size
+compare --apparent-size -h docs
+compare -d 1
+compare -d 2 docs
+compare --max-depth=0 src docs
+compare docs src
+compare src docs
+compare docs docs/Guide
+compare docs/README.TXT link dangling
+compare src/lib.rs src/hard docs
+compare nope docs
+compare -s -d 0 docs
+
+# Mistakes in the options.
+compare -d x
+compare -s -d 1
+compare --bogus
@@ -0,0 +1,112 @@
+#!/usr/bin/env bash
+set -e
+
+script_dir=$(cd "$(dirname "${BASH_SOURCE[0]}")" &>/dev/null && pwd -P)
+pj_root_dir="$script_dir/../../../"
+cd "$pj_root_dir"
+
+cargo build --release --bin find
+my_find="$PWD/target/release/find"
+export LC_ALL=C
+
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+
+# Runs both finds in $tmp/tree and compares what they printed and their exit statuses.
+# Both list directories in the order the file system does, so the output needs no sorting.
+compare() {
+    (cd "$tmp/tree" && "$my_find" "$@" >../mine.out 2>&1 || echo $? >>../mine.out)
+    (cd "$tmp/tree" && command find "$@" >../theirs.out 2>&1 || echo $? >>../theirs.out)
+    diff "$tmp/mine.out" "$tmp/theirs.out"
+}
+
+mkdir -p "$tmp/tree" && cd "$tmp/tree"
+mkdir -p src/applets src/bin docs/Guide .hidden empty_dir
+cp "$pj_root_dir"/mimic_commands/src/*.rs src
+cp "$pj_root_dir"/mimic_commands/src/applets/*.rs src/applets
+cp "$pj_root_dir"/mimic_commands/src/bin/*.sh src/bin
+printf 'notes\n' >docs/README.TXT
+printf 'more\n' >docs/Guide/intro.txt
+head -c 5000 /dev/zero >docs/Guide/blank.bin
+: >.hidden/empty
+: >"with space"
+ln -s src link
+ln -s nowhere dangling
+mkfifo pipe
+touch -d '2000-01-01 00:00' docs/README.TXT
+touch -d '5 days ago' docs/Guide/intro.txt
+touch -d '1 day ago' src/lib.rs
+touch -d '2 days ago' reference
+cd "$pj_root_dir"
+
+compare
+compare .
+compare src docs ./docs/ nope
+compare -name '*.rs'
+compare . -name '*.TXT'
+compare . -iname '*.txt'
+compare . -name '[.]*'
+compare . -name 'mod.rs' -o -name 'lib.rs'
+compare . -type d
+compare . -type f,l
+compare . -type p -o -type l
+compare -L . -type l
+compare -L . -name '*.rs' -type f
+compare -H link -type f
+compare link
+compare . -size 0
+compare . -size -1k
+compare . -size +8k
+compare . -size 10b
+compare . -size 5000c
+compare . -size -2M -type f -name '*.bin'
+compare . -mtime -1
+compare . -mtime +3 -type f
+compare . -mtime 1
+compare . -mtime 5
+compare . -newer reference
+compare . ! -newer reference -type f
+compare . -maxdepth 1
+compare . -mindepth 2 -maxdepth 2
+compare . -maxdepth 0
+compare . -mindepth 1 -type d
+compare . -name src -prune -o -print
+compare . -name src -prune -o -name '*.rs' -print
+compare . \( -name docs -o -name .hidden \) -prune -o -type f -print
+compare . -not -type d -not -name '*.rs' -not -name '*.sh'
+compare . ! \( -type d -o -name '*.rs' \) -a -name '*s*'
+compare . -type d -print0
+compare . -name '*.txt' -print -print0
+compare . -print -name lib.rs -print
+compare . -type f -name '*.TXT' -exec cat {} \;
+compare . -type f -name '*.txt' -exec echo 'found:{}.' \;
+compare . -name '*.rs' -exec true \; -name 'w*'
+compare . -name 'w*' -exec test -d {} \; -o -name 'w*' -print
+compare . -name '*.sh' -exec echo {} +
+compare . -maxdepth 1 -exec echo first {} + -exec echo second {} +
+compare . -type f -exec sh -c 'test -s "$1"' sh {} \; -print
+compare . -type f -exec sh -c 'exit 3' sh {} +
+compare . -name lib.rs -exec nope {} \;
+compare . -name lib.rs -exec nope {} +
+
+# Mistakes in the expression.
+compare . -name
+compare . -bogus
+compare --bogus
+compare . -type q
+compare . -type fd
+compare . -type f,
+compare . -size 3q
+compare . -size 1.5k
+compare . -size +
+compare . -mtime x
+compare . -newer nope
+compare . -maxdepth x
+compare . \( -name x
+compare . -name x \)
+compare . \( \)
+compare . -o
+compare . -name x -a
+compare . !
+compare . -exec echo
+compare . -exec echo x{}y {} +
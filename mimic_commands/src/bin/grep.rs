@@ -0,0 +1,5 @@
+use std::env;
+
+fn main() {
+    mimic_commands::applets::grep::main(env::args().skip(1).collect());
+}
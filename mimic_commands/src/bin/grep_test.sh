@@ -0,0 +1,106 @@
+#!/usr/bin/env bash
+set -e
+
+script_dir=$(cd "$(dirname "${BASH_SOURCE[0]}")" &>/dev/null && pwd -P)
+pj_root_dir="$script_dir/../../../"
+cd "$pj_root_dir"
+
+cargo build --release --bin grep
+my_grep="$PWD/target/release/grep"
+# Both sides match characters, not bytes.
+export LC_ALL=C.UTF-8
+
+compare() {
+    command diff <($my_grep "$@" 2>&1 || echo $?) <(grep "$@" 2>&1 || echo $?)
+}
+
+compare version Cargo.lock
+compare -n 'name = "m' Cargo.lock Cargo.toml
+compare -c -v '^$' Cargo.lock Cargo.toml
+compare -l -c dependencies Cargo.lock Cargo.toml
+compare -l dependencies Cargo.lock
+compare -l nothing_matches_this Cargo.lock
+compare -h -n name Cargo.lock Cargo.toml
+compare -H -x '\[workspace\]' Cargo.toml
+compare -E -i '^NAME = "(mimic|rusty)[-_a-z]*"$' Cargo.lock
+compare -E -o '[0-9]+\.[0-9]+\.[0-9]+' Cargo.lock
+compare -on -w 'checksum\|source' Cargo.lock
+compare -F -e '[[' -e 'a.b' Cargo.lock
+compare -F -x -i '[[PACKAGE]]' Cargo.lock
+compare -n -C 2 '^name = "mimic' Cargo.lock
+compare -A 1 -B 3 dependencies Cargo.lock
+compare -o -C 1 -n mimic Cargo.lock
+compare -c '' Cargo.lock
+compare -q package Cargo.lock
+compare -q nothing_matches_this Cargo.lock
+compare nothing_matches_this Cargo.lock
+compare -s x invalid_file Cargo.toml
+compare x invalid_file Cargo.toml
+compare x mimic_commands Cargo.toml
+compare -q members invalid_file Cargo.toml
+compare -E '*members' Cargo.toml
+compare -E 'a{1' Cargo.toml
+compare 'a\{1' Cargo.toml
+compare -E '(a' Cargo.toml
+compare '[[:alpha:]' Cargo.toml
+compare '[:space:]' Cargo.toml
+compare -A x a Cargo.toml
+compare
+compare -f /dev/null Cargo.toml
+compare -f invalid_file Cargo.toml
+command diff <(printf 'members\nresolver\n' | $my_grep -n -f - Cargo.toml) <(printf 'members\nresolver\n' | grep -n -f - Cargo.toml)
+command diff <(printf 'ああa\nいい\nxあx\n' | $my_grep -o 'あ.') <(printf 'ああa\nいい\nxあx\n' | grep -o 'あ.')
+command diff <(printf 'Straße\nSTRASSE\n' | $my_grep -i 'straße') <(printf 'Straße\nSTRASSE\n' | grep -i 'straße')
+command diff <(printf 'no newline' | $my_grep new) <(printf 'no newline' | grep new)
+command diff <(printf 'a\nb\n' | $my_grep -c -H b) <(printf 'a\nb\n' | grep -c -H b)
+command diff <(printf 'a\nb\n' | $my_grep b - Cargo.toml) <(printf 'a\nb\n' | grep b - Cargo.toml)
+command diff <(printf 'a\nb\n' | $my_grep -l b) <(printf 'a\nb\n' | grep -l b)
+
+# recursive search and binary files, in a tree made up for it
+tree=$(mktemp -d)
+mkdir -p "$tree/d/e" "$tree/x"
+printf 'foo\n' > "$tree/d/a.txt"
+printf 'foo\nbar\n' > "$tree/d/b.rs"
+printf 'foo\n' > "$tree/d/e/c.txt"
+printf 'foo\0bar\nfoo\n' > "$tree/bin.dat"
+printf 'x\nfoo\xff\nfoo\n' > "$tree/bad.txt"
+ln -s d "$tree/link"
+ln -s ../d/a.txt "$tree/x/l.txt"
+ln -s .. "$tree/d/up"
+cd "$tree"
+compare -r foo
+compare -r foo .
+compare -r foo d/a.txt
+compare -r foo x link
+compare -R foo x link
+compare -R foo d
+compare -Rs foo d
+compare -r --include='*.txt' foo
+compare -r --exclude='*.txt' --exclude-dir=x foo
+compare -r --exclude-dir=e foo d
+compare -r --exclude-dir=e foo d/e
+compare -r --exclude-dir=e foo d/e/
+compare --exclude='*.txt' foo d/a.txt d/b.rs
+compare --include='*.txt' foo d/a.txt d/b.rs
+compare -rc foo d
+compare -rl foo
+compare -rh -n foo d
+compare -rn -C1 foo
+command diff <($my_grep -rn -C1 foo 2>/dev/null) <(grep -rn -C1 foo 2>/dev/null)
+compare foo d
+compare foo bin.dat bad.txt
+compare -c foo bin.dat
+compare -a foo bin.dat
+compare -o foo bin.dat
+compare -n . bad.txt
+compare -A1 x bad.txt
+compare -v zzz bin.dat
+command diff <($my_grep -a foo bin.dat) <(grep -a foo bin.dat)
+# A name that is not UTF-8 is still opened; only how it is shown may differ.
+mkdir u && printf 'needle\n' >u/bad$'\xff'
+compare -rh needle u
+compare -rq needle u
+cd - >/dev/null
+rm -r "$tree"
+
+echo "done"
@@ -0,0 +1,91 @@
+#!/usr/bin/env bash
+set -e
+
+script_dir=$(cd "$(dirname "${BASH_SOURCE[0]}")" &>/dev/null && pwd -P)
+pj_root_dir="$script_dir/../../../"
+cd "$pj_root_dir"
+
+cargo build --release --bin gzip --bin zcat
+my_bin="$PWD/target/release"
+export LC_ALL=C
+
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+
+# Runs both commands on a fresh copy of the fixtures, without a terminal on standard
+# input, and compares what they printed, their exit statuses and the files they left.
+compare() {
+    local command=$1
+    shift
+    for side in mine theirs; do
+        rm -rf "$tmp/$side" && cp -a "$tmp/fixtures" "$tmp/$side"
+        local program=$command
+        [ $side = mine ] && program="$my_bin/$command"
+        (cd "$tmp/$side" && "$program" "$@" </dev/null >../$side.out 2>&1 || echo $? >>../$side.out)
+        (cd "$tmp/$side" && ls -l --time-style=+%F >>../$side.out)
+    done
+    diff "$tmp/mine.out" "$tmp/theirs.out"
+}
+
+mkdir "$tmp/fixtures" && cd "$tmp/fixtures"
+cp "$pj_root_dir"/Cargo.lock lock
+seq 1 200000 >numbers
+head -c 300000 /dev/urandom >random
+printf 'hello\n' >hello
+: >empty
+gzip -9 -k lock numbers empty
+gzip -1 -k random
+gzip -n -k hello
+cat hello.gz numbers.gz >members.gz
+cp hello.gz garbage.gz && printf 'garbage' >>garbage.gz
+cp hello.gz zeros.gz && head -c 100 /dev/zero >>zeros.gz
+head -c 1000 numbers.gz >truncated.gz
+# The last byte of the CRC in the trailer.
+cp hello.gz crc.gz && printf '\x00' | dd of=crc.gz bs=1 seek=21 conv=notrunc 2>/dev/null
+printf 'plain\n' >plain.gz
+: >nothing.gz
+printf '\x1f\x8b\x07\x00' >method.gz
+printf '\x1f\x8b\x08\x20' >encrypted.gz
+printf '\x1f\x8b\x08\x40' >flags.gz
+cp hello.gz UPPER.GZ && cp hello.gz archive.tgz
+chmod 600 lock.gz && touch -d 2001-02-03 lock.gz
+ln -s hello.gz link.gz
+mkdir dir.gz
+rm lock numbers random hello empty
+cd "$pj_root_dir"
+
+compare zcat lock.gz numbers.gz random.gz empty.gz
+compare zcat members.gz hello.gz
+compare zcat hello
+compare zcat garbage.gz zeros.gz
+# GNU gzip loses some of what it decoded before the end of truncated data, so only
+# the messages and the statuses are compared.
+compare gzip -t truncated.gz hello.gz
+compare zcat crc.gz
+compare zcat plain.gz nothing.gz
+compare zcat method.gz encrypted.gz flags.gz hello.gz
+compare zcat -f plain.gz hello.gz
+compare zcat link.gz dir.gz missing
+compare gzip -d lock.gz numbers.gz
+compare gzip -dk random.gz UPPER.GZ archive.tgz
+compare gzip -d members.gz garbage.gz zeros.gz
+compare gzip -d truncated.gz crc.gz plain.gz nothing.gz
+compare gzip -d link.gz dir.gz missing hello.gz
+compare gzip -df link.gz
+compare gzip -dc hello.gz numbers.gz
+compare gzip -t lock.gz garbage.gz crc.gz
+
+# Things left in the way of the output.
+printf 'in the way\n' >"$tmp/fixtures/hello"
+compare gzip -d hello.gz
+compare gzip -df hello.gz
+rm "$tmp/fixtures/hello"
+
+# Standard input.
+for side in mine theirs; do
+    program=zcat
+    [ $side = mine ] && program="$my_bin/zcat"
+    "$program" <"$tmp/fixtures/members.gz" >"$tmp/$side.out" 2>&1
+    "$program" - <"$tmp/fixtures/nothing.gz" >>"$tmp/$side.out" 2>&1 || echo $? >>"$tmp/$side.out"
+done
+diff "$tmp/mine.out" "$tmp/theirs.out"
@@ -10,8 +10,25 @@ my_head="./target/release/head"
 
 [ "$($my_head < Cargo.lock)" = "$(head < Cargo.lock)" ]
 [ "$($my_head invalid_file 2>&1)" = "$(head invalid_file 2>&1)" ]
+command diff <($my_head invalid_file Cargo.toml mimic_commands 2>&1; echo $?) <(head invalid_file Cargo.toml mimic_commands 2>&1; echo $?)
+command diff <($my_head Cargo.toml < mimic_commands 2>&1; echo $?) <(head Cargo.toml < mimic_commands 2>&1; echo $?)
+command diff <($my_head - < mimic_commands 2>&1; echo $?) <(head - < mimic_commands 2>&1; echo $?)
 [ "$($my_head Cargo.lock)" = "$(head Cargo.lock)" ]
 [ "$($my_head Cargo.toml)" = "$(head Cargo.toml)" ]
 [ "$($my_head Cargo.lock Cargo.toml)" = "$(head Cargo.lock Cargo.toml)" ]
+[ "$($my_head -n 3 Cargo.lock)" = "$(head -n 3 Cargo.lock)" ]
+[ "$($my_head -n -3 < Cargo.lock)" = "$(head -n -3 < Cargo.lock)" ]
+[ "$($my_head -c 20 Cargo.lock)" = "$(head -c 20 Cargo.lock)" ]
+[ "$($my_head -c -20 < Cargo.lock)" = "$(head -c -20 < Cargo.lock)" ]
+[ "$($my_head -q Cargo.lock Cargo.toml)" = "$(head -q Cargo.lock Cargo.toml)" ]
+[ "$($my_head -v Cargo.toml)" = "$(head -v Cargo.toml)" ]
+
+gz=$(mktemp)
+gzip -c Cargo.lock > "$gz"
+[ "$($my_head --decompress -n 3 "$gz" Cargo.toml)" = "$(head -n 3 Cargo.lock Cargo.toml | sed "s|Cargo.lock|$gz|")" ]
+[ "$($my_head --decompress -c -20 < "$gz")" = "$(head -c -20 Cargo.lock)" ]
+# -z means NUL-terminated lines to GNU head, so it is not taken as --decompress.
+! $my_head -z "$gz" >/dev/null 2>&1
+rm "$gz"
 
 echo "done"
\ No newline at end of file
@@ -0,0 +1,79 @@
+#!/usr/bin/env bash
+set -e
+
+script_dir=$(cd "$(dirname "${BASH_SOURCE[0]}")" &>/dev/null && pwd -P)
+pj_root_dir="$script_dir/../../../"
+cd "$pj_root_dir"
+
+cargo build --release --bin ls
+my_ls="$PWD/target/release/ls"
+export LC_ALL=C
+
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+
+# Runs both lss in $tmp/tree and compares what they printed and their exit statuses.
+compare() {
+    (cd "$tmp/tree" && "$my_ls" "$@" >../mine.out 2>&1 || echo $? >>../mine.out)
+    (cd "$tmp/tree" && command ls "$@" >../theirs.out 2>&1 || echo $? >>../theirs.out)
+    diff "$tmp/mine.out" "$tmp/theirs.out"
+}
+
+mkdir -p "$tmp/tree" && cd "$tmp/tree"
+mkdir -p src/applets docs/Guide .hidden empty_dir
+cp "$pj_root_dir"/mimic_commands/src/*.rs src
+cp "$pj_root_dir"/mimic_commands/src/applets/*.rs src/applets
+printf 'notes\n' >docs/README.TXT
+head -c 5000 /dev/zero >docs/Guide/blank.bin
+head -c 3000000 /dev/zero >docs/Guide/big.bin
+: >.hidden/empty
+: >"with space"
+ln -s src link
+ln -s nowhere dangling
+ln docs/README.TXT hard
+mkfifo pipe
+chmod 4755 docs/README.TXT
+chmod 1777 empty_dir
+chmod 2640 "with space"
+chown 12345:54321 "with space"
+touch -d '2000-01-01 00:00' docs/README.TXT
+touch -d '5 days ago' docs/Guide/blank.bin
+touch -d '1 hour ago' src/lib.rs
+touch -d '2 hours ago' docs/Guide
+touch -d '2 hours ago' src/args.rs
+cd "$pj_root_dir"
+
+compare
+compare -a
+compare -1 src
+compare -C src/applets
+compare -C -w 40 src/applets
+compare -C -w 1 src
+compare -C -w 0 src
+compare -C -w 100 -a
+compare -C -w 60 -r src
+compare -w 30 -C docs src empty_dir
+compare -l
+compare -la
+compare -l docs docs/Guide
+compare -lh docs/Guide
+compare -lh src/applets
+compare -l link dangling hard pipe /dev/null
+compare link
+compare -l -S src
+compare -S -r src
+compare -t src
+compare -lt docs/README.TXT src/lib.rs src/args.rs
+compare -tr src
+compare -R
+compare -Ra docs
+compare -lR docs
+compare -R src/lib.rs docs
+compare src/lib.rs nope docs
+compare nope
+compare empty_dir
+compare -l empty_dir
+
+# Mistakes in the options.
+compare -w x
+compare --bogus
@@ -0,0 +1,81 @@
+use mimic_commands::applets::{self, APPLETS};
+use mimic_commands::args::Command;
+use mimic_commands::error::{self, Reporter, Style};
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::os::unix::fs::symlink;
+use std::path::Path;
+use std::process::exit;
+
+fn main() {
+    let mut args = env::args();
+    let program = args.next().unwrap_or_default();
+    // Run as `cat` through a symlink, or as `mimic cat`.
+    let invoked_as = Path::new(&program)
+        .file_name()
+        .and_then(|name| name.to_str());
+    if let Some(applet) = invoked_as.and_then(applets::find) {
+        return (applet.main)(args.collect());
+    }
+
+    let command = Command::new("mimic", "[OPTION]... | APPLET [ARG]...")
+        .about("Run APPLET, one of the commands built into this binary. Symlinks named after an applet run it directly.")
+        .opt("--list", "list the applets, one per line")
+        .opt(
+            "--install=DIR",
+            "create a symlink to this binary in DIR for every applet",
+        )
+        .stop_at_operand();
+    let matches = command.parse_or_exit(args);
+
+    if matches.flag("list") {
+        let names: String = APPLETS.iter().map(|a| format!("{}\n", a.name)).collect();
+        io::stdout().write_all(names.as_ref()).ok();
+        return;
+    }
+    if let Some(dir) = matches.value("install") {
+        install(Path::new(dir));
+        return;
+    }
+
+    let (name, rest) = match matches.operands().split_first() {
+        Some(operands) => operands,
+        None => command.exit_with_usage_error(&"missing applet"),
+    };
+    match applets::find(name) {
+        Some(applet) => (applet.main)(rest.to_vec()),
+        None => command.exit_with_usage_error(&format!("'{}' is not an applet", name)),
+    }
+}
+
+/// Links every applet name in `dir` to this binary. Links that already point here are
+/// left alone, so installing again after an upgrade is harmless.
+fn install(dir: &Path) {
+    let exe = match env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            eprintln!(
+                "mimic: cannot find this executable: {}",
+                error::describe(&e)
+            );
+            exit(1);
+        }
+    };
+
+    let mut reporter = Reporter::new("mimic", Style::Bare);
+    for applet in APPLETS {
+        let link = dir.join(applet.name);
+        if fs::read_link(&link).ok() == Some(exe.clone()) {
+            continue;
+        }
+        if let Err(e) = symlink(&exe, &link) {
+            reporter.fail(&format!(
+                "cannot create symlink '{}': {}",
+                link.display(),
+                error::describe(&e)
+            ));
+        }
+    }
+    reporter.finish();
+}
@@ -0,0 +1,23 @@
+#!/usr/bin/env bash
+set -e
+
+script_dir=$(cd "$(dirname "${BASH_SOURCE[0]}")" &>/dev/null && pwd -P)
+pj_root_dir="$script_dir/../../../"
+cd "$pj_root_dir"
+
+cargo build --release --bin mimic
+mimic="./target/release/mimic"
+command diff <($mimic --list) <(printf 'b2sum\nbase32\nbase64\nbasenc\ncat\ncmp\ncsplit\ncut\ndiff\ndu\nfind\ngrep\ngzip\nhead\nls\nmd5sum\nod\npaste\npv\nrev\nsha1sum\nsha256sum\nsort\nsplit\ntac\ntail\ntee\ntr\nuniq\nwc\nxargs\nxxd\nzcat\n')
+command diff <($mimic cat -n Cargo.toml) <(cat -n Cargo.toml)
+command diff <($mimic head -n 3 Cargo.lock) <(head -n 3 Cargo.lock)
+command diff <($mimic tail -c 20 Cargo.lock) <(tail -c 20 Cargo.lock)
+command diff <($mimic wc Cargo.toml Cargo.lock) <(wc Cargo.toml Cargo.lock)
+command diff <($mimic nope 2>&1 || echo $?) <(printf "mimic: 'nope' is not an applet\nTry 'mimic --help' for more information.\n2\n")
+
+# applets run through symlinks named after them
+bin_dir=$(mktemp -d)
+$mimic --install "$bin_dir"
+$mimic --install "$bin_dir"
+command diff <("$bin_dir/cat" Cargo.toml invalid_file 2>&1 || echo $?) <(cat Cargo.toml invalid_file 2>&1 || echo $?)
+command diff <("$bin_dir/wc" -l < Cargo.lock) <(wc -l < Cargo.lock)
+rm -r "$bin_dir"
@@ -0,0 +1,54 @@
+#!/usr/bin/env bash
+set -e
+
+script_dir=$(cd "$(dirname "${BASH_SOURCE[0]}")" &>/dev/null && pwd -P)
+pj_root_dir="$script_dir/../../../"
+cd "$pj_root_dir"
+
+cargo build --release --bin od
+my_od="$PWD/target/release/od"
+export LC_ALL=C
+
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+
+compare() {
+    command diff <($my_od "$@" 2>&1 || echo $?) <(od "$@" 2>&1 || echo $?)
+}
+
+printf 'hello world, this is od\n\0\1\377\a\b\f\r\t\v\177' >"$tmp/bin"
+head -c 100 /dev/zero >"$tmp/zeros"
+printf 'abc' >>"$tmp/zeros"
+: >"$tmp/empty"
+
+compare "$tmp/bin"
+compare -t x1 "$tmp/bin"
+compare -t o2 "$tmp/bin"
+compare -t d4 "$tmp/bin"
+compare -t c "$tmp/bin"
+compare -t u1 -t d2 "$tmp/bin"
+compare -t x1 -t c "$tmp/bin"
+compare -t x8 -t o1 -t c "$tmp/bin"
+compare -t cx1 "$tmp/bin"
+compare -t dL -t uS "$tmp/bin"
+compare -A x -t o2 "$tmp/bin"
+compare -A d -t d4 "$tmp/bin"
+compare -A n -t c "$tmp/bin"
+compare -A n -t x1 -t c "$tmp/bin"
+compare -j 3 -N 7 -t u1 "$tmp/bin"
+compare -j 0x10 -N 010 -t c "$tmp/bin"
+compare -t x1 "$tmp/zeros"
+compare -v -t x1 "$tmp/zeros"
+compare -t x1 "$tmp/zeros" "$tmp/bin" "$tmp/zeros"
+compare -j 100 -t c "$tmp/zeros" "$tmp/bin"
+compare "$tmp/empty"
+compare Cargo.lock
+command diff <($my_od -t x2 <"$tmp/bin") <(od -t x2 <"$tmp/bin")
+compare -N 4 -t c "$tmp/bin" "$tmp/nope"
+compare -t c "$tmp/nope" "$tmp/bin"
+compare -t q "$tmp/bin"
+compare -t x3 "$tmp/bin"
+compare -A q "$tmp/bin"
+compare -j 1k "$tmp/bin"
+compare -j q "$tmp/bin"
+compare -N 1x "$tmp/bin"
@@ -0,0 +1,40 @@
+#!/usr/bin/env bash
+set -e
+
+script_dir=$(cd "$(dirname "${BASH_SOURCE[0]}")" &>/dev/null && pwd -P)
+pj_root_dir="$script_dir/../../../"
+cd "$pj_root_dir"
+
+cargo build --release --bin paste
+my_paste="$PWD/target/release/paste"
+# Both sides work on bytes.
+export LC_ALL=C
+
+
+compare() {
+    command diff <($my_paste "$@" 2>&1 || echo $?) <(paste "$@" 2>&1 || echo $?)
+}
+
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+printf '1\n2\n3\n' >"$tmp/numbers"
+printf 'a\nb' >"$tmp/letters"
+: >"$tmp/empty"
+
+compare "$tmp/numbers" "$tmp/letters" "$tmp/empty"
+compare -d ':,' "$tmp/numbers" "$tmp/letters" "$tmp/numbers" "$tmp/letters"
+compare -s "$tmp/numbers" "$tmp/letters" "$tmp/empty"
+compare -s -d '\n:' "$tmp/numbers"
+compare -d '' "$tmp/numbers" "$tmp/letters"
+compare -d '\0-' "$tmp/numbers" "$tmp/letters" "$tmp/numbers"
+compare -d 'a\tb\\' "$tmp/numbers" "$tmp/letters" "$tmp/numbers"
+compare Cargo.toml Cargo.lock
+compare -s Cargo.toml
+compare "$tmp/empty" "$tmp/empty"
+command diff <($my_paste - - <"$tmp/numbers") <(paste - - <"$tmp/numbers")
+command diff <($my_paste -s - "$tmp/letters" <"$tmp/numbers") <(paste -s - "$tmp/letters" <"$tmp/numbers")
+compare -d '\' "$tmp/numbers"
+compare "$tmp/numbers" invalid_file
+compare -s invalid_file "$tmp/numbers"
+compare -s "$tmp/letters" mimic_commands invalid_file "$tmp/numbers"
+command diff <($my_paste -s - invalid_file - <"$tmp/numbers" 2>&1 || echo $?) <(paste -s - invalid_file - <"$tmp/numbers" 2>&1 || echo $?)
@@ -0,0 +1,67 @@
+#!/usr/bin/env bash
+set -e
+
+script_dir=$(cd "$(dirname "${BASH_SOURCE[0]}")" &>/dev/null && pwd -P)
+pj_root_dir="$script_dir/../../../"
+cd "$pj_root_dir"
+
+cargo build --release --bin pv
+my_pv="$PWD/target/release/pv"
+export LC_ALL=C
+
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+
+# There is no pv to compare against, so these check that the data comes through
+# unchanged, that the rate limit holds it back and that the progress looks right.
+head -c 500000 /dev/urandom >"$tmp/random"
+seq 100000 >"$tmp/lines"
+
+# Passes FILES through pv given OPTIONS, and checks the output is their concatenation.
+passes() {
+    local options=$1
+    shift
+    cat "$@" >"$tmp/expected"
+    # shellcheck disable=SC2086
+    "$my_pv" $options "$@" >"$tmp/out" 2>"$tmp/err"
+    cmp "$tmp/out" "$tmp/expected"
+}
+
+passes "-q" "$tmp/random"
+passes "-f" "$tmp/random" "$tmp/lines"
+passes "-f -s 10" "$tmp/lines"
+"$my_pv" -q <"$tmp/random" | cmp - "$tmp/random"
+"$my_pv" -q - "$tmp/lines" <"$tmp/random" | cmp - <(cat "$tmp/random" "$tmp/lines")
+
+# With standard error not a terminal, nothing is shown unless forced.
+"$my_pv" "$tmp/lines" >/dev/null 2>"$tmp/err"
+test ! -s "$tmp/err"
+
+# A known size gets a bar and an ETA, ending in 100% on a line of its own.
+"$my_pv" -f "$tmp/lines" 2>&1 >/dev/null | tr '\r' '\n' | tail -1 >"$tmp/err"
+grep -Eq '^ *[0-9.]+[KMG]?i?B [0-9]+:[0-9]{2}:[0-9]{2} \[ *[0-9.]+[KMG]?i?B/s\] \[=+\] 100% ETA 0:00:00$' "$tmp/err"
+# Standard input from a pipe has no size, so only the count, time and rate.
+seq 10 | "$my_pv" -f 2>&1 >/dev/null | tr '\r' '\n' | tail -1 >"$tmp/err"
+grep -Eq '^ +21B 0:00:00 \[ *[0-9.]+[KMG]?i?B/s\]$' "$tmp/err"
+COLUMNS=50 "$my_pv" -f "$tmp/lines" 2>&1 >/dev/null | tr '\r' '\n' | tail -1 >"$tmp/err"
+test "$(wc -L <"$tmp/err")" = 50
+
+# 100k at 200k a second takes at least half a second.
+head -c 100000 "$tmp/random" >"$tmp/part"
+start=$(date +%s%N)
+passes "-q -L 200k" "$tmp/part"
+elapsed=$((($(date +%s%N) - start) / 1000000))
+test "$elapsed" -ge 480
+
+# Missing inputs are reported and skipped.
+if "$my_pv" -q "$tmp/missing" "$tmp/lines" >"$tmp/out" 2>"$tmp/err"; then false; fi
+grep -qx "pv: $tmp/missing: No such file or directory" "$tmp/err"
+cmp "$tmp/out" "$tmp/lines"
+# A reader that goes away ends the copy as it would have a killed pv.
+status=$("$my_pv" -q "$tmp/lines" | head -1 >/dev/null; echo "${PIPESTATUS[0]}")
+test "$status" = 141
+
+for bad in "-L 0" "-L x" "-i 0" "-s x"; do
+    # shellcheck disable=SC2086
+    if "$my_pv" $bad </dev/null 2>/dev/null; then false; fi
+done
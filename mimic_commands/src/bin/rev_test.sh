@@ -0,0 +1,30 @@
+#!/usr/bin/env bash
+set -e
+
+script_dir=$(cd "$(dirname "${BASH_SOURCE[0]}")" &>/dev/null && pwd -P)
+pj_root_dir="$script_dir/../../../"
+cd "$pj_root_dir"
+
+cargo build --release --bin rev
+my_rev="$PWD/target/release/rev"
+# util-linux rev reverses characters, not bytes, in a UTF-8 locale.
+export LC_ALL=C.UTF-8
+
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+
+compare() {
+    command diff <($my_rev "$@" 2>&1 || echo $?) <(rev "$@" 2>&1 || echo $?)
+}
+
+printf 'añ€😀 x\n\nzß\n' >"$tmp/utf8"
+printf 'one\ntwo' >"$tmp/unterminated"
+head -c 300000 /dev/zero | tr '\0' x >"$tmp/long"
+printf 'y\nz\n' >>"$tmp/long"
+
+compare Cargo.lock
+compare "$tmp/utf8"
+compare "$tmp/unterminated" "$tmp/utf8"
+compare "$tmp/long"
+command diff <($my_rev <"$tmp/utf8") <(rev <"$tmp/utf8")
+compare "$tmp/nope" "$tmp/utf8"
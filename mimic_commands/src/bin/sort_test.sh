@@ -0,0 +1,65 @@
+#!/usr/bin/env bash
+set -e
+
+script_dir=$(cd "$(dirname "${BASH_SOURCE[0]}")" &>/dev/null && pwd -P)
+pj_root_dir="$script_dir/../../../"
+cd "$pj_root_dir"
+
+cargo build --release --bin sort
+my_sort="$PWD/target/release/sort"
+# Both sides compare bytes.
+export LC_ALL=C
+
+compare() {
+    command diff <($my_sort "$@" 2>&1 || echo $?) <(sort "$@" 2>&1 || echo $?)
+}
+
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+printf ' 10\n9\n-1.5\nabc\n1e3\n-0\n0\n9\n' >"$tmp/numbers"
+printf '1K\n2\n1G\n-1M\n3k\n0\n' >"$tmp/human"
+printf 'b:2:x\na:10:y\nc:1:x\nB:2:z\nb:2:x\n' >"$tmp/fields"
+printf 'a  b c\nb a\n  c\tz\nA b\n\nno newline' >"$tmp/blanks"
+seq 1 20000 | awk '{ print ($1 * 7919) % 10007 ":" $1 }' >"$tmp/large"
+
+compare Cargo.lock Cargo.toml
+compare -r Cargo.lock
+compare -u Cargo.lock
+compare -n "$tmp/numbers"
+compare -nr "$tmp/numbers"
+compare -nu "$tmp/numbers"
+compare -ns "$tmp/numbers"
+compare -h "$tmp/human"
+compare -hr "$tmp/human"
+compare -t : -k 2n "$tmp/fields"
+compare -t : -k 2,2n -k 1,1 "$tmp/fields"
+compare -t : -k 3,3 -s "$tmp/fields"
+compare -t : -k 3 -u "$tmp/fields"
+compare -t : -k 1,1f -u "$tmp/fields"
+compare -t : -k 18446744073709551615,18446744073709551615 "$tmp/fields"
+compare -k 9999999999999 -s "$tmp/fields"
+compare -f "$tmp/fields"
+compare -k 2 "$tmp/blanks"
+compare -k 2b "$tmp/blanks"
+compare -k 1.2,1.3 "$tmp/blanks"
+compare -b -k 2,2 -r "$tmp/blanks"
+compare -d -f "$tmp/blanks" Cargo.toml
+compare -t : -k 1n "$tmp/large"
+compare -S 1K -t : -k 1n "$tmp/large"
+compare -S 64K -t : -k 1,1nr -u "$tmp/large"
+compare -S 100b -T "$tmp" -r "$tmp/large" "$tmp/fields"
+command diff <($my_sort -n - <"$tmp/numbers") <(sort -n - <"$tmp/numbers")
+compare -k 0 Cargo.toml
+compare -k 1.0 Cargo.toml
+compare -k a Cargo.toml
+compare -k 1,1x Cargo.toml
+compare -S x Cargo.toml
+compare -t ab Cargo.toml
+compare invalid_file Cargo.toml
+compare mimic_commands
+
+# The output may be one of the inputs.
+cp "$tmp/fields" "$tmp/in_place"
+$my_sort -o "$tmp/in_place" "$tmp/in_place"
+command diff "$tmp/in_place" <(sort "$tmp/fields")
+compare -o /nonexistent/x Cargo.toml
@@ -0,0 +1,87 @@
+#!/usr/bin/env bash
+set -e
+
+script_dir=$(cd "$(dirname "${BASH_SOURCE[0]}")" &>/dev/null && pwd -P)
+pj_root_dir="$script_dir/../../../"
+cd "$pj_root_dir"
+
+cargo build --release --bin split
+my_split="$PWD/target/release/split"
+export LC_ALL=C
+
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+
+# Runs each split in a directory of its own, then compares what they printed, their exit
+# statuses and the files they made. With `-i FILE`, FILE is the standard input of both.
+compare() {
+    local stdin=/dev/null
+    if [ "$1" = -i ]; then
+        stdin=$2
+        shift 2
+    fi
+    rm -rf "$tmp/mine" "$tmp/theirs"
+    mkdir "$tmp/mine" "$tmp/theirs"
+    (cd "$tmp/mine" && "$my_split" "$@" <"$stdin" >../mine.out 2>&1 || echo $? >>../mine.out)
+    (cd "$tmp/theirs" && split "$@" <"$stdin" >../theirs.out 2>&1 || echo $? >>../theirs.out)
+    command diff "$tmp/mine.out" "$tmp/theirs.out"
+    command diff -r "$tmp/mine" "$tmp/theirs"
+}
+
+seq 1 1000 >"$tmp/seq"
+printf 'one\ntwo\nthree\nfour\nfive\nsix\nseven' >"$tmp/words"
+printf 'a\nb\n' >"$tmp/ab"
+printf 'abc' >"$tmp/abc"
+head -c 100000 /dev/urandom >"$tmp/random"
+: >"$tmp/empty"
+
+compare "$tmp/seq"
+compare -l 1 "$tmp/seq"
+compare -l 7 -d "$tmp/seq" part.
+compare -l 3 "$tmp/words"
+compare -b 1K "$tmp/random"
+compare -b 999 -a 4 "$tmp/random" r_
+compare -b 3 "$tmp/words"
+compare -l 2 -d -a 1 "$tmp/seq"
+compare -b 1 -a 1 "$tmp/words"
+compare -l 2 "$tmp/empty"
+compare -i "$tmp/seq" -l 100
+compare -i "$tmp/seq" -l 100 - in.
+compare -n 3 "$tmp/words"
+compare -n 7 "$tmp/random"
+compare -n 5 "$tmp/abc"
+compare -n 1000 "$tmp/abc"
+compare -n 2/3 "$tmp/words"
+compare -n 4/5 "$tmp/abc"
+compare -n 13/13 "$tmp/random"
+compare -i "$tmp/random" -n 3/7
+for n in 2 3 5 8 10 64; do
+    compare -n l/$n "$tmp/words"
+    compare -n l/$n "$tmp/ab"
+    compare -n l/$n -d "$tmp/seq"
+    compare -n r/$n "$tmp/words"
+done
+compare -n l/2/5 "$tmp/ab"
+compare -n l/3/5 "$tmp/ab"
+compare -n l/4/9 "$tmp/seq"
+compare -n r/2/3 "$tmp/words"
+compare -n r/3 "$tmp/empty"
+compare -n 4 "$tmp/empty"
+compare -l 300 --filter='wc -l; echo "$FILE"' "$tmp/seq"
+compare -n r/3 --filter='tr a-z A-Z >$FILE.up' "$tmp/words"
+compare -l 2 --filter='head -c 1' "$tmp/words"
+compare -l 3 --filter='cat >/dev/null; exit 3' "$tmp/words"
+compare -l 3 --filter='kill -9 $$' "$tmp/words"
+compare "$tmp/nope"
+compare -l 10 "$tmp/seq" no/such/dir
+
+# Numbers out of range.
+compare -l 0 "$tmp/words"
+compare -l 99999999999999999999 "$tmp/words"
+compare -a x "$tmp/words"
+compare -a -1 "$tmp/words"
+compare -a 18446744073709551615 "$tmp/words"
+compare -a 2305843009213693952 "$tmp/words"
+compare -n 18446744073709551615 "$tmp/words"
+compare -n l/9223372036854775808 "$tmp/words"
+compare -n 9223372036854775807 -a 1 "$tmp/words"
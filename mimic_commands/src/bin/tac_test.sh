@@ -0,0 +1,58 @@
+#!/usr/bin/env bash
+set -e
+
+script_dir=$(cd "$(dirname "${BASH_SOURCE[0]}")" &>/dev/null && pwd -P)
+pj_root_dir="$script_dir/../../../"
+cd "$pj_root_dir"
+
+cargo build --release --bin tac
+my_tac="$PWD/target/release/tac"
+export LC_ALL=C
+
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+
+compare() {
+    command diff <($my_tac "$@" 2>&1 || echo $?) <(tac "$@" 2>&1 || echo $?)
+}
+
+# Both sides read the same pipe, so the scratch copy is used.
+compare_pipe() {
+    local input=$1
+    shift
+    command diff <(cat "$input" | $my_tac "$@" 2>&1 || echo $?) <(cat "$input" | tac "$@" 2>&1 || echo $?)
+}
+
+printf 'one\ntwo\n\nthree' >"$tmp/unterminated"
+printf 'aXYbXYXYc' >"$tmp/xy"
+printf 'a\0b\0c\0' >"$tmp/nul"
+printf 'a1b22c333d\n4' >"$tmp/digits"
+head -c 300000 /dev/zero | tr '\0' x >"$tmp/long"
+printf '\nshort\n' >>"$tmp/long"
+printf 'first\n' >"$tmp/long2"
+cat "$tmp/long" >>"$tmp/long2"
+
+compare Cargo.lock
+compare -b Cargo.lock
+compare Cargo.lock "$tmp/unterminated" Cargo.toml
+compare -b "$tmp/unterminated"
+compare -s XY "$tmp/xy"
+compare -b -s XY "$tmp/xy"
+compare -s '' "$tmp/nul"
+compare -r -s '[0-9]+' "$tmp/digits"
+compare -b -r -s '[0-9]+' "$tmp/digits"
+compare -r -s '[]=]+ *' Cargo.lock
+# Only `+` and `?` are bare operators; groups and alternation take a backslash.
+printf 'axxbyc(x)dx|ye{2}xf+g' >"$tmp/syntax"
+for separator in 'x\|y' '\(x\)y*' '(x)' 'x|y' 'x{2}' 'x\{2\}' 'x\+' 'a?x' 'a\?x' '+g'; do
+    compare -r -s "$separator" "$tmp/syntax"
+done
+# GNU tac runs out of memory when a second file has a line this long, so each goes alone.
+compare "$tmp/long"
+compare "$tmp/long2"
+compare -s x "$tmp/long"
+compare_pipe Cargo.lock
+compare_pipe "$tmp/long2"
+compare_pipe "$tmp/digits" -r -s '[0-9]+'
+compare "$tmp/nope"
+compare "$tmp/nope" Cargo.toml
@@ -8,10 +8,51 @@ cd "$pj_root_dir"
 cargo build --release --bin tail
 my_tail="./target/release/tail"
 
-#[ "$($my_tail < Cargo.lock)" = "$(tail < Cargo.lock)" ]
+[ "$($my_tail < Cargo.lock)" = "$(tail < Cargo.lock)" ]
+[ "$(cat Cargo.lock | $my_tail)" = "$(cat Cargo.lock | tail)" ]
+[ "$(cat Cargo.lock | $my_tail -n 3 - Cargo.toml)" = "$(cat Cargo.lock | tail -n 3 - Cargo.toml)" ]
+[ "$(cat Cargo.lock | $my_tail -c 100)" = "$(cat Cargo.lock | tail -c 100)" ]
+[ "$($my_tail -n +20 Cargo.lock)" = "$(tail -n +20 Cargo.lock)" ]
+[ "$(cat Cargo.lock | $my_tail -n +20)" = "$(cat Cargo.lock | tail -n +20)" ]
+[ "$($my_tail -c +100 Cargo.lock)" = "$(tail -c +100 Cargo.lock)" ]
+[ "$($my_tail -c 100 Cargo.lock)" = "$(tail -c 100 Cargo.lock)" ]
+[ "$(tr '\n' '\0' < Cargo.lock | $my_tail -z -n 3 | tr '\0' '\n')" = "$(tail -n 3 Cargo.lock)" ]
 [ "$($my_tail invalid_file 2>&1)" = "$(tail invalid_file 2>&1)" ]
+command diff <($my_tail invalid_file Cargo.toml mimic_commands 2>&1; echo $?) <(tail invalid_file Cargo.toml mimic_commands 2>&1; echo $?)
+command diff <($my_tail Cargo.toml < mimic_commands 2>&1; echo $?) <(tail Cargo.toml < mimic_commands 2>&1; echo $?)
+command diff <($my_tail - < mimic_commands 2>&1; echo $?) <(tail - < mimic_commands 2>&1; echo $?)
 [ "$($my_tail Cargo.lock)" = "$(tail Cargo.lock)" ]
 [ "$($my_tail Cargo.toml)" = "$(tail Cargo.toml)" ]
 [ "$($my_tail Cargo.lock Cargo.toml)" = "$(tail Cargo.lock Cargo.toml)" ]
 
+gz=$(mktemp)
+gzip -c Cargo.lock > "$gz"
+[ "$($my_tail --decompress -n 3 "$gz")" = "$(tail -n 3 Cargo.lock)" ]
+[ "$($my_tail --decompress -c +100 < "$gz")" = "$(tail -c +100 Cargo.lock)" ]
+[ "$($my_tail --decompress Cargo.toml)" = "$(tail Cargo.toml)" ]
+# -z is NUL-terminated lines, not --decompress.
+cmp -s <($my_tail -z -n 1 "$gz") <(tail -z -n 1 "$gz")
+rm "$gz"
+
+# Follows a, a missing file and b with the tail given, while b grows, and prints what
+# it wrote.
+follow() {
+    local dir
+    dir=$(mktemp -d)
+    printf 'a\n' >"$dir/a"
+    printf 'b\n' >"$dir/b"
+    sleep 1 &
+    local writer=$!
+    (
+        cd "$dir"
+        "$@" -f -s 0.1 --pid=$writer a missing b >out 2>&1 &
+        sleep 0.4
+        printf 'more\n' >>b
+        wait
+    )
+    cat "$dir/out"
+    rm -r "$dir"
+}
+command diff <(follow "$PWD/$my_tail") <(follow tail)
+
 echo "done"
@@ -0,0 +1,81 @@
+#!/usr/bin/env bash
+set -e
+
+script_dir=$(cd "$(dirname "${BASH_SOURCE[0]}")" &>/dev/null && pwd -P)
+pj_root_dir="$script_dir/../../../"
+cd "$pj_root_dir"
+
+cargo build --release --bin tee
+my_tee="$PWD/target/release/tee"
+export LC_ALL=C
+
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+
+# Runs each tee in a directory of its own with INPUT on standard input, then compares
+# what they printed, their exit statuses and the files they wrote. The inputs are kept
+# under a read's worth where an error stops the copy part way.
+compare() {
+    local input=$1
+    shift
+    rm -rf "$tmp/mine" "$tmp/theirs"
+    mkdir "$tmp/mine" "$tmp/theirs"
+    (cd "$tmp/mine" && "$my_tee" "$@" <"$input" >../mine.out 2>&1 || echo $? >>../mine.out)
+    (cd "$tmp/theirs" && tee "$@" <"$input" >../theirs.out 2>&1 || echo $? >>../theirs.out)
+    command diff "$tmp/mine.out" "$tmp/theirs.out"
+    command diff -r "$tmp/mine" "$tmp/theirs"
+}
+
+# As compare, but standard output is a pipe closed after its first line.
+compare_pipe() {
+    local input=$1
+    shift
+    rm -rf "$tmp/mine" "$tmp/theirs"
+    mkdir "$tmp/mine" "$tmp/theirs"
+    (cd "$tmp/mine" && "$my_tee" "$@" <"$input" 2>../mine.err | head -1 >../mine.out; echo "${PIPESTATUS[0]}" >>../mine.out)
+    (cd "$tmp/theirs" && tee "$@" <"$input" 2>../theirs.err | head -1 >../theirs.out; echo "${PIPESTATUS[0]}" >>../theirs.out)
+    command diff "$tmp/mine.out" "$tmp/theirs.out"
+    command diff "$tmp/mine.err" "$tmp/theirs.err"
+    command diff -r "$tmp/mine" "$tmp/theirs"
+}
+
+seq 1 500 >"$tmp/small"
+seq 1 200000 >"$tmp/large"
+: >"$tmp/empty"
+
+compare "$tmp/small"
+compare "$tmp/small" a b
+compare "$tmp/large" a b
+compare "$tmp/empty" a
+compare "$tmp/small" 'with space' -
+compare "$tmp/small" a a
+compare "$tmp/small" no/such/dir a
+compare "$tmp/small" /dev/full a
+compare "$tmp/small" --output-error=warn /dev/full a
+compare "$tmp/small" -p /dev/full a
+compare "$tmp/small" --output-error=exit /dev/full a
+compare "$tmp/small" --output-error=exit-nop /dev/full a
+compare "$tmp/small" -i a
+
+# Appending to what is there.
+rm -rf "$tmp/mine" "$tmp/theirs"
+mkdir "$tmp/mine" "$tmp/theirs"
+echo old >"$tmp/mine/a"
+echo old >"$tmp/theirs/a"
+(cd "$tmp/mine" && "$my_tee" -a a <"$tmp/small" >/dev/null)
+(cd "$tmp/theirs" && tee -a a <"$tmp/small" >/dev/null)
+command diff -r "$tmp/mine" "$tmp/theirs"
+
+# A closed pipe ends tee, unless it is told otherwise.
+compare_pipe "$tmp/large"
+# How much reaches a file before tee is ended depends on timing, so that output goes nowhere.
+compare_pipe "$tmp/large" /dev/null
+compare_pipe "$tmp/large" -p a
+compare_pipe "$tmp/large" --output-error a
+compare_pipe "$tmp/large" --output-error=warn a
+compare_pipe "$tmp/large" --output-error=exit-nopipe a
+compare_pipe "$tmp/large" -p /dev/full
+
+# GNU tee exits 1 on usage errors where ours exits 2, so only the messages are compared.
+command diff <($my_tee --output-error=bogus </dev/null 2>&1 || true) <(tee --output-error=bogus </dev/null 2>&1 || true)
+command diff <($my_tee --output-error=e </dev/null 2>&1 || true) <(tee --output-error=e </dev/null 2>&1 || true)
@@ -0,0 +1,48 @@
+#!/usr/bin/env bash
+set -e
+
+script_dir=$(cd "$(dirname "${BASH_SOURCE[0]}")" &>/dev/null && pwd -P)
+pj_root_dir="$script_dir/../../../"
+cd "$pj_root_dir"
+
+cargo build --release --bin tr
+my_tr="$PWD/target/release/tr"
+# Both sides work on bytes.
+export LC_ALL=C
+
+
+compare() {
+    local input=$1
+    shift
+    command diff <($my_tr "$@" <"$input" 2>&1 || echo $?) <(tr "$@" <"$input" 2>&1 || echo $?)
+}
+
+compare Cargo.lock a-z A-Z
+compare Cargo.lock '[:lower:]' '[:upper:]'
+compare Cargo.lock '[:upper:][:lower:]' '[:lower:][:upper:]'
+compare Cargo.lock -d '[:digit:]'
+compare Cargo.lock -cd '[:alnum:]\n'
+compare Cargo.lock -s 'a-z '
+compare Cargo.lock -s '"' "'"
+compare Cargo.lock -ds '[:digit:]' '[:space:]'
+compare Cargo.lock a-f 'x[y*]z'
+compare Cargo.lock a-f 'x[y*2]'
+compare Cargo.lock '[a*2]' xy
+compare Cargo.lock -c 'a-z\n' _
+compare Cargo.lock -cs 'a-z\n' '[_*]'
+compare Cargo.lock '\n\"=' '\t\101\0'
+compare Cargo.lock '[=a=]b-' 'xyz'
+compare /dev/null
+compare /dev/null a
+compare /dev/null -d a b
+compare /dev/null -ds a
+compare /dev/null a b c
+compare Cargo.lock z-a x
+compare Cargo.lock a '[:foo:]'
+compare Cargo.lock '[:lower:]' '[:digit:]'
+compare Cargo.lock a-z '[:upper:]'
+compare Cargo.lock -c a '[:upper:]'
+compare Cargo.lock '[a*]' x
+compare Cargo.lock a ''
+compare Cargo.lock a '[x*y]'
+compare mimic_commands a b
@@ -0,0 +1,47 @@
+#!/usr/bin/env bash
+set -e
+
+script_dir=$(cd "$(dirname "${BASH_SOURCE[0]}")" &>/dev/null && pwd -P)
+pj_root_dir="$script_dir/../../../"
+cd "$pj_root_dir"
+
+cargo build --release --bin uniq
+my_uniq="$PWD/target/release/uniq"
+# Both sides work on bytes.
+export LC_ALL=C
+
+
+compare() {
+    command diff <($my_uniq "$@" 2>&1 || echo $?) <(uniq "$@" 2>&1 || echo $?)
+}
+
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+printf 'a\na\nb\nA\na\nc\nc\nc\n' >"$tmp/letters"
+printf 'x a\ny a\nz b\n1  b\n2\tb\n\n\nlast' >"$tmp/fields"
+head -c 20000 /dev/zero | tr '\0' x >"$tmp/long"
+printf '\n' >>"$tmp/long"
+cat "$tmp/long" "$tmp/long" >>"$tmp/long2"
+
+compare "$tmp/letters"
+compare -c "$tmp/letters"
+compare -ci "$tmp/letters"
+compare -d "$tmp/letters"
+compare -u "$tmp/letters"
+compare -du "$tmp/letters"
+compare -cd -i "$tmp/letters"
+compare -f 1 "$tmp/fields"
+compare -f 1 -c "$tmp/fields"
+compare -s 2 -c "$tmp/fields"
+compare -f 5 "$tmp/fields"
+compare -f 18446744073709551615 -c "$tmp/fields"
+compare -c "$tmp/long2"
+command diff <($my_uniq -c <"$tmp/letters") <(uniq -c <"$tmp/letters")
+compare -f x "$tmp/letters"
+compare -s -1 "$tmp/letters"
+compare a b c
+compare invalid_file
+compare mimic_commands
+
+$my_uniq "$tmp/letters" "$tmp/out"
+command diff "$tmp/out" <(uniq "$tmp/letters")
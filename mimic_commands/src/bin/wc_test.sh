@@ -10,12 +10,28 @@ my_wc="./target/release/wc"
 
 [ "$($my_wc < Cargo.lock)" = "$(wc < Cargo.lock)" ]
 [ "$($my_wc invalid_file 2>&1)" = "$(wc invalid_file 2>&1)" ]
+command diff <($my_wc invalid_file Cargo.toml mimic_commands 2>&1; echo $?) <(wc invalid_file Cargo.toml mimic_commands 2>&1; echo $?)
+command diff <($my_wc Cargo.toml < mimic_commands 2>&1; echo $?) <(wc Cargo.toml < mimic_commands 2>&1; echo $?)
+command diff <($my_wc - < mimic_commands 2>&1; echo $?) <(wc - < mimic_commands 2>&1; echo $?)
 [ "$($my_wc Cargo.lock)" = "$(wc Cargo.lock)" ]
 [ "$($my_wc Cargo.lock Cargo.toml)" = "$(wc Cargo.lock Cargo.toml)" ]
 [ "$(echo 'ああaa' | $my_wc)" = "$(echo 'ああaa' | wc)" ]
+[ "$($my_wc -l Cargo.lock)" = "$(wc -l Cargo.lock)" ]
+[ "$($my_wc -lwmcL Cargo.lock Cargo.toml)" = "$(wc -lwmcL Cargo.lock Cargo.toml)" ]
+[ "$(cat Cargo.lock | $my_wc -w)" = "$(cat Cargo.lock | wc -w)" ]
+[ "$(printf 'a\tb\rc d\n' | $my_wc)" = "$(printf 'a\tb\rc d\n' | wc)" ]
+[ "$(echo 'ああaa' | LC_ALL=C.UTF-8 $my_wc -mL)" = "$(echo 'ああaa' | LC_ALL=C.UTF-8 wc -mL)" ]
+[ "$(printf 'Cargo.lock\0Cargo.toml\0' | $my_wc --files0-from=-)" = "$(printf 'Cargo.lock\0Cargo.toml\0' | wc --files0-from=-)" ]
+[ "$($my_wc -j 4 Cargo.lock Cargo.toml Cargo.lock)" = "$(wc Cargo.lock Cargo.toml Cargo.lock)" ]
+
+gz=$(mktemp)
+gzip -c Cargo.lock > "$gz"
+[ "$($my_wc --decompress < "$gz")" = "$(zcat "$gz" | wc)" ]
+[ "$($my_wc --decompress -l "$gz" Cargo.toml)" = "$(zcat "$gz" | wc -l - Cargo.toml | sed "s| -\$| $gz|")" ]
+rm "$gz"
 
 #command diff <($my_wc < Cargo.lock) <(wc < Cargo.lock)
-#command diff <($my_wc invalid_file 2>&1) <(wc invalid_file 2>&1)
+command diff <($my_wc invalid_file 2>&1) <(wc invalid_file 2>&1)
 #command diff <($my_wc Cargo.lock) <(wc Cargo.lock)
 #command diff <($my_wc Cargo.lock Cargo.toml) <(wc Cargo.lock Cargo.toml)
 
@@ -0,0 +1,98 @@
+#!/usr/bin/env bash
+set -e
+
+script_dir=$(cd "$(dirname "${BASH_SOURCE[0]}")" &>/dev/null && pwd -P)
+pj_root_dir="$script_dir/../../../"
+cd "$pj_root_dir"
+
+cargo build --release --bin xargs
+my_xargs="$PWD/target/release/xargs"
+export LC_ALL=C
+
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+
+# Runs both xargs in $tmp with the file as standard input, and compares what they
+# printed and their exit statuses.
+compare() {
+    local input=$1
+    shift
+    (cd "$tmp" && "$my_xargs" "$@" <"$input" >mine.out 2>&1 || echo $? >>mine.out)
+    (cd "$tmp" && command xargs "$@" <"$input" >theirs.out 2>&1 || echo $? >>theirs.out)
+    diff "$tmp/mine.out" "$tmp/theirs.out"
+}
+
+# The same, with what went to standard error compared apart. GNU xargs reads on while a
+# command runs, so whether a message about what it read next comes before or after the
+# output of the command is up to chance.
+compare_apart() {
+    local input=$1
+    shift
+    (cd "$tmp" && "$my_xargs" "$@" <"$input" >mine.out 2>mine.err || echo $? >>mine.out)
+    (cd "$tmp" && command xargs "$@" <"$input" >theirs.out 2>theirs.err || echo $? >>theirs.out)
+    diff "$tmp/mine.out" "$tmp/theirs.out"
+    diff "$tmp/mine.err" "$tmp/theirs.err"
+}
+
+cd "$tmp"
+printf 'a b\n  c\t"d e" f\\ g\n\nh'\''i j'\''\n' >words
+printf 'one two \nthree\nfour five\n\nsix\n' >lines
+printf 'a b\0c\n\0\0d' >nulls
+printf 'a '\''b\nc\n' >open_single
+printf 'a "b\n' >open_double
+seq 1 30000 >many
+echo 0.9 0.6 0.2 >sleeps
+: >empty
+cd "$pj_root_dir"
+
+for input in words lines nulls empty; do
+    compare $input
+    if [ $input = nulls ]; then
+        compare_apart $input -n 1
+    else
+        compare $input -n 1
+    fi
+    compare $input -n 3 echo start
+    compare $input -L 1
+    compare $input -L 2 echo -
+    compare $input -0 -n 2 echo
+    compare $input -I {} echo '[{}]' '{}{}'
+    compare $input -r echo nothing
+    compare $input -t -n 4 echo
+done
+compare many
+compare many wc -l
+compare many -L 5000 wc -l
+compare many -n 1000 sh -c 'echo $#'
+compare many -0 echo
+compare lines --max-args=2 --no-run-if-empty
+compare lines -I X echo X has no replacement
+compare lines -I _ echo
+compare lines -n 2 -L 1
+compare lines -L 1 -I {} echo {}
+compare lines -I {} -n 1 echo {}
+compare_apart open_single
+compare_apart open_double
+compare open_single -0
+compare nulls -0 od -c
+
+# How the commands fared.
+compare words false
+compare words -n 1 sh -c 'test "$0" != c'
+compare words -n 1 sh -c 'exit 255'
+compare words sh -c 'kill $$'
+compare words nope
+compare words /
+# In parallel, the shortest sleep ends first.
+compare sleeps -P 3 -n 1 sh -c 'sleep $0; echo $0'
+compare sleeps -P 2 -n 1 sh -c 'sleep $0; echo $0'
+compare many -P 0 -n 5000 sh -c 'echo $#'
+
+# Mistakes in the options.
+compare words -n 0
+compare words -n x
+compare words -L 0
+compare words -P -1
+compare words -P x
+compare words -I
+compare words --bogus
@@ -0,0 +1,85 @@
+#!/usr/bin/env bash
+set -e
+
+script_dir=$(cd "$(dirname "${BASH_SOURCE[0]}")" &>/dev/null && pwd -P)
+pj_root_dir="$script_dir/../../../"
+cd "$pj_root_dir"
+
+cargo build --release --bin xxd
+my_xxd="$PWD/target/release/xxd"
+export LC_ALL=C
+
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+
+compare() {
+    command diff <($my_xxd "$@" 2>&1 || echo $?) <(xxd "$@" 2>&1 || echo $?)
+}
+
+# Reverts the dump in `$1` with both, comparing the bytes written.
+compare_revert() {
+    local dump=$1
+    shift
+    command diff <($my_xxd -r "$@" <"$dump" 2>&1 | od -c) <(xxd -r "$@" <"$dump" 2>&1 | od -c)
+}
+
+printf 'hello world, this is xxd\n\0\1\377' >"$tmp/bin"
+cp "$tmp/bin" "$tmp/9x-y.z"
+: >"$tmp/empty"
+
+compare "$tmp/bin"
+compare -g1 -c10 "$tmp/bin"
+compare -g 3 -c 7 "$tmp/bin"
+compare -g 0 -c 5 "$tmp/bin"
+compare -g 16 "$tmp/bin"
+compare -c 40 "$tmp/bin"
+compare -c 0x8 "$tmp/bin"
+compare -c 0 "$tmp/bin"
+compare -p "$tmp/bin"
+compare -p -c 8 "$tmp/bin"
+compare -i "$tmp/bin"
+compare -i -c 5 "$tmp/bin"
+compare -i "$tmp/9x-y.z"
+compare -i "$tmp/empty"
+compare -s 5 -l 10 "$tmp/bin"
+compare -s -4 "$tmp/bin"
+compare -s +2 "$tmp/bin"
+compare -s 100 "$tmp/bin"
+compare -l 4 -p "$tmp/bin"
+compare -s 10 -i "$tmp/bin"
+compare Cargo.lock
+command diff <($my_xxd -i <"$tmp/bin") <(xxd -i <"$tmp/bin")
+command diff <(cat "$tmp/bin" | $my_xxd -s 1) <(cat "$tmp/bin" | xxd -s 1)
+command diff <(cat "$tmp/bin" | $my_xxd -s -1 2>&1 || echo $?) <(cat "$tmp/bin" | xxd -s -1 2>&1 || echo $?)
+command diff <(cat "$tmp/bin" | $my_xxd -s 50 2>&1 || echo $?) <(cat "$tmp/bin" | xxd -s 50 2>&1 || echo $?)
+$my_xxd "$tmp/bin" "$tmp/out"
+command diff "$tmp/out" <(xxd "$tmp/bin")
+compare "$tmp/nope"
+compare -c 300 "$tmp/bin"
+
+xxd Cargo.lock >"$tmp/dump"
+compare_revert "$tmp/dump"
+xxd -p Cargo.lock >"$tmp/dump"
+compare_revert "$tmp/dump" -p
+xxd -g1 -c 10 "$tmp/bin" >"$tmp/dump"
+compare_revert "$tmp/dump" -c 10
+printf '00000010: 4142  AB\n' >"$tmp/dump"
+compare_revert "$tmp/dump"
+printf '10: 41 42\n20:43\n0: 4142 zz 43\n' >"$tmp/dump"
+compare_revert "$tmp/dump"
+printf '0: 41  4243\n5 4142\n0: 41 4 2 43\n' >"$tmp/dump"
+compare_revert "$tmp/dump"
+printf '0: 414243444546\n' >"$tmp/dump"
+compare_revert "$tmp/dump" -c 2
+compare_revert "$tmp/dump" -s 2
+printf '41 4 2\n4' >"$tmp/dump"
+compare_revert "$tmp/dump" -p
+
+# Patching a file in place, going back and forth.
+printf '0:41\n4:42\n2:43\n' >"$tmp/dump"
+printf 'XXXXXXXX' >"$tmp/mine"
+printf 'XXXXXXXX' >"$tmp/theirs"
+$my_xxd -r "$tmp/dump" "$tmp/mine"
+xxd -r "$tmp/dump" "$tmp/theirs"
+cmp "$tmp/mine" "$tmp/theirs"
+command diff <($my_xxd -r "$tmp/dump" 2>&1 | cat; echo "${PIPESTATUS[0]}") <(xxd -r "$tmp/dump" 2>&1 | cat; echo "${PIPESTATUS[0]}")
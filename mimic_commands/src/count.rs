@@ -0,0 +1,427 @@
+//! The counters behind `wc`.
+//!
+//! A `Count` can describe a whole input or just one chunk of it. Chunks of the same input
+//! counted independently (e.g. on different threads) can be joined back with `Count::merge`,
+//! which fixes up the words and lines that straddle the chunk boundary.
+use crate::pool;
+use crate::unicode::{display_width, Utf8Decoder};
+use std::fs::File;
+use std::io::{self, Read};
+use std::ops::AddAssign;
+use std::os::unix::fs::FileExt;
+
+/// Newline, word, character and byte counts, plus the widest line.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Count {
+    pub bytes: usize,
+    pub chars: usize,
+    pub lines: usize,
+    pub words: usize,
+    pub max_line_length: usize,
+    edges: Edges,
+}
+
+/// What `merge` needs to know about the two ends of a chunk.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Edges {
+    /// Whether the first character that affects word counting is printable (`Some(true)`)
+    /// or whitespace (`Some(false)`). Control characters and invalid bytes affect neither.
+    first_in_word: Option<bool>,
+    /// Same for the last such character.
+    last_in_word: Option<bool>,
+    /// Whether the chunk contains a line break (`\n`, `\r` or `\f`) as far as `-L` is concerned.
+    has_break: bool,
+    /// The text before the first line break, which continues the previous chunk's last line.
+    head: Segment,
+    /// The column at the end of the chunk, counted from its last line break.
+    tail: usize,
+}
+
+/// The width of a piece of a line that does not start at column 0.
+/// Tabs make the width depend on the starting column, but only up to the first tab:
+/// after it the column is a multiple of 8 and everything else follows from that.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Segment {
+    /// Width of the text before the first tab.
+    before_tab: usize,
+    /// Width from the first tab stop to the end, if there is a tab.
+    after_tab: Option<usize>,
+}
+
+impl Segment {
+    /// The column reached after this segment when it starts at `column`.
+    fn end_column(&self, column: usize) -> usize {
+        match self.after_tab {
+            None => column + self.before_tab,
+            Some(after_tab) => next_tab_stop(column + self.before_tab) + after_tab,
+        }
+    }
+
+    fn push_tab(&mut self) {
+        self.after_tab = Some(match self.after_tab {
+            None => 0,
+            Some(after_tab) => next_tab_stop(after_tab),
+        });
+    }
+
+    fn push_width(&mut self, width: usize) {
+        match &mut self.after_tab {
+            None => self.before_tab += width,
+            Some(after_tab) => *after_tab += width,
+        }
+    }
+
+    /// This segment followed by `next`.
+    fn then(self, next: Segment) -> Segment {
+        match (self.after_tab, next.after_tab) {
+            (_, None) => {
+                let mut joined = self;
+                joined.push_width(next.before_tab);
+                joined
+            }
+            (None, Some(_)) => Segment {
+                before_tab: self.before_tab + next.before_tab,
+                after_tab: next.after_tab,
+            },
+            (Some(after_tab), Some(next_after_tab)) => Segment {
+                before_tab: self.before_tab,
+                after_tab: Some(next_tab_stop(after_tab + next.before_tab) + next_after_tab),
+            },
+        }
+    }
+}
+
+fn next_tab_stop(column: usize) -> usize {
+    column + 8 - column % 8
+}
+
+impl Count {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Joins the counts of two adjacent chunks of the same input, `self` coming first.
+    pub fn merge(self, next: Count) -> Count {
+        let straddling_word =
+            self.edges.last_in_word == Some(true) && next.edges.first_in_word == Some(true);
+        let end_column = if self.edges.has_break {
+            self.edges.tail
+        } else {
+            self.edges.head.end_column(0)
+        };
+        let joined_line = next.edges.head.end_column(end_column);
+
+        let (head, tail) = match (self.edges.has_break, next.edges.has_break) {
+            (true, true) => (self.edges.head, next.edges.tail),
+            (true, false) => (self.edges.head, joined_line),
+            (false, true) => (self.edges.head.then(next.edges.head), next.edges.tail),
+            (false, false) => (self.edges.head.then(next.edges.head), 0),
+        };
+
+        Count {
+            bytes: self.bytes + next.bytes,
+            chars: self.chars + next.chars,
+            lines: self.lines + next.lines,
+            words: self.words + next.words - straddling_word as usize,
+            max_line_length: self
+                .max_line_length
+                .max(next.max_line_length)
+                .max(joined_line),
+            edges: Edges {
+                first_in_word: self.edges.first_in_word.or(next.edges.first_in_word),
+                last_in_word: next.edges.last_in_word.or(self.edges.last_in_word),
+                has_break: self.edges.has_break || next.edges.has_break,
+                head,
+                tail,
+            },
+        }
+    }
+}
+
+/// Adds up the counts of independent inputs, as for the `total` line.
+impl AddAssign for Count {
+    fn add_assign(&mut self, rhs: Self) {
+        self.bytes += rhs.bytes;
+        self.chars += rhs.chars;
+        self.lines += rhs.lines;
+        self.words += rhs.words;
+        self.max_line_length = self.max_line_length.max(rhs.max_line_length);
+    }
+}
+
+/// Counts input fed to it in pieces of any size.
+pub struct Counter {
+    count: Count,
+    decode: bool,
+    decoder: Utf8Decoder,
+    column: usize,
+}
+
+impl Counter {
+    /// With `decode` false only bytes and lines are counted, which is much faster
+    /// than decoding UTF-8 for words, characters and line widths.
+    pub fn new(decode: bool) -> Self {
+        Self {
+            count: Count::new(),
+            decode,
+            decoder: Utf8Decoder::new(),
+            column: 0,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.count.bytes += data.len();
+        if !self.decode {
+            self.count.lines += data.iter().filter(|b| **b == b'\n').count();
+            return;
+        }
+
+        let Counter {
+            count,
+            decoder,
+            column,
+            ..
+        } = self;
+        for b in data {
+            decoder.push(*b, |c| count_char(c, count, column));
+        }
+    }
+
+    pub fn finish(mut self) -> Count {
+        let Counter {
+            count,
+            decoder,
+            column,
+            ..
+        } = &mut self;
+        decoder.finish(|c| count_char(c, count, column));
+        self.count
+    }
+}
+
+/// Counts one decoded character; `None` is an invalid byte, which is not a character at all.
+/// As in GNU wc, whitespace ends a word, printable characters start one,
+/// and anything else (control characters, invalid bytes) does neither.
+fn count_char(c: Option<char>, count: &mut Count, column: &mut usize) {
+    let c = match c {
+        Some(c) => c,
+        None => return,
+    };
+    count.chars += 1;
+
+    let edges = &mut count.edges;
+    match c {
+        '\n' | '\r' | '\x0c' => {
+            if c == '\n' {
+                count.lines += 1;
+            }
+            count.max_line_length = count.max_line_length.max(*column);
+            *column = 0;
+            edges.has_break = true;
+        }
+        '\t' => {
+            *column = next_tab_stop(*column);
+            if !edges.has_break {
+                edges.head.push_tab();
+            }
+        }
+        _ => {
+            let width = display_width(c);
+            *column += width;
+            if !edges.has_break {
+                edges.head.push_width(width);
+            }
+        }
+    }
+    edges.tail = *column;
+    count.max_line_length = count.max_line_length.max(*column);
+
+    let in_word = if c.is_whitespace() {
+        false
+    } else if !c.is_control() {
+        true
+    } else {
+        return;
+    };
+    if in_word && edges.last_in_word != Some(true) {
+        count.words += 1;
+    }
+    edges.first_in_word.get_or_insert(in_word);
+    edges.last_in_word = Some(in_word);
+}
+
+/// Counts everything `reader` yields.
+pub fn count<R: Read>(mut reader: R, decode: bool) -> io::Result<Count> {
+    let mut buffer = [0; 1024 * 4];
+    let mut counter = Counter::new(decode);
+    loop {
+        let size = reader.read(&mut buffer[..])?;
+        if size == 0 {
+            break;
+        }
+        counter.update(&buffer[..size]);
+    }
+    Ok(counter.finish())
+}
+
+/// Counts a regular file by splitting it into `jobs` chunks that are counted on separate
+/// threads and merged back in order. Chunks are split between UTF-8 characters.
+pub fn count_file_parallel(file: &File, jobs: usize, decode: bool) -> io::Result<Count> {
+    let jobs = jobs.max(1) as u64;
+    let size = file.metadata()?.len();
+    let mut bounds = vec![0];
+    for i in 1..jobs {
+        let at = char_boundary(file, size * i / jobs, size)?;
+        if at > *bounds.last().unwrap_or(&0) && at < size {
+            bounds.push(at);
+        }
+    }
+    bounds.push(size);
+
+    let ranges: Vec<(u64, u64)> = bounds.windows(2).map(|w| (w[0], w[1])).collect();
+    let mut counts = Vec::new();
+    pool::map_ordered(
+        &ranges,
+        ranges.len(),
+        |(start, end)| count_range(file, *start, *end, decode),
+        |_, count| counts.push(count),
+    );
+    counts
+        .into_iter()
+        .try_fold(Count::new(), |total, count| Ok(total.merge(count?)))
+}
+
+/// Moves `at` forward past UTF-8 continuation bytes.
+fn char_boundary(file: &File, at: u64, size: u64) -> io::Result<u64> {
+    let mut bytes = [0; 4];
+    let read = file.read_at(&mut bytes, at)?;
+    let skip = bytes[..read]
+        .iter()
+        .take_while(|b| **b & 0xC0 == 0x80)
+        .count();
+    Ok((at + skip as u64).min(size))
+}
+
+fn count_range(file: &File, start: u64, end: u64, decode: bool) -> io::Result<Count> {
+    let mut buffer = vec![0; 64 * 1024];
+    let mut counter = Counter::new(decode);
+    let mut offset = start;
+    while offset < end {
+        let want = buffer.len().min((end - offset) as usize);
+        let size = file.read_at(&mut buffer[..want], offset)?;
+        if size == 0 {
+            break;
+        }
+        counter.update(&buffer[..size]);
+        offset += size as u64;
+    }
+    Ok(counter.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count_bytes(input: &[u8]) -> Count {
+        count(input, true).unwrap()
+    }
+
+    fn assert_counts(expected: &Count, actual: &Count) {
+        assert_eq!(
+            (
+                expected.bytes,
+                expected.chars,
+                expected.lines,
+                expected.words,
+                expected.max_line_length
+            ),
+            (
+                actual.bytes,
+                actual.chars,
+                actual.lines,
+                actual.words,
+                actual.max_line_length
+            )
+        );
+    }
+
+    #[test]
+    fn test_count() {
+        let count = count_bytes(b"a\tb\rc\xe3\x81\x82\xff d\n\xe3\x81\x82\xe3\x81\x82 \xc2\xa0x\n");
+        assert_eq!(
+            (
+                count.bytes,
+                count.chars,
+                count.lines,
+                count.words,
+                count.max_line_length
+            ),
+            (23, 15, 2, 6, 9)
+        );
+
+        let count = count_bytes(b"one \x01 two\x01three \xff");
+        assert_eq!(
+            (count.chars, count.words, count.max_line_length),
+            (16, 2, 14)
+        );
+    }
+
+    #[test]
+    fn test_merge_every_split() {
+        let inputs: &[&[u8]] = &[
+            b"hello world\nfoo  bar\n",
+            b"ab\tcd\te\tf\n\tx\ty\n123\t4",
+            b"word\x01word \x01 w\n",
+            b"\xe3\x81\x82\xe3\x81\x82 x\ty\n\n",
+            b"  leading and trailing  ",
+            b"\r\n\x0c",
+        ];
+        for input in inputs {
+            let whole = count_bytes(input);
+            // Chunks are only ever split between characters.
+            let boundaries: Vec<usize> = (0..=input.len())
+                .filter(|k| *k == input.len() || input[*k] & 0xC0 != 0x80)
+                .collect();
+            for &i in &boundaries {
+                for &j in boundaries.iter().filter(|j| **j >= i) {
+                    let merged = count_bytes(&input[..i])
+                        .merge(count_bytes(&input[i..j]))
+                        .merge(count_bytes(&input[j..]));
+                    assert_counts(&whole, &merged);
+                    assert_eq!(
+                        whole.edges.head, merged.edges.head,
+                        "{:?} {} {}",
+                        input, i, j
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_count_file_parallel() {
+        let path = std::env::temp_dir().join(format!("count_test_{}", std::process::id()));
+        let text = "ab\tcd ああ\n".repeat(1000) + "x\ty z";
+        std::fs::write(&path, &text).unwrap();
+        let file = File::open(&path).unwrap();
+        let whole = count_bytes(text.as_bytes());
+        for jobs in [1, 2, 7, 64] {
+            assert_counts(&whole, &count_file_parallel(&file, jobs, true).unwrap());
+        }
+        assert_eq!(
+            count_file_parallel(&file, 3, false).unwrap().lines,
+            whole.lines
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_add_assign() {
+        let mut total = count_bytes(b"a b\n");
+        total += count_bytes(b"cc\n");
+        assert_eq!(
+            (total.bytes, total.lines, total.words, total.max_line_length),
+            (7, 2, 3, 3)
+        );
+    }
+}
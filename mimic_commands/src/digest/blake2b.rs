@@ -0,0 +1,162 @@
+//! BLAKE2b without a key, as in RFC 7693.
+use super::Digest;
+
+const IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+/// The order the message words are used in, by round.
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+#[derive(Debug, Clone)]
+pub struct Blake2b {
+    state: [u64; 8],
+    /// The last block is compressed differently, so a full buffer waits for more input
+    /// before it is compressed.
+    buffer: [u8; 128],
+    len: usize,
+    total: u128,
+    size: usize,
+}
+
+impl Blake2b {
+    /// The longest digest, in bytes.
+    pub const MAX_SIZE: usize = 64;
+
+    /// A digest of `size` bytes, from 1 to [`MAX_SIZE`].
+    pub fn new(size: usize) -> Self {
+        assert!(
+            (1..=Self::MAX_SIZE).contains(&size),
+            "invalid BLAKE2b size {}",
+            size
+        );
+        let mut state = IV;
+        state[0] ^= 0x01010000 ^ size as u64;
+        Blake2b {
+            state,
+            buffer: [0; 128],
+            len: 0,
+            total: 0,
+            size,
+        }
+    }
+
+    fn compress(&mut self, last: bool) {
+        let mut words = [0u64; 16];
+        for (word, bytes) in words.iter_mut().zip(self.buffer.chunks_exact(8)) {
+            let mut le = [0; 8];
+            le.copy_from_slice(bytes);
+            *word = u64::from_le_bytes(le);
+        }
+        let mut v = [0u64; 16];
+        v[..8].copy_from_slice(&self.state);
+        v[8..].copy_from_slice(&IV);
+        v[12] ^= self.total as u64;
+        v[13] ^= (self.total >> 64) as u64;
+        if last {
+            v[14] = !v[14];
+        }
+        for round in 0..12 {
+            let s = &SIGMA[round % 10];
+            mix(&mut v, 0, 4, 8, 12, words[s[0]], words[s[1]]);
+            mix(&mut v, 1, 5, 9, 13, words[s[2]], words[s[3]]);
+            mix(&mut v, 2, 6, 10, 14, words[s[4]], words[s[5]]);
+            mix(&mut v, 3, 7, 11, 15, words[s[6]], words[s[7]]);
+            mix(&mut v, 0, 5, 10, 15, words[s[8]], words[s[9]]);
+            mix(&mut v, 1, 6, 11, 12, words[s[10]], words[s[11]]);
+            mix(&mut v, 2, 7, 8, 13, words[s[12]], words[s[13]]);
+            mix(&mut v, 3, 4, 9, 14, words[s[14]], words[s[15]]);
+        }
+        for (i, word) in self.state.iter_mut().enumerate() {
+            *word ^= v[i] ^ v[i + 8];
+        }
+    }
+}
+
+/// The G function, mixing two message words into four of the working words.
+fn mix(v: &mut [u64; 16], a: usize, b: usize, c: usize, d: usize, x: u64, y: u64) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+impl Digest for Blake2b {
+    fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            if self.len == self.buffer.len() {
+                self.total += self.len as u128;
+                self.compress(false);
+                self.len = 0;
+            }
+            let size = data.len().min(self.buffer.len() - self.len);
+            self.buffer[self.len..self.len + size].copy_from_slice(&data[..size]);
+            self.len += size;
+            data = &data[size..];
+        }
+    }
+
+    fn finish(&mut self) -> Vec<u8> {
+        self.total += self.len as u128;
+        self.buffer[self.len..].fill(0);
+        self.compress(true);
+        let mut digest: Vec<u8> = self
+            .state
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect();
+        digest.truncate(self.size);
+        digest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::digest::tests::digest_hex;
+
+    fn blake2b(size: usize, message: &[u8]) -> String {
+        digest_hex(|| Box::new(Blake2b::new(size)), message)
+    }
+
+    #[test]
+    fn test_rfc_7693() {
+        assert_eq!(
+            blake2b(64, b"abc"),
+            "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+             7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+        );
+        assert_eq!(
+            blake2b(64, b""),
+            "786a02f742015903c6c6fd852552d272912f4740e15847618a86e217f71f5419\
+             d25e1031afee585313896444934eb04b903a685b1448b755d56f701afe9be2ce"
+        );
+        // The digest size is part of the parameters, so shorter digests are not prefixes.
+        assert_eq!(
+            blake2b(32, b"abc"),
+            "bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319"
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! Message digests for the `*sum` applets, so the crate stays dependency-free.
+//!
+//! MD5, SHA-1 and SHA-256 share the Merkle–Damgård framing in [`Blocks`]; BLAKE2b has
+//! its own.
+mod blake2b;
+mod md5;
+mod sha1;
+mod sha256;
+
+pub use blake2b::Blake2b;
+pub use md5::Md5;
+pub use sha1::Sha1;
+pub use sha256::Sha256;
+
+/// A hash function fed its message in pieces.
+pub trait Digest {
+    fn update(&mut self, data: &[u8]);
+
+    /// The digest of everything passed to `update`. The digest is used up.
+    fn finish(&mut self) -> Vec<u8>;
+}
+
+/// The digest in lowercase hex.
+pub fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Splits a message into 64-byte blocks, and pads the last with a 1 bit, zeros, and the
+/// message length in bits.
+#[derive(Debug, Clone)]
+struct Blocks {
+    buffer: [u8; 64],
+    len: usize,
+    total: u64,
+}
+
+impl Blocks {
+    fn new() -> Self {
+        Blocks {
+            buffer: [0; 64],
+            len: 0,
+            total: 0,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8], mut compress: impl FnMut(&[u8; 64])) {
+        self.total = self.total.wrapping_add(data.len() as u64);
+        while !data.is_empty() {
+            let size = data.len().min(64 - self.len);
+            self.buffer[self.len..self.len + size].copy_from_slice(&data[..size]);
+            self.len += size;
+            data = &data[size..];
+            if self.len == 64 {
+                compress(&self.buffer);
+                self.len = 0;
+            }
+        }
+    }
+
+    /// Pads the message, with its length big-endian as SHA does or little-endian as MD5 does.
+    fn finish(&mut self, big_endian: bool, mut compress: impl FnMut(&[u8; 64])) {
+        let bits = self.total.wrapping_mul(8);
+        let length = if big_endian {
+            bits.to_be_bytes()
+        } else {
+            bits.to_le_bytes()
+        };
+        let total = self.total;
+        let padding = (64 + 56 - (self.len + 1) % 64) % 64;
+        let mut tail = vec![0x80];
+        tail.resize(1 + padding, 0);
+        tail.extend_from_slice(&length);
+        self.update(&tail, &mut compress);
+        self.total = total;
+        debug_assert_eq!(self.len, 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The hex digest of `message`, checking that feeding it in pieces changes nothing.
+    pub(super) fn digest_hex(new: impl Fn() -> Box<dyn Digest>, message: &[u8]) -> String {
+        let mut digest = new();
+        digest.update(message);
+        let whole = hex(&digest.finish());
+        for piece in [1, 3, 63, 64, 65, 127, 128, 129] {
+            let mut digest = new();
+            for chunk in message.chunks(piece) {
+                digest.update(chunk);
+            }
+            assert_eq!(hex(&digest.finish()), whole, "pieces of {}", piece);
+        }
+        whole
+    }
+
+    #[test]
+    fn test_padding() {
+        // Messages whose padding just fits in, or just spills over, the last block.
+        for len in [55, 56, 63, 64, 119, 120] {
+            let mut blocks = Blocks::new();
+            let mut count = 0;
+            blocks.update(&vec![0; len], |_| count += 1);
+            blocks.finish(true, |_| count += 1);
+            assert_eq!(count, (len + 8) / 64 + 1, "length {}", len);
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! Formatting binary data as columns of numbers and characters, for `od` and `xxd`.
+use std::io::{self, Read};
+
+/// How offsets are written.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Radix {
+    Octal,
+    Decimal,
+    Hex,
+    /// Offsets are left out.
+    None,
+}
+
+impl Radix {
+    /// `offset` zero-padded to `width` digits, or nothing for `Radix::None`.
+    pub fn format(self, offset: u64, width: usize) -> String {
+        match self {
+            Radix::Octal => format!("{:0width$o}", offset, width = width),
+            Radix::Decimal => format!("{:0width$}", offset, width = width),
+            Radix::Hex => format!("{:0width$x}", offset, width = width),
+            Radix::None => String::new(),
+        }
+    }
+}
+
+/// How the bytes of a unit are read as a value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Hex,
+    Octal,
+    Signed,
+    Unsigned,
+    /// A single byte as a character, a C escape or an octal number.
+    Char,
+}
+
+/// A group of `size` bytes shown as one value, little end first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Unit {
+    pub kind: Kind,
+    pub size: usize,
+}
+
+impl Unit {
+    /// One byte in two hex digits.
+    pub const HEX_BYTE: Unit = Unit {
+        kind: Kind::Hex,
+        size: 1,
+    };
+
+    /// How many columns the widest value takes.
+    pub fn width(self) -> usize {
+        let bits = self.size as u32 * 8;
+        let digits = |mut n: u128, radix: u128| {
+            let mut count = 1;
+            while n >= radix {
+                n /= radix;
+                count += 1;
+            }
+            count
+        };
+        let max = (1u128 << bits) - 1;
+        match self.kind {
+            Kind::Hex => self.size * 2,
+            Kind::Octal => digits(max, 8),
+            Kind::Unsigned => digits(max, 10),
+            // The minus sign of the most negative value counts too.
+            Kind::Signed => digits(1u128 << (bits - 1), 10) + 1,
+            Kind::Char => 3,
+        }
+    }
+
+    /// Appends the unit in `bytes` right-aligned in `width` columns. Missing bytes at the
+    /// end of the input count as zeros.
+    pub fn write(self, bytes: &[u8], width: usize, out: &mut String) {
+        let mut value = 0u64;
+        for i in (0..self.size).rev() {
+            value = value << 8 | u64::from(bytes.get(i).copied().unwrap_or(0));
+        }
+        let size = self.width();
+        let text = match self.kind {
+            Kind::Hex => format!("{:0size$x}", value, size = size),
+            Kind::Octal => format!("{:0size$o}", value, size = size),
+            Kind::Unsigned => value.to_string(),
+            Kind::Signed => {
+                let shift = 64 - self.size * 8;
+                ((value << shift) as i64 >> shift).to_string()
+            }
+            Kind::Char => char_name(value as u8),
+        };
+        out.extend(std::iter::repeat_n(' ', width.saturating_sub(text.len())));
+        out.push_str(&text);
+    }
+}
+
+/// How `od -t c` shows a byte.
+fn char_name(b: u8) -> String {
+    match b {
+        b'\0' => "\\0".to_string(),
+        0x07 => "\\a".to_string(),
+        0x08 => "\\b".to_string(),
+        0x0c => "\\f".to_string(),
+        b'\n' => "\\n".to_string(),
+        b'\r' => "\\r".to_string(),
+        b'\t' => "\\t".to_string(),
+        0x0b => "\\v".to_string(),
+        b' '..=b'~' => (b as char).to_string(),
+        b => format!("{:03o}", b),
+    }
+}
+
+/// The byte as printed in a text column, with `.` for anything but printable ASCII.
+pub fn printable(b: u8) -> char {
+    match b {
+        b' '..=b'~' => b as char,
+        _ => '.',
+    }
+}
+
+/// Reads until `buffer` is full or the input ends, returning how much was read.
+pub fn read_full<R: Read>(reader: &mut R, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(size) => filled += size,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Reads and discards up to `count` bytes, returning how many there were.
+pub fn skip<R: Read>(reader: &mut R, count: u64) -> io::Result<u64> {
+    io::copy(&mut reader.take(count), &mut io::sink())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(kind: Kind, size: usize, bytes: &[u8]) -> String {
+        let unit = Unit { kind, size };
+        let mut out = String::new();
+        unit.write(bytes, unit.width() + 1, &mut out);
+        out
+    }
+
+    #[test]
+    fn test_widths() {
+        let width = |kind, size| Unit { kind, size }.width();
+        assert_eq!(width(Kind::Hex, 4), 8);
+        assert_eq!(width(Kind::Octal, 1), 3);
+        assert_eq!(width(Kind::Octal, 2), 6);
+        assert_eq!(width(Kind::Octal, 8), 22);
+        assert_eq!(width(Kind::Unsigned, 2), 5);
+        assert_eq!(width(Kind::Signed, 1), 4);
+        assert_eq!(width(Kind::Signed, 4), 11);
+        assert_eq!(width(Kind::Signed, 8), 20);
+    }
+
+    #[test]
+    fn test_write() {
+        assert_eq!(format(Kind::Hex, 2, b"\x01\xab"), " ab01");
+        assert_eq!(format(Kind::Octal, 2, b"he"), " 062550");
+        assert_eq!(format(Kind::Signed, 1, b"\xff"), "   -1");
+        assert_eq!(format(Kind::Signed, 4, b"\x0a\x00\x01\xff"), "   -16711670");
+        assert_eq!(format(Kind::Unsigned, 4, b"\xff"), "        255");
+        assert_eq!(format(Kind::Char, 1, b"\n"), "  \\n");
+        assert_eq!(format(Kind::Char, 1, b"\xff"), " 377");
+        assert_eq!(format(Kind::Char, 1, b"a"), "   a");
+        assert_eq!(Radix::Octal.format(28, 7), "0000034");
+        assert_eq!(Radix::Hex.format(28, 8), "0000001c");
+    }
+}
@@ -0,0 +1,266 @@
+//! I/O error reporting shared by the commands, worded like GNU coreutils.
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{self, Seek, SeekFrom, Write};
+use std::process::exit;
+
+/// An I/O error together with what the command was doing when it happened.
+#[derive(Debug)]
+pub enum Error {
+    /// A file could not be opened.
+    Open(String, io::Error),
+    /// Reading an input failed.
+    Read(String, io::Error),
+    /// Writing to standard output failed.
+    Write(io::Error),
+}
+
+impl Error {
+    /// Sorts out an error from a loop that reads `name` and writes to an [`Output`].
+    pub fn copying(name: &str, e: io::Error) -> Self {
+        if is_write_failure(&e) {
+            Error::writing(e)
+        } else {
+            Error::Read(name.to_string(), e)
+        }
+    }
+
+    /// Wraps an error from writing, unmarking it if it came from an [`Output`].
+    pub fn writing(e: io::Error) -> Self {
+        if !is_write_failure(&e) {
+            return Error::Write(e);
+        }
+        let failure = e
+            .into_inner()
+            .and_then(|inner| inner.downcast::<WriteFailure>().ok())
+            .expect("checked to be a write failure");
+        Error::Write(failure.0)
+    }
+
+    pub fn is_broken_pipe(&self) -> bool {
+        matches!(self, Error::Write(e) if e.kind() == io::ErrorKind::BrokenPipe)
+    }
+
+    /// The message for this error, without the command name.
+    pub fn message(&self, style: Style) -> String {
+        match (self, style) {
+            (Error::Open(name, e), Style::Bare) | (Error::Read(name, e), Style::Bare) => {
+                format!("{}: {}", quote_if_needed(name), describe(e))
+            }
+            (Error::Open(name, e), Style::Verbose) => {
+                format!("cannot open '{}' for reading: {}", name, describe(e))
+            }
+            (Error::Read(name, e), Style::Verbose) => {
+                format!("error reading '{}': {}", name, describe(e))
+            }
+            (Error::Write(e), _) => format!("write error: {}", describe(e)),
+        }
+    }
+}
+
+/// How a command words errors about its inputs; GNU tools are not consistent about it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Style {
+    /// `cat: x: No such file or directory`
+    Bare,
+    /// `head: cannot open 'x' for reading: No such file or directory`
+    Verbose,
+}
+
+/// The system's description of `e`, without the "(os error N)" that `Display` appends.
+pub fn describe(e: &io::Error) -> String {
+    let message = e.to_string();
+    match e.raw_os_error() {
+        Some(code) => message
+            .strip_suffix(&format!(" (os error {})", code))
+            .unwrap_or(&message)
+            .to_string(),
+        None => message,
+    }
+}
+
+/// `name` as GNU tools show it in messages: quoted if it has spaces or quotes in it.
+pub fn quote_if_needed(name: &str) -> String {
+    if name
+        .chars()
+        .any(|c| c.is_whitespace() || c == '\'' || c == '"')
+    {
+        format!("'{}'", name.replace('\'', "'\\''"))
+    } else {
+        name.to_string()
+    }
+}
+
+/// `arg` as the shell would need it written: as it is if that is safe, else quoted.
+pub fn shell_quote(arg: &str) -> Cow<'_, str> {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "%+,-./:@_^".contains(c);
+    if !arg.is_empty() && arg.chars().all(safe) {
+        Cow::Borrowed(arg)
+    } else {
+        Cow::Owned(format!("'{}'", arg.replace('\'', "'\\''")))
+    }
+}
+
+/// The shell reports a process killed by SIGPIPE with this status, so exiting with it on
+/// EPIPE keeps `set -o pipefail` scripts behaving as they do with GNU tools.
+pub const EXIT_BROKEN_PIPE: i32 = 128 + 13;
+
+/// Prints errors as they happen and remembers whether any did, so a command can go on
+/// with its remaining inputs and still exit with failure at the end.
+#[derive(Debug)]
+pub struct Reporter {
+    name: &'static str,
+    style: Style,
+    failed: bool,
+}
+
+impl Reporter {
+    pub fn new(name: &'static str, style: Style) -> Self {
+        Self {
+            name,
+            style,
+            failed: false,
+        }
+    }
+
+    /// Prints `e` to standard error. A reader that went away ends the command quietly,
+    /// since there is nobody left to write to.
+    pub fn report(&mut self, e: &Error) {
+        if e.is_broken_pipe() {
+            exit(EXIT_BROKEN_PIPE);
+        }
+        self.fail(&e.message(self.style));
+    }
+
+    /// Prints a failure that is not an I/O error, such as a bad file name.
+    pub fn fail(&mut self, message: &dyn fmt::Display) {
+        io::stderr()
+            .write_all(format!("{}: {}\n", self.name, message).as_ref())
+            .ok();
+        self.failed = true;
+    }
+
+    /// Reports the error of `result`, if any, and hands back its value.
+    pub fn check<T>(&mut self, result: Result<T, Error>) -> Option<T> {
+        result.map_err(|e| self.report(&e)).ok()
+    }
+
+    /// Whether anything was reported so far.
+    pub fn failed(&self) -> bool {
+        self.failed
+    }
+
+    /// Exits with status 1 if anything was reported.
+    pub fn finish(&self) {
+        if self.failed {
+            exit(1);
+        }
+    }
+}
+
+/// A writer that marks its errors, so that [`Error::copying`] can tell them apart from
+/// errors of the reader they were copied from.
+#[derive(Debug)]
+pub struct Output<W>(pub W);
+
+impl<W: Write> Write for Output<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf).map_err(mark_write_failure)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush().map_err(mark_write_failure)
+    }
+}
+
+/// Seeking is part of writing, for outputs that are patched in place.
+impl<W: Seek> Seek for Output<W> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.0.seek(pos).map_err(mark_write_failure)
+    }
+}
+
+#[derive(Debug)]
+struct WriteFailure(io::Error);
+
+impl fmt::Display for WriteFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for WriteFailure {}
+
+fn is_write_failure(e: &io::Error) -> bool {
+    e.get_ref().is_some_and(|inner| inner.is::<WriteFailure>())
+}
+
+fn mark_write_failure(e: io::Error) -> io::Error {
+    if is_write_failure(&e) {
+        e
+    } else {
+        io::Error::new(e.kind(), WriteFailure(e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    struct Failing(io::ErrorKind);
+
+    impl Read for Failing {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::from(self.0))
+        }
+    }
+
+    impl Write for Failing {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::from_raw_os_error(32))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_copying() {
+        let e = io::copy(&mut &b"abc"[..], &mut Output(Failing(io::ErrorKind::Other))).unwrap_err();
+        let e = Error::copying("x", e);
+        assert!(e.is_broken_pipe());
+        assert_eq!(e.message(Style::Bare), "write error: Broken pipe");
+
+        let e = io::copy(
+            &mut Failing(io::ErrorKind::PermissionDenied),
+            &mut Output(Vec::new()),
+        )
+        .unwrap_err();
+        let e = Error::copying("a b", e);
+        assert!(!e.is_broken_pipe());
+        assert_eq!(e.message(Style::Bare), "'a b': permission denied");
+        assert_eq!(
+            e.message(Style::Verbose),
+            "error reading 'a b': permission denied"
+        );
+    }
+
+    #[test]
+    fn test_message() {
+        let not_found = || io::Error::from_raw_os_error(2);
+        assert_eq!(
+            Error::Open("x".to_string(), not_found()).message(Style::Bare),
+            "x: No such file or directory"
+        );
+        assert_eq!(
+            Error::Open("x".to_string(), not_found()).message(Style::Verbose),
+            "cannot open 'x' for reading: No such file or directory"
+        );
+        assert_eq!(
+            Error::Read("d".to_string(), io::Error::from_raw_os_error(21)).message(Style::Bare),
+            "d: Is a directory"
+        );
+    }
+}
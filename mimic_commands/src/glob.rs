@@ -0,0 +1,241 @@
+//! Shell wildcard patterns, as matched by `fnmatch(3)` without flags: `*`, `?`, bracket
+//! expressions and backslash escapes. A `/` or a leading `.` is not treated specially.
+//! Case may be ignored, as with `FNM_CASEFOLD`, for `find -iname`.
+
+#[derive(Debug, Clone)]
+enum Token {
+    Literal(char),
+    /// `?`
+    AnyChar,
+    /// `*`
+    AnyString,
+    Bracket(Bracket),
+}
+
+#[derive(Debug, Clone)]
+struct Bracket {
+    negated: bool,
+    ranges: Vec<(char, char)>,
+    classes: Vec<fn(char) -> bool>,
+}
+
+impl Bracket {
+    fn contains(&self, c: char, caseless: bool) -> bool {
+        let found = cases(c, caseless).any(|c| {
+            self.ranges.iter().any(|(lo, hi)| (*lo..=*hi).contains(&c))
+                || self.classes.iter().any(|class| class(c))
+        });
+        found != self.negated
+    }
+}
+
+/// A compiled wildcard pattern.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    tokens: Vec<Token>,
+    caseless: bool,
+}
+
+impl Pattern {
+    /// Compiles `pattern`. Like `fnmatch`, a `[` that does not start a valid bracket
+    /// expression stands for itself, so every pattern is valid.
+    pub fn new(pattern: &str) -> Self {
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            let token = match chars[i] {
+                '*' => Token::AnyString,
+                '?' => Token::AnyChar,
+                '[' => match parse_bracket(&chars[i + 1..]) {
+                    Some((bracket, len)) => {
+                        i += len;
+                        Token::Bracket(bracket)
+                    }
+                    None => Token::Literal('['),
+                },
+                '\\' if i + 1 < chars.len() => {
+                    i += 1;
+                    Token::Literal(chars[i])
+                }
+                c => Token::Literal(c),
+            };
+            // Consecutive stars match the same as one.
+            if !matches!(
+                (&token, tokens.last()),
+                (Token::AnyString, Some(Token::AnyString))
+            ) {
+                tokens.push(token);
+            }
+            i += 1;
+        }
+        Pattern {
+            tokens,
+            caseless: false,
+        }
+    }
+
+    /// Compiles `pattern` to match regardless of case.
+    pub fn new_caseless(pattern: &str) -> Self {
+        Pattern {
+            caseless: true,
+            ..Pattern::new(pattern)
+        }
+    }
+
+    pub fn matches(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        let (mut t, mut p) = (0, 0);
+        // Where to resume after the last `*` if the rest fails to match: the token after
+        // it, and the text position it has swallowed up to.
+        let mut backtrack: Option<(usize, usize)> = None;
+        while t < text.len() {
+            let step = match self.tokens.get(p) {
+                Some(Token::AnyString) => {
+                    backtrack = Some((p + 1, t));
+                    p += 1;
+                    continue;
+                }
+                Some(Token::AnyChar) => true,
+                Some(Token::Literal(c)) => cases(text[t], self.caseless).any(|t| *c == t),
+                Some(Token::Bracket(bracket)) => bracket.contains(text[t], self.caseless),
+                None => false,
+            };
+            if step {
+                p += 1;
+                t += 1;
+            } else if let Some((resume, swallowed)) = backtrack {
+                p = resume;
+                t = swallowed + 1;
+                backtrack = Some((resume, swallowed + 1));
+            } else {
+                return false;
+            }
+        }
+        self.tokens[p..]
+            .iter()
+            .all(|token| matches!(token, Token::AnyString))
+    }
+}
+
+/// `c`, and when ignoring case, its lower and upper case too.
+fn cases(c: char, caseless: bool) -> impl Iterator<Item = char> {
+    let other = |convert: fn(char) -> Option<char>| convert(c).filter(|_| caseless);
+    std::iter::once(c)
+        .chain(other(|c| c.to_lowercase().next()))
+        .chain(other(|c| c.to_uppercase().next()))
+}
+
+/// Parses a bracket expression after its `[`, returning it with the number of
+/// characters it took including the closing `]`.
+fn parse_bracket(chars: &[char]) -> Option<(Bracket, usize)> {
+    let mut bracket = Bracket {
+        negated: false,
+        ranges: Vec::new(),
+        classes: Vec::new(),
+    };
+    let mut i = 0;
+    if matches!(chars.first(), Some('!') | Some('^')) {
+        bracket.negated = true;
+        i += 1;
+    }
+    let first = i;
+    loop {
+        let c = *chars.get(i)?;
+        if c == ']' && i > first {
+            return Some((bracket, i + 1));
+        }
+        if c == '[' && chars.get(i + 1) == Some(&':') {
+            let rest: String = chars[i + 2..].iter().collect();
+            let end = rest.find(":]")?;
+            bracket.classes.push(class(&rest[..end])?);
+            i += 2 + rest[..end].chars().count() + 2;
+            continue;
+        }
+        let (lo, len) = match c {
+            '\\' => (*chars.get(i + 1)?, 2),
+            c => (c, 1),
+        };
+        i += len;
+        if chars.get(i) == Some(&'-') && chars.get(i + 1).is_some_and(|c| *c != ']') {
+            let (hi, len) = match chars[i + 1] {
+                '\\' => (*chars.get(i + 2)?, 3),
+                c => (c, 2),
+            };
+            i += len;
+            bracket.ranges.push((lo, hi));
+        } else {
+            bracket.ranges.push((lo, lo));
+        }
+    }
+}
+
+fn class(name: &str) -> Option<fn(char) -> bool> {
+    let class: fn(char) -> bool = match name {
+        "alnum" => char::is_alphanumeric,
+        "alpha" => char::is_alphabetic,
+        "blank" => |c| c == ' ' || c == '\t',
+        "cntrl" => char::is_control,
+        "digit" => |c| c.is_ascii_digit(),
+        "graph" => |c| !c.is_whitespace() && !c.is_control(),
+        "lower" => char::is_lowercase,
+        "print" => |c| !c.is_control(),
+        "punct" => |c| c.is_ascii_punctuation(),
+        "space" => char::is_whitespace,
+        "upper" => char::is_uppercase,
+        "xdigit" => |c| c.is_ascii_hexdigit(),
+        _ => return None,
+    };
+    Some(class)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        Pattern::new(pattern).matches(text)
+    }
+
+    #[test]
+    fn test_wildcards() {
+        assert!(matches("*.rs", "main.rs"));
+        assert!(matches("*.rs", ".rs"));
+        assert!(!matches("*.rs", "main.rs~"));
+        assert!(matches("a*b*c", "aXbYbZc"));
+        assert!(!matches("a*b*c", "aXbYbZ"));
+        assert!(matches("?at", "cat"));
+        assert!(!matches("?at", "at"));
+        assert!(matches("**", ""));
+        assert!(matches("*/x", "a/b/x"));
+        assert!(matches("ああ?", "ああa"));
+        assert!(matches("\\*", "*"));
+        assert!(!matches("\\*", "a"));
+    }
+
+    #[test]
+    fn test_brackets() {
+        assert!(matches("[abc]x", "bx"));
+        assert!(!matches("[!abc]x", "bx"));
+        assert!(matches("[^abc]x", "dx"));
+        assert!(matches("[a-c0-9]", "7"));
+        assert!(matches("[]]", "]"));
+        assert!(matches("[!]]", "a"));
+        assert!(matches("[a-]", "-"));
+        assert!(matches("[[:digit:][:upper:]]*", "Q1"));
+        assert!(!matches("[[:digit:]]", "x"));
+        // Not a bracket expression, so a literal `[`.
+        assert!(matches("[ab", "[ab"));
+    }
+
+    #[test]
+    fn test_caseless() {
+        let pattern = Pattern::new_caseless("*.TXT");
+        assert!(pattern.matches("notes.txt"));
+        assert!(pattern.matches("NOTES.Txt"));
+        assert!(!pattern.matches("notes.txt~"));
+        assert!(Pattern::new_caseless("[a-c]X").matches("Bx"));
+        assert!(!Pattern::new_caseless("[!a-c]x").matches("Bx"));
+        assert!(!matches("*.TXT", "notes.txt"));
+    }
+}
@@ -0,0 +1,46 @@
+//! Sizes printed for people, as `ls -h` and `du -h` print them.
+
+/// A byte count in powers of 1024 with a one letter unit, rounded up as GNU does: one
+/// decimal below 10 (`4.1K`), none above (`15M`), and plain bytes below 1024 (`100`).
+pub fn size(bytes: u64) -> String {
+    const UNITS: &[char] = &['K', 'M', 'G', 'T', 'P', 'E'];
+    if bytes < 1024 {
+        return bytes.to_string();
+    }
+    let bytes = u128::from(bytes);
+    let mut unit = 0;
+    let mut scale: u128 = 1024;
+    while bytes >= scale * 1024 && unit + 1 < UNITS.len() {
+        scale *= 1024;
+        unit += 1;
+    }
+    let tenths = (bytes * 10).div_ceil(scale);
+    if tenths < 100 {
+        return format!("{}.{}{}", tenths / 10, tenths % 10, UNITS[unit]);
+    }
+    let whole = bytes.div_ceil(scale);
+    if whole >= 1024 && unit + 1 < UNITS.len() {
+        // Rounding up reached the next unit.
+        return format!("1.0{}", UNITS[unit + 1]);
+    }
+    format!("{}{}", whole, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_size() {
+        assert_eq!(size(0), "0");
+        assert_eq!(size(1023), "1023");
+        assert_eq!(size(1024), "1.0K");
+        assert_eq!(size(4097), "4.1K");
+        assert_eq!(size(10000), "9.8K");
+        assert_eq!(size(10 * 1024 - 1), "10K");
+        assert_eq!(size(26484), "26K");
+        assert_eq!(size(1024 * 1024 - 1), "1.0M");
+        assert_eq!(size(15 * 1024 * 1024), "15M");
+        assert_eq!(size(u64::MAX), "16E");
+    }
+}
@@ -0,0 +1,109 @@
+//! The bit stream DEFLATE data is packed in, least significant bit first.
+use std::io::{self, BufRead, ErrorKind};
+
+/// Bits taken from a reader a few bytes at a time. The reader is never read ahead of what
+/// `Bits` holds, so the bytes after the compressed data, like a gzip trailer, can be
+/// taken from here once the data is aligned to a byte.
+#[derive(Debug)]
+pub(super) struct Bits<R> {
+    reader: R,
+    buffer: u64,
+    /// How many bits of `buffer` are valid, from the least significant.
+    count: u32,
+}
+
+pub(super) fn unexpected_end() -> io::Error {
+    io::Error::new(ErrorKind::UnexpectedEof, "unexpected end of file")
+}
+
+impl<R: BufRead> Bits<R> {
+    pub(super) fn new(reader: R) -> Self {
+        Bits {
+            reader,
+            buffer: 0,
+            count: 0,
+        }
+    }
+
+    /// How many bits are at hand without reading.
+    pub(super) fn available(&self) -> u32 {
+        self.count
+    }
+
+    /// Tops the buffer up with what the reader has at hand, reading only if it has nothing.
+    pub(super) fn refill(&mut self) -> io::Result<()> {
+        let available = loop {
+            match self.reader.fill_buf() {
+                Ok(available) => break available,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        };
+        let size = available.len().min(((64 - self.count) / 8) as usize);
+        for (i, &byte) in available[..size].iter().enumerate() {
+            self.buffer |= u64::from(byte) << (self.count as usize + 8 * i);
+        }
+        self.count += 8 * size as u32;
+        self.reader.consume(size);
+        Ok(())
+    }
+
+    /// Makes sure at least `count` bits are at hand; the data ending before is an error.
+    pub(super) fn need(&mut self, count: u32) -> io::Result<()> {
+        while self.count < count {
+            let before = self.count;
+            self.refill()?;
+            if self.count == before {
+                return Err(unexpected_end());
+            }
+        }
+        Ok(())
+    }
+
+    /// The next `count` bits, at most 32, with zeros past those at hand.
+    pub(super) fn peek(&self, count: u32) -> u32 {
+        (self.buffer & ((1 << count) - 1)) as u32
+    }
+
+    pub(super) fn consume(&mut self, count: u32) {
+        self.buffer >>= count;
+        self.count -= count;
+    }
+
+    pub(super) fn take(&mut self, count: u32) -> io::Result<u32> {
+        self.need(count)?;
+        let bits = self.peek(count);
+        self.consume(count);
+        Ok(bits)
+    }
+
+    /// Skips to the next byte boundary.
+    pub(super) fn align(&mut self) {
+        self.consume(self.count % 8);
+    }
+
+    /// Appends the next `size` bytes to `output`; the bits must be aligned.
+    pub(super) fn copy(&mut self, mut size: usize, output: &mut Vec<u8>) -> io::Result<()> {
+        while size > 0 && self.count >= 8 {
+            output.push(self.buffer as u8);
+            self.consume(8);
+            size -= 1;
+        }
+        while size > 0 {
+            let available = self.reader.fill_buf()?;
+            if available.is_empty() {
+                return Err(unexpected_end());
+            }
+            let taken = available.len().min(size);
+            output.extend_from_slice(&available[..taken]);
+            self.reader.consume(taken);
+            size -= taken;
+        }
+        Ok(())
+    }
+
+    /// Whether nothing is left; the bits must be aligned.
+    pub(super) fn at_end(&mut self) -> io::Result<bool> {
+        Ok(self.count == 0 && self.reader.fill_buf()?.is_empty())
+    }
+}
@@ -0,0 +1,296 @@
+//! The gzip format (RFC 1952): one or more members, each DEFLATE data between a header
+//! and a trailer with the CRC-32 and size of what it holds.
+use super::{Inflate, GZIP_MAGIC};
+use std::io::{self, BufRead, ErrorKind, Read};
+
+/// The header flags, and those reserved that no gzip sets. Encryption was planned for
+/// but never came.
+const HEADER_CRC: u8 = 0x02;
+const EXTRA: u8 = 0x04;
+const NAME: u8 = 0x08;
+const COMMENT: u8 = 0x10;
+const ENCRYPTED: u8 = 0x20;
+const RESERVED: u8 = 0xc0;
+
+/// The only compression method there is, DEFLATE.
+const DEFLATE: u8 = 8;
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
+}
+
+/// A header of a kind that is valid but not understood. Except for the method, the
+/// message is worded to follow the name of the file, as in "x.gz is encrypted".
+fn unsupported(message: String) -> io::Error {
+    io::Error::new(ErrorKind::Unsupported, message)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Member {
+    Header,
+    Data,
+    Done,
+}
+
+/// Decompresses gzip data, all of its members one after another as `gzip -d` does.
+/// Zeros after the last member are ignored, and so is anything else, which
+/// [`trailing_garbage`](GzDecoder::trailing_garbage) then tells.
+#[derive(Debug)]
+pub struct GzDecoder<R> {
+    inflate: Inflate<R>,
+    crc: Crc32,
+    /// The size of the member's data so far, modulo 2^32 as the trailer has it.
+    size: u32,
+    member: Member,
+    trailing_garbage: bool,
+}
+
+impl<R: BufRead> GzDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        GzDecoder {
+            inflate: Inflate::new(reader),
+            crc: Crc32::new(),
+            size: 0,
+            member: Member::Header,
+            trailing_garbage: false,
+        }
+    }
+
+    /// Whether something that is not a member followed the last one.
+    pub fn trailing_garbage(&self) -> bool {
+        self.trailing_garbage
+    }
+
+    fn byte(&mut self) -> io::Result<u8> {
+        self.inflate.bits.take(8).map(|byte| byte as u8)
+    }
+
+    fn header(&mut self) -> io::Result<()> {
+        if [self.byte()?, self.byte()?] != GZIP_MAGIC {
+            return Err(invalid("not in gzip format".to_string()));
+        }
+        let method = self.byte()?;
+        if method != DEFLATE {
+            return Err(unsupported(format!(
+                "unknown method {} -- not supported",
+                method
+            )));
+        }
+        let flags = self.byte()?;
+        if flags & ENCRYPTED != 0 {
+            return Err(unsupported("is encrypted -- not supported".to_string()));
+        }
+        if flags & RESERVED != 0 {
+            return Err(unsupported(format!(
+                "has flags 0x{:x} -- not supported",
+                flags
+            )));
+        }
+        // The modification time, the extra flags and the operating system.
+        for _ in 0..6 {
+            self.byte()?;
+        }
+        if flags & EXTRA != 0 {
+            let size = u16::from_le_bytes([self.byte()?, self.byte()?]);
+            for _ in 0..size {
+                self.byte()?;
+            }
+        }
+        for field in [NAME, COMMENT] {
+            if flags & field != 0 {
+                while self.byte()? != 0 {}
+            }
+        }
+        if flags & HEADER_CRC != 0 {
+            self.byte()?;
+            self.byte()?;
+        }
+        self.inflate.restart();
+        self.crc = Crc32::new();
+        self.size = 0;
+        Ok(())
+    }
+
+    fn trailer(&mut self) -> io::Result<()> {
+        self.inflate.bits.align();
+        let mut word = || -> io::Result<u32> {
+            Ok(u32::from_le_bytes([
+                self.byte()?,
+                self.byte()?,
+                self.byte()?,
+                self.byte()?,
+            ]))
+        };
+        let (crc, size) = (word()?, word()?);
+        if crc != self.crc.value() {
+            return Err(invalid("invalid compressed data--crc error".to_string()));
+        }
+        if size != self.size {
+            return Err(invalid("invalid compressed data--length error".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Whether another member follows; what follows otherwise is skipped.
+    fn another_member(&mut self) -> io::Result<bool> {
+        let bits = &mut self.inflate.bits;
+        match bits.need(16) {
+            Ok(()) if bits.peek(16).to_le_bytes()[..2] == GZIP_MAGIC => return Ok(true),
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => {}
+            Err(e) => return Err(e),
+        }
+        while !bits.at_end()? {
+            if bits.take(8)? != 0 {
+                self.trailing_garbage = true;
+                break;
+            }
+        }
+        Ok(false)
+    }
+
+    fn read_members(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            match self.member {
+                Member::Header => {
+                    self.header()?;
+                    self.member = Member::Data;
+                }
+                Member::Data => {
+                    let size = self.inflate.read(buf)?;
+                    if size > 0 {
+                        self.crc.update(&buf[..size]);
+                        self.size = self.size.wrapping_add(size as u32);
+                        return Ok(size);
+                    }
+                    self.trailer()?;
+                    self.member = if self.another_member()? {
+                        Member::Header
+                    } else {
+                        Member::Done
+                    };
+                }
+                Member::Done => return Ok(0),
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Read for GzDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let result = self.read_members(buf);
+        if result.is_err() {
+            self.member = Member::Done;
+        }
+        result
+    }
+}
+
+/// The CRC-32 of ISO 3309 and ITU-T V.42 that gzip checks its data with.
+#[derive(Debug, Clone)]
+struct Crc32 {
+    value: u32,
+}
+
+/// The CRC of each byte value, for the reversed polynomial 0xedb88320.
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+impl Crc32 {
+    fn new() -> Self {
+        Crc32 { value: 0 }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        let mut crc = !self.value;
+        for &byte in data {
+            crc = CRC_TABLE[((crc ^ u32::from(byte)) & 0xff) as usize] ^ (crc >> 8);
+        }
+        self.value = !crc;
+    }
+
+    fn value(&self) -> u32 {
+        self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::read_all;
+    use super::*;
+
+    /// `printf 'hello hello hello\n' | gzip -n`, padded with zeros after its 29 bytes.
+    const HELLO: [u8; 38] = [
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xcb, 0x48, 0xcd, 0xc9, 0xc9,
+        0x57, 0xc8, 0x40, 0x90, 0x5c, 0x00, 0x3b, 0x7c, 0x8a, 0xdf, 0x12, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    fn gunzip(data: &[u8]) -> io::Result<(Vec<u8>, bool)> {
+        let mut decoder = GzDecoder::new(data);
+        let output = read_all(&mut decoder)?;
+        Ok((output, decoder.trailing_garbage()))
+    }
+
+    #[test]
+    fn test_crc32() {
+        let mut crc = Crc32::new();
+        crc.update(b"123456789");
+        assert_eq!(crc.value(), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn test_gz_decoder() {
+        let hello = &HELLO[..29];
+        assert_eq!(
+            gunzip(hello).unwrap(),
+            (b"hello hello hello\n".to_vec(), false)
+        );
+
+        // `(printf 'hi\n' | gzip -n; printf 'there\n' | gzip -n)`
+        let two = [
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xcb, 0xc8, 0xe4, 0x02,
+            0x00, 0x7a, 0x7a, 0x6f, 0xed, 0x03, 0x00, 0x00, 0x00, 0x1f, 0x8b, 0x08, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x03, 0x2b, 0xc9, 0x48, 0x2d, 0x4a, 0xe5, 0x02, 0x00, 0x0b,
+            0x1f, 0xe1, 0x2b, 0x06, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(gunzip(&two).unwrap().0, b"hi\nthere\n");
+
+        // Padding with zeros is fine, anything else is noted.
+        assert!(!gunzip(&HELLO).unwrap().1);
+        let mut garbage = hello.to_vec();
+        garbage.extend_from_slice(b"\0\0x");
+        assert!(gunzip(&garbage).unwrap().1);
+
+        let mut bad_crc = hello.to_vec();
+        bad_crc[21] ^= 1;
+        let e = gunzip(&bad_crc).unwrap_err();
+        assert_eq!(e.to_string(), "invalid compressed data--crc error");
+        let e = gunzip(&hello[..25]).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+        let e = gunzip(b"plain text").unwrap_err();
+        assert_eq!(e.to_string(), "not in gzip format");
+        let e = gunzip(&[0x1f, 0x8b, 0x07, 0x00]).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::Unsupported);
+    }
+}
@@ -0,0 +1,137 @@
+//! The canonical Huffman codes DEFLATE describes its symbols with.
+use super::bits::Bits;
+use super::corrupt;
+use std::io::{self, BufRead};
+
+/// The longest code DEFLATE allows.
+const MAX_BITS: usize = 15;
+
+/// How many bits are looked up in one go; longer codes are then decoded a bit at a time.
+const FAST_BITS: u32 = 9;
+
+#[derive(Debug)]
+pub(super) struct Huffman {
+    /// By the next `FAST_BITS` bits of input, the symbol shifted left by 4 and ORed with
+    /// the length of its code, or 0 where the code is longer.
+    fast: Vec<u16>,
+    /// How many codes there are of each length.
+    counts: [u16; MAX_BITS + 1],
+    /// The symbols in the order of their codes.
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    /// The code giving each symbol a code of the length at its index; 0 leaves the symbol
+    /// out. A code with unused bit patterns is allowed, as DEFLATE needs for a single
+    /// distance code; one with more codes than bit patterns is not.
+    pub(super) fn new(lengths: &[u8]) -> io::Result<Self> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &length in lengths {
+            counts[usize::from(length)] += 1;
+        }
+        counts[0] = 0;
+        let mut left: i32 = 1;
+        for &count in &counts[1..] {
+            left = 2 * left - i32::from(count);
+            if left < 0 {
+                return Err(corrupt());
+            }
+        }
+
+        // Where the codes of each length start among the symbols.
+        let mut offsets = [0u16; MAX_BITS + 1];
+        for length in 1..MAX_BITS {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0; usize::from(offsets[MAX_BITS] + counts[MAX_BITS])];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                let offset = &mut offsets[usize::from(length)];
+                symbols[usize::from(*offset)] = symbol as u16;
+                *offset += 1;
+            }
+        }
+
+        // Codes are packed starting with their most significant bit, so the table is
+        // indexed by them reversed.
+        let mut fast = vec![0; 1 << FAST_BITS];
+        let mut code: u32 = 0;
+        let mut next = 0;
+        for length in 1..=FAST_BITS {
+            for _ in 0..counts[length as usize] {
+                let entry = symbols[next] << 4 | length as u16;
+                next += 1;
+                let mut index = (code.reverse_bits() >> (32 - length)) as usize;
+                while index < fast.len() {
+                    fast[index] = entry;
+                    index += 1 << length;
+                }
+                code += 1;
+            }
+            code <<= 1;
+        }
+        Ok(Huffman {
+            fast,
+            counts,
+            symbols,
+        })
+    }
+
+    /// Reads one code and returns its symbol.
+    pub(super) fn decode<R: BufRead>(&self, bits: &mut Bits<R>) -> io::Result<u16> {
+        if bits.available() < MAX_BITS as u32 {
+            bits.refill()?;
+        }
+        loop {
+            let available = bits.available();
+            let entry = self.fast[bits.peek(FAST_BITS) as usize];
+            let length = u32::from(entry & 15);
+            if entry != 0 && length <= available {
+                bits.consume(length);
+                return Ok(entry >> 4);
+            }
+            if available >= FAST_BITS {
+                break;
+            }
+            // Too close to the end of what was read to tell.
+            bits.need(available + 1)?;
+        }
+
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+        for &count in &self.counts[1..] {
+            code |= bits.take(1)? as i32;
+            let count = i32::from(count);
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(corrupt())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        // A is 0, B is 10, C is 110 and D is 111, so "A B C D A" is read as the bits
+        // 0 10 110 111 0, each byte from its least significant bit.
+        let code = Huffman::new(&[1, 2, 3, 3]).unwrap();
+        let mut bits = Bits::new(&[0b1101_1010, 0b0000_0001][..]);
+        let symbols: Vec<u16> = (0..5).map(|_| code.decode(&mut bits).unwrap()).collect();
+        assert_eq!(symbols, [0, 1, 2, 3, 0]);
+
+        // Longer than the lookup table: 1 to 13 bits, then two of 14 bits, all ones last.
+        let lengths: Vec<u8> = (1..=14).chain([14]).collect();
+        let code = Huffman::new(&lengths).unwrap();
+        let mut bits = Bits::new(&[0xff, 0x3f][..]);
+        assert_eq!(code.decode(&mut bits).unwrap(), 14);
+        assert!(Huffman::new(&[1, 1, 1]).is_err());
+    }
+}
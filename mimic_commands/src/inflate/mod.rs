@@ -0,0 +1,354 @@
+//! DEFLATE decompression (RFC 1951), and the gzip (RFC 1952) and zlib (RFC 1950)
+//! formats around it, as `Read` adapters so the crate stays dependency-free.
+//!
+//! Data is decoded a chunk at a time into a buffer that keeps the last 32 KiB of output
+//! behind it, for the back references of later chunks to copy from.
+mod bits;
+mod gzip;
+mod huffman;
+mod zlib;
+
+pub use gzip::GzDecoder;
+pub use zlib::ZlibDecoder;
+
+use bits::Bits;
+use huffman::Huffman;
+use std::io::{self, BufRead, Cursor, Read};
+
+/// The first two bytes of gzip data.
+pub const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// How far back a back reference may reach.
+const WINDOW_SIZE: usize = 32 * 1024;
+
+/// How much is decoded ahead of the reader at a time.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// The length of a back reference for each of the length symbols from 257, before the
+/// extra bits are added, and how many extra bits there are.
+const LENGTH_BASES: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA_BITS: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+/// The same for the distance symbols.
+const DISTANCE_BASES: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA_BITS: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// The order in which a dynamic block gives the code lengths of the code length code.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Data that breaks the format, worded as GNU gzip words it.
+fn corrupt() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "invalid compressed data--format violated",
+    )
+}
+
+/// The data of `reader` decompressed if it is gzip, or as it is otherwise, as
+/// `zcat -f` has it.
+pub fn decompressed<'a, R: BufRead + 'a>(mut reader: R) -> io::Result<Box<dyn Read + 'a>> {
+    let mut magic = Vec::with_capacity(GZIP_MAGIC.len());
+    (&mut reader)
+        .take(GZIP_MAGIC.len() as u64)
+        .read_to_end(&mut magic)?;
+    let is_gzip = magic == GZIP_MAGIC;
+    let reader = Cursor::new(magic).chain(reader);
+    Ok(if is_gzip {
+        Box::new(GzDecoder::new(reader))
+    } else {
+        Box::new(reader)
+    })
+}
+
+/// The Huffman codes of a compressed block.
+#[derive(Debug)]
+struct Codes {
+    /// Literal bytes, the end of the block, and the lengths of back references.
+    literals: Huffman,
+    distances: Huffman,
+}
+
+impl Codes {
+    /// The codes of a block compressed with the codes RFC 1951 fixes.
+    fn fixed() -> io::Result<Self> {
+        let mut lengths = [8; 288];
+        lengths[144..256].fill(9);
+        lengths[256..280].fill(7);
+        Ok(Codes {
+            literals: Huffman::new(&lengths)?,
+            distances: Huffman::new(&[5; 30])?,
+        })
+    }
+
+    /// Reads the codes a block with codes of its own starts with.
+    fn read<R: BufRead>(bits: &mut Bits<R>) -> io::Result<Self> {
+        let literal_count = bits.take(5)? as usize + 257;
+        let distance_count = bits.take(5)? as usize + 1;
+        let code_length_count = bits.take(4)? as usize + 4;
+        if literal_count > 286 || distance_count > 30 {
+            return Err(corrupt());
+        }
+        let mut code_lengths = [0; 19];
+        for &symbol in &CODE_LENGTH_ORDER[..code_length_count] {
+            code_lengths[symbol] = bits.take(3)? as u8;
+        }
+        let code_length_code = Huffman::new(&code_lengths)?;
+
+        // Both codes' lengths are given in one run, which repeats may cross.
+        let mut lengths = Vec::with_capacity(literal_count + distance_count);
+        while lengths.len() < literal_count + distance_count {
+            let (length, repeat) = match code_length_code.decode(bits)? {
+                symbol @ 0..=15 => (symbol as u8, 1),
+                16 => match lengths.last() {
+                    Some(&previous) => (previous, 3 + bits.take(2)?),
+                    None => return Err(corrupt()),
+                },
+                17 => (0, 3 + bits.take(3)?),
+                _ => (0, 11 + bits.take(7)?),
+            };
+            if lengths.len() + repeat as usize > literal_count + distance_count {
+                return Err(corrupt());
+            }
+            lengths.extend((0..repeat).map(|_| length));
+        }
+        // A block that cannot end cannot be right.
+        if lengths[256] == 0 {
+            return Err(corrupt());
+        }
+        Ok(Codes {
+            literals: Huffman::new(&lengths[..literal_count])?,
+            distances: Huffman::new(&lengths[literal_count..])?,
+        })
+    }
+}
+
+/// Where the decoder is in the stream.
+#[derive(Debug)]
+enum State {
+    /// At the header of a block.
+    Header,
+    /// In a block stored as it is, with this many bytes left.
+    Stored(usize),
+    Compressed(Box<Codes>),
+    /// Past the last block.
+    Done,
+}
+
+/// Decompresses raw DEFLATE data.
+#[derive(Debug)]
+pub struct Inflate<R> {
+    bits: Bits<R>,
+    /// The window of past output, followed by what was decoded but not read yet.
+    output: Vec<u8>,
+    /// Where in `output` the bytes not read yet start.
+    position: usize,
+    state: State,
+    last_block: bool,
+    /// An error met after decoding some data, held back until that data is read.
+    error: Option<io::Error>,
+}
+
+impl<R: BufRead> Inflate<R> {
+    pub fn new(reader: R) -> Self {
+        Inflate {
+            bits: Bits::new(reader),
+            output: Vec::new(),
+            position: 0,
+            state: State::Header,
+            last_block: false,
+            error: None,
+        }
+    }
+
+    /// Starts over on a new stream right after the end of this one, for the next gzip
+    /// member.
+    fn restart(&mut self) {
+        self.output.clear();
+        self.position = 0;
+        self.state = State::Header;
+        self.last_block = false;
+    }
+
+    /// Decodes the next chunk, dropping the output already read except for the window.
+    fn decode(&mut self) -> io::Result<()> {
+        let read = self.output.len().saturating_sub(WINDOW_SIZE);
+        self.output.drain(..read);
+        self.position = self.output.len();
+        let end = self.output.len() + CHUNK_SIZE;
+        while self.output.len() < end {
+            match &mut self.state {
+                State::Header if self.last_block => self.state = State::Done,
+                State::Header => {
+                    self.last_block = self.bits.take(1)? == 1;
+                    self.state = match self.bits.take(2)? {
+                        0 => {
+                            self.bits.align();
+                            let size = self.bits.take(16)?;
+                            if self.bits.take(16)? != !size & 0xffff {
+                                return Err(corrupt());
+                            }
+                            State::Stored(size as usize)
+                        }
+                        1 => State::Compressed(Box::new(Codes::fixed()?)),
+                        2 => State::Compressed(Box::new(Codes::read(&mut self.bits)?)),
+                        _ => return Err(corrupt()),
+                    };
+                }
+                State::Stored(0) => self.state = State::Header,
+                State::Stored(left) => {
+                    let size = (*left).min(end - self.output.len());
+                    self.bits.copy(size, &mut self.output)?;
+                    *left -= size;
+                }
+                State::Compressed(codes) => {
+                    if decode_block(codes, &mut self.bits, &mut self.output, end)? {
+                        self.state = State::Header;
+                    }
+                }
+                State::Done => break,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Decodes a compressed block into `output` until it reaches `end` or the block ends,
+/// telling which.
+fn decode_block<R: BufRead>(
+    codes: &Codes,
+    bits: &mut Bits<R>,
+    output: &mut Vec<u8>,
+    end: usize,
+) -> io::Result<bool> {
+    while output.len() < end {
+        let symbol = codes.literals.decode(bits)?;
+        let i = match symbol {
+            0..=255 => {
+                output.push(symbol as u8);
+                continue;
+            }
+            256 => return Ok(true),
+            257..=285 => usize::from(symbol - 257),
+            _ => return Err(corrupt()),
+        };
+        let length =
+            usize::from(LENGTH_BASES[i]) + bits.take(LENGTH_EXTRA_BITS[i].into())? as usize;
+        let i = usize::from(codes.distances.decode(bits)?);
+        if i >= DISTANCE_BASES.len() {
+            return Err(corrupt());
+        }
+        let distance =
+            usize::from(DISTANCE_BASES[i]) + bits.take(DISTANCE_EXTRA_BITS[i].into())? as usize;
+        if distance > output.len() {
+            return Err(corrupt());
+        }
+        let start = output.len() - distance;
+        if distance >= length {
+            output.extend_from_within(start..start + length);
+        } else {
+            // The copy overlaps what it makes, repeating the last `distance` bytes.
+            for i in start..start + length {
+                output.push(output[i]);
+            }
+        }
+    }
+    Ok(false)
+}
+
+impl<R: BufRead> Read for Inflate<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position == self.output.len() {
+            if let Some(e) = self.error.take() {
+                return Err(e);
+            }
+            if let Err(e) = self.decode() {
+                // Nothing can be decoded after an error.
+                self.state = State::Done;
+                if self.position == self.output.len() {
+                    return Err(e);
+                }
+                self.error = Some(e);
+            }
+        }
+        let size = buf.len().min(self.output.len() - self.position);
+        buf[..size].copy_from_slice(&self.output[self.position..][..size]);
+        self.position += size;
+        Ok(size)
+    }
+}
+
+/// Reads to the end of `reader`, for the tests here and of the formats.
+#[cfg(test)]
+fn read_all<R: Read>(mut reader: R) -> io::Result<Vec<u8>> {
+    let mut output = Vec::new();
+    reader.read_to_end(&mut output)?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inflate() {
+        // From Python's zlib, without the zlib wrapper: a stored block, then a block with
+        // codes of its own.
+        let stored = [
+            0x01, 0x06, 0x00, 0xf9, 0xff, b's', b't', b'o', b'r', b'e', b'd',
+        ];
+        assert_eq!(read_all(Inflate::new(&stored[..])).unwrap(), b"stored");
+        let dynamic = [
+            0x7d, 0xd0, 0xbb, 0x0d, 0x80, 0x30, 0x0c, 0x45, 0xd1, 0x9e, 0x29, 0x3c, 0x42, 0x1e,
+            0x7f, 0xc6, 0x21, 0x92, 0x23, 0x0a, 0x8b, 0x48, 0x60, 0x89, 0xf5, 0xd9, 0xe0, 0xd6,
+            0xa7, 0x3b, 0xc5, 0x6a, 0xcf, 0x0c, 0x7f, 0xad, 0x37, 0xab, 0xee, 0x8f, 0xf5, 0xdb,
+            0xf2, 0x72, 0xfb, 0xce, 0x88, 0x41, 0xa8, 0x23, 0xea, 0x84, 0x3a, 0xa3, 0x2e, 0xa8,
+            0x2b, 0xea, 0x86, 0xba, 0xa3, 0x1e, 0xbc, 0x51, 0x98, 0x79, 0x4b, 0xdc, 0x25, 0xfe,
+            0x12, 0x87, 0x89, 0xc7, 0xc4, 0x65, 0xe2, 0x33, 0x71, 0x9a, 0x78, 0xed, 0x07,
+        ];
+        let beer: String = (0..20)
+            .map(|i| format!("{} bottles of beer on the wall\n", i))
+            .collect();
+        assert_eq!(
+            read_all(Inflate::new(&dynamic[..])).unwrap(),
+            beer.as_bytes()
+        );
+
+        // Cut short, what was decoded still comes out before the error.
+        let mut inflate = Inflate::new(&dynamic[..60]);
+        let mut output = Vec::new();
+        let e = inflate.read_to_end(&mut output).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(!output.is_empty() && beer.as_bytes().starts_with(&output));
+
+        // Reserved block type 3.
+        let e = read_all(Inflate::new(&[0x07][..])).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_decompressed() {
+        let gzip = [
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x4b, 0x04, 0x00, 0x43,
+            0xbe, 0xb7, 0xe8, 0x01, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(read_all(decompressed(&gzip[..]).unwrap()).unwrap(), b"a");
+        assert_eq!(
+            read_all(decompressed(&b"\x1fplain"[..]).unwrap()).unwrap(),
+            b"\x1fplain"
+        );
+        assert_eq!(read_all(decompressed(&b""[..]).unwrap()).unwrap(), b"");
+    }
+}
@@ -0,0 +1,164 @@
+//! The zlib format (RFC 1950): DEFLATE data between a two byte header and the Adler-32
+//! of what it holds.
+use super::{corrupt, Inflate};
+use std::io::{self, BufRead, ErrorKind, Read};
+
+/// The largest prime below 2^16, which Adler-32 sums modulo.
+const ADLER_MODULUS: u32 = 65521;
+
+/// How many bytes can be summed before the sums could overflow 32 bits.
+const ADLER_RUN: usize = 5552;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Stage {
+    Header,
+    Data,
+    Done,
+}
+
+/// Decompresses zlib data.
+#[derive(Debug)]
+pub struct ZlibDecoder<R> {
+    inflate: Inflate<R>,
+    adler: Adler32,
+    stage: Stage,
+}
+
+impl<R: BufRead> ZlibDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        ZlibDecoder {
+            inflate: Inflate::new(reader),
+            adler: Adler32::new(),
+            stage: Stage::Header,
+        }
+    }
+
+    fn header(&mut self) -> io::Result<()> {
+        let bits = &mut self.inflate.bits;
+        let method = bits.take(8)?;
+        let flags = bits.take(8)?;
+        // DEFLATE with a window of at most 32 KiB, and a check that makes the two a
+        // multiple of 31.
+        if method & 0x0f != 8 || method >> 4 > 7 || (method << 8 | flags) % 31 != 0 {
+            return Err(corrupt());
+        }
+        if flags & 0x20 != 0 {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "preset dictionary not supported",
+            ));
+        }
+        Ok(())
+    }
+
+    fn trailer(&mut self) -> io::Result<()> {
+        let bits = &mut self.inflate.bits;
+        bits.align();
+        let mut check = 0;
+        for _ in 0..4 {
+            check = check << 8 | bits.take(8)?;
+        }
+        if check != self.adler.value() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "invalid compressed data--crc error",
+            ));
+        }
+        Ok(())
+    }
+
+    fn read_stream(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.stage == Stage::Header {
+            self.header()?;
+            self.stage = Stage::Data;
+        }
+        if self.stage == Stage::Done {
+            return Ok(0);
+        }
+        let size = self.inflate.read(buf)?;
+        if size > 0 {
+            self.adler.update(&buf[..size]);
+        } else {
+            self.trailer()?;
+            self.stage = Stage::Done;
+        }
+        Ok(size)
+    }
+}
+
+impl<R: BufRead> Read for ZlibDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let result = self.read_stream(buf);
+        if result.is_err() {
+            self.stage = Stage::Done;
+        }
+        result
+    }
+}
+
+/// Adler-32: two sums, of the bytes and of those sums, packed into one word.
+#[derive(Debug, Clone)]
+struct Adler32 {
+    a: u32,
+    b: u32,
+}
+
+impl Adler32 {
+    fn new() -> Self {
+        Adler32 { a: 1, b: 0 }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for run in data.chunks(ADLER_RUN) {
+            for &byte in run {
+                self.a += u32::from(byte);
+                self.b += self.a;
+            }
+            self.a %= ADLER_MODULUS;
+            self.b %= ADLER_MODULUS;
+        }
+    }
+
+    fn value(&self) -> u32 {
+        self.b << 16 | self.a
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::read_all;
+    use super::*;
+
+    #[test]
+    fn test_zlib_decoder() {
+        // From Python's `zlib.compress`.
+        let data = [
+            120, 156, 203, 72, 205, 201, 201, 87, 200, 64, 144, 92, 0, 64, 181, 6, 135,
+        ];
+        let output = read_all(ZlibDecoder::new(&data[..])).unwrap();
+        assert_eq!(output, b"hello hello hello\n");
+
+        let mut bad_check = data;
+        bad_check[16] ^= 1;
+        assert!(read_all(ZlibDecoder::new(&bad_check[..])).is_err());
+        assert!(read_all(ZlibDecoder::new(&data[1..])).is_err());
+    }
+
+    #[test]
+    fn test_adler32() {
+        let mut adler = Adler32::new();
+        adler.update(b"Wikipedia");
+        assert_eq!(adler.value(), 0x11e6_0398);
+        let mut adler = Adler32::new();
+        adler.update(&[0xff; 100_000]);
+        let mut expected = (1u64, 0u64);
+        for _ in 0..100_000 {
+            expected.0 = (expected.0 + 0xff) % 65521;
+            expected.1 = (expected.1 + expected.0) % 65521;
+        }
+        assert_eq!(adler.value(), (expected.1 << 16 | expected.0) as u32);
+    }
+}
@@ -1 +1,23 @@
-
+pub mod applets;
+pub mod args;
+pub mod backward;
+pub mod batch;
+pub mod count;
+pub mod digest;
+pub mod dump;
+pub mod error;
+pub mod glob;
+pub mod human;
+pub mod inflate;
+pub mod myers;
+pub mod pool;
+pub mod radix;
+pub mod regex;
+pub mod ring;
+pub mod stdio;
+pub mod tempfile;
+pub mod terminal;
+pub mod time;
+pub mod unicode;
+pub mod walk;
+pub mod zerocopy;
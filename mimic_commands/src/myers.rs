@@ -0,0 +1,356 @@
+//! The differences between two sequences, found with Myers' O(ND) algorithm, for `diff`.
+//!
+//! This is the linear space variant: the middle snake of the edit graph is found by
+//! searching from both ends at once, and the parts before and after it are compared in
+//! turn. As in GNU diff, a search that grows too costly settles for a good split point
+//! rather than the best one, so that large inputs with little in common still finish
+//! quickly; the result is then a correct edit script, if not always the shortest.
+//!
+//! Among edit scripts of the same length, the one chosen is the one GNU diff would
+//! usually choose, so that output can be compared with what GNU diff made.
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::ops::Range;
+
+/// Elements `a` of the first sequence replaced by elements `b` of the second. One of the
+/// two may be empty, for a plain deletion or insertion.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub a: Range<usize>,
+    pub b: Range<usize>,
+}
+
+/// The changes that turn `a` into `b`, in order and separated by at least one element
+/// the two have in common.
+pub fn diff<T: Eq + Hash>(a: &[T], b: &[T]) -> Vec<Change> {
+    diff_within(a, b, |diagonals| {
+        // GNU diff's limit: about twice the square root of the number of diagonals.
+        let mut too_expensive = 1;
+        let mut diagonals = diagonals;
+        while diagonals != 0 {
+            diagonals >>= 2;
+            too_expensive <<= 1;
+        }
+        too_expensive.max(4096)
+    })
+}
+
+fn diff_within<T: Eq + Hash>(
+    a: &[T],
+    b: &[T],
+    too_expensive: impl Fn(usize) -> isize,
+) -> Vec<Change> {
+    // Elements with no equal in the other sequence are changed whatever happens, so
+    // only the rest are searched, which matters most for inputs with little in common.
+    let (in_a, in_b): (HashSet<&T>, HashSet<&T>) = (a.iter().collect(), b.iter().collect());
+    let kept_a: Vec<usize> = (0..a.len()).filter(|&i| in_b.contains(&a[i])).collect();
+    let kept_b: Vec<usize> = (0..b.len()).filter(|&j| in_a.contains(&b[j])).collect();
+    let sub_a: Vec<&T> = kept_a.iter().map(|&i| &a[i]).collect();
+    let sub_b: Vec<&T> = kept_b.iter().map(|&j| &b[j]).collect();
+    let mut search = Search {
+        a: &sub_a,
+        b: &sub_b,
+        changed_a: vec![false; sub_a.len()],
+        changed_b: vec![false; sub_b.len()],
+        too_expensive: too_expensive(sub_a.len() + sub_b.len() + 3),
+    };
+    search.compare(0..sub_a.len(), 0..sub_b.len());
+
+    // With a false at either end, so that scans stop there.
+    let spread = |len: usize, kept: &[usize], changed: &[bool]| {
+        let mut padded = vec![true; len + 2];
+        padded[0] = false;
+        padded[len + 1] = false;
+        for (&i, &changed) in kept.iter().zip(changed) {
+            padded[i + 1] = changed;
+        }
+        padded
+    };
+    let mut changed_a = spread(a.len(), &kept_a, &search.changed_a);
+    let mut changed_b = spread(b.len(), &kept_b, &search.changed_b);
+    shift_boundaries(a, &mut changed_a, &changed_b);
+    shift_boundaries(b, &mut changed_b, &changed_a);
+
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        let (start_i, start_j) = (i, j);
+        while changed_a[i + 1] {
+            i += 1;
+        }
+        while changed_b[j + 1] {
+            j += 1;
+        }
+        if i > start_i || j > start_j {
+            changes.push(Change {
+                a: start_i..i,
+                b: start_j..j,
+            });
+        } else {
+            i += 1;
+            j += 1;
+        }
+    }
+    changes
+}
+
+/// Slides each run of changed elements of `keys` as far as it will go without changing
+/// the script's length, so that runs merge where they can and otherwise end up as late
+/// as possible, or in line with a run changed in the other sequence. This is GNU diff's
+/// `shift_boundaries`, which makes the output match GNU's where several are as short.
+///
+/// Both `changed` and `other` are padded with a false element at either end.
+fn shift_boundaries<T: PartialEq>(keys: &[T], changed: &mut [bool], other: &[bool]) {
+    let end = keys.len() + 1;
+    let same = |x: usize, y: usize| keys[x - 1] == keys[y - 1];
+    let (mut i, mut j) = (1, 1);
+    loop {
+        // Find the next run of changes, keeping `j` at the same place in the other.
+        while i < end && !changed[i] {
+            while other[j] {
+                j += 1;
+            }
+            j += 1;
+            i += 1;
+        }
+        if i == end {
+            return;
+        }
+        let mut start = i;
+        i += 1;
+        while changed[i] {
+            i += 1;
+        }
+        while other[j] {
+            j += 1;
+        }
+
+        let mut corresponding;
+        loop {
+            let length = i - start;
+            // Move the run back while the element before it matches its last one,
+            // merging it with any run before.
+            while start > 1 && same(start - 1, i - 1) {
+                start -= 1;
+                changed[start] = true;
+                i -= 1;
+                changed[i] = false;
+                while changed[start - 1] {
+                    start -= 1;
+                }
+                j -= 1;
+                while other[j] {
+                    j -= 1;
+                }
+            }
+            // Where the run last ended in line with a run in the other sequence.
+            corresponding = if other[j - 1] { i } else { end };
+            // Then forward while its first element matches the one after it.
+            while i != end && same(start, i) {
+                changed[start] = false;
+                start += 1;
+                changed[i] = true;
+                i += 1;
+                while changed[i] {
+                    i += 1;
+                }
+                j += 1;
+                while other[j] {
+                    corresponding = i;
+                    j += 1;
+                }
+            }
+            if i - start == length {
+                break;
+            }
+        }
+
+        // Back into line with the other sequence's run, if it was ever.
+        while corresponding < i {
+            start -= 1;
+            changed[start] = true;
+            i -= 1;
+            changed[i] = false;
+            j -= 1;
+            while other[j] {
+                j -= 1;
+            }
+        }
+    }
+}
+
+struct Search<'a, T> {
+    a: &'a [T],
+    b: &'a [T],
+    changed_a: Vec<bool>,
+    changed_b: Vec<bool>,
+    /// How many edits the search for a middle snake may go to before settling.
+    too_expensive: isize,
+}
+
+impl<T: PartialEq> Search<'_, T> {
+    fn compare(&mut self, mut a: Range<usize>, mut b: Range<usize>) {
+        while !a.is_empty() && !b.is_empty() && self.a[a.start] == self.b[b.start] {
+            a.start += 1;
+            b.start += 1;
+        }
+        while !a.is_empty() && !b.is_empty() && self.a[a.end - 1] == self.b[b.end - 1] {
+            a.end -= 1;
+            b.end -= 1;
+        }
+        if a.is_empty() || b.is_empty() {
+            self.changed_a[a].fill(true);
+            self.changed_b[b].fill(true);
+            return;
+        }
+        match self.split(a.clone(), b.clone()) {
+            Some((x, y)) => {
+                self.compare(a.start..x, b.start..y);
+                self.compare(x..a.end, y..b.end);
+            }
+            None => {
+                self.changed_a[a].fill(true);
+                self.changed_b[b].fill(true);
+            }
+        }
+    }
+
+    /// A point on a shortest path through the edit graph of two non-empty ranges that
+    /// differ at both ends, with edits on both sides of it.
+    fn split(&self, a: Range<usize>, b: Range<usize>) -> Option<(usize, usize)> {
+        let (n, m) = (a.len() as isize, b.len() as isize);
+        let same =
+            |x: isize, y: isize| self.a[a.start + x as usize] == self.b[b.start + y as usize];
+        let delta = n - m;
+        let odd = delta % 2 != 0;
+        let max = (n + m + 1) / 2 + 1;
+        // The furthest x reached on each diagonal k = x - y, searching forwards, and on
+        // each diagonal of the reversed graph, searching backwards.
+        let offset = max + 1;
+        let mut forward = vec![0; 2 * offset as usize + 1];
+        let mut backward = vec![0; 2 * offset as usize + 1];
+        let at = |k: isize| (k + offset) as usize;
+
+        for d in 0..max {
+            if d >= self.too_expensive {
+                // Settle for the furthest point reached going forwards.
+                return (-(d - 1)..=d - 1)
+                    .step_by(2)
+                    .map(|k| (forward[at(k)], forward[at(k)] - k))
+                    .filter(|&(x, y)| x <= n && y <= m)
+                    .max_by_key(|&(x, y)| x + y)
+                    .filter(|&(x, y)| (x, y) != (n, m))
+                    .map(|(x, y)| (a.start + x as usize, b.start + y as usize));
+            }
+            for k in (-d..=d).step_by(2) {
+                let mut x = if k == -d || (k != d && forward[at(k - 1)] < forward[at(k + 1)]) {
+                    forward[at(k + 1)]
+                } else {
+                    forward[at(k - 1)] + 1
+                };
+                let mut y = x - k;
+                let start = (x, y);
+                while x < n && y < m && same(x, y) {
+                    x += 1;
+                    y += 1;
+                }
+                forward[at(k)] = x;
+                if odd && (k - delta).abs() < d && x + backward[at(delta - k)] >= n {
+                    return Some((a.start + start.0 as usize, b.start + start.1 as usize));
+                }
+            }
+            for k in (-d..=d).step_by(2) {
+                let mut x = if k == -d || (k != d && backward[at(k - 1)] < backward[at(k + 1)]) {
+                    backward[at(k + 1)]
+                } else {
+                    backward[at(k - 1)] + 1
+                };
+                let mut y = x - k;
+                while x < n && y < m && same(n - x - 1, m - y - 1) {
+                    x += 1;
+                    y += 1;
+                }
+                backward[at(k)] = x;
+                if !odd && (k - delta).abs() <= d && x + forward[at(delta - k)] >= n {
+                    return Some((a.start + (n - x) as usize, b.start + (m - y) as usize));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The length of a longest common subsequence, the slow way.
+    fn lcs(a: &[u8], b: &[u8]) -> usize {
+        let mut table = vec![vec![0; b.len() + 1]; a.len() + 1];
+        for i in 0..a.len() {
+            for j in 0..b.len() {
+                table[i + 1][j + 1] = if a[i] == b[j] {
+                    table[i][j] + 1
+                } else {
+                    table[i][j + 1].max(table[i + 1][j])
+                };
+            }
+        }
+        table[a.len()][b.len()]
+    }
+
+    /// Applies `changes` to `a`, checking that what is left alone matches.
+    fn apply(a: &[u8], b: &[u8], changes: &[Change]) -> Vec<u8> {
+        let (mut result, mut i) = (Vec::new(), 0);
+        for change in changes {
+            assert_eq!(change.a.start - i, change.b.start - result.len());
+            result.extend_from_slice(&a[i..change.a.start]);
+            result.extend_from_slice(&b[change.b.clone()]);
+            i = change.a.end;
+        }
+        result.extend_from_slice(&a[i..]);
+        result
+    }
+
+    #[test]
+    fn test_diff() {
+        assert_eq!(diff(b"abc", b"abc"), vec![]);
+        assert_eq!(
+            diff(b"abcd", b"axcdy"),
+            vec![Change { a: 1..2, b: 1..2 }, Change { a: 4..4, b: 4..5 }]
+        );
+        assert_eq!(diff(b"", b"ab"), vec![Change { a: 0..0, b: 0..2 }]);
+        assert_eq!(diff(b"ab", b""), vec![Change { a: 0..2, b: 0..0 }]);
+    }
+
+    #[test]
+    fn test_shortest() {
+        // A small deterministic generator, so that failures can be reproduced.
+        let mut state = 0x2545_f491_u32;
+        let mut next = move |limit: u32| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state % limit
+        };
+        for _ in 0..500 {
+            let len_a = next(30) as usize;
+            let len_b = next(30) as usize;
+            let alphabet = 1 + next(4);
+            let a: Vec<u8> = (0..len_a).map(|_| b'a' + next(alphabet) as u8).collect();
+            let b: Vec<u8> = (0..len_b).map(|_| b'a' + next(alphabet) as u8).collect();
+            let changes = diff(&a, &b);
+            assert_eq!(apply(&a, &b, &changes), b);
+            let edits: usize = changes.iter().map(|c| c.a.len() + c.b.len()).sum();
+            assert_eq!(
+                edits,
+                a.len() + b.len() - 2 * lcs(&a, &b),
+                "{:?} {:?}",
+                a,
+                b
+            );
+            // Settling early still makes a correct script.
+            assert_eq!(apply(&a, &b, &diff_within(&a, &b, |_| 2)), b);
+        }
+    }
+}
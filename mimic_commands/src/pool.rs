@@ -0,0 +1,118 @@
+//! A tiny worker pool for commands that process many independent inputs.
+use std::collections::BTreeMap;
+use std::sync::{mpsc, Mutex};
+use std::thread;
+
+/// Runs `f` on every item using up to `jobs` threads, and hands the results to `emit`
+/// on the calling thread in the original order, as soon as each one's predecessors are done.
+pub fn map_ordered<T, R, F, E>(items: &[T], jobs: usize, f: F, emit: E)
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+    E: FnMut(usize, R),
+{
+    let jobs = jobs.min(items.len());
+    map_ordered_with(items.iter(), jobs, || (), |_, item| f(item), emit);
+}
+
+/// Like [`map_ordered`], but the threads take turns pulling items from `items`, so producing
+/// them (walking a directory tree, say) overlaps with processing them. Each thread calls
+/// `init` once for state of its own, such as a cache that cannot be shared.
+pub fn map_ordered_with<I, S, R, N, F, E>(items: I, jobs: usize, init: N, f: F, mut emit: E)
+where
+    I: Iterator + Send,
+    R: Send,
+    N: Fn() -> S + Sync,
+    F: Fn(&mut S, I::Item) -> R + Sync,
+    E: FnMut(usize, R),
+{
+    if jobs <= 1 {
+        let mut state = init();
+        for (i, item) in items.enumerate() {
+            emit(i, f(&mut state, item));
+        }
+        return;
+    }
+
+    let items = Mutex::new(items.enumerate());
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            let sender = sender.clone();
+            let (items, init, f) = (&items, &init, &f);
+            scope.spawn(move || {
+                let mut state = init();
+                loop {
+                    // The lock is released before the item is processed.
+                    let next = items.lock().ok().and_then(|mut items| items.next());
+                    let (i, item) = match next {
+                        Some(next) => next,
+                        None => break,
+                    };
+                    if sender.send((i, f(&mut state, item))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        let mut pending = BTreeMap::new();
+        let mut next_to_emit = 0;
+        for (i, result) in receiver {
+            pending.insert(i, result);
+            while let Some(result) = pending.remove(&next_to_emit) {
+                emit(next_to_emit, result);
+                next_to_emit += 1;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_map_ordered() {
+        let items: Vec<u64> = (0..50).collect();
+        for jobs in [1, 4, 100] {
+            let mut emitted = Vec::new();
+            map_ordered(
+                &items,
+                jobs,
+                |n| {
+                    // Make later items finish first.
+                    thread::sleep(Duration::from_micros(50 - n));
+                    n * 2
+                },
+                |i, result| emitted.push((i, result)),
+            );
+            let expected: Vec<(usize, u64)> = (0..50).map(|n| (n as usize, n * 2)).collect();
+            assert_eq!(emitted, expected);
+        }
+    }
+
+    #[test]
+    fn test_map_ordered_with() {
+        for jobs in [1, 3] {
+            let mut emitted = Vec::new();
+            map_ordered_with(
+                (0..20u64).map(|n| n * 10),
+                jobs,
+                Vec::new,
+                |seen: &mut Vec<u64>, n| {
+                    // Each thread only sees its own state.
+                    seen.push(n);
+                    assert!(seen.windows(2).all(|w| w[0] < w[1]));
+                    n + 1
+                },
+                |i, result| emitted.push((i, result)),
+            );
+            let expected: Vec<(usize, u64)> = (0..20).map(|n| (n as usize, n * 10 + 1)).collect();
+            assert_eq!(emitted, expected);
+        }
+    }
+}
@@ -0,0 +1,330 @@
+//! The base16, base32 and base64 encodings of RFC 4648, for `base64`, `base32` and
+//! `basenc`.
+//!
+//! Symbols come in groups that each stand for a whole number of bytes: 4 symbols for 3
+//! bytes in base64, 8 for 5 in base32 and 2 for 1 in base16. Both directions work on
+//! whole groups, so input of any size can be fed through in pieces.
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Alphabet {
+    /// RFC 4648 section 4.
+    Base64,
+    /// The URL and file name safe base64 of section 5.
+    Base64Url,
+    /// Section 6.
+    Base32,
+    /// The base32 with the extended hex alphabet of section 7.
+    Base32Hex,
+    /// Section 8, in uppercase.
+    Base16,
+}
+
+/// What each byte stands for in an alphabet, or `INVALID`.
+type Values = [u8; 256];
+
+const INVALID: u8 = 0xff;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64_URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+const BASE32: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const BASE32_HEX: &[u8; 32] = b"0123456789ABCDEFGHIJKLMNOPQRSTUV";
+const BASE16: &[u8; 16] = b"0123456789ABCDEF";
+
+const fn values(symbols: &[u8]) -> Values {
+    let mut values = [INVALID; 256];
+    let mut i = 0;
+    while i < symbols.len() {
+        values[symbols[i] as usize] = i as u8;
+        i += 1;
+    }
+    values
+}
+
+const BASE64_VALUES: Values = values(BASE64);
+const BASE64_URL_VALUES: Values = values(BASE64_URL);
+const BASE32_VALUES: Values = values(BASE32);
+const BASE32_HEX_VALUES: Values = values(BASE32_HEX);
+const BASE16_VALUES: Values = values(BASE16);
+
+/// What fills out the last group of symbols when the data runs out partway.
+const PADDING: u8 = b'=';
+
+impl Alphabet {
+    fn symbols(self) -> &'static [u8] {
+        match self {
+            Alphabet::Base64 => BASE64,
+            Alphabet::Base64Url => BASE64_URL,
+            Alphabet::Base32 => BASE32,
+            Alphabet::Base32Hex => BASE32_HEX,
+            Alphabet::Base16 => BASE16,
+        }
+    }
+
+    fn values(self) -> &'static Values {
+        match self {
+            Alphabet::Base64 => &BASE64_VALUES,
+            Alphabet::Base64Url => &BASE64_URL_VALUES,
+            Alphabet::Base32 => &BASE32_VALUES,
+            Alphabet::Base32Hex => &BASE32_HEX_VALUES,
+            Alphabet::Base16 => &BASE16_VALUES,
+        }
+    }
+
+    /// How many bits each symbol stands for.
+    fn bits(self) -> u32 {
+        match self {
+            Alphabet::Base64 | Alphabet::Base64Url => 6,
+            Alphabet::Base32 | Alphabet::Base32Hex => 5,
+            Alphabet::Base16 => 4,
+        }
+    }
+
+    /// How many bytes a group of symbols stands for.
+    fn group_bytes(self) -> usize {
+        match self {
+            Alphabet::Base64 | Alphabet::Base64Url => 3,
+            Alphabet::Base32 | Alphabet::Base32Hex => 5,
+            Alphabet::Base16 => 1,
+        }
+    }
+
+    /// How many symbols there are in a group.
+    fn group_symbols(self) -> usize {
+        self.group_bytes() * 8 / self.bits() as usize
+    }
+}
+
+/// Appends `data` encoded to `output`. Only the last piece of the data may be anything
+/// but a whole number of groups; its last group is padded out.
+pub fn encode(alphabet: Alphabet, data: &[u8], output: &mut Vec<u8>) {
+    let symbols = alphabet.symbols();
+    let bits = alphabet.bits();
+    let mask = (1 << bits) - 1;
+    for group in data.chunks(alphabet.group_bytes()) {
+        let value = group
+            .iter()
+            .fold(0u64, |value, &byte| value << 8 | u64::from(byte));
+        let size = group.len() as u32 * 8;
+        // The bits of the bytes, filled out with zeros to a whole number of symbols.
+        let count = size.div_ceil(bits);
+        let value = value << (count * bits - size);
+        for i in (0..count).rev() {
+            output.push(symbols[(value >> (i * bits) & mask) as usize]);
+        }
+        let padding = alphabet.group_symbols() - count as usize;
+        output.extend(std::iter::repeat_n(PADDING, padding));
+    }
+}
+
+/// Encoded data that does not decode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InvalidInput;
+
+impl fmt::Display for InvalidInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid input")
+    }
+}
+
+/// Decodes data fed to it in pieces of any size, as GNU coreutils does: newlines are
+/// skipped, padding may be followed by more groups, and the bytes of a group that goes
+/// wrong are given as far as its symbols are valid.
+#[derive(Debug, Clone)]
+pub struct Decoder {
+    alphabet: Alphabet,
+    /// Whether bytes that are not in the alphabet are skipped, rather than invalid.
+    ignore_garbage: bool,
+    /// The symbols of the group so far.
+    group: [u8; 8],
+    len: usize,
+}
+
+impl Decoder {
+    pub fn new(alphabet: Alphabet, ignore_garbage: bool) -> Self {
+        Decoder {
+            alphabet,
+            ignore_garbage,
+            group: [0; 8],
+            len: 0,
+        }
+    }
+
+    /// Appends what `input` decodes to to `output`, up to where it stops being valid.
+    pub fn update(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<(), InvalidInput> {
+        let values = self.alphabet.values();
+        let group_symbols = self.alphabet.group_symbols();
+        for &byte in input {
+            if byte == b'\n'
+                || (self.ignore_garbage && byte != PADDING && values[usize::from(byte)] == INVALID)
+            {
+                continue;
+            }
+            self.group[self.len] = byte;
+            self.len += 1;
+            if self.len == group_symbols {
+                self.len = 0;
+                decode_group(self.alphabet, &self.group[..group_symbols], output)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Fails if the data ended partway through a group. What base64 symbols there are of
+    /// it are decoded first; GNU coreutils gives nothing of base32 groups cut short.
+    pub fn finish(&mut self, output: &mut Vec<u8>) -> Result<(), InvalidInput> {
+        if self.len == 0 {
+            return Ok(());
+        }
+        let len = std::mem::take(&mut self.len);
+        if matches!(self.alphabet, Alphabet::Base64 | Alphabet::Base64Url) {
+            decode_group(self.alphabet, &self.group[..len], output)?;
+        }
+        Err(InvalidInput)
+    }
+}
+
+/// Appends the bytes of a group to `output`, each as soon as its symbols are all valid.
+/// Padding may only start where no more than a symbol's worth of bits is left over, and
+/// must fill out the group.
+fn decode_group(
+    alphabet: Alphabet,
+    group: &[u8],
+    output: &mut Vec<u8>,
+) -> Result<(), InvalidInput> {
+    let values = alphabet.values();
+    let bits = alphabet.bits();
+    let mut value: u32 = 0;
+    let mut count = 0;
+    for (i, &symbol) in group.iter().enumerate() {
+        if symbol == PADDING {
+            let padded = i > 0
+                && count < bits
+                && group.len() == alphabet.group_symbols()
+                && group[i..].iter().all(|&symbol| symbol == PADDING);
+            return if padded { Ok(()) } else { Err(InvalidInput) };
+        }
+        let symbol_value = values[usize::from(symbol)];
+        if symbol_value == INVALID {
+            return Err(InvalidInput);
+        }
+        value = value << bits | u32::from(symbol_value);
+        count += bits;
+        if count >= 8 {
+            count -= 8;
+            output.push((value >> count) as u8);
+            value &= (1 << count) - 1;
+        }
+    }
+    if group.len() < alphabet.group_symbols() {
+        return Err(InvalidInput);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(alphabet: Alphabet, data: &[u8]) -> String {
+        let mut output = Vec::new();
+        encode(alphabet, data, &mut output);
+        String::from_utf8(output).unwrap()
+    }
+
+    fn decoded(alphabet: Alphabet, ignore_garbage: bool, input: &str) -> (String, bool) {
+        let mut decoder = Decoder::new(alphabet, ignore_garbage);
+        let mut output = Vec::new();
+        // A byte at a time, to split the groups every way.
+        let valid = input
+            .as_bytes()
+            .chunks(1)
+            .try_for_each(|piece| decoder.update(piece, &mut output))
+            .and_then(|_| decoder.finish(&mut output))
+            .is_ok();
+        (String::from_utf8_lossy(&output).into_owned(), valid)
+    }
+
+    #[test]
+    fn test_encode() {
+        // The test vectors of RFC 4648 section 10.
+        let data = ["", "f", "fo", "foo", "foob", "fooba", "foobar"];
+        let vectors = [
+            (
+                Alphabet::Base64,
+                [
+                    "", "Zg==", "Zm8=", "Zm9v", "Zm9vYg==", "Zm9vYmE=", "Zm9vYmFy",
+                ],
+            ),
+            (
+                Alphabet::Base32,
+                [
+                    "",
+                    "MY======",
+                    "MZXQ====",
+                    "MZXW6===",
+                    "MZXW6YQ=",
+                    "MZXW6YTB",
+                    "MZXW6YTBOI======",
+                ],
+            ),
+            (
+                Alphabet::Base32Hex,
+                [
+                    "",
+                    "CO======",
+                    "CPNG====",
+                    "CPNMU===",
+                    "CPNMUOG=",
+                    "CPNMUOJ1",
+                    "CPNMUOJ1E8======",
+                ],
+            ),
+            (
+                Alphabet::Base16,
+                [
+                    "",
+                    "66",
+                    "666F",
+                    "666F6F",
+                    "666F6F62",
+                    "666F6F6261",
+                    "666F6F626172",
+                ],
+            ),
+        ];
+        for (alphabet, expected) in vectors {
+            for (data, expected) in data.iter().zip(expected) {
+                assert_eq!(encoded(alphabet, data.as_bytes()), expected);
+                assert_eq!(decoded(alphabet, false, expected), (data.to_string(), true));
+            }
+        }
+        assert_eq!(encoded(Alphabet::Base64Url, &[0xfb, 0xff]), "-_8=");
+    }
+
+    #[test]
+    fn test_decoder() {
+        let ok = |s: &str| (s.to_string(), true);
+        let invalid = |s: &str| (s.to_string(), false);
+        assert_eq!(decoded(Alphabet::Base64, false, "YW\nJj\n"), ok("abc"));
+        assert_eq!(decoded(Alphabet::Base64, false, "YQ==YWJj"), ok("aabc"));
+        // Bits left over past the last byte need not be zero.
+        assert_eq!(decoded(Alphabet::Base64, false, "YR=="), ok("a"));
+        assert_eq!(decoded(Alphabet::Base64, false, "YQ"), invalid("a"));
+        assert_eq!(decoded(Alphabet::Base64, false, "YQ=A"), invalid("a"));
+        assert_eq!(decoded(Alphabet::Base64, false, "Y==="), invalid(""));
+        assert_eq!(decoded(Alphabet::Base64, false, "YW Jj"), invalid("a"));
+        assert_eq!(decoded(Alphabet::Base64, true, "YW Jj"), ok("abc"));
+        assert_eq!(decoded(Alphabet::Base64, true, "YQ=*="), ok("a"));
+        assert_eq!(decoded(Alphabet::Base64Url, false, "/+8="), invalid(""));
+
+        assert_eq!(decoded(Alphabet::Base32, false, "MFRG*GZD"), invalid("ab"));
+        assert_eq!(decoded(Alphabet::Base32, false, "MFRG"), invalid(""));
+        assert_eq!(decoded(Alphabet::Base32, false, "MFR====="), invalid("a"));
+        assert_eq!(decoded(Alphabet::Base32, false, "mfrggzdf"), invalid(""));
+
+        assert_eq!(decoded(Alphabet::Base16, false, "61626"), invalid("ab"));
+        assert_eq!(decoded(Alphabet::Base16, false, "6a"), invalid(""));
+        assert_eq!(decoded(Alphabet::Base16, false, "6="), invalid(""));
+    }
+}
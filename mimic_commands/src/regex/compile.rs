@@ -0,0 +1,130 @@
+//! Compiles a syntax tree into a Thompson NFA: a program of instructions whose `Split`s
+//! are followed in parallel by the matchers.
+use super::parse::{Assertion, Ast, Class};
+use super::{fold_case, Error};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Inst {
+    /// Matches this character, already case-folded when the program ignores case.
+    Char(char),
+    Any,
+    /// Matches a character in `Prog::classes[i]`.
+    Class(usize),
+    Assert(Assertion),
+    /// Continues at both targets.
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Prog {
+    pub insts: Vec<Inst>,
+    pub classes: Vec<Class>,
+    pub ignore_case: bool,
+}
+
+/// Programs bigger than this take too long to run to be worth it.
+const MAX_INSTS: usize = 1 << 20;
+
+impl Prog {
+    pub fn compile(ast: &Ast, ignore_case: bool) -> Result<Self, Error> {
+        let mut prog = Prog {
+            insts: Vec::new(),
+            classes: Vec::new(),
+            ignore_case,
+        };
+        prog.emit(ast)?;
+        prog.insts.push(Inst::Match);
+        Ok(prog)
+    }
+
+    /// Whether a word assertion appears anywhere, which the DFA cannot evaluate.
+    pub fn has_word_assertions(&self) -> bool {
+        self.insts.iter().any(|inst| {
+            matches!(inst, Inst::Assert(assertion) if !matches!(assertion, Assertion::LineStart | Assertion::LineEnd))
+        })
+    }
+
+    pub fn class_contains(&self, index: usize, c: char) -> bool {
+        self.classes[index].contains(c, self.ignore_case)
+    }
+
+    fn push(&mut self, inst: Inst) -> Result<usize, Error> {
+        if self.insts.len() >= MAX_INSTS {
+            return Err(Error::new("Regular expression too big"));
+        }
+        self.insts.push(inst);
+        Ok(self.insts.len() - 1)
+    }
+
+    fn emit(&mut self, ast: &Ast) -> Result<(), Error> {
+        match ast {
+            Ast::Empty => {}
+            Ast::Literal(c) => {
+                let c = if self.ignore_case { fold_case(*c) } else { *c };
+                self.push(Inst::Char(c))?;
+            }
+            Ast::Any => {
+                self.push(Inst::Any)?;
+            }
+            Ast::Class(class) => {
+                self.classes.push(class.clone());
+                self.push(Inst::Class(self.classes.len() - 1))?;
+            }
+            Ast::Assert(assertion) => {
+                self.push(Inst::Assert(*assertion))?;
+            }
+            Ast::Concat(items) => {
+                for item in items {
+                    self.emit(item)?;
+                }
+            }
+            Ast::Alternate(branches) => {
+                // split L1, next; L1: branch; jump end; next: split ...; last branch
+                let mut jumps = Vec::new();
+                for (i, branch) in branches.iter().enumerate() {
+                    if i + 1 < branches.len() {
+                        let split = self.push(Inst::Split(0, 0))?;
+                        self.emit(branch)?;
+                        jumps.push(self.push(Inst::Jump(0))?);
+                        self.insts[split] = Inst::Split(split + 1, self.insts.len());
+                    } else {
+                        self.emit(branch)?;
+                    }
+                }
+                let end = self.insts.len();
+                for jump in jumps {
+                    self.insts[jump] = Inst::Jump(end);
+                }
+            }
+            Ast::Repeat { ast, min, max } => {
+                for _ in 0..*min {
+                    self.emit(ast)?;
+                }
+                match max {
+                    None => {
+                        // loop: split body, end; body; jump loop
+                        let split = self.push(Inst::Split(0, 0))?;
+                        self.emit(ast)?;
+                        self.push(Inst::Jump(split))?;
+                        self.insts[split] = Inst::Split(split + 1, self.insts.len());
+                    }
+                    Some(max) => {
+                        // Nested optionals, so that a missing copy skips all the later ones.
+                        let mut splits = Vec::new();
+                        for _ in *min..*max {
+                            splits.push(self.push(Inst::Split(0, 0))?);
+                            self.emit(ast)?;
+                        }
+                        let end = self.insts.len();
+                        for split in splits {
+                            self.insts[split] = Inst::Split(split + 1, end);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
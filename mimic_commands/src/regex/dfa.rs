@@ -0,0 +1,203 @@
+//! A DFA built lazily from the NFA, one state per set of NFA threads actually reached,
+//! for answering "does this line match" in one pass and one lookup per character.
+//!
+//! It only understands the line anchors, so programs with word assertions use the NFA.
+use super::compile::{Inst, Prog};
+use super::parse::Assertion;
+use super::{decode, step, Unit};
+use std::collections::HashMap;
+
+/// Transitions not computed yet.
+const UNKNOWN: u32 = u32::MAX;
+/// Marks a transition in the ASCII table into a matching state, so the inner loop only
+/// needs one comparison to know the table entry is an ordinary state.
+const MATCHING: u32 = 1 << 31;
+/// When the cache grows past this many states it is thrown away and rebuilt as needed.
+const MAX_STATES: usize = 4096;
+
+#[derive(Debug, Clone)]
+struct State {
+    /// NFA instructions waiting for input, plus `$` assertions waiting for the end of the line.
+    pcs: Vec<usize>,
+    is_match: bool,
+    /// Whether the line matches if it ends in this state.
+    matches_at_end: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Dfa {
+    states: Vec<State>,
+    ids: HashMap<Vec<usize>, u32>,
+    /// Transitions on ASCII characters, by far the most common ones: 128 per state, in one
+    /// table so the inner loop does a single lookup per byte.
+    ascii: Vec<u32>,
+    /// Transitions on everything else.
+    other: HashMap<(u32, Unit), u32>,
+    start: Option<u32>,
+}
+
+impl Dfa {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_match(&mut self, prog: &Prog, haystack: &[u8]) -> bool {
+        let mut state = match self.start {
+            Some(start) => start,
+            None => {
+                let mut pcs = Vec::new();
+                closure(prog, 0, true, &mut pcs);
+                let start = self.add_state(prog, pcs);
+                self.start = Some(start);
+                start
+            }
+        };
+        if self.states[state as usize].is_match {
+            return true;
+        }
+
+        let mut pos = 0;
+        while pos < haystack.len() {
+            let b = haystack[pos];
+            if b < 0x80 {
+                let next = self.ascii[state as usize * 128 + usize::from(b)];
+                if next < MATCHING {
+                    state = next;
+                } else if next == UNKNOWN {
+                    state = self.transition(prog, state, Unit::Char(char::from(b)));
+                    if self.states[state as usize].is_match {
+                        return true;
+                    }
+                } else {
+                    return true;
+                }
+                pos += 1;
+            } else {
+                let (unit, len) = decode(haystack, pos);
+                state = match self.other.get(&(state, unit)) {
+                    Some(next) => *next,
+                    None => self.transition(prog, state, unit),
+                };
+                if self.states[state as usize].is_match {
+                    return true;
+                }
+                pos += len;
+            }
+        }
+        let state = &self.states[state as usize];
+        state.is_match || state.matches_at_end
+    }
+
+    fn transition(&mut self, prog: &Prog, from: u32, unit: Unit) -> u32 {
+        let mut pcs = Vec::new();
+        for &pc in &self.states[from as usize].pcs {
+            if step(prog, &prog.insts[pc], unit) {
+                closure(prog, pc + 1, false, &mut pcs);
+            }
+        }
+        // Unanchored: a new attempt may start after every character.
+        closure(prog, 0, false, &mut pcs);
+        pcs.sort_unstable();
+        pcs.dedup();
+
+        let from = if self.states.len() >= MAX_STATES {
+            // Keep the state we are in; everything else can be recomputed.
+            let current = self.states[from as usize].pcs.clone();
+            self.states.clear();
+            self.ids.clear();
+            self.ascii.clear();
+            self.other.clear();
+            self.start = None;
+            self.add_state(prog, current)
+        } else {
+            from
+        };
+        let to = self.add_state(prog, pcs);
+        match unit {
+            Unit::Char(c) if c.is_ascii() => {
+                let marked = if self.states[to as usize].is_match {
+                    to | MATCHING
+                } else {
+                    to
+                };
+                self.ascii[from as usize * 128 + c as usize] = marked;
+            }
+            _ => {
+                self.other.insert((from, unit), to);
+            }
+        }
+        to
+    }
+
+    fn add_state(&mut self, prog: &Prog, mut pcs: Vec<usize>) -> u32 {
+        pcs.sort_unstable();
+        pcs.dedup();
+        if let Some(id) = self.ids.get(&pcs) {
+            return *id;
+        }
+
+        let is_match = pcs.iter().any(|pc| prog.insts[*pc] == Inst::Match);
+        let mut at_end = Vec::new();
+        for &pc in &pcs {
+            if prog.insts[pc] == Inst::Assert(Assertion::LineEnd) {
+                closure_at_end(prog, pc + 1, &mut at_end);
+            }
+        }
+        let matches_at_end = at_end.iter().any(|pc| prog.insts[*pc] == Inst::Match);
+
+        let id = self.states.len() as u32;
+        self.ids.insert(pcs.clone(), id);
+        self.states.push(State {
+            pcs,
+            is_match,
+            matches_at_end,
+        });
+        self.ascii.extend_from_slice(&[UNKNOWN; 128]);
+        id
+    }
+}
+
+/// Adds the instructions reachable from `pc` without input that either consume input,
+/// match, or wait for the end of the line.
+fn closure(prog: &Prog, pc: usize, at_line_start: bool, pcs: &mut Vec<usize>) {
+    let mut seen = vec![false; prog.insts.len()];
+    let mut stack = vec![pc];
+    while let Some(pc) = stack.pop() {
+        if std::mem::replace(&mut seen[pc], true) {
+            continue;
+        }
+        match &prog.insts[pc] {
+            Inst::Jump(target) => stack.push(*target),
+            Inst::Split(first, second) => {
+                stack.push(*second);
+                stack.push(*first);
+            }
+            Inst::Assert(Assertion::LineStart) => {
+                if at_line_start {
+                    stack.push(pc + 1);
+                }
+            }
+            _ => pcs.push(pc),
+        }
+    }
+}
+
+/// Like `closure`, once the end of the line has been reached.
+fn closure_at_end(prog: &Prog, pc: usize, pcs: &mut Vec<usize>) {
+    let mut seen = vec![false; prog.insts.len()];
+    let mut stack = vec![pc];
+    while let Some(pc) = stack.pop() {
+        if std::mem::replace(&mut seen[pc], true) {
+            continue;
+        }
+        match &prog.insts[pc] {
+            Inst::Jump(target) => stack.push(*target),
+            Inst::Split(first, second) => {
+                stack.push(*second);
+                stack.push(*first);
+            }
+            Inst::Assert(Assertion::LineEnd) => stack.push(pc + 1),
+            _ => pcs.push(pc),
+        }
+    }
+}
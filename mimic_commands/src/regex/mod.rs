@@ -0,0 +1,410 @@
+//! A small regular expression engine for `grep`, so the crate stays dependency-free.
+//!
+//! Patterns in POSIX basic or extended syntax (with the GNU extensions), or in GNU regex's
+//! default syntax, are compiled to a Thompson NFA. Whether a line matches at all is answered
+//! by a DFA built lazily from the NFA; finding where a match is needs the NFA itself,
+//! simulated Pike-VM style. Input is matched as UTF-8 characters, and bytes that are not
+//! valid UTF-8 match nothing, as in GNU grep in a UTF-8 locale.
+mod compile;
+mod dfa;
+mod parse;
+mod pike;
+
+use compile::{Inst, Prog};
+use dfa::Dfa;
+use parse::{Assertion, Ast, Class};
+use std::cell::RefCell;
+use std::fmt;
+
+/// How patterns are written.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Syntax {
+    /// POSIX basic regular expressions (`grep -G`).
+    Basic,
+    /// POSIX extended regular expressions (`grep -E`).
+    Extended,
+    /// Plain strings (`grep -F`).
+    Fixed,
+    /// GNU regex's default syntax (`tac -r`): groups and alternation are written as in a
+    /// basic expression, but `+` and `?` are bare operators and there are no intervals.
+    Emacs,
+}
+
+/// A pattern that could not be compiled, worded like GNU grep's messages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    message: String,
+}
+
+impl Error {
+    fn new(message: &str) -> Self {
+        Error {
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Compiles patterns with the options `grep` offers.
+#[derive(Debug, Clone)]
+pub struct RegexBuilder {
+    syntax: Syntax,
+    ignore_case: bool,
+    whole_words: bool,
+    whole_lines: bool,
+}
+
+impl RegexBuilder {
+    pub fn new(syntax: Syntax) -> Self {
+        RegexBuilder {
+            syntax,
+            ignore_case: false,
+            whole_words: false,
+            whole_lines: false,
+        }
+    }
+
+    pub fn ignore_case(mut self, yes: bool) -> Self {
+        self.ignore_case = yes;
+        self
+    }
+
+    /// Only match whole words, like `grep -w`.
+    pub fn whole_words(mut self, yes: bool) -> Self {
+        self.whole_words = yes;
+        self
+    }
+
+    /// Only match whole lines, like `grep -x`. Takes precedence over `whole_words`.
+    pub fn whole_lines(mut self, yes: bool) -> Self {
+        self.whole_lines = yes;
+        self
+    }
+
+    /// Compiles a regex matching any of `patterns`. With no patterns at all it matches nothing.
+    pub fn build<S: AsRef<str>>(&self, patterns: &[S]) -> Result<Regex, Error> {
+        let mut warnings = Vec::new();
+        let mut branches = patterns
+            .iter()
+            .map(|pattern| parse::parse(pattern.as_ref(), self.syntax, &mut warnings))
+            .collect::<Result<Vec<_>, _>>()?;
+        let ast = match branches.len() {
+            0 => Ast::Class(Class::default()),
+            1 => branches.pop().unwrap_or(Ast::Empty),
+            _ => Ast::Alternate(branches),
+        };
+        let ast = if self.whole_lines {
+            Ast::Concat(vec![
+                Ast::Assert(Assertion::LineStart),
+                ast,
+                Ast::Assert(Assertion::LineEnd),
+            ])
+        } else if self.whole_words {
+            Ast::Concat(vec![
+                Ast::Assert(Assertion::NoWordBefore),
+                ast,
+                Ast::Assert(Assertion::NoWordAfter),
+            ])
+        } else {
+            ast
+        };
+
+        let prog = Prog::compile(&ast, self.ignore_case)?;
+        let dfa = if prog.has_word_assertions() {
+            None
+        } else {
+            Some(RefCell::new(Dfa::new()))
+        };
+        Ok(Regex {
+            prog,
+            dfa,
+            warnings,
+        })
+    }
+}
+
+/// A compiled regex. It caches DFA states as it goes, so it is not `Sync`; threads should
+/// each use a clone.
+#[derive(Debug, Clone)]
+pub struct Regex {
+    prog: Prog,
+    dfa: Option<RefCell<Dfa>>,
+    warnings: Vec<String>,
+}
+
+impl Regex {
+    /// Whether `haystack`, one line without its terminator, contains a match.
+    pub fn is_match(&self, haystack: &[u8]) -> bool {
+        match &self.dfa {
+            Some(dfa) => dfa.borrow_mut().is_match(&self.prog, haystack),
+            None => self.find_at(haystack, 0).is_some(),
+        }
+    }
+
+    /// The leftmost-longest match starting at or after `start`, as a byte range.
+    pub fn find_at(&self, haystack: &[u8], start: usize) -> Option<(usize, usize)> {
+        pike::find_at(&self.prog, haystack, start)
+    }
+
+    /// Every match from left to right, without overlaps. After an empty match the search
+    /// resumes one character later.
+    pub fn find_iter<'r, 'h>(&'r self, haystack: &'h [u8]) -> FindIter<'r, 'h> {
+        FindIter {
+            regex: self,
+            haystack,
+            pos: Some(0),
+        }
+    }
+
+    /// Things GNU grep would warn about, such as `* at start of expression`.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+}
+
+/// The iterator returned by [`Regex::find_iter`].
+#[derive(Debug)]
+pub struct FindIter<'r, 'h> {
+    regex: &'r Regex,
+    haystack: &'h [u8],
+    /// Where to search next, or `None` once the end has been passed.
+    pos: Option<usize>,
+}
+
+impl Iterator for FindIter<'_, '_> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (start, end) = self.regex.find_at(self.haystack, self.pos?)?;
+        self.pos = if end > start {
+            Some(end)
+        } else if end < self.haystack.len() {
+            Some(end + decode(self.haystack, end).1)
+        } else {
+            None
+        };
+        Some((start, end))
+    }
+}
+
+/// One step of input: a UTF-8 encoded character, or a byte that is not part of one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Unit {
+    Char(char),
+    Byte(u8),
+}
+
+impl Unit {
+    fn is_word(unit: Option<Unit>) -> bool {
+        matches!(unit, Some(Unit::Char(c)) if parse::is_word_char(c))
+    }
+}
+
+/// Decodes the unit at `i` and returns it with its length in bytes.
+pub(crate) fn decode(haystack: &[u8], i: usize) -> (Unit, usize) {
+    let b = haystack[i];
+    let len = match b {
+        0x00..=0x7F => return (Unit::Char(char::from(b)), 1),
+        0xC2..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF4 => 4,
+        _ => return (Unit::Byte(b), 1),
+    };
+    let decoded = haystack
+        .get(i..i + len)
+        .and_then(|bytes| std::str::from_utf8(bytes).ok())
+        .and_then(|s| s.chars().next());
+    match decoded {
+        Some(c) => (Unit::Char(c), len),
+        None => (Unit::Byte(b), 1),
+    }
+}
+
+/// The unit that ends right at `i`, if any.
+pub(crate) fn decode_before(haystack: &[u8], i: usize) -> Option<Unit> {
+    if i == 0 {
+        return None;
+    }
+    for len in 1..=4.min(i) {
+        let (unit, unit_len) = decode(haystack, i - len);
+        if unit_len == len {
+            return Some(unit);
+        }
+    }
+    Some(Unit::Byte(haystack[i - 1]))
+}
+
+/// Folds a character for case-insensitive comparison.
+pub(crate) fn fold_case(c: char) -> char {
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(lower), None) => lower,
+        _ => c,
+    }
+}
+
+/// Whether `inst` consumes `unit`.
+pub(crate) fn step(prog: &Prog, inst: &Inst, unit: Unit) -> bool {
+    let c = match unit {
+        Unit::Char(c) => c,
+        Unit::Byte(_) => return false,
+    };
+    match inst {
+        Inst::Char(expected) if prog.ignore_case => fold_case(c) == *expected,
+        Inst::Char(expected) => c == *expected,
+        Inst::Any => true,
+        Inst::Class(index) => prog.class_contains(*index, c),
+        _ => false,
+    }
+}
+
+/// Whether `assertion` holds between `prev` and `next`; `None` is the edge of the line.
+pub(crate) fn holds(assertion: Assertion, prev: Option<Unit>, next: Option<Unit>) -> bool {
+    let (before, after) = (Unit::is_word(prev), Unit::is_word(next));
+    match assertion {
+        Assertion::LineStart => prev.is_none(),
+        Assertion::LineEnd => next.is_none(),
+        Assertion::WordStart => !before && after,
+        Assertion::WordEnd => before && !after,
+        Assertion::WordBoundary => before != after,
+        Assertion::NotWordBoundary => before == after,
+        Assertion::NoWordBefore => !before,
+        Assertion::NoWordAfter => !after,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn regex(syntax: Syntax, pattern: &str) -> Regex {
+        RegexBuilder::new(syntax).build(&[pattern]).unwrap()
+    }
+
+    /// Checks the DFA and the NFA agree, and returns the first match.
+    fn find(regex: &Regex, haystack: &str) -> Option<(usize, usize)> {
+        let found = regex.find_at(haystack.as_bytes(), 0);
+        assert_eq!(
+            regex.is_match(haystack.as_bytes()),
+            found.is_some(),
+            "{:?}",
+            haystack
+        );
+        found
+    }
+
+    #[test]
+    fn test_leftmost_longest() {
+        let re = regex(Syntax::Extended, "a|ab|abc");
+        assert_eq!(find(&re, "xabcd"), Some((1, 4)));
+        let re = regex(Syntax::Extended, "b+|ab");
+        assert_eq!(find(&re, "abbb"), Some((0, 2)));
+        let re = regex(Syntax::Basic, "x*");
+        assert_eq!(find(&re, "abc"), Some((0, 0)));
+        assert_eq!(re.find_at(b"axxb", 1), Some((1, 3)));
+        let re = regex(Syntax::Extended, "(ab){2,3}c?");
+        assert_eq!(find(&re, "abababababc"), Some((0, 6)));
+        assert_eq!(find(&re, "abab c"), Some((0, 4)));
+        assert_eq!(find(&re, "ab"), None);
+    }
+
+    #[test]
+    fn test_anchors_and_words() {
+        let re = regex(Syntax::Basic, "^ab$");
+        assert_eq!(find(&re, "ab"), Some((0, 2)));
+        assert_eq!(find(&re, "aab"), None);
+        let re = regex(Syntax::Basic, "^$");
+        assert_eq!(find(&re, ""), Some((0, 0)));
+        assert_eq!(find(&re, "a"), None);
+        let re = regex(Syntax::Basic, "\\<fo*\\>");
+        assert_eq!(find(&re, "xfoo foo"), Some((5, 8)));
+        let re = regex(Syntax::Basic, "o\\b");
+        assert_eq!(find(&re, "foo bar"), Some((2, 3)));
+
+        let words = RegexBuilder::new(Syntax::Fixed)
+            .whole_words(true)
+            .build(&["foo"])
+            .unwrap();
+        assert_eq!(find(&words, "foobar foo_ foo."), Some((12, 15)));
+        assert_eq!(find(&words, "foobar"), None);
+        let lines = RegexBuilder::new(Syntax::Basic)
+            .whole_lines(true)
+            .build(&["a", "b*"])
+            .unwrap();
+        assert_eq!(find(&lines, "bbb"), Some((0, 3)));
+        assert_eq!(find(&lines, "ab"), None);
+    }
+
+    #[test]
+    fn test_classes_and_case() {
+        let re = regex(Syntax::Extended, "[[:digit:]]+[^[:space:]a-c]");
+        assert_eq!(find(&re, "x 123 12d"), Some((2, 5)));
+        assert_eq!(find(&re, "x 1 2b c"), None);
+        let re = regex(Syntax::Basic, "\\w\\W\\s\\S");
+        assert_eq!(find(&re, "a- x"), Some((0, 4)));
+
+        let re = RegexBuilder::new(Syntax::Basic)
+            .ignore_case(true)
+            .build(&["straße[a-c]"])
+            .unwrap();
+        assert_eq!(find(&re, "xStraßeB"), Some((1, 9)));
+        let re = RegexBuilder::new(Syntax::Extended)
+            .ignore_case(true)
+            .build(&["[[:upper:]]ÄÖ"])
+            .unwrap();
+        assert_eq!(find(&re, "aäö"), Some((0, 5)));
+
+        // A negated class leaves out every case of what it lists.
+        let caseless = |pattern| {
+            RegexBuilder::new(Syntax::Basic)
+                .ignore_case(true)
+                .build(&[pattern])
+                .unwrap()
+        };
+        assert_eq!(find(&caseless("[^a-z ]"), "foo bar"), None);
+        assert_eq!(find(&caseless("[^a-z]"), "FOO"), None);
+        assert_eq!(find(&caseless("[^a-z]"), "FO1"), Some((2, 3)));
+        assert_eq!(find(&caseless("[^[:upper:]]"), "abc"), None);
+    }
+
+    #[test]
+    fn test_utf8() {
+        let re = regex(Syntax::Basic, "a.c");
+        assert_eq!(find(&re, "aあc"), Some((0, 5)));
+        assert_eq!(find(&re, "a\u{ff}c"), Some((0, 4)));
+        assert!(!re.is_match(b"a\xffc"));
+        assert_eq!(re.find_at(b"a\xffc", 0), None);
+        let re = regex(Syntax::Fixed, "b");
+        assert!(re.is_match(b"\xffb\xe3\x81"));
+    }
+
+    #[test]
+    fn test_multiple_patterns() {
+        let re = RegexBuilder::new(Syntax::Fixed)
+            .build(&["a.b", "c"])
+            .unwrap();
+        assert_eq!(find(&re, "axb c a.b"), Some((4, 5)));
+        let none = RegexBuilder::new(Syntax::Basic).build::<&str>(&[]).unwrap();
+        assert_eq!(find(&none, ""), None);
+        assert_eq!(find(&none, "abc"), None);
+        let empty = regex(Syntax::Basic, "");
+        assert_eq!(find(&empty, "abc"), Some((0, 0)));
+    }
+
+    #[test]
+    fn test_find_iter() {
+        let re = regex(Syntax::Basic, "a*");
+        let found: Vec<_> = re.find_iter("baaあa".as_bytes()).collect();
+        assert_eq!(found, vec![(0, 0), (1, 3), (3, 3), (6, 7), (7, 7)]);
+        let re = regex(Syntax::Extended, "[0-9]+");
+        let found: Vec<_> = re.find_iter(b"a1 22 333").collect();
+        assert_eq!(found, vec![(1, 2), (3, 5), (6, 9)]);
+    }
+}
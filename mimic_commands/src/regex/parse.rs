@@ -0,0 +1,663 @@
+//! Parses POSIX basic and extended regular expressions, with the GNU extensions, into a syntax tree.
+use super::{Error, Syntax};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Ast {
+    Empty,
+    Literal(char),
+    /// `.`, any character.
+    Any,
+    Class(Class),
+    Assert(Assertion),
+    Concat(Vec<Ast>),
+    Alternate(Vec<Ast>),
+    Repeat {
+        ast: Box<Ast>,
+        min: u32,
+        max: Option<u32>,
+    },
+}
+
+/// Zero-width conditions on the characters around a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Assertion {
+    /// `^`
+    LineStart,
+    /// `$`
+    LineEnd,
+    /// `\<`
+    WordStart,
+    /// `\>`
+    WordEnd,
+    /// `\b`
+    WordBoundary,
+    /// `\B`
+    NotWordBoundary,
+    /// Not preceded by a word character, for `grep -w`.
+    NoWordBefore,
+    /// Not followed by a word character, for `grep -w`.
+    NoWordAfter,
+}
+
+/// A bracket expression such as `[^a-z[:digit:]]`, or one of `\w`, `\W`, `\s` and `\S`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct Class {
+    pub negated: bool,
+    pub ranges: Vec<(char, char)>,
+    pub named: Vec<Named>,
+}
+
+/// The POSIX character classes, plus `Word` for `\w`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Named {
+    Alnum,
+    Alpha,
+    Blank,
+    Cntrl,
+    Digit,
+    Graph,
+    Lower,
+    Print,
+    Punct,
+    Space,
+    Upper,
+    Xdigit,
+    Word,
+}
+
+impl Named {
+    fn from_name(name: &str) -> Option<Self> {
+        let named = match name {
+            "alnum" => Named::Alnum,
+            "alpha" => Named::Alpha,
+            "blank" => Named::Blank,
+            "cntrl" => Named::Cntrl,
+            "digit" => Named::Digit,
+            "graph" => Named::Graph,
+            "lower" => Named::Lower,
+            "print" => Named::Print,
+            "punct" => Named::Punct,
+            "space" => Named::Space,
+            "upper" => Named::Upper,
+            "xdigit" => Named::Xdigit,
+            _ => return None,
+        };
+        Some(named)
+    }
+
+    fn contains(self, c: char) -> bool {
+        match self {
+            Named::Alnum => c.is_alphanumeric(),
+            Named::Alpha => c.is_alphabetic(),
+            Named::Blank => c == ' ' || c == '\t',
+            Named::Cntrl => c.is_control(),
+            Named::Digit => c.is_ascii_digit(),
+            Named::Graph => !c.is_control() && !c.is_whitespace(),
+            Named::Lower => c.is_lowercase(),
+            Named::Print => !c.is_control(),
+            Named::Punct => !c.is_alphanumeric() && !c.is_whitespace() && !c.is_control(),
+            Named::Space => c.is_whitespace(),
+            Named::Upper => c.is_uppercase(),
+            Named::Xdigit => c.is_ascii_hexdigit(),
+            Named::Word => is_word_char(c),
+        }
+    }
+}
+
+pub(crate) fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl Class {
+    fn named(named: Named, negated: bool) -> Self {
+        Class {
+            negated,
+            ranges: Vec::new(),
+            named: vec![named],
+        }
+    }
+
+    /// Whether `c` is in the class, or when ignoring case, any of its cases is.
+    pub fn contains(&self, c: char, ignore_case: bool) -> bool {
+        let has = |c: char| {
+            self.ranges.iter().any(|(lo, hi)| (*lo..=*hi).contains(&c))
+                || self.named.iter().any(|named| named.contains(c))
+        };
+        let found =
+            has(c) || (ignore_case && (c.to_lowercase().any(has) || c.to_uppercase().any(has)));
+        found != self.negated
+    }
+}
+
+/// Parses one pattern. Warnings GNU grep would print are appended to `warnings`.
+pub(crate) fn parse(
+    pattern: &str,
+    syntax: Syntax,
+    warnings: &mut Vec<String>,
+) -> Result<Ast, Error> {
+    if syntax == Syntax::Fixed {
+        return Ok(Ast::Concat(pattern.chars().map(Ast::Literal).collect()));
+    }
+
+    let mut parser = Parser {
+        chars: pattern.chars().collect(),
+        pos: 0,
+        extended: syntax == Syntax::Extended,
+        bare_operators: syntax != Syntax::Basic,
+        intervals: syntax != Syntax::Emacs,
+        depth: 0,
+        warnings,
+    };
+    let ast = parser.parse_alternation()?;
+    if parser.pos < parser.chars.len() {
+        // Only an unmatched `\)` can stop the top level early.
+        return Err(Error::new("Unmatched ) or \\)"));
+    }
+    Ok(ast)
+}
+
+struct Parser<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    extended: bool,
+    /// Whether `+` and `?` are operators without a backslash, and literals with one.
+    bare_operators: bool,
+    /// Whether `{m,n}` (or `\{m,n\}`) is an interval rather than literal text.
+    intervals: bool,
+    /// How many groups are open.
+    depth: usize,
+    warnings: &'a mut Vec<String>,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    /// Whether the input continues with `s`, without consuming it.
+    fn looking_at(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(i, c)| self.peek_at(i) == Some(c))
+    }
+
+    fn at_alternation(&self) -> bool {
+        if self.extended {
+            self.looking_at("|")
+        } else {
+            self.looking_at("\\|")
+        }
+    }
+
+    fn at_group_end(&self) -> bool {
+        if self.extended {
+            self.depth > 0 && self.looking_at(")")
+        } else {
+            self.looking_at("\\)")
+        }
+    }
+
+    fn parse_alternation(&mut self) -> Result<Ast, Error> {
+        let mut branches = vec![self.parse_concat()?];
+        while self.at_alternation() {
+            self.pos += if self.extended { 1 } else { 2 };
+            branches.push(self.parse_concat()?);
+        }
+        Ok(if branches.len() == 1 {
+            branches.pop().unwrap_or(Ast::Empty)
+        } else {
+            Ast::Alternate(branches)
+        })
+    }
+
+    fn parse_concat(&mut self) -> Result<Ast, Error> {
+        let mut items: Vec<Ast> = Vec::new();
+        while self.peek().is_some() && !self.at_alternation() && !self.at_group_end() {
+            let is_first = items.is_empty();
+            if let Some(atom) = self.parse_atom(is_first)? {
+                items.push(atom);
+            }
+            // In a basic expression, `*` right after a leading `^` is an ordinary character.
+            let nothing_before = items.is_empty()
+                || (!self.extended && items == [Ast::Assert(Assertion::LineStart)]);
+            while let Some((min, max)) = self.parse_repetition(nothing_before)? {
+                if let Some(last) = items.pop() {
+                    items.push(Ast::Repeat {
+                        ast: Box::new(last),
+                        min,
+                        max,
+                    });
+                }
+            }
+        }
+        Ok(match items.len() {
+            0 => Ast::Empty,
+            1 => items.pop().unwrap_or(Ast::Empty),
+            _ => Ast::Concat(items),
+        })
+    }
+
+    /// Parses one atom. `None` means something that matches nothing at all was skipped,
+    /// such as a repetition operator at the start of an extended expression.
+    fn parse_atom(&mut self, is_first: bool) -> Result<Option<Ast>, Error> {
+        let c = match self.peek() {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+        self.pos += 1;
+        let atom = match c {
+            '.' => Ast::Any,
+            '[' => Ast::Class(self.parse_bracket()?),
+            '^' if self.extended || is_first => Ast::Assert(Assertion::LineStart),
+            '$' if self.extended || self.at_basic_end() => Ast::Assert(Assertion::LineEnd),
+            '*' if !self.extended && is_first => Ast::Literal('*'),
+            '+' | '?' if !self.extended && self.bare_operators && is_first => Ast::Literal(c),
+            '*' | '+' | '?' if self.extended => {
+                self.warnings.push(format!("{} at start of expression", c));
+                return Ok(None);
+            }
+            '{' if self.extended && self.interval_follows() => {
+                self.warnings
+                    .push("{...} at start of expression".to_string());
+                self.pos -= 1;
+                self.parse_repetition(true)?;
+                return Ok(None);
+            }
+            '(' if self.extended => self.parse_group()?,
+            ')' if self.extended => Ast::Literal(')'),
+            '\\' => return self.parse_escape(is_first).map(Some),
+            c => Ast::Literal(c),
+        };
+        Ok(Some(atom))
+    }
+
+    /// In a basic expression `$` is an anchor only at the end of the pattern or a branch.
+    fn at_basic_end(&self) -> bool {
+        self.peek().is_none() || self.at_alternation() || self.looking_at("\\)")
+    }
+
+    fn parse_group(&mut self) -> Result<Ast, Error> {
+        self.depth += 1;
+        let ast = self.parse_alternation()?;
+        let close = if self.extended { ")" } else { "\\)" };
+        if !self.looking_at(close) {
+            return Err(Error::new("Unmatched ( or \\("));
+        }
+        self.pos += close.len();
+        self.depth -= 1;
+        Ok(ast)
+    }
+
+    fn parse_escape(&mut self, is_first: bool) -> Result<Ast, Error> {
+        let c = match self.peek() {
+            Some(c) => c,
+            None => return Err(Error::new("Trailing backslash")),
+        };
+        self.pos += 1;
+        let ast = match c {
+            '(' if !self.extended => self.parse_group()?,
+            ')' if !self.extended => return Err(Error::new("Unmatched ) or \\)")),
+            '{' if !self.extended && is_first => Ast::Literal('{'),
+            '+' | '?' if !self.extended && is_first => Ast::Literal(c),
+            '1'..='9' => return Err(Error::new("back-references are not supported")),
+            'w' => Ast::Class(Class::named(Named::Word, false)),
+            'W' => Ast::Class(Class::named(Named::Word, true)),
+            's' => Ast::Class(Class::named(Named::Space, false)),
+            'S' => Ast::Class(Class::named(Named::Space, true)),
+            'b' => Ast::Assert(Assertion::WordBoundary),
+            'B' => Ast::Assert(Assertion::NotWordBoundary),
+            '<' => Ast::Assert(Assertion::WordStart),
+            '>' => Ast::Assert(Assertion::WordEnd),
+            '`' => Ast::Assert(Assertion::LineStart),
+            '\'' => Ast::Assert(Assertion::LineEnd),
+            c => Ast::Literal(c),
+        };
+        Ok(ast)
+    }
+
+    /// Parses a postfix operator, if one follows.
+    fn parse_repetition(
+        &mut self,
+        nothing_before: bool,
+    ) -> Result<Option<(u32, Option<u32>)>, Error> {
+        // A basic expression takes these literally instead; see `parse_atom`.
+        if nothing_before && !self.extended {
+            return Ok(None);
+        }
+        let (repetition, len) = match (self.peek(), self.peek_at(1), self.bare_operators) {
+            (Some('*'), _, _) => ((0, None), 1),
+            (Some('+'), _, true) => ((1, None), 1),
+            (Some('?'), _, true) => ((0, Some(1)), 1),
+            (Some('\\'), Some('+'), false) => ((1, None), 2),
+            (Some('\\'), Some('?'), false) => ((0, Some(1)), 2),
+            (Some('{'), _, _) if self.extended && self.interval_follows() => {
+                self.pos += 1;
+                return self.parse_interval().map(Some);
+            }
+            (Some('\\'), Some('{'), _) if !self.extended && self.intervals => {
+                self.pos += 2;
+                return self.parse_interval().map(Some);
+            }
+            _ => return Ok(None),
+        };
+        self.pos += len;
+        Ok(Some(repetition))
+    }
+
+    /// In an extended expression, a `{` that does not start a valid interval is an ordinary character.
+    fn interval_follows(&self) -> bool {
+        let mut i = self.pos + 1;
+        let digits_from = |i: &mut usize| {
+            let start = *i;
+            while self.chars.get(*i).is_some_and(|c| c.is_ascii_digit()) {
+                *i += 1;
+            }
+            *i > start
+        };
+        let has_min = digits_from(&mut i);
+        if self.chars.get(i) == Some(&',') {
+            i += 1;
+            digits_from(&mut i);
+        } else if !has_min {
+            return false;
+        }
+        self.chars.get(i) == Some(&'}')
+    }
+
+    /// Parses `m}`, `m,}`, `,n}` or `m,n}` after the opening brace.
+    fn parse_interval(&mut self) -> Result<(u32, Option<u32>), Error> {
+        const MAX: u32 = 32767;
+        let invalid = || Error::new("Invalid content of \\{\\}");
+        let close = if self.extended { "}" } else { "\\}" };
+        let closed = (self.pos..self.chars.len()).any(|i| {
+            close
+                .chars()
+                .enumerate()
+                .all(|(j, c)| self.chars.get(i + j) == Some(&c))
+        });
+        if !closed {
+            return Err(Error::new("Unmatched \\{"));
+        }
+        let number = |parser: &mut Self| -> Result<Option<u32>, Error> {
+            let start = parser.pos;
+            while parser.peek().is_some_and(|c| c.is_ascii_digit()) {
+                parser.pos += 1;
+            }
+            if parser.pos == start {
+                return Ok(None);
+            }
+            let digits: String = parser.chars[start..parser.pos].iter().collect();
+            match digits.parse::<u32>() {
+                Ok(n) if n <= MAX => Ok(Some(n)),
+                _ => Err(Error::new("Regular expression too big")),
+            }
+        };
+
+        let min = number(self)?;
+        let max = if self.peek() == Some(',') {
+            self.pos += 1;
+            number(self)?
+        } else {
+            Some(min.ok_or_else(invalid)?)
+        };
+        if !self.looking_at(close) {
+            return Err(invalid());
+        }
+        self.pos += close.len();
+
+        let min = min.unwrap_or(0);
+        if max.is_some_and(|max| max < min) {
+            return Err(invalid());
+        }
+        Ok((min, max))
+    }
+
+    /// Parses a bracket expression after the opening `[`.
+    fn parse_bracket(&mut self) -> Result<Class, Error> {
+        let unmatched = || Error::new("Unmatched [, [^, [:, [., or [=");
+        let start = self.pos;
+        let mut class = Class::default();
+        if self.peek() == Some('^') {
+            class.negated = true;
+            self.pos += 1;
+        }
+
+        let mut first = true;
+        loop {
+            let c = self.peek().ok_or_else(unmatched)?;
+            if c == ']' && !first {
+                self.pos += 1;
+                break;
+            }
+            first = false;
+
+            if self.looking_at("[:") {
+                let name = self.bracket_term(':')?;
+                let named = Named::from_name(&name)
+                    .ok_or_else(|| Error::new("Invalid character class name"))?;
+                class.named.push(named);
+                continue;
+            }
+
+            let lo = self.bracket_char()?;
+            if self.peek() == Some('-') && self.peek_at(1).is_some_and(|c| c != ']') {
+                self.pos += 1;
+                let hi = self.bracket_char()?;
+                if hi < lo {
+                    return Err(Error::new("Invalid range end"));
+                }
+                class.ranges.push((lo, hi));
+            } else {
+                class.ranges.push((lo, lo));
+            }
+        }
+
+        // `[:space:]` is almost certainly meant as `[[:space:]]`.
+        let body: String = self.chars[start..self.pos - 1].iter().collect();
+        if body.len() > 2 && body.starts_with(':') && body.ends_with(':') {
+            return Err(Error::new(&format!(
+                "character class syntax is [[{}]], not [{}]",
+                body, body
+            )));
+        }
+        Ok(class)
+    }
+
+    /// One character in a bracket expression, possibly written as `[.c.]` or `[=c=]`.
+    fn bracket_char(&mut self) -> Result<char, Error> {
+        for delimiter in ['.', '='] {
+            if self.looking_at(&format!("[{}", delimiter)) {
+                let term = self.bracket_term(delimiter)?;
+                let mut chars = term.chars();
+                return match (chars.next(), chars.next()) {
+                    (Some(c), None) => Ok(c),
+                    _ => Err(Error::new("Invalid collation character")),
+                };
+            }
+        }
+        let c = self
+            .peek()
+            .ok_or_else(|| Error::new("Unmatched [, [^, [:, [., or [="))?;
+        self.pos += 1;
+        Ok(c)
+    }
+
+    /// Reads `[:name:]`-style terms, returning what is between the delimiters.
+    fn bracket_term(&mut self, delimiter: char) -> Result<String, Error> {
+        self.pos += 2;
+        let start = self.pos;
+        while !(self.peek() == Some(delimiter) && self.peek_at(1) == Some(']')) {
+            if self.peek().is_none() {
+                return Err(Error::new("Unmatched [, [^, [:, [., or [="));
+            }
+            self.pos += 1;
+        }
+        let term = self.chars[start..self.pos].iter().collect();
+        self.pos += 2;
+        Ok(term)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_ok(pattern: &str, syntax: Syntax) -> Ast {
+        parse(pattern, syntax, &mut Vec::new()).unwrap()
+    }
+
+    fn parse_err(pattern: &str, syntax: Syntax) -> String {
+        parse(pattern, syntax, &mut Vec::new())
+            .unwrap_err()
+            .to_string()
+    }
+
+    fn lit(s: &str) -> Vec<Ast> {
+        s.chars().map(Ast::Literal).collect()
+    }
+
+    #[test]
+    fn test_parse_basic() {
+        use Ast::*;
+        assert_eq!(
+            parse_ok("a*", Syntax::Basic),
+            Repeat {
+                ast: Box::new(Literal('a')),
+                min: 0,
+                max: None
+            }
+        );
+        assert_eq!(parse_ok("*a", Syntax::Basic), Concat(lit("*a")));
+        assert_eq!(parse_ok("a+?", Syntax::Basic), Concat(lit("a+?")));
+        assert_eq!(
+            parse_ok("a\\{2,3\\}", Syntax::Basic),
+            Repeat {
+                ast: Box::new(Literal('a')),
+                min: 2,
+                max: Some(3)
+            }
+        );
+        assert_eq!(
+            parse_ok("^a^$b$", Syntax::Basic),
+            Concat(vec![
+                Assert(Assertion::LineStart),
+                Literal('a'),
+                Literal('^'),
+                Literal('$'),
+                Literal('b'),
+                Assert(Assertion::LineEnd),
+            ])
+        );
+        assert_eq!(
+            parse_ok("\\(a\\|b\\)c", Syntax::Basic),
+            Concat(vec![
+                Alternate(vec![Literal('a'), Literal('b')]),
+                Literal('c')
+            ])
+        );
+        assert_eq!(parse_ok("a|b(c)", Syntax::Basic), Concat(lit("a|b(c)")));
+    }
+
+    #[test]
+    fn test_parse_extended() {
+        use Ast::*;
+        assert_eq!(
+            parse_ok("a|b+", Syntax::Extended),
+            Alternate(vec![
+                Literal('a'),
+                Repeat {
+                    ast: Box::new(Literal('b')),
+                    min: 1,
+                    max: None
+                }
+            ])
+        );
+        assert_eq!(parse_ok("a{", Syntax::Extended), Concat(lit("a{")));
+        assert_eq!(parse_ok("a{1", Syntax::Extended), Concat(lit("a{1")));
+        assert_eq!(parse_ok("a)", Syntax::Extended), Concat(lit("a)")));
+        assert_eq!(parse_ok("()", Syntax::Extended), Empty);
+        assert_eq!(
+            parse_ok("a|", Syntax::Extended),
+            Alternate(vec![Literal('a'), Empty])
+        );
+
+        let mut warnings = Vec::new();
+        assert_eq!(
+            parse("*a", Syntax::Extended, &mut warnings).unwrap(),
+            Literal('a')
+        );
+        assert_eq!(warnings, vec!["* at start of expression"]);
+    }
+
+    #[test]
+    fn test_parse_emacs() {
+        use Ast::*;
+        assert_eq!(
+            parse_ok("\\(a\\|b\\)+", Syntax::Emacs),
+            Repeat {
+                ast: Box::new(Alternate(vec![Literal('a'), Literal('b')])),
+                min: 1,
+                max: None
+            }
+        );
+        assert_eq!(parse_ok("+a\\+\\?", Syntax::Emacs), Concat(lit("+a+?")));
+        assert_eq!(parse_ok("a|(b)", Syntax::Emacs), Concat(lit("a|(b)")));
+        assert_eq!(parse_ok("a{2}", Syntax::Emacs), Concat(lit("a{2}")));
+        assert_eq!(parse_ok("a\\{2\\}", Syntax::Emacs), Concat(lit("a{2}")));
+    }
+
+    #[test]
+    fn test_parse_bracket() {
+        let class = |pattern: &str| match parse_ok(pattern, Syntax::Extended) {
+            Ast::Class(class) => class,
+            ast => panic!("{:?}", ast),
+        };
+        let c = class("[]a-c-]");
+        assert!(!c.negated);
+        assert_eq!(c.ranges, vec![(']', ']'), ('a', 'c'), ('-', '-')]);
+        let c = class("[^[:digit:][.-.]x]");
+        assert!(c.negated);
+        assert_eq!(c.named, vec![Named::Digit]);
+        assert!(c.contains('a', false) && !c.contains('5', false));
+        assert!(!c.contains('-', false) && !c.contains('x', false) && !c.contains('X', true));
+        assert_eq!(class("[\\n]").ranges, vec![('\\', '\\'), ('n', 'n')]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse_err("(a", Syntax::Extended), "Unmatched ( or \\(");
+        assert_eq!(parse_err("\\(a", Syntax::Basic), "Unmatched ( or \\(");
+        assert_eq!(parse_err("a\\)", Syntax::Basic), "Unmatched ) or \\)");
+        assert_eq!(parse_err("a\\{", Syntax::Basic), "Unmatched \\{");
+        assert_eq!(
+            parse_err("a{2,1}", Syntax::Extended),
+            "Invalid content of \\{\\}"
+        );
+        assert_eq!(
+            parse_err("a{99999}", Syntax::Extended),
+            "Regular expression too big"
+        );
+        assert_eq!(
+            parse_err("[a", Syntax::Basic),
+            "Unmatched [, [^, [:, [., or [="
+        );
+        assert_eq!(
+            parse_err("[[:foo:]]", Syntax::Basic),
+            "Invalid character class name"
+        );
+        assert_eq!(parse_err("[z-a]", Syntax::Basic), "Invalid range end");
+        assert_eq!(
+            parse_err("[:space:]", Syntax::Basic),
+            "character class syntax is [[:space:]], not [:space:]"
+        );
+        assert_eq!(parse_err("a\\", Syntax::Basic), "Trailing backslash");
+        assert_eq!(
+            parse_err("\\(a\\)\\1", Syntax::Basic),
+            "back-references are not supported"
+        );
+    }
+}
@@ -0,0 +1,122 @@
+//! Simulates the NFA directly, following every thread in lockstep, to find where matches are.
+use super::compile::{Inst, Prog};
+use super::{decode, decode_before, holds, step, Unit};
+
+/// The threads alive at one position: a sparse set of program counters, each with the
+/// position its match attempt started at. Earlier starts come first and win ties.
+struct Threads {
+    sparse: Vec<usize>,
+    dense: Vec<(usize, usize)>,
+}
+
+impl Threads {
+    fn new(len: usize) -> Self {
+        Threads {
+            sparse: vec![0; len],
+            dense: Vec::with_capacity(len),
+        }
+    }
+
+    fn contains(&self, pc: usize) -> bool {
+        let i = self.sparse[pc];
+        i < self.dense.len() && self.dense[i].0 == pc
+    }
+
+    fn insert(&mut self, pc: usize, start: usize) {
+        self.sparse[pc] = self.dense.len();
+        self.dense.push((pc, start));
+    }
+
+    fn clear(&mut self) {
+        self.dense.clear();
+    }
+
+    /// Adds `pc` and everything reachable from it without consuming input.
+    fn add(
+        &mut self,
+        prog: &Prog,
+        pc: usize,
+        start: usize,
+        around: (Option<Unit>, Option<Unit>),
+        stack: &mut Vec<usize>,
+    ) {
+        stack.push(pc);
+        while let Some(pc) = stack.pop() {
+            if self.contains(pc) {
+                continue;
+            }
+            self.insert(pc, start);
+            match &prog.insts[pc] {
+                Inst::Jump(target) => stack.push(*target),
+                Inst::Split(first, second) => {
+                    stack.push(*second);
+                    stack.push(*first);
+                }
+                Inst::Assert(assertion) if holds(*assertion, around.0, around.1) => {
+                    stack.push(pc + 1)
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// The leftmost-longest match starting at or after `start`.
+pub(crate) fn find_at(prog: &Prog, haystack: &[u8], start: usize) -> Option<(usize, usize)> {
+    let len = prog.insts.len();
+    let (mut current, mut next) = (Threads::new(len), Threads::new(len));
+    let mut stack = Vec::new();
+    let mut best: Option<(usize, usize)> = None;
+
+    let mut pos = start;
+    let mut prev = decode_before(haystack, start);
+    loop {
+        let unit = (pos < haystack.len()).then(|| decode(haystack, pos));
+        let around = (prev, unit.map(|(unit, _)| unit));
+        // A new attempt starts at every position until something matched.
+        if best.is_none() {
+            current.add(prog, 0, pos, around, &mut stack);
+        }
+
+        for &(pc, thread_start) in &current.dense {
+            let is_better = match best {
+                None => true,
+                Some((best_start, best_end)) => {
+                    thread_start < best_start || (thread_start == best_start && pos > best_end)
+                }
+            };
+            if prog.insts[pc] == Inst::Match && is_better {
+                best = Some((thread_start, pos));
+            }
+        }
+
+        let (unit, unit_len) = match unit {
+            Some(unit) => unit,
+            None => return best,
+        };
+        let next_pos = pos + unit_len;
+        let next_unit = (next_pos < haystack.len()).then(|| decode(haystack, next_pos).0);
+        next.clear();
+        for &(pc, thread_start) in &current.dense {
+            // Attempts starting after the best match so far cannot win.
+            if best.is_some_and(|(best_start, _)| thread_start > best_start) {
+                continue;
+            }
+            if step(prog, &prog.insts[pc], unit) {
+                next.add(
+                    prog,
+                    pc + 1,
+                    thread_start,
+                    (Some(unit), next_unit),
+                    &mut stack,
+                );
+            }
+        }
+        std::mem::swap(&mut current, &mut next);
+        if best.is_some() && current.dense.is_empty() {
+            return best;
+        }
+        pos = next_pos;
+        prev = Some(unit);
+    }
+}
@@ -0,0 +1,158 @@
+//! Bounded buffers that remember only the end of a stream.
+//!
+//! Data that falls out of the window is handed back to the caller, so `head -n -N` can print it
+//! while `tail` on a pipe simply drops it. Either way memory stays bounded by the window size.
+use std::collections::VecDeque;
+use std::io;
+
+/// Keeps the last `capacity` lines. A final line without a delimiter still counts as a line.
+pub struct LineRing {
+    capacity: usize,
+    delimiter: u8,
+    lines: VecDeque<Vec<u8>>,
+}
+
+impl LineRing {
+    pub fn new(capacity: usize, delimiter: u8) -> Self {
+        Self {
+            capacity,
+            delimiter,
+            lines: VecDeque::new(),
+        }
+    }
+
+    /// Appends `data`, calling `evict` with every line pushed out of the window, oldest first.
+    pub fn push<F>(&mut self, data: &[u8], mut evict: F) -> io::Result<()>
+    where
+        F: FnMut(&[u8]) -> io::Result<()>,
+    {
+        let delimiter = self.delimiter;
+        for piece in data.split_inclusive(|b| *b == delimiter) {
+            match self.lines.back_mut() {
+                Some(last) if last.last() != Some(&delimiter) => last.extend_from_slice(piece),
+                _ => self.lines.push_back(piece.to_vec()),
+            }
+
+            if self.lines.len() > self.capacity {
+                let line = self.lines.pop_front().unwrap_or_default();
+                evict(&line)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The lines currently in the window, oldest first.
+    pub fn lines(&self) -> impl Iterator<Item = &[u8]> {
+        self.lines.iter().map(|line| line.as_slice())
+    }
+}
+
+/// Keeps the last `capacity` bytes.
+pub struct ByteRing {
+    capacity: usize,
+    bytes: VecDeque<u8>,
+}
+
+impl ByteRing {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            bytes: VecDeque::new(),
+        }
+    }
+
+    /// Appends `data`, calling `evict` with the bytes pushed out of the window, oldest first.
+    pub fn push<F>(&mut self, data: &[u8], mut evict: F) -> io::Result<()>
+    where
+        F: FnMut(&[u8]) -> io::Result<()>,
+    {
+        let overflow = (self.bytes.len() + data.len()).saturating_sub(self.capacity);
+        let from_window = overflow.min(self.bytes.len());
+        if from_window > 0 {
+            let (front, back) = self.bytes.as_slices();
+            let front_len = from_window.min(front.len());
+            evict(&front[..front_len])?;
+            if from_window > front_len {
+                evict(&back[..from_window - front_len])?;
+            }
+            self.bytes.drain(..from_window);
+        }
+
+        let from_data = overflow - from_window;
+        if from_data > 0 {
+            evict(&data[..from_data])?;
+        }
+        self.bytes.extend(&data[from_data..]);
+        Ok(())
+    }
+
+    /// The bytes currently in the window, as the two halves of the ring.
+    pub fn as_slices(&self) -> (&[u8], &[u8]) {
+        self.bytes.as_slices()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect_lines(capacity: usize, chunks: &[&str]) -> (String, String) {
+        let mut ring = LineRing::new(capacity, b'\n');
+        let mut evicted = Vec::new();
+        for chunk in chunks {
+            ring.push(chunk.as_bytes(), |line| {
+                evicted.extend_from_slice(line);
+                Ok(())
+            })
+            .unwrap();
+        }
+        let kept: Vec<u8> = ring.lines().flatten().copied().collect();
+        (
+            String::from_utf8(evicted).unwrap(),
+            String::from_utf8(kept).unwrap(),
+        )
+    }
+
+    fn collect_bytes(capacity: usize, chunks: &[&str]) -> (String, String) {
+        let mut ring = ByteRing::new(capacity);
+        let mut evicted = Vec::new();
+        for chunk in chunks {
+            ring.push(chunk.as_bytes(), |bytes| {
+                evicted.extend_from_slice(bytes);
+                Ok(())
+            })
+            .unwrap();
+        }
+        let (front, back) = ring.as_slices();
+        (
+            String::from_utf8(evicted).unwrap(),
+            String::from_utf8([front, back].concat()).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_line_ring() {
+        assert_eq!(
+            collect_lines(2, &["1\n2\n3\n4"]),
+            ("1\n2\n".into(), "3\n4".into())
+        );
+        assert_eq!(
+            collect_lines(2, &["1\n2", "2\n3", "\n4\n"]),
+            ("1\n22\n".into(), "3\n4\n".into())
+        );
+        assert_eq!(collect_lines(5, &["1\n2\n"]), ("".into(), "1\n2\n".into()));
+        assert_eq!(collect_lines(0, &["1\n2"]), ("1\n2".into(), "".into()));
+        assert_eq!(collect_lines(1, &[]), ("".into(), "".into()));
+    }
+
+    #[test]
+    fn test_byte_ring() {
+        assert_eq!(collect_bytes(3, &["abcdef"]), ("abc".into(), "def".into()));
+        assert_eq!(
+            collect_bytes(3, &["ab", "cd", "e", "fghij"]),
+            ("abcdefg".into(), "hij".into())
+        );
+        assert_eq!(collect_bytes(10, &["ab", "cd"]), ("".into(), "abcd".into()));
+        assert_eq!(collect_bytes(0, &["ab", "cd"]), ("abcd".into(), "".into()));
+    }
+}
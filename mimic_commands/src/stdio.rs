@@ -0,0 +1,21 @@
+//! Standard input and output as `File`s, for what needs the file descriptor itself:
+//! seeking a redirected file, finding its size, or handing the data to the kernel.
+use std::fs::File;
+use std::mem::ManuallyDrop;
+use std::os::unix::io::{FromRawFd, RawFd};
+
+/// Borrows fd 0. Clone it with `try_clone` to get a `File` of its own.
+pub fn stdin_file() -> ManuallyDrop<File> {
+    borrow(0)
+}
+
+/// Borrows fd 1, which is written to unbuffered.
+pub fn stdout_file() -> ManuallyDrop<File> {
+    borrow(1)
+}
+
+fn borrow(fd: RawFd) -> ManuallyDrop<File> {
+    // The standard descriptors are open for as long as the process runs, and as the
+    // `File` is never dropped, it never closes them behind `io::stdin` and `io::stdout`.
+    ManuallyDrop::new(unsafe { File::from_raw_fd(fd) })
+}
@@ -0,0 +1,63 @@
+//! Scratch files for data that does not fit in memory or cannot be read twice.
+//!
+//! The files are unlinked as soon as they are created, so nothing is left behind however
+//! the command ends; they live on only as long as the returned `File`.
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The directory for scratch files: `$TMPDIR`, or `/tmp`.
+pub fn dir() -> PathBuf {
+    match env::var_os("TMPDIR") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from("/tmp"),
+    }
+}
+
+/// Creates an empty file in `dir` that can be written, then rewound and read back.
+pub fn create_in(dir: &Path) -> io::Result<File> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    loop {
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = dir.join(format!("mimic.{}.{}", process::id(), n));
+        let created = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path);
+        match created {
+            Ok(file) => {
+                fs::remove_file(&path)?;
+                return Ok(file);
+            }
+            // Left over from an earlier process with the same id.
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    #[test]
+    fn test_create_in() {
+        let mut file = create_in(&dir()).unwrap();
+        let mut other = create_in(&dir()).unwrap();
+        file.write_all(b"first").unwrap();
+        other.write_all(b"second").unwrap();
+
+        let mut read = String::new();
+        file.seek(SeekFrom::Start(0)).unwrap();
+        file.read_to_string(&mut read).unwrap();
+        assert_eq!(read, "first");
+        assert!(create_in(Path::new("/nonexistent")).is_err());
+    }
+}
@@ -0,0 +1,28 @@
+//! The size of the terminal, for commands that lay their output out to fit it.
+use std::env;
+use std::os::unix::io::RawFd;
+
+/// How many columns the terminal on `fd` has; failing that, `$COLUMNS`, or 80.
+pub fn width(fd: RawFd) -> usize {
+    #[repr(C)]
+    #[derive(Default)]
+    struct WinSize {
+        rows: u16,
+        columns: u16,
+        x_pixels: u16,
+        y_pixels: u16,
+    }
+    extern "C" {
+        fn ioctl(fd: i32, request: u64, ...) -> i32;
+    }
+    const TIOCGWINSZ: u64 = 0x5413;
+    let mut size = WinSize::default();
+    if unsafe { ioctl(fd, TIOCGWINSZ, &mut size as *mut WinSize) } == 0 && size.columns > 0 {
+        return size.columns.into();
+    }
+    env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .filter(|&columns| columns > 0)
+        .unwrap_or(80)
+}
@@ -0,0 +1,102 @@
+//! Timestamps broken down in the local time zone, for the commands that show when files
+//! were modified (`diff` headers, `ls -l`).
+use std::mem;
+use std::os::raw::{c_char, c_int, c_long};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// The broken down local time, as C's `struct tm` on glibc.
+#[repr(C)]
+struct Tm {
+    second: c_int,
+    minute: c_int,
+    hour: c_int,
+    day: c_int,
+    /// From 0.
+    month: c_int,
+    /// Since 1900.
+    year: c_int,
+    /// From Sunday, 0.
+    weekday: c_int,
+    year_day: c_int,
+    dst: c_int,
+    /// Seconds east of UTC.
+    offset: c_long,
+    zone: *const c_char,
+}
+
+extern "C" {
+    fn localtime_r(time: *const i64, tm: *mut Tm) -> *mut Tm;
+}
+
+/// Seconds and nanoseconds since the epoch, right now.
+pub fn now() -> (i64, i64) {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    (now.as_secs() as i64, now.subsec_nanos().into())
+}
+
+/// A point in time in the local time zone.
+pub struct LocalTime {
+    tm: Tm,
+    nanoseconds: i64,
+}
+
+impl LocalTime {
+    pub fn new(seconds: i64, nanoseconds: i64) -> Self {
+        let mut tm: Tm = unsafe { mem::zeroed() };
+        unsafe { localtime_r(&seconds, &mut tm) };
+        LocalTime { tm, nanoseconds }
+    }
+
+    /// `2024-01-31 13:05:09.123456789 +0100`, as in unified diff headers.
+    pub fn iso(&self) -> String {
+        let tm = &self.tm;
+        let offset = tm.offset.abs() / 60;
+        format!(
+            "{}-{:02}-{:02} {:02}:{:02}:{:02}.{:09} {}{:02}{:02}",
+            tm.year + 1900,
+            tm.month + 1,
+            tm.day,
+            tm.hour,
+            tm.minute,
+            tm.second,
+            self.nanoseconds,
+            if tm.offset < 0 { '-' } else { '+' },
+            offset / 60,
+            offset % 60
+        )
+    }
+
+    /// `Wed Jan 31 13:05:09 2024`, as C's `ctime`.
+    pub fn ctime(&self) -> String {
+        let tm = &self.tm;
+        format!(
+            "{} {} {:>2} {:02}:{:02}:{:02} {}",
+            DAYS[tm.weekday as usize],
+            MONTHS[tm.month as usize],
+            tm.day,
+            tm.hour,
+            tm.minute,
+            tm.second,
+            tm.year + 1900
+        )
+    }
+
+    /// `Jan 31 13:05` for a recent time, or `Jan 31  2024` for one further away, as in
+    /// `ls -l`.
+    pub fn short(&self, recent: bool) -> String {
+        let tm = &self.tm;
+        let month = MONTHS[tm.month as usize];
+        if recent {
+            format!("{} {:>2} {:02}:{:02}", month, tm.day, tm.hour, tm.minute)
+        } else {
+            format!("{} {:>2}  {}", month, tm.day, tm.year + 1900)
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! Just enough Unicode support for the commands that care about characters rather than bytes.
+
+/// Incremental UTF-8 decoder for input that arrives in arbitrary chunks,
+/// so a character split across two reads is still decoded as one.
+#[derive(Debug, Default)]
+pub struct Utf8Decoder {
+    code: u32,
+    len: u32,
+    remaining: u32,
+}
+
+impl Utf8Decoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds one byte. `f` receives `Some(c)` for every completed character
+    /// and `None` for every invalid or truncated sequence.
+    pub fn push<F: FnMut(Option<char>)>(&mut self, b: u8, mut f: F) {
+        if self.remaining > 0 {
+            if b & 0xC0 == 0x80 {
+                self.code = (self.code << 6) | u32::from(b & 0x3F);
+                self.remaining -= 1;
+                if self.remaining == 0 {
+                    f(self.complete());
+                }
+                return;
+            }
+            // The sequence was cut short; `b` starts something new.
+            self.remaining = 0;
+            f(None);
+        }
+
+        let (code, len) = match b {
+            0x00..=0x7F => return f(Some(char::from(b))),
+            0xC2..=0xDF => (b & 0x1F, 2),
+            0xE0..=0xEF => (b & 0x0F, 3),
+            0xF0..=0xF4 => (b & 0x07, 4),
+            _ => return f(None),
+        };
+        self.code = u32::from(code);
+        self.len = len;
+        self.remaining = len - 1;
+    }
+
+    /// Reports a sequence left incomplete at the end of input.
+    pub fn finish<F: FnMut(Option<char>)>(&mut self, mut f: F) {
+        if self.remaining > 0 {
+            self.remaining = 0;
+            f(None);
+        }
+    }
+
+    fn complete(&self) -> Option<char> {
+        let overlong = match self.len {
+            3 => self.code < 0x800,
+            4 => self.code < 0x10000,
+            _ => false,
+        };
+        if overlong {
+            None
+        } else {
+            char::from_u32(self.code)
+        }
+    }
+}
+
+/// The number of terminal columns `c` occupies, like `wcwidth(3)`:
+/// 0 for control and combining characters, 2 for East Asian wide ones, 1 otherwise.
+pub fn display_width(c: char) -> usize {
+    const ZERO_WIDTH: &[(u32, u32)] = &[
+        (0x0300, 0x036F),
+        (0x0483, 0x0489),
+        (0x0591, 0x05BD),
+        (0x0610, 0x061A),
+        (0x064B, 0x065F),
+        (0x0E31, 0x0E31),
+        (0x0E34, 0x0E3A),
+        (0x1AB0, 0x1AFF),
+        (0x1DC0, 0x1DFF),
+        (0x200B, 0x200F),
+        (0x20D0, 0x20FF),
+        (0xFE00, 0xFE0F),
+        (0xFE20, 0xFE2F),
+    ];
+    const WIDE: &[(u32, u32)] = &[
+        (0x1100, 0x115F),
+        (0x2E80, 0x303E),
+        (0x3040, 0xA4CF),
+        (0xAC00, 0xD7A3),
+        (0xF900, 0xFAFF),
+        (0xFE30, 0xFE4F),
+        (0xFF00, 0xFF60),
+        (0xFFE0, 0xFFE6),
+        (0x1F300, 0x1F64F),
+        (0x1F900, 0x1F9FF),
+        (0x20000, 0x3FFFD),
+    ];
+
+    let code = u32::from(c);
+    let in_ranges =
+        |ranges: &[(u32, u32)]| ranges.iter().any(|(lo, hi)| (*lo..=*hi).contains(&code));
+    if c.is_control() || in_ranges(ZERO_WIDTH) {
+        0
+    } else if in_ranges(WIDE) {
+        2
+    } else {
+        1
+    }
+}
+
+/// The display width of a whole string.
+pub fn str_width(s: &str) -> usize {
+    s.chars().map(display_width).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(chunks: &[&[u8]]) -> Vec<Option<char>> {
+        let mut decoder = Utf8Decoder::new();
+        let mut decoded = Vec::new();
+        for chunk in chunks {
+            for b in *chunk {
+                decoder.push(*b, |c| decoded.push(c));
+            }
+        }
+        decoder.finish(|c| decoded.push(c));
+        decoded
+    }
+
+    #[test]
+    fn test_utf8_decoder() {
+        assert_eq!(decode(&["aあ".as_bytes()]), vec![Some('a'), Some('あ')]);
+        assert_eq!(
+            decode(&[b"\xE3", b"\x81", b"\x82\xF0\x9F", b"\x98\x80"]),
+            vec![Some('あ'), Some('😀')]
+        );
+        assert_eq!(decode(&[b"\xFFa"]), vec![None, Some('a')]);
+        assert_eq!(decode(&[b"\xE3\x81a"]), vec![None, Some('a')]);
+        assert_eq!(decode(&[b"\xE3\x81"]), vec![None]);
+        assert_eq!(decode(&[b"\xE0\x80\x80"]), vec![None]);
+        assert_eq!(decode(&[b"\xED\xA0\x80"]), vec![None]);
+    }
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(str_width("abc"), 3);
+        assert_eq!(str_width("ああ"), 4);
+        assert_eq!(str_width("a\u{301}"), 1);
+        assert_eq!(str_width("\u{7}"), 0);
+    }
+}
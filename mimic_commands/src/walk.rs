@@ -0,0 +1,237 @@
+//! Depth-first directory traversal for the commands that recurse, such as `grep -r`.
+//!
+//! Entries come out in pre-order, each directory before its contents, and the contents in
+//! the order the file system lists them, as with GNU tools. Directories already being
+//! walked are not entered again, so following symbolic links cannot loop forever.
+use std::fs::{self, Metadata};
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/// Which symbolic links to follow.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Follow {
+    Never,
+    /// Only the starting point itself (`grep -r`, `find -H`).
+    Root,
+    Always,
+}
+
+/// A file or directory found.
+#[derive(Debug)]
+pub struct Entry {
+    path: PathBuf,
+    depth: usize,
+    metadata: Metadata,
+}
+
+impl Entry {
+    /// The path, made by joining the starting point with the names below it.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// How far below the starting point this is; the starting point itself is 0.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The metadata of the entry, or of what it links to if the link was followed and
+    /// leads somewhere.
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// The last component of the path, or the whole path for a starting point like `/`.
+    pub fn file_name(&self) -> &Path {
+        self.path
+            .file_name()
+            .map_or(&self.path, |name| Path::new(name))
+    }
+}
+
+/// Why an entry could not be walked.
+#[derive(Debug)]
+pub enum Error {
+    /// Getting its metadata or listing it failed.
+    Io(PathBuf, io::Error),
+    /// A directory that contains itself through a symbolic link, and the path it was
+    /// entered by higher up.
+    Loop(PathBuf, PathBuf),
+}
+
+#[derive(Debug)]
+enum Pending {
+    Path(PathBuf, usize),
+    /// All of a directory's contents have been handed out.
+    Leave,
+}
+
+/// Walks the tree below one starting point.
+#[derive(Debug)]
+pub struct Walker {
+    follow: Follow,
+    pending: Vec<Pending>,
+    /// The directory last handed out, to be listed next unless skipped, with its depth
+    /// and its device and inode numbers.
+    directory: Option<(PathBuf, usize, (u64, u64))>,
+    /// The device and inode numbers of the directories being walked, with their paths.
+    ancestors: Vec<((u64, u64), PathBuf)>,
+}
+
+impl Walker {
+    pub fn new<P: AsRef<Path>>(root: P, follow: Follow) -> Self {
+        Walker {
+            follow,
+            pending: vec![Pending::Path(root.as_ref().to_path_buf(), 0)],
+            directory: None,
+            ancestors: Vec::new(),
+        }
+    }
+
+    /// Does not descend into the directory just returned.
+    pub fn skip_current_dir(&mut self) {
+        self.directory = None;
+    }
+
+    fn enter(&mut self, path: PathBuf, depth: usize) -> Result<(), Error> {
+        let mut names = Vec::new();
+        let read = fs::read_dir(&path).and_then(|entries| {
+            for entry in entries {
+                names.push(entry?.file_name());
+            }
+            Ok(())
+        });
+        read.map_err(|e| Error::Io(path.clone(), e))?;
+
+        self.pending.push(Pending::Leave);
+        self.pending.extend(
+            names
+                .into_iter()
+                .rev()
+                .map(|name| Pending::Path(path.join(name), depth + 1)),
+        );
+        Ok(())
+    }
+}
+
+impl Iterator for Walker {
+    type Item = Result<Entry, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some((path, depth, id)) = self.directory.take() {
+            if let Err(e) = self.enter(path.clone(), depth) {
+                return Some(Err(e));
+            }
+            self.ancestors.push((id, path));
+        }
+
+        loop {
+            let (path, depth) = match self.pending.pop()? {
+                Pending::Path(path, depth) => (path, depth),
+                Pending::Leave => {
+                    self.ancestors.pop();
+                    continue;
+                }
+            };
+            let follow = match self.follow {
+                Follow::Never => false,
+                Follow::Root => depth == 0,
+                Follow::Always => true,
+            };
+            let metadata = if follow {
+                // A link to nothing is handed out as the link itself.
+                fs::metadata(&path).or_else(|e| match fs::symlink_metadata(&path) {
+                    Ok(metadata) if metadata.file_type().is_symlink() => Ok(metadata),
+                    _ => Err(e),
+                })
+            } else {
+                fs::symlink_metadata(&path)
+            };
+            let metadata = match metadata {
+                Ok(metadata) => metadata,
+                Err(e) => return Some(Err(Error::Io(path, e))),
+            };
+            if metadata.is_dir() {
+                let id = (metadata.dev(), metadata.ino());
+                if let Some((_, ancestor)) = self.ancestors.iter().find(|(seen, _)| *seen == id) {
+                    return Some(Err(Error::Loop(path, ancestor.clone())));
+                }
+                self.directory = Some((path.clone(), depth, id));
+            }
+            return Some(Ok(Entry {
+                path,
+                depth,
+                metadata,
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::os::unix::fs::symlink;
+
+    fn walk(root: &Path, follow: Follow) -> Vec<String> {
+        let mut found: Vec<String> = Walker::new(root, follow)
+            .map(|entry| match entry {
+                Ok(entry) => {
+                    let path = entry.path().strip_prefix(root).unwrap();
+                    format!("{} {}", entry.depth(), path.display())
+                }
+                Err(Error::Loop(path, ancestor)) => {
+                    format!("loop {} {}", path.display(), ancestor.display())
+                }
+                Err(Error::Io(path, e)) => format!("{} {}", path.display(), e),
+            })
+            .collect();
+        // Contents are listed in no particular order.
+        found.sort();
+        found
+    }
+
+    #[test]
+    fn test_walk() {
+        let root = env::temp_dir().join(format!("mimic_walk_test_{}", std::process::id()));
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        fs::write(root.join("a/b/file"), "").unwrap();
+        fs::write(root.join("top"), "").unwrap();
+        symlink("a", root.join("link")).unwrap();
+        symlink("..", root.join("a/up")).unwrap();
+
+        assert_eq!(
+            walk(&root, Follow::Never),
+            vec![
+                "0 ",
+                "1 a",
+                "1 link",
+                "1 top",
+                "2 a/b",
+                "2 a/up",
+                "3 a/b/file"
+            ]
+        );
+        let followed = walk(&root, Follow::Always);
+        assert!(followed.contains(&"2 link/b".to_string()));
+        assert!(followed.contains(&"3 link/b/file".to_string()));
+        assert!(followed.contains(&format!(
+            "loop {} {}",
+            root.join("a/up").display(),
+            root.display()
+        )));
+
+        let mut walker = Walker::new(&root, Follow::Never);
+        let mut found = Vec::new();
+        while let Some(Ok(entry)) = walker.next() {
+            if entry.file_name() == Path::new("a") {
+                walker.skip_current_dir();
+            }
+            found.push(entry.depth());
+        }
+        assert_eq!(found.iter().max(), Some(&1));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
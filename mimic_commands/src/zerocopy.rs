@@ -0,0 +1,225 @@
+//! Copying between files without passing the data through user space,
+//! for commands that hand their input over untouched.
+use crate::error::Output;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::unix::fs::FileTypeExt;
+
+/// How the data gets from one file to the other.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+    /// `copy_file_range(2)` between two regular files.
+    CopyFileRange,
+    /// `sendfile(2)` from a regular file to anything else, e.g. a socket.
+    Sendfile,
+    /// `splice(2)`, which needs a pipe on at least one side.
+    Splice,
+    /// `read(2)` and `write(2)` through a user-space buffer.
+    Buffered,
+}
+
+impl Method {
+    /// The fastest method the kernel supports between these two files.
+    pub fn choose(input: &File, output: &File) -> Self {
+        if !cfg!(target_os = "linux") {
+            return Method::Buffered;
+        }
+        let (input, output) = match (input.metadata(), output.metadata()) {
+            (Ok(input), Ok(output)) => (input.file_type(), output.file_type()),
+            _ => return Method::Buffered,
+        };
+        if input.is_fifo() || output.is_fifo() {
+            Method::Splice
+        } else if input.is_file() && output.is_file() {
+            Method::CopyFileRange
+        } else if input.is_file() {
+            Method::Sendfile
+        } else {
+            Method::Buffered
+        }
+    }
+}
+
+/// The buffer size for [`Method::Buffered`], big enough to keep the number of system calls down.
+pub const BUFFER_SIZE: usize = 128 * 1024;
+
+/// Copies the rest of `input` to `output` with whatever method suits the two files.
+pub fn copy(input: &File, output: &File) -> io::Result<u64> {
+    copy_with(Method::choose(input, output), input, output)
+}
+
+/// Copies the rest of `input` to `output`, starting with `method`.
+///
+/// Whatever the kernel does not copy, because it refuses the method part way through or
+/// because the input lies about its size like files in `/proc` do, is copied through a
+/// buffer afterwards. A genuine I/O error therefore shows up in a plain read or write,
+/// where write errors are marked like those of an [`Output`].
+pub fn copy_with(method: Method, input: &File, output: &File) -> io::Result<u64> {
+    let mut copied = 0;
+    if method != Method::Buffered {
+        // Any error is left for the buffered copy to run into again.
+        kernel_copy(method, input, output, &mut copied).ok();
+    }
+
+    let mut input = input;
+    let mut output = Output(output);
+    let mut buffer = vec![0; BUFFER_SIZE];
+    loop {
+        let size = match input.read(&mut buffer) {
+            Ok(0) => return Ok(copied),
+            Ok(size) => size,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        output.write_all(&buffer[..size])?;
+        copied += size as u64;
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn kernel_copy(method: Method, input: &File, output: &File, copied: &mut u64) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+    use std::ptr::null_mut;
+
+    extern "C" {
+        fn copy_file_range(
+            fd_in: i32,
+            off_in: *mut i64,
+            fd_out: i32,
+            off_out: *mut i64,
+            len: usize,
+            flags: u32,
+        ) -> isize;
+        fn sendfile(out_fd: i32, in_fd: i32, offset: *mut i64, count: usize) -> isize;
+        fn splice(
+            fd_in: i32,
+            off_in: *mut i64,
+            fd_out: i32,
+            off_out: *mut i64,
+            len: usize,
+            flags: u32,
+        ) -> isize;
+    }
+    const SPLICE_F_MOVE: u32 = 1;
+    // Asked for per call; the kernel copies less whenever it likes.
+    const CHUNK_SIZE: usize = 1 << 30;
+
+    let (fd_in, fd_out) = (input.as_raw_fd(), output.as_raw_fd());
+    loop {
+        let size = unsafe {
+            match method {
+                Method::CopyFileRange => {
+                    copy_file_range(fd_in, null_mut(), fd_out, null_mut(), CHUNK_SIZE, 0)
+                }
+                Method::Sendfile => sendfile(fd_out, fd_in, null_mut(), CHUNK_SIZE),
+                Method::Splice => splice(
+                    fd_in,
+                    null_mut(),
+                    fd_out,
+                    null_mut(),
+                    CHUNK_SIZE,
+                    SPLICE_F_MOVE,
+                ),
+                Method::Buffered => return Ok(()),
+            }
+        };
+        match size {
+            0 => return Ok(()),
+            size if size > 0 => *copied += size as u64,
+            _ => {
+                let e = io::Error::last_os_error();
+                if e.kind() != io::ErrorKind::Interrupted {
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn kernel_copy(_: Method, _: &File, _: &File, _: &mut u64) -> io::Result<()> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Seek, SeekFrom};
+    use std::{env, fs, thread};
+
+    const METHODS: [Method; 4] = [
+        Method::CopyFileRange,
+        Method::Sendfile,
+        Method::Splice,
+        Method::Buffered,
+    ];
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        env::temp_dir().join(format!("zerocopy_{}_{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_copy_to_file() {
+        let data: Vec<u8> = (0..300_000).map(|i| (i % 251) as u8).collect();
+        let (from, to) = (temp_path("from"), temp_path("to"));
+        fs::write(&from, &data).unwrap();
+
+        for method in METHODS {
+            let mut input = File::open(&from).unwrap();
+            // Starts from the current offset, like `cat` on an inherited descriptor.
+            input.seek(SeekFrom::Start(10)).unwrap();
+            let output = File::create(&to).unwrap();
+            assert_eq!(copy_with(method, &input, &output).unwrap(), 299_990);
+            assert_eq!(fs::read(&to).unwrap(), &data[10..], "{:?}", method);
+        }
+        fs::remove_file(&from).unwrap();
+        fs::remove_file(&to).unwrap();
+    }
+
+    #[test]
+    fn test_copy_to_pipe() {
+        let data: Vec<u8> = (0..300_000).map(|i| (i % 251) as u8).collect();
+        let from = temp_path("pipe");
+        fs::write(&from, &data).unwrap();
+
+        for method in METHODS {
+            let (mut reader, writer) = io::pipe().unwrap();
+            let drain = thread::spawn(move || {
+                let mut received = Vec::new();
+                reader.read_to_end(&mut received).unwrap();
+                received
+            });
+            let output = File::from(std::os::fd::OwnedFd::from(writer));
+            assert_eq!(
+                Method::choose(&File::open(&from).unwrap(), &output),
+                Method::Splice
+            );
+            copy_with(method, &File::open(&from).unwrap(), &output).unwrap();
+            drop(output);
+            assert_eq!(drain.join().unwrap(), data, "{:?}", method);
+        }
+        fs::remove_file(&from).unwrap();
+    }
+
+    #[test]
+    fn test_copy_from_proc() {
+        // Files in /proc report a size of 0, so only the buffered copy sees their contents.
+        let input = File::open("/proc/self/status").unwrap();
+        let to = temp_path("proc");
+        let output = File::create(&to).unwrap();
+        copy(&input, &output).unwrap();
+        assert!(fs::read_to_string(&to).unwrap().contains("Name:"));
+        fs::remove_file(&to).unwrap();
+    }
+
+    #[test]
+    fn test_write_error_is_marked() {
+        let input = File::open("/proc/self/status").unwrap();
+        let output = File::options().write(true).open("/dev/full").unwrap();
+        let e = copy(&input, &output).unwrap_err();
+        assert!(matches!(
+            crate::error::Error::copying("status", e),
+            crate::error::Error::Write(_)
+        ));
+    }
+}